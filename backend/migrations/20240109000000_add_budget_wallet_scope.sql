@@ -0,0 +1,24 @@
+-- Wallet-scoped budgets
+-- A budget can now be limited to one wallet ("kartu kredit max 5 juta/bulan") or a group of
+-- wallets, optionally combined with a category ("kartu kantor hanya untuk transport")
+
+-- 1. Add wallet scope
+-- wallet_ids NULL = semua wallet, otherwise only expenses from the listed wallets count
+ALTER TABLE budgets ADD COLUMN IF NOT EXISTS wallet_ids UUID[];
+
+-- 2. Replace the category/month uniqueness with one that covers the full scope
+-- The old constraint treated NULL category_id as distinct and also counted soft-deleted rows.
+-- The new index treats NULL as a value (total budget / all wallets) and ignores deleted budgets.
+ALTER TABLE budgets DROP CONSTRAINT IF EXISTS unique_budget_per_category_month;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_budgets_unique_scope_period
+ON budgets (
+    user_id,
+    COALESCE(category_id, '00000000-0000-0000-0000-000000000000'::uuid),
+    COALESCE(wallet_ids, '{}'::uuid[]),
+    month,
+    year
+)
+WHERE deleted_at IS NULL;
+
+COMMENT ON COLUMN budgets.wallet_ids IS 'NULL for all wallets, otherwise the wallets (sorted, unique) whose expenses count toward the budget';
@@ -0,0 +1,36 @@
+-- Hierarchical categories
+-- parent_id NULL = top-level category, otherwise the category is a sub-category
+-- (e.g. "Restoran" and "Groceries" under "Makanan"). Spending on a sub-category rolls up
+-- into its parents for dashboard totals and category budgets.
+
+ALTER TABLE categories ADD COLUMN IF NOT EXISTS parent_id UUID;
+
+-- SET NULL: if a parent is ever hard-deleted its children become top-level categories
+ALTER TABLE categories
+DROP CONSTRAINT IF EXISTS categories_parent_id_fkey;
+
+ALTER TABLE categories
+ADD CONSTRAINT categories_parent_id_fkey
+FOREIGN KEY (parent_id)
+REFERENCES categories(id)
+ON DELETE SET NULL;
+
+-- A category can never be its own parent; deeper cycles are rejected by the API
+ALTER TABLE categories
+DROP CONSTRAINT IF EXISTS categories_parent_not_self;
+
+ALTER TABLE categories
+ADD CONSTRAINT categories_parent_not_self CHECK (parent_id IS NULL OR parent_id <> id);
+
+CREATE INDEX IF NOT EXISTS idx_categories_parent_id ON categories(parent_id);
+
+COMMENT ON COLUMN categories.parent_id IS 'Parent category for sub-categories. NULL for top-level categories.';
+
+-- Categories never got updated_at although update_category sets it
+ALTER TABLE categories ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+
+DROP TRIGGER IF EXISTS update_categories_updated_at ON categories;
+CREATE TRIGGER update_categories_updated_at
+    BEFORE UPDATE ON categories
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
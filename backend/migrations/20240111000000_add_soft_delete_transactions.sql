@@ -0,0 +1,10 @@
+-- Add soft delete support for transactions
+-- Deleting a transaction reverses its wallet balance change and sets deleted_at;
+-- undo re-applies the balance change and clears it
+
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+
+-- Most queries only read live transactions of one user
+CREATE INDEX IF NOT EXISTS idx_transactions_user_active ON transactions(user_id, date) WHERE deleted_at IS NULL;
+
+COMMENT ON COLUMN transactions.deleted_at IS 'Soft delete timestamp. NULL means not deleted.';
@@ -0,0 +1,41 @@
+-- Append-only audit trail for transactions, wallets, budgets and categories
+-- Rows keep a JSON snapshot of the record before and after each change, so history
+-- survives even after the record itself is purged
+
+CREATE TABLE IF NOT EXISTS audit_logs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- Who made the change; NULL for background jobs. No FK so rows never need rewriting
+    actor_id UUID,
+    source_channel VARCHAR(20) NOT NULL,
+    entity_type VARCHAR(20) NOT NULL,
+    entity_id UUID NOT NULL,
+    action VARCHAR(20) NOT NULL,
+    before_data JSONB,
+    after_data JSONB,
+    -- clock_timestamp() keeps entries written in one database transaction in order
+    created_at TIMESTAMPTZ NOT NULL DEFAULT clock_timestamp(),
+
+    CONSTRAINT audit_logs_source_channel_check CHECK (source_channel IN ('web', 'whatsapp', 'import', 'system')),
+    CONSTRAINT audit_logs_entity_type_check CHECK (entity_type IN ('transaction', 'wallet', 'budget', 'category')),
+    CONSTRAINT audit_logs_action_check CHECK (action IN ('create', 'update', 'delete', 'restore', 'purge'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_logs_entity ON audit_logs(user_id, entity_type, entity_id, created_at);
+
+-- Audit rows are never edited; deletes are still allowed so removing a user cascades
+CREATE OR REPLACE FUNCTION prevent_audit_log_update()
+RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_logs is append-only';
+END;
+$$ language 'plpgsql';
+
+DROP TRIGGER IF EXISTS audit_logs_append_only ON audit_logs;
+CREATE TRIGGER audit_logs_append_only
+    BEFORE UPDATE ON audit_logs
+    FOR EACH ROW
+    EXECUTE FUNCTION prevent_audit_log_update();
+
+COMMENT ON TABLE audit_logs IS 'Append-only change history of financial records';
+COMMENT ON COLUMN audit_logs.source_channel IS 'Where the change came from: web, whatsapp, import or system';
@@ -0,0 +1,17 @@
+-- Statement cycle for credit cards and paylater
+-- The statement closes at the end of statement_closing_day and must be paid by the next
+-- payment_due_day. Days past the end of a short month fall on its last day.
+
+ALTER TABLE wallets ADD COLUMN IF NOT EXISTS statement_closing_day INTEGER;
+ALTER TABLE wallets ADD COLUMN IF NOT EXISTS payment_due_day INTEGER;
+
+ALTER TABLE wallets DROP CONSTRAINT IF EXISTS wallets_statement_closing_day_check;
+ALTER TABLE wallets ADD CONSTRAINT wallets_statement_closing_day_check
+    CHECK (statement_closing_day BETWEEN 1 AND 31);
+
+ALTER TABLE wallets DROP CONSTRAINT IF EXISTS wallets_payment_due_day_check;
+ALTER TABLE wallets ADD CONSTRAINT wallets_payment_due_day_check
+    CHECK (payment_due_day BETWEEN 1 AND 31);
+
+COMMENT ON COLUMN wallets.statement_closing_day IS 'Day of month the credit statement closes. NULL for regular wallets.';
+COMMENT ON COLUMN wallets.payment_due_day IS 'Day of month the closed statement is due. NULL for regular wallets.';
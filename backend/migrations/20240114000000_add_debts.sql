@@ -0,0 +1,45 @@
+-- Loans and debts ledger (hutang/piutang)
+-- direction 'lent' is money others owe the user (piutang), 'borrowed' is money the user owes
+-- (hutang). Repayments are ordinary wallet transactions linked through debt_repayments, so a
+-- deleted repayment transaction automatically stops counting.
+
+CREATE TABLE IF NOT EXISTS debts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    counterparty VARCHAR(100) NOT NULL,
+    direction VARCHAR(20) NOT NULL,
+    principal FLOAT8 NOT NULL,
+    description TEXT,
+    date DATE NOT NULL DEFAULT CURRENT_DATE,
+    due_date DATE,
+    -- Transaction that moved the principal in or out of a wallet, if any
+    transaction_id UUID REFERENCES transactions(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    deleted_at TIMESTAMPTZ,
+
+    CONSTRAINT debts_direction_check CHECK (direction IN ('lent', 'borrowed')),
+    CONSTRAINT debts_principal_check CHECK (principal > 0)
+);
+
+CREATE INDEX IF NOT EXISTS idx_debts_user_active ON debts(user_id, due_date) WHERE deleted_at IS NULL;
+
+CREATE TABLE IF NOT EXISTS debt_repayments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    debt_id UUID NOT NULL REFERENCES debts(id) ON DELETE CASCADE,
+    transaction_id UUID NOT NULL REFERENCES transactions(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT unique_debt_repayment_transaction UNIQUE (transaction_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_debt_repayments_debt ON debt_repayments(debt_id);
+
+DROP TRIGGER IF EXISTS update_debts_updated_at ON debts;
+CREATE TRIGGER update_debts_updated_at
+    BEFORE UPDATE ON debts
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+COMMENT ON TABLE debts IS 'Money lent to (piutang) or borrowed from (hutang) other people';
+COMMENT ON COLUMN debts.direction IS 'lent = receivable (piutang), borrowed = payable (hutang)';
@@ -0,0 +1,48 @@
+-- Installment plans (cicilan) on credit cards and paylater
+-- A purchase is split into tenor_months monthly installments with optional flat monthly
+-- interest. Each installment is posted as an expense transaction on the credit wallet once
+-- its due date arrives; installments not posted yet are future committed spending.
+
+CREATE TABLE IF NOT EXISTS installment_plans (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    category_id UUID REFERENCES categories(id) ON DELETE SET NULL,
+    description VARCHAR(255) NOT NULL,
+    principal FLOAT8 NOT NULL,
+    interest_rate FLOAT8 NOT NULL DEFAULT 0, -- flat, percent of principal per month
+    tenor_months INTEGER NOT NULL,
+    purchase_date DATE NOT NULL DEFAULT CURRENT_DATE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    deleted_at TIMESTAMPTZ,
+
+    CONSTRAINT installment_plans_principal_check CHECK (principal > 0),
+    CONSTRAINT installment_plans_interest_rate_check CHECK (interest_rate >= 0),
+    CONSTRAINT installment_plans_tenor_check CHECK (tenor_months BETWEEN 2 AND 36)
+);
+
+CREATE INDEX IF NOT EXISTS idx_installment_plans_user_active ON installment_plans(user_id) WHERE deleted_at IS NULL;
+
+CREATE TABLE IF NOT EXISTS installments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    plan_id UUID NOT NULL REFERENCES installment_plans(id) ON DELETE CASCADE,
+    installment_number INTEGER NOT NULL,
+    due_date DATE NOT NULL,
+    amount FLOAT8 NOT NULL,
+    -- Set once the installment is booked; the transaction may later be deleted by the user
+    posted_at TIMESTAMPTZ,
+    transaction_id UUID REFERENCES transactions(id) ON DELETE SET NULL,
+
+    CONSTRAINT unique_installment_number UNIQUE (plan_id, installment_number)
+);
+
+CREATE INDEX IF NOT EXISTS idx_installments_unposted ON installments(due_date) WHERE posted_at IS NULL;
+
+DROP TRIGGER IF EXISTS update_installment_plans_updated_at ON installment_plans;
+CREATE TRIGGER update_installment_plans_updated_at
+    BEFORE UPDATE ON installment_plans
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+COMMENT ON TABLE installments IS 'Monthly schedule of an installment plan; posted_at IS NULL means not booked yet';
@@ -0,0 +1,41 @@
+-- Savings goals (e.g. wedding, hajj, emergency fund)
+-- A goal either follows the balance of a linked wallet or collects manual contributions.
+
+CREATE TABLE IF NOT EXISTS goals (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    target_amount FLOAT8 NOT NULL,
+    target_date DATE,
+    -- Progress is this wallet's balance; NULL means progress comes from goal_contributions
+    wallet_id UUID REFERENCES wallets(id) ON DELETE SET NULL,
+    icon VARCHAR(50),
+    color VARCHAR(20),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    deleted_at TIMESTAMPTZ,
+
+    CONSTRAINT goals_target_amount_check CHECK (target_amount > 0)
+);
+
+CREATE INDEX IF NOT EXISTS idx_goals_user_active ON goals(user_id) WHERE deleted_at IS NULL;
+
+-- Negative amounts are withdrawals from the goal
+CREATE TABLE IF NOT EXISTS goal_contributions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    goal_id UUID NOT NULL REFERENCES goals(id) ON DELETE CASCADE,
+    amount FLOAT8 NOT NULL,
+    date DATE NOT NULL DEFAULT CURRENT_DATE,
+    note TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT goal_contributions_amount_check CHECK (amount <> 0)
+);
+
+CREATE INDEX IF NOT EXISTS idx_goal_contributions_goal ON goal_contributions(goal_id, date);
+
+DROP TRIGGER IF EXISTS update_goals_updated_at ON goals;
+CREATE TRIGGER update_goals_updated_at
+    BEFORE UPDATE ON goals
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
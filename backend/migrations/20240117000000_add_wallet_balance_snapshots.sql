@@ -0,0 +1,15 @@
+-- Daily wallet balances for net worth history
+-- One row per wallet per day holding the balance at the end of that day. Today's row is
+-- refreshed while the day is running; past rows can be backfilled from the transaction ledger.
+
+CREATE TABLE IF NOT EXISTS wallet_balance_snapshots (
+    wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    date DATE NOT NULL,
+    balance FLOAT8 NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (wallet_id, date)
+);
+
+CREATE INDEX IF NOT EXISTS idx_wallet_balance_snapshots_user_date ON wallet_balance_snapshots(user_id, date);
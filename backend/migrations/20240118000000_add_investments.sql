@@ -0,0 +1,50 @@
+-- Investment holdings on 'investment' wallets
+-- The wallet balance stays the uninvested cash; each holding is valued at quantity times the
+-- latest known price of its symbol, or at cost until a price is recorded.
+
+CREATE TABLE IF NOT EXISTS investment_holdings (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    asset_type VARCHAR(20) NOT NULL,
+    symbol VARCHAR(30) NOT NULL, -- stored upper case
+    name VARCHAR(100),
+    quantity FLOAT8 NOT NULL, -- shares, fund units, grams or coins
+    cost_basis FLOAT8 NOT NULL, -- total amount paid for the quantity held
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    deleted_at TIMESTAMPTZ,
+
+    CONSTRAINT investment_holdings_asset_type_check CHECK (asset_type IN ('stock', 'mutual_fund', 'gold', 'crypto')),
+    CONSTRAINT investment_holdings_quantity_check CHECK (quantity >= 0),
+    CONSTRAINT investment_holdings_cost_basis_check CHECK (cost_basis >= 0)
+);
+
+CREATE INDEX IF NOT EXISTS idx_investment_holdings_user_active ON investment_holdings(user_id) WHERE deleted_at IS NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS unique_investment_holding_symbol
+    ON investment_holdings(wallet_id, asset_type, symbol) WHERE deleted_at IS NULL;
+
+-- Price per unit (per share, per fund unit, per gram, per coin) recorded by the user
+CREATE TABLE IF NOT EXISTS asset_prices (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    asset_type VARCHAR(20) NOT NULL,
+    symbol VARCHAR(30) NOT NULL,
+    price FLOAT8 NOT NULL,
+    price_date DATE NOT NULL DEFAULT CURRENT_DATE,
+    source VARCHAR(20) NOT NULL DEFAULT 'manual',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT asset_prices_price_check CHECK (price >= 0),
+    CONSTRAINT asset_prices_source_check CHECK (source IN ('manual', 'import')),
+    CONSTRAINT unique_asset_price_per_day UNIQUE (user_id, asset_type, symbol, price_date)
+);
+
+DROP TRIGGER IF EXISTS update_investment_holdings_updated_at ON investment_holdings;
+CREATE TRIGGER update_investment_holdings_updated_at
+    BEFORE UPDATE ON investment_holdings
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Market value of the wallet's holdings on the snapshot date, next to its cash balance
+ALTER TABLE wallet_balance_snapshots ADD COLUMN IF NOT EXISTS holdings_value FLOAT8 NOT NULL DEFAULT 0;
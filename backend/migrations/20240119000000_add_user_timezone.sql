@@ -0,0 +1,3 @@
+-- Per-user IANA time zone, used to decide which calendar day "today" is for default
+-- transaction dates, month boundaries, scheduled postings and reports
+ALTER TABLE users ADD COLUMN IF NOT EXISTS timezone VARCHAR(64) NOT NULL DEFAULT 'Asia/Jakarta';
@@ -0,0 +1,21 @@
+-- In-app notifications written by background jobs (e.g. unusual spending)
+-- dedupe_key stops a job from notifying about the same thing twice
+
+CREATE TABLE IF NOT EXISTS notifications (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind VARCHAR(50) NOT NULL,
+    title VARCHAR(255) NOT NULL,
+    body TEXT NOT NULL,
+    data JSONB,
+    dedupe_key VARCHAR(255),
+    read_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_notifications_user ON notifications(user_id, created_at DESC);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_notifications_dedupe
+    ON notifications(user_id, dedupe_key) WHERE dedupe_key IS NOT NULL;
+
+-- Users can turn off notifications about unusual spending
+ALTER TABLE users ADD COLUMN IF NOT EXISTS anomaly_alerts BOOLEAN NOT NULL DEFAULT TRUE;
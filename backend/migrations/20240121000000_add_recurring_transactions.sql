@@ -0,0 +1,42 @@
+-- Recurring transactions (salary, rent, subscriptions)
+-- Each occurrence is booked as a transaction on next_date, or only announced with a
+-- notification when auto_post is off. next_date then moves on by one period.
+
+CREATE TABLE IF NOT EXISTS recurring_transactions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    category_id UUID REFERENCES categories(id) ON DELETE SET NULL,
+    transaction_type VARCHAR(20) NOT NULL,
+    amount FLOAT8 NOT NULL,
+    description VARCHAR(255) NOT NULL,
+    frequency VARCHAR(20) NOT NULL,
+    interval_count INTEGER NOT NULL DEFAULT 1, -- every N weeks/months/years
+    -- Day of month monthly and yearly schedules return to after a shorter month
+    anchor_day SMALLINT NOT NULL,
+    next_date DATE NOT NULL,
+    end_date DATE,
+    auto_post BOOLEAN NOT NULL DEFAULT TRUE,
+    -- Key of the detected subscription this was created from
+    subscription_key VARCHAR(255),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    deleted_at TIMESTAMPTZ,
+
+    CONSTRAINT recurring_transactions_type_check CHECK (transaction_type IN ('income', 'expense')),
+    CONSTRAINT recurring_transactions_amount_check CHECK (amount > 0),
+    CONSTRAINT recurring_transactions_frequency_check CHECK (frequency IN ('weekly', 'monthly', 'yearly')),
+    CONSTRAINT recurring_transactions_interval_check CHECK (interval_count BETWEEN 1 AND 12),
+    CONSTRAINT recurring_transactions_anchor_day_check CHECK (anchor_day BETWEEN 1 AND 31)
+);
+
+CREATE INDEX IF NOT EXISTS idx_recurring_transactions_user_active
+    ON recurring_transactions(user_id) WHERE deleted_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_recurring_transactions_due
+    ON recurring_transactions(next_date) WHERE deleted_at IS NULL;
+
+DROP TRIGGER IF EXISTS update_recurring_transactions_updated_at ON recurring_transactions;
+CREATE TRIGGER update_recurring_transactions_updated_at
+    BEFORE UPDATE ON recurring_transactions
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,58 @@
+-- Bills (listrik, internet, BPJS, credit card statements) with payment reminders
+-- A bill is due on next_due_date and repeats on its schedule; paying it books an expense
+-- and moves next_due_date to the following period. One-off bills end with next_due_date NULL.
+
+CREATE TABLE IF NOT EXISTS bills (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    payee VARCHAR(100) NOT NULL,
+    description VARCHAR(255),
+    -- Either a fixed amount or an estimated range (e.g. electricity)
+    amount FLOAT8,
+    amount_min FLOAT8,
+    amount_max FLOAT8,
+    wallet_id UUID REFERENCES wallets(id) ON DELETE SET NULL,
+    category_id UUID REFERENCES categories(id) ON DELETE SET NULL,
+    frequency VARCHAR(20) NOT NULL, -- once, weekly, monthly, yearly
+    interval_count INTEGER NOT NULL DEFAULT 1,
+    anchor_day SMALLINT NOT NULL,
+    next_due_date DATE,
+    remind_days_before INTEGER NOT NULL DEFAULT 3,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    deleted_at TIMESTAMPTZ,
+
+    CONSTRAINT bills_amount_check CHECK (
+        (amount IS NOT NULL AND amount > 0 AND amount_min IS NULL AND amount_max IS NULL)
+        OR (amount IS NULL AND amount_min > 0 AND amount_max >= amount_min)
+    ),
+    CONSTRAINT bills_frequency_check CHECK (frequency IN ('once', 'weekly', 'monthly', 'yearly')),
+    CONSTRAINT bills_interval_check CHECK (interval_count BETWEEN 1 AND 12),
+    CONSTRAINT bills_anchor_day_check CHECK (anchor_day BETWEEN 1 AND 31),
+    CONSTRAINT bills_remind_days_check CHECK (remind_days_before BETWEEN 0 AND 30)
+);
+
+CREATE INDEX IF NOT EXISTS idx_bills_user_active ON bills(user_id) WHERE deleted_at IS NULL;
+
+CREATE TABLE IF NOT EXISTS bill_payments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    bill_id UUID NOT NULL REFERENCES bills(id) ON DELETE CASCADE,
+    due_date DATE NOT NULL,
+    paid_date DATE NOT NULL,
+    amount FLOAT8 NOT NULL,
+    -- The expense booked for the payment; the user may later delete it
+    transaction_id UUID REFERENCES transactions(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT unique_bill_payment_due_date UNIQUE (bill_id, due_date)
+);
+
+DROP TRIGGER IF EXISTS update_bills_updated_at ON bills;
+CREATE TRIGGER update_bills_updated_at
+    BEFORE UPDATE ON bills
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Secret for the bills calendar feed, which calendar apps fetch without a login
+ALTER TABLE users ADD COLUMN IF NOT EXISTS calendar_token VARCHAR(64);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_calendar_token ON users(calendar_token) WHERE calendar_token IS NOT NULL;
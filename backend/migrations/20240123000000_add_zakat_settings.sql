@@ -0,0 +1,20 @@
+-- Zakat maal settings: the gold price the nisab is valued at and which items count as wealth
+
+CREATE TABLE IF NOT EXISTS zakat_settings (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    gold_price_per_gram FLOAT8 NOT NULL,
+    nisab_grams FLOAT8 NOT NULL DEFAULT 85,
+    include_receivables BOOLEAN NOT NULL DEFAULT TRUE, -- piutang expected to be repaid
+    deduct_liabilities BOOLEAN NOT NULL DEFAULT TRUE,  -- credit wallet debt and hutang
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT zakat_gold_price_check CHECK (gold_price_per_gram > 0),
+    CONSTRAINT zakat_nisab_grams_check CHECK (nisab_grams > 0)
+);
+
+DROP TRIGGER IF EXISTS update_zakat_settings_updated_at ON zakat_settings;
+CREATE TRIGGER update_zakat_settings_updated_at
+    BEFORE UPDATE ON zakat_settings
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,31 @@
+-- Weekly and monthly summary emails. Users without a preferences row get the defaults
+-- (monthly only); the row is created on first save or first delivery.
+
+CREATE TABLE IF NOT EXISTS digest_preferences (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    weekly BOOLEAN NOT NULL DEFAULT FALSE,
+    monthly BOOLEAN NOT NULL DEFAULT TRUE,
+    -- Secret in unsubscribe links, so they work without logging in
+    unsubscribe_token VARCHAR(64) NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+DROP TRIGGER IF EXISTS update_digest_preferences_updated_at ON digest_preferences;
+CREATE TRIGGER update_digest_preferences_updated_at
+    BEFORE UPDATE ON digest_preferences
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- One row per digest sent, so each period is mailed once
+CREATE TABLE IF NOT EXISTS digest_deliveries (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    frequency VARCHAR(10) NOT NULL,
+    period_start DATE NOT NULL,
+    period_end DATE NOT NULL,
+    sent_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT digest_deliveries_frequency_check CHECK (frequency IN ('weekly', 'monthly')),
+    CONSTRAINT digest_deliveries_period_unique UNIQUE (user_id, frequency, period_start)
+);
@@ -0,0 +1,14 @@
+-- Transfers move money between a wallet and something outside the income/expense ledger,
+-- such as a loan handed out or a debt repayment. They change wallet balances but are left
+-- out of income and expense totals, budgets and spending analysis.
+
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS is_transfer BOOLEAN NOT NULL DEFAULT FALSE;
+
+UPDATE transactions SET is_transfer = TRUE
+WHERE id IN (
+    SELECT transaction_id FROM debts WHERE transaction_id IS NOT NULL
+    UNION
+    SELECT transaction_id FROM debt_repayments
+);
+
+COMMENT ON COLUMN transactions.is_transfer IS 'Loan disbursements and repayments; counted in balances, not in income/expense';
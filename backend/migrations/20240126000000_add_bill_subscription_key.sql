@@ -0,0 +1,4 @@
+-- Bills created from a detected subscription remember its key, like recurring transactions
+-- do, so the subscription shows as converted
+
+ALTER TABLE bills ADD COLUMN IF NOT EXISTS subscription_key VARCHAR(255);
@@ -7,19 +7,54 @@ pub struct Config {
     pub jwt_secret: String,
     pub host: String,
     pub port: u16,
+    // Soft-deleted wallets, categories and budgets are purged after this many days (0 = never)
+    pub trash_retention_days: i64,
+    // Base URL clients reach the API at, used for links that leave the app (calendar feeds,
+    // unsubscribe links in emails)
+    pub public_url: String,
+    // "smtp" or "file" (writes .eml files to mail_dir)
+    pub mail_transport: String,
+    pub mail_from: String,
+    pub mail_dir: String,
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub smtp_security: String, // tls, starttls or none
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
 }
 
 impl Config {
     pub fn from_env() -> Result<Self, env::VarError> {
+        let host = env::var("HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
+        let port = env::var("PORT")
+            .unwrap_or_else(|_| "8080".to_string())
+            .parse()
+            .unwrap_or(8080);
+
         Ok(Config {
             database_url: env::var("DATABASE_URL")?,
             redis_url: env::var("REDIS_URL").ok(),
             jwt_secret: env::var("JWT_SECRET")?,
-            host: env::var("HOST").unwrap_or_else(|_| "127.0.0.1".to_string()),
-            port: env::var("PORT")
-                .unwrap_or_else(|_| "8080".to_string())
+            public_url: env::var("PUBLIC_URL")
+                .map(|url| url.trim_end_matches('/').to_string())
+                .unwrap_or_else(|_| format!("http://{}:{}", host, port)),
+            host,
+            port,
+            trash_retention_days: env::var("TRASH_RETENTION_DAYS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .unwrap_or(30),
+            mail_transport: env::var("MAIL_TRANSPORT").unwrap_or_else(|_| "file".to_string()),
+            mail_from: env::var("MAIL_FROM").unwrap_or_else(|_| "FinTrack <noreply@fintrack.local>".to_string()),
+            mail_dir: env::var("MAIL_DIR").unwrap_or_else(|_| "mail".to_string()),
+            smtp_host: env::var("SMTP_HOST").unwrap_or_else(|_| "localhost".to_string()),
+            smtp_port: env::var("SMTP_PORT")
+                .unwrap_or_else(|_| "587".to_string())
                 .parse()
-                .unwrap_or(8080),
+                .unwrap_or(587),
+            smtp_security: env::var("SMTP_SECURITY").unwrap_or_else(|_| "starttls".to_string()),
+            smtp_username: env::var("SMTP_USERNAME").ok(),
+            smtp_password: env::var("SMTP_PASSWORD").ok(),
         })
     }
 }
@@ -1,18 +1,40 @@
 // Database utilities and queries
 // This module contains reusable database functions
 
-use sqlx::PgPool;
+use chrono::{Duration, NaiveDate, Utc};
+use sqlx::types::Json;
+use sqlx::{PgConnection, PgPool};
 use uuid::Uuid;
 
+use crate::models::anomaly::ExpenseRow;
+use crate::models::audit::{AuditAction, AuditContext, AuditLog, Auditable};
 use crate::models::user::User;
-use crate::models::wallet::Wallet;
+use crate::models::zakat::ZakatSettings;
+use crate::models::wallet::{CreditActivity, Wallet, CREDIT_WALLET_TYPES};
 use crate::models::category::Category;
-use crate::models::budget::Budget;
+use crate::models::bill::{Bill, BillPayment};
+use crate::models::budget::{Budget, BudgetWithUsage, DeletedBudget};
+use crate::models::debt::{DebtTotals, DebtWithRepayments};
+use crate::models::digest::{new_unsubscribe_token, DigestPreferences};
+use crate::models::goal::{GoalContribution, GoalMonthlyContribution, GoalWithProgress};
+use crate::models::installment::{round_currency, Installment, InstallmentPlanWithProgress, MonthlyCommitment};
+use crate::models::investment::{AssetPrice, HoldingWithPrice};
+use crate::models::net_worth::NetWorthPoint;
+use crate::models::notification::Notification;
+use crate::models::question::{QuestionFilter, QuestionTotals};
+use crate::models::recurring::RecurringTransaction;
+use crate::models::statement::{
+    category_breakdown, Statement, StatementBudget, StatementSummary, StatementTransaction, StatementWallet,
+    MAX_STATEMENT_TRANSACTIONS,
+};
+use crate::models::report::{CashFlowBucket, CategoryStat};
+use crate::models::transaction::{NewTransaction, Transaction, TransactionQuery, TransactionWithCategory};
+use crate::utils::dates::{day_of_month, parse_timezone, today_in, DEFAULT_TIMEZONE};
 
 // User queries
 pub async fn find_user_by_email(pool: &PgPool, email: &str) -> Result<Option<User>, sqlx::Error> {
     sqlx::query_as::<_, User>(
-        r#"SELECT id, email, username, name, password_hash, created_at, updated_at FROM users WHERE email = $1"#
+        r#"SELECT id, email, username, name, password_hash, timezone, anomaly_alerts, created_at, updated_at FROM users WHERE email = $1"#
     )
     .bind(email)
     .fetch_optional(pool)
@@ -21,7 +43,7 @@ pub async fn find_user_by_email(pool: &PgPool, email: &str) -> Result<Option<Use
 
 pub async fn find_user_by_username(pool: &PgPool, username: &str) -> Result<Option<User>, sqlx::Error> {
     sqlx::query_as::<_, User>(
-        r#"SELECT id, email, username, name, password_hash, created_at, updated_at FROM users WHERE username = $1"#
+        r#"SELECT id, email, username, name, password_hash, timezone, anomaly_alerts, created_at, updated_at FROM users WHERE username = $1"#
     )
     .bind(username)
     .fetch_optional(pool)
@@ -30,7 +52,7 @@ pub async fn find_user_by_username(pool: &PgPool, username: &str) -> Result<Opti
 
 pub async fn find_user_by_username_or_email(pool: &PgPool, username_or_email: &str) -> Result<Option<User>, sqlx::Error> {
     sqlx::query_as::<_, User>(
-        r#"SELECT id, email, username, name, password_hash, created_at, updated_at FROM users WHERE username = $1 OR email = $1"#
+        r#"SELECT id, email, username, name, password_hash, timezone, anomaly_alerts, created_at, updated_at FROM users WHERE username = $1 OR email = $1"#
     )
     .bind(username_or_email)
     .fetch_optional(pool)
@@ -39,17 +61,32 @@ pub async fn find_user_by_username_or_email(pool: &PgPool, username_or_email: &s
 
 pub async fn find_user_by_id(pool: &PgPool, id: Uuid) -> Result<Option<User>, sqlx::Error> {
     sqlx::query_as::<_, User>(
-        r#"SELECT id, email, username, name, password_hash, created_at, updated_at FROM users WHERE id = $1"#
+        r#"SELECT id, email, username, name, password_hash, timezone, anomaly_alerts, created_at, updated_at FROM users WHERE id = $1"#
     )
     .bind(id)
     .fetch_optional(pool)
     .await
 }
 
+// The current calendar day in the user's time zone
+pub async fn get_user_today(pool: &PgPool, user_id: Uuid) -> Result<NaiveDate, sqlx::Error> {
+    let timezone: Option<String> = sqlx::query_scalar(r#"SELECT timezone FROM users WHERE id = $1"#)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+
+    let timezone = timezone
+        .as_deref()
+        .and_then(parse_timezone)
+        .or_else(|| parse_timezone(DEFAULT_TIMEZONE))
+        .unwrap();
+    Ok(today_in(timezone))
+}
+
 // Wallet queries
 pub async fn get_user_wallets(pool: &PgPool, user_id: Uuid) -> Result<Vec<Wallet>, sqlx::Error> {
     sqlx::query_as::<_, Wallet>(
-        r#"SELECT id, user_id, name, wallet_type, balance, icon, color, credit_limit, is_default, created_at, updated_at, deleted_at 
+        r#"SELECT id, user_id, name, wallet_type, balance, icon, color, credit_limit, statement_closing_day, payment_due_day, is_default, created_at, updated_at, deleted_at 
            FROM wallets WHERE user_id = $1 AND deleted_at IS NULL ORDER BY is_default DESC, created_at DESC"#
     )
     .bind(user_id)
@@ -59,7 +96,7 @@ pub async fn get_user_wallets(pool: &PgPool, user_id: Uuid) -> Result<Vec<Wallet
 
 pub async fn get_wallet_by_id(pool: &PgPool, id: Uuid, user_id: Uuid) -> Result<Option<Wallet>, sqlx::Error> {
     sqlx::query_as::<_, Wallet>(
-        r#"SELECT id, user_id, name, wallet_type, balance, icon, color, credit_limit, is_default, created_at, updated_at, deleted_at 
+        r#"SELECT id, user_id, name, wallet_type, balance, icon, color, credit_limit, statement_closing_day, payment_due_day, is_default, created_at, updated_at, deleted_at 
            FROM wallets WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL"#
     )
     .bind(id)
@@ -68,10 +105,151 @@ pub async fn get_wallet_by_id(pool: &PgPool, id: Uuid, user_id: Uuid) -> Result<
     .await
 }
 
+// Charges and payments per credit wallet on transactions dated after that wallet's cutoff
+// date, as one query for any number of wallets. Wallets without activity get zeros.
+pub async fn get_credit_activity_since(
+    pool: &PgPool,
+    user_id: Uuid,
+    cutoffs: &[(Uuid, NaiveDate)],
+) -> Result<Vec<CreditActivity>, sqlx::Error> {
+    let wallet_ids: Vec<Uuid> = cutoffs.iter().map(|(id, _)| *id).collect();
+    let dates: Vec<NaiveDate> = cutoffs.iter().map(|(_, date)| *date).collect();
+
+    sqlx::query_as::<_, CreditActivity>(
+        r#"
+        SELECT c.wallet_id,
+               COALESCE(SUM(t.amount) FILTER (WHERE t.transaction_type <> 'income'), 0)::float8 AS charges,
+               COALESCE(SUM(t.amount) FILTER (WHERE t.transaction_type = 'income'), 0)::float8 AS payments
+        FROM UNNEST($2::uuid[], $3::date[]) AS c(wallet_id, cutoff)
+        LEFT JOIN transactions t
+            ON t.wallet_id = c.wallet_id AND t.user_id = $1 AND t.deleted_at IS NULL AND t.date > c.cutoff
+        GROUP BY c.wallet_id
+        "#
+    )
+    .bind(user_id)
+    .bind(&wallet_ids)
+    .bind(&dates)
+    .fetch_all(pool)
+    .await
+}
+
+// Transaction queries
+// Category names are resolved with a LEFT JOIN so listing stays a single query
+pub async fn get_user_transactions(
+    pool: &PgPool,
+    user_id: Uuid,
+    query: &TransactionQuery,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<TransactionWithCategory>, sqlx::Error> {
+    sqlx::query_as::<_, TransactionWithCategory>(
+        r#"
+        SELECT t.id, t.user_id, t.wallet_id, t.category_id, t.transaction_type, t.amount, t.description, t.date, t.is_transfer, t.created_at, t.updated_at, t.deleted_at,
+               c.name AS category_name
+        FROM transactions t
+        LEFT JOIN categories c ON c.id = t.category_id AND c.deleted_at IS NULL
+        WHERE t.user_id = $1
+            AND t.deleted_at IS NULL
+            AND ($2::uuid IS NULL OR t.wallet_id = $2)
+            AND ($3::uuid IS NULL OR t.category_id = $3)
+            AND ($4::text IS NULL OR t.transaction_type = $4)
+            AND ($5::date IS NULL OR t.date >= $5)
+            AND ($6::date IS NULL OR t.date <= $6)
+        ORDER BY t.date DESC, t.created_at DESC
+        LIMIT $7 OFFSET $8
+        "#
+    )
+    .bind(user_id)
+    .bind(query.wallet_id)
+    .bind(query.category_id)
+    .bind(&query.transaction_type)
+    .bind(query.start_date)
+    .bind(query.end_date)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn get_transaction_by_id(pool: &PgPool, id: Uuid, user_id: Uuid) -> Result<Option<TransactionWithCategory>, sqlx::Error> {
+    sqlx::query_as::<_, TransactionWithCategory>(
+        r#"
+        SELECT t.id, t.user_id, t.wallet_id, t.category_id, t.transaction_type, t.amount, t.description, t.date, t.is_transfer, t.created_at, t.updated_at, t.deleted_at,
+               c.name AS category_name
+        FROM transactions t
+        LEFT JOIN categories c ON c.id = t.category_id AND c.deleted_at IS NULL
+        WHERE t.id = $1 AND t.user_id = $2 AND t.deleted_at IS NULL
+        "#
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn get_deleted_transactions(pool: &PgPool, user_id: Uuid) -> Result<Vec<TransactionWithCategory>, sqlx::Error> {
+    sqlx::query_as::<_, TransactionWithCategory>(
+        r#"
+        SELECT t.id, t.user_id, t.wallet_id, t.category_id, t.transaction_type, t.amount, t.description, t.date, t.is_transfer, t.created_at, t.updated_at, t.deleted_at,
+               c.name AS category_name
+        FROM transactions t
+        LEFT JOIN categories c ON c.id = t.category_id AND c.deleted_at IS NULL
+        WHERE t.user_id = $1 AND t.deleted_at IS NOT NULL
+        ORDER BY t.deleted_at DESC
+        "#
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+// Books a transaction and moves its wallet's balance with it (up for income, down otherwise),
+// with an audit entry, the same way create_transaction does. Run it in the caller's transaction
+// together with the record the transaction belongs to.
+pub async fn record_wallet_transaction(
+    conn: &mut PgConnection,
+    audit: &AuditContext,
+    new: &NewTransaction<'_>,
+) -> Result<Transaction, sqlx::Error> {
+    let transaction = sqlx::query_as::<_, Transaction>(
+        r#"
+        INSERT INTO transactions (id, user_id, wallet_id, category_id, transaction_type, amount, description, date, is_transfer)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        RETURNING id, user_id, wallet_id, category_id, transaction_type, amount, description, date, is_transfer, created_at, updated_at, deleted_at
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(audit.user_id)
+    .bind(new.wallet_id)
+    .bind(new.category_id)
+    .bind(new.transaction_type)
+    .bind(new.amount)
+    .bind(new.description)
+    .bind(new.date)
+    .bind(new.is_transfer)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let balance_change = if new.transaction_type == "income" { new.amount } else { -new.amount };
+
+    sqlx::query(
+        r#"UPDATE wallets SET balance = balance + $1, updated_at = NOW() WHERE id = $2 AND user_id = $3"#
+    )
+    .bind(balance_change)
+    .bind(new.wallet_id)
+    .bind(audit.user_id)
+    .execute(&mut *conn)
+    .await?;
+
+    insert_audit_log(&mut *conn, audit, AuditAction::Create, None, &transaction).await?;
+
+    Ok(transaction)
+}
+
 // Category queries
 pub async fn get_user_categories(pool: &PgPool, user_id: Uuid) -> Result<Vec<Category>, sqlx::Error> {
     sqlx::query_as::<_, Category>(
-        r#"SELECT id, user_id, name, icon, color, category_type, created_at, deleted_at FROM categories 
+        r#"SELECT id, user_id, name, icon, color, category_type, parent_id, created_at, deleted_at FROM categories 
            WHERE (user_id = $1 OR user_id IS NULL) AND deleted_at IS NULL ORDER BY name"#
     )
     .bind(user_id)
@@ -79,6 +257,26 @@ pub async fn get_user_categories(pool: &PgPool, user_id: Uuid) -> Result<Vec<Cat
     .await
 }
 
+// Ids of a category and all of its sub-categories visible to the user
+pub async fn get_category_subtree_ids(pool: &PgPool, user_id: Uuid, category_id: Uuid) -> Result<Vec<Uuid>, sqlx::Error> {
+    sqlx::query_scalar::<_, Uuid>(
+        r#"
+        WITH RECURSIVE subtree AS (
+            SELECT id FROM categories WHERE id = $1
+            UNION
+            SELECT c.id FROM categories c
+            JOIN subtree s ON c.parent_id = s.id
+            WHERE c.user_id = $2 OR c.user_id IS NULL
+        )
+        SELECT id FROM subtree
+        "#
+    )
+    .bind(category_id)
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
 // Budget queries
 pub async fn get_user_budgets(
     pool: &PgPool,
@@ -88,7 +286,7 @@ pub async fn get_user_budgets(
 ) -> Result<Vec<Budget>, sqlx::Error> {
     let query = if month.is_some() && year.is_some() {
         sqlx::query_as::<_, Budget>(
-            r#"SELECT id, user_id, category_id, amount, month, year, is_active, alert_threshold, created_at, updated_at, deleted_at
+            r#"SELECT id, user_id, category_id, amount, month, year, is_active, alert_threshold, wallet_ids, created_at, updated_at, deleted_at
                FROM budgets WHERE user_id = $1 AND month = $2 AND year = $3 AND deleted_at IS NULL ORDER BY category_id NULLS LAST"#
         )
         .bind(user_id)
@@ -98,7 +296,7 @@ pub async fn get_user_budgets(
         .await
     } else {
         sqlx::query_as::<_, Budget>(
-            r#"SELECT id, user_id, category_id, amount, month, year, is_active, alert_threshold, created_at, updated_at, deleted_at
+            r#"SELECT id, user_id, category_id, amount, month, year, is_active, alert_threshold, wallet_ids, created_at, updated_at, deleted_at
                FROM budgets WHERE user_id = $1 AND deleted_at IS NULL ORDER BY year DESC, month DESC, category_id NULLS LAST"#
         )
         .bind(user_id)
@@ -110,7 +308,7 @@ pub async fn get_user_budgets(
 
 pub async fn get_budget_by_id(pool: &PgPool, id: Uuid, user_id: Uuid) -> Result<Option<Budget>, sqlx::Error> {
     sqlx::query_as::<_, Budget>(
-        r#"SELECT id, user_id, category_id, amount, month, year, is_active, alert_threshold, created_at, updated_at, deleted_at
+        r#"SELECT id, user_id, category_id, amount, month, year, is_active, alert_threshold, wallet_ids, created_at, updated_at, deleted_at
            FROM budgets WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL"#
     )
     .bind(id)
@@ -118,3 +316,1472 @@ pub async fn get_budget_by_id(pool: &PgPool, id: Uuid, user_id: Uuid) -> Result<
     .fetch_optional(pool)
     .await
 }
+
+// Budget usage queries
+// Expenses are summed once per (category, wallet, month) for every period the selected
+// budgets cover, then matched back to each budget. A category budget takes its own
+// category's total including its sub-categories; a total budget (category_id NULL) takes
+// the sum over all categories. Wallet-scoped budgets only count expenses from their
+// wallet_ids. The number of queries stays at one regardless of how many budgets are returned.
+const BUDGET_USAGE_QUERY: &str = r#"
+    WITH RECURSIVE scoped AS (
+        SELECT id, user_id, category_id, amount, month, year, is_active, alert_threshold, wallet_ids, created_at, updated_at, deleted_at
+        FROM budgets
+        WHERE user_id = $1
+            AND deleted_at IS NULL
+            AND ($2::uuid IS NULL OR id = $2)
+            AND ($3::int IS NULL OR month = $3)
+            AND ($4::int IS NULL OR year = $4)
+            AND ($5::date IS NULL OR make_date(year, month, 1) >= $5)
+            AND ($6::date IS NULL OR make_date(year, month, 1) <= $6)
+    ),
+    category_tree AS (
+        SELECT DISTINCT category_id AS ancestor_id, category_id
+        FROM scoped
+        WHERE category_id IS NOT NULL
+        UNION
+        SELECT ct.ancestor_id, c.id
+        FROM categories c
+        JOIN category_tree ct ON c.parent_id = ct.category_id
+        WHERE c.user_id = $1 OR c.user_id IS NULL
+    ),
+    spending AS (
+        SELECT t.category_id,
+               t.wallet_id,
+               EXTRACT(YEAR FROM t.date)::int AS year,
+               EXTRACT(MONTH FROM t.date)::int AS month,
+               SUM(t.amount)::float8 AS total
+        FROM transactions t
+        WHERE t.user_id = $1
+            AND t.deleted_at IS NULL
+            AND NOT t.is_transfer
+            AND t.transaction_type = 'expense'
+            AND t.date >= (SELECT MIN(make_date(year, month, 1)) FROM scoped)
+            AND t.date < (SELECT MAX(make_date(year, month, 1)) FROM scoped) + INTERVAL '1 month'
+        GROUP BY t.category_id, t.wallet_id, EXTRACT(YEAR FROM t.date), EXTRACT(MONTH FROM t.date)
+    ),
+    -- Installments not posted yet; they become spending once their due date arrives
+    committed AS (
+        SELECT p.category_id,
+               p.wallet_id,
+               EXTRACT(YEAR FROM i.due_date)::int AS year,
+               EXTRACT(MONTH FROM i.due_date)::int AS month,
+               SUM(i.amount)::float8 AS total
+        FROM installments i
+        JOIN installment_plans p ON p.id = i.plan_id
+        JOIN wallets w ON w.id = p.wallet_id
+        WHERE p.user_id = $1
+            AND p.deleted_at IS NULL
+            AND w.deleted_at IS NULL
+            AND i.posted_at IS NULL
+        GROUP BY p.category_id, p.wallet_id, EXTRACT(YEAR FROM i.due_date), EXTRACT(MONTH FROM i.due_date)
+    )
+    SELECT b.id, b.user_id, b.category_id, b.amount, b.month, b.year, b.is_active, b.alert_threshold, b.wallet_ids,
+           b.created_at, b.updated_at, b.deleted_at,
+           c.name AS category_name,
+           COALESCE(SUM(s.total), 0)::float8 AS used_amount,
+           COALESCE((
+               SELECT SUM(ci.total)
+               FROM committed ci
+               WHERE ci.year = b.year
+                   AND ci.month = b.month
+                   AND (b.category_id IS NULL OR ci.category_id IN (
+                       SELECT ct.category_id FROM category_tree ct WHERE ct.ancestor_id = b.category_id
+                   ))
+                   AND (b.wallet_ids IS NULL OR ci.wallet_id = ANY(b.wallet_ids))
+           ), 0)::float8 AS committed_amount
+    FROM scoped b
+    LEFT JOIN categories c ON c.id = b.category_id AND c.deleted_at IS NULL
+    LEFT JOIN spending s ON s.year = b.year
+        AND s.month = b.month
+        AND (b.category_id IS NULL OR s.category_id IN (
+            SELECT ct.category_id FROM category_tree ct WHERE ct.ancestor_id = b.category_id
+        ))
+        AND (b.wallet_ids IS NULL OR s.wallet_id = ANY(b.wallet_ids))
+    GROUP BY b.id, b.user_id, b.category_id, b.amount, b.month, b.year, b.is_active, b.alert_threshold, b.wallet_ids,
+             b.created_at, b.updated_at, b.deleted_at, c.name
+"#;
+
+pub async fn get_user_budgets_with_usage(
+    pool: &PgPool,
+    user_id: Uuid,
+    month: Option<i32>,
+    year: Option<i32>,
+) -> Result<Vec<BudgetWithUsage>, sqlx::Error> {
+    // Same ordering as get_user_budgets
+    let order_by = if month.is_some() && year.is_some() {
+        "ORDER BY b.category_id NULLS LAST"
+    } else {
+        "ORDER BY b.year DESC, b.month DESC, b.category_id NULLS LAST"
+    };
+    let sql = format!("{} {}", BUDGET_USAGE_QUERY, order_by);
+
+    sqlx::query_as::<_, BudgetWithUsage>(&sql)
+        .bind(user_id)
+        .bind(None::<Uuid>)
+        .bind(month.filter(|_| year.is_some()))
+        .bind(year.filter(|_| month.is_some()))
+        .bind(None::<NaiveDate>)
+        .bind(None::<NaiveDate>)
+        .fetch_all(pool)
+        .await
+}
+
+pub async fn get_budget_with_usage(pool: &PgPool, id: Uuid, user_id: Uuid) -> Result<Option<BudgetWithUsage>, sqlx::Error> {
+    sqlx::query_as::<_, BudgetWithUsage>(BUDGET_USAGE_QUERY)
+        .bind(user_id)
+        .bind(id)
+        .bind(None::<i32>)
+        .bind(None::<i32>)
+        .bind(None::<NaiveDate>)
+        .bind(None::<NaiveDate>)
+        .fetch_optional(pool)
+        .await
+}
+
+// Budgets whose period starts between `from` and `to` (both first-of-month dates), oldest first
+pub async fn get_user_budgets_with_usage_in_range(
+    pool: &PgPool,
+    user_id: Uuid,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<BudgetWithUsage>, sqlx::Error> {
+    let sql = format!("{} ORDER BY b.year, b.month, b.category_id NULLS FIRST", BUDGET_USAGE_QUERY);
+
+    sqlx::query_as::<_, BudgetWithUsage>(&sql)
+        .bind(user_id)
+        .bind(None::<Uuid>)
+        .bind(None::<i32>)
+        .bind(None::<i32>)
+        .bind(from)
+        .bind(to)
+        .fetch_all(pool)
+        .await
+}
+
+// Monthly expense totals as (year, month, total) for one category and its sub-categories,
+// or all categories when category_id is None. Months without expenses are not returned.
+pub async fn get_monthly_expenses(
+    pool: &PgPool,
+    user_id: Uuid,
+    category_id: Option<Uuid>,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<(i32, i32, f64)>, sqlx::Error> {
+    sqlx::query_as::<_, (i32, i32, f64)>(
+        r#"
+        WITH RECURSIVE subtree AS (
+            SELECT id FROM categories WHERE id = $2
+            UNION
+            SELECT c.id FROM categories c
+            JOIN subtree s ON c.parent_id = s.id
+            WHERE c.user_id = $1 OR c.user_id IS NULL
+        )
+        SELECT EXTRACT(YEAR FROM date)::int AS year,
+               EXTRACT(MONTH FROM date)::int AS month,
+               COALESCE(SUM(amount), 0)::float8 AS total
+        FROM transactions
+        WHERE user_id = $1
+            AND deleted_at IS NULL
+            AND NOT is_transfer
+            AND transaction_type = 'expense'
+            AND ($2::uuid IS NULL OR category_id IN (SELECT id FROM subtree))
+            AND date >= $3
+            AND date < $4::date + INTERVAL '1 month'
+        GROUP BY EXTRACT(YEAR FROM date), EXTRACT(MONTH FROM date)
+        ORDER BY year, month
+        "#
+    )
+    .bind(user_id)
+    .bind(category_id)
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await
+}
+
+// Debt queries
+// Repayments count only while their transaction is not deleted, so deleting a repayment
+// transaction reopens the debt. $2 narrows the result to a single debt when not NULL.
+const DEBT_WITH_REPAYMENTS_QUERY: &str = r#"
+    SELECT d.id, d.user_id, d.counterparty, d.direction, d.principal, d.description, d.date, d.due_date,
+           d.transaction_id, d.created_at, d.updated_at, d.deleted_at,
+           COALESCE(SUM(t.amount), 0)::float8 AS repaid_amount,
+           COUNT(t.id) AS repayment_count
+    FROM debts d
+    LEFT JOIN debt_repayments r ON r.debt_id = d.id
+    LEFT JOIN transactions t ON t.id = r.transaction_id AND t.deleted_at IS NULL
+    WHERE d.user_id = $1 AND ($2::uuid IS NULL OR d.id = $2) AND d.deleted_at IS NULL
+    GROUP BY d.id
+"#;
+
+pub async fn get_user_debts(pool: &PgPool, user_id: Uuid) -> Result<Vec<DebtWithRepayments>, sqlx::Error> {
+    let query = format!("{} ORDER BY d.due_date NULLS LAST, d.date DESC", DEBT_WITH_REPAYMENTS_QUERY);
+
+    sqlx::query_as::<_, DebtWithRepayments>(&query)
+        .bind(user_id)
+        .bind(None::<Uuid>)
+        .fetch_all(pool)
+        .await
+}
+
+// Takes any executor so a handler holding the debt's row lock can read it in the same transaction
+pub async fn get_debt_by_id<'e, E>(executor: E, id: Uuid, user_id: Uuid) -> Result<Option<DebtWithRepayments>, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+{
+    sqlx::query_as::<_, DebtWithRepayments>(DEBT_WITH_REPAYMENTS_QUERY)
+        .bind(user_id)
+        .bind(id)
+        .fetch_optional(executor)
+        .await
+}
+
+// Repayment transactions of a debt that are not deleted, oldest first
+pub async fn get_debt_repayments(pool: &PgPool, debt_id: Uuid, user_id: Uuid) -> Result<Vec<Transaction>, sqlx::Error> {
+    sqlx::query_as::<_, Transaction>(
+        r#"SELECT t.id, t.user_id, t.wallet_id, t.category_id, t.transaction_type, t.amount, t.description, t.date, t.is_transfer,
+                  t.created_at, t.updated_at, t.deleted_at
+           FROM debt_repayments r
+           JOIN transactions t ON t.id = r.transaction_id
+           WHERE r.debt_id = $1 AND t.user_id = $2 AND t.deleted_at IS NULL
+           ORDER BY t.date, t.created_at"#
+    )
+    .bind(debt_id)
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+// Outstanding amounts of all open debts, split into receivables and payables
+pub async fn get_debt_totals(pool: &PgPool, user_id: Uuid) -> Result<DebtTotals, sqlx::Error> {
+    let query = format!(
+        r#"
+        SELECT COALESCE(SUM(GREATEST(principal - repaid_amount, 0)) FILTER (WHERE direction = 'lent'), 0)::float8 AS receivable,
+               COALESCE(SUM(GREATEST(principal - repaid_amount, 0)) FILTER (WHERE direction = 'borrowed'), 0)::float8 AS payable
+        FROM ({}) debts_with_repayments
+        "#,
+        DEBT_WITH_REPAYMENTS_QUERY
+    );
+
+    sqlx::query_as::<_, DebtTotals>(&query)
+        .bind(user_id)
+        .bind(None::<Uuid>)
+        .fetch_one(pool)
+        .await
+}
+
+// Outstanding amount of open payables due on or before `until`, overdue ones included.
+// Payables without a due date are left out since nothing says when they must be paid.
+pub async fn get_payables_due(pool: &PgPool, user_id: Uuid, until: NaiveDate) -> Result<f64, sqlx::Error> {
+    let query = format!(
+        r#"
+        SELECT COALESCE(SUM(GREATEST(principal - repaid_amount, 0)), 0)::float8
+        FROM ({}) debts_with_repayments
+        WHERE direction = 'borrowed' AND due_date <= $3
+        "#,
+        DEBT_WITH_REPAYMENTS_QUERY
+    );
+
+    sqlx::query_scalar::<_, f64>(&query)
+        .bind(user_id)
+        .bind(None::<Uuid>)
+        .bind(until)
+        .fetch_one(pool)
+        .await
+}
+
+// Installment queries
+// $2 narrows the result to a single plan when not NULL
+const INSTALLMENT_PLAN_PROGRESS_QUERY: &str = r#"
+    SELECT p.id, p.user_id, p.wallet_id, p.category_id, p.description, p.principal, p.interest_rate, p.tenor_months,
+           p.purchase_date, p.created_at, p.updated_at, p.deleted_at,
+           COALESCE(MAX(i.amount) FILTER (WHERE i.installment_number = 1), 0)::float8 AS monthly_amount,
+           COALESCE(SUM(i.amount), 0)::float8 AS total_amount,
+           COUNT(i.posted_at) AS paid_count,
+           COALESCE(SUM(i.amount) FILTER (WHERE i.posted_at IS NOT NULL), 0)::float8 AS paid_amount,
+           MIN(i.due_date) FILTER (WHERE i.posted_at IS NULL) AS next_due_date
+    FROM installment_plans p
+    LEFT JOIN installments i ON i.plan_id = p.id
+    WHERE p.user_id = $1 AND ($2::uuid IS NULL OR p.id = $2) AND p.deleted_at IS NULL
+    GROUP BY p.id
+"#;
+
+pub async fn get_user_installment_plans(pool: &PgPool, user_id: Uuid) -> Result<Vec<InstallmentPlanWithProgress>, sqlx::Error> {
+    let query = format!("{} ORDER BY p.purchase_date DESC, p.created_at DESC", INSTALLMENT_PLAN_PROGRESS_QUERY);
+
+    sqlx::query_as::<_, InstallmentPlanWithProgress>(&query)
+        .bind(user_id)
+        .bind(None::<Uuid>)
+        .fetch_all(pool)
+        .await
+}
+
+pub async fn get_installment_plan_by_id(pool: &PgPool, id: Uuid, user_id: Uuid) -> Result<Option<InstallmentPlanWithProgress>, sqlx::Error> {
+    sqlx::query_as::<_, InstallmentPlanWithProgress>(INSTALLMENT_PLAN_PROGRESS_QUERY)
+        .bind(user_id)
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+pub async fn get_installment_schedule(pool: &PgPool, plan_id: Uuid) -> Result<Vec<Installment>, sqlx::Error> {
+    sqlx::query_as::<_, Installment>(
+        r#"SELECT id, installment_number, due_date, amount, posted_at, transaction_id
+           FROM installments WHERE plan_id = $1 ORDER BY installment_number"#
+    )
+    .bind(plan_id)
+    .fetch_all(pool)
+    .await
+}
+
+// Installments of active plans on active wallets that are still to be posted, per month
+// from `from` (inclusive) to `to` (exclusive). Months without installments are not returned.
+pub async fn get_installment_commitments(
+    pool: &PgPool,
+    user_id: Uuid,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<MonthlyCommitment>, sqlx::Error> {
+    sqlx::query_as::<_, MonthlyCommitment>(
+        r#"
+        SELECT EXTRACT(MONTH FROM i.due_date)::int AS month,
+               EXTRACT(YEAR FROM i.due_date)::int AS year,
+               SUM(i.amount)::float8 AS amount,
+               COUNT(*) AS installment_count
+        FROM installments i
+        JOIN installment_plans p ON p.id = i.plan_id
+        JOIN wallets w ON w.id = p.wallet_id
+        WHERE p.user_id = $1
+            AND p.deleted_at IS NULL
+            AND w.deleted_at IS NULL
+            AND i.posted_at IS NULL
+            AND i.due_date >= $2
+            AND i.due_date < $3
+        GROUP BY 1, 2
+        ORDER BY 2, 1
+        "#
+    )
+    .bind(user_id)
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await
+}
+
+// Goal queries
+// A linked goal follows its wallet's balance; a goal whose wallet was deleted shows nothing
+// saved until it is linked again or switched to manual contributions.
+// $2 narrows the result to a single goal when not NULL.
+const GOAL_PROGRESS_QUERY: &str = r#"
+    SELECT g.id, g.user_id, g.name, g.target_amount, g.target_date, g.wallet_id, g.icon, g.color,
+           g.created_at, g.updated_at, g.deleted_at,
+           w.name AS wallet_name,
+           CASE
+               WHEN g.wallet_id IS NULL THEN COALESCE(
+                   (SELECT SUM(gc.amount) FROM goal_contributions gc WHERE gc.goal_id = g.id), 0
+               )
+               ELSE COALESCE(w.balance, 0)
+           END::float8 AS saved_amount
+    FROM goals g
+    LEFT JOIN wallets w ON w.id = g.wallet_id AND w.deleted_at IS NULL
+    WHERE g.user_id = $1 AND ($2::uuid IS NULL OR g.id = $2) AND g.deleted_at IS NULL
+"#;
+
+pub async fn get_user_goals(pool: &PgPool, user_id: Uuid) -> Result<Vec<GoalWithProgress>, sqlx::Error> {
+    let query = format!("{} ORDER BY g.target_date NULLS LAST, g.created_at", GOAL_PROGRESS_QUERY);
+
+    sqlx::query_as::<_, GoalWithProgress>(&query)
+        .bind(user_id)
+        .bind(None::<Uuid>)
+        .fetch_all(pool)
+        .await
+}
+
+pub async fn get_goal_by_id(pool: &PgPool, id: Uuid, user_id: Uuid) -> Result<Option<GoalWithProgress>, sqlx::Error> {
+    sqlx::query_as::<_, GoalWithProgress>(GOAL_PROGRESS_QUERY)
+        .bind(user_id)
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+// Monthly net amount added to each goal since `from`: manual contributions, or income minus
+// expenses on the linked wallet. $2 narrows the result to a single goal when not NULL.
+pub async fn get_goal_monthly_contributions(
+    pool: &PgPool,
+    user_id: Uuid,
+    goal_id: Option<Uuid>,
+    from: NaiveDate,
+) -> Result<Vec<GoalMonthlyContribution>, sqlx::Error> {
+    sqlx::query_as::<_, GoalMonthlyContribution>(
+        r#"
+        WITH scoped AS (
+            SELECT id, wallet_id FROM goals
+            WHERE user_id = $1 AND ($2::uuid IS NULL OR id = $2) AND deleted_at IS NULL
+        ),
+        flows AS (
+            SELECT gc.goal_id, gc.date, gc.amount
+            FROM goal_contributions gc
+            JOIN scoped g ON g.id = gc.goal_id
+            WHERE g.wallet_id IS NULL AND gc.date >= $3
+            UNION ALL
+            SELECT g.id, t.date,
+                   CASE WHEN t.transaction_type = 'income' THEN t.amount ELSE -t.amount END
+            FROM transactions t
+            JOIN scoped g ON g.wallet_id = t.wallet_id
+            WHERE t.user_id = $1 AND t.deleted_at IS NULL AND t.date >= $3
+        )
+        SELECT goal_id,
+               EXTRACT(YEAR FROM date)::int AS year,
+               EXTRACT(MONTH FROM date)::int AS month,
+               SUM(amount)::float8 AS amount
+        FROM flows
+        GROUP BY goal_id, EXTRACT(YEAR FROM date), EXTRACT(MONTH FROM date)
+        ORDER BY 2, 3
+        "#
+    )
+    .bind(user_id)
+    .bind(goal_id)
+    .bind(from)
+    .fetch_all(pool)
+    .await
+}
+
+// Contributions of a manual goal, newest first
+pub async fn get_goal_contributions(pool: &PgPool, goal_id: Uuid) -> Result<Vec<GoalContribution>, sqlx::Error> {
+    sqlx::query_as::<_, GoalContribution>(
+        r#"SELECT id, goal_id, amount, date, note, created_at
+           FROM goal_contributions WHERE goal_id = $1 ORDER BY date DESC, created_at DESC"#
+    )
+    .bind(goal_id)
+    .fetch_all(pool)
+    .await
+}
+
+// Balance snapshot queries
+// The current calendar day of user `u` in their own time zone, for queries that cover many users
+pub const USER_TODAY_SQL: &str = "(NOW() AT TIME ZONE u.timezone)::date";
+
+// Market value of a wallet's holdings at the end of a day: quantity times the latest price
+// recorded on or before it, or the cost basis when there is none. Only holdings that existed
+// on that day count.
+fn holdings_value_sql(wallet_id: &str, date: &str) -> String {
+    format!(
+        r#"COALESCE((
+            SELECT SUM(COALESCE(h.quantity * (
+                SELECT ap.price FROM asset_prices ap
+                WHERE ap.user_id = h.user_id AND ap.asset_type = h.asset_type AND ap.symbol = h.symbol
+                    AND ap.price_date <= {date}
+                ORDER BY ap.price_date DESC
+                LIMIT 1
+            ), h.cost_basis))
+            FROM investment_holdings h
+            WHERE h.wallet_id = {wallet_id}
+                AND h.created_at::date <= {date}
+                AND (h.deleted_at IS NULL OR h.deleted_at::date > {date})
+        ), 0)::float8"#
+    )
+}
+
+// Records the current balance of every active wallet as the balance of `date` (by default the
+// owner's current day in their time zone), for one user or everyone. Running it again on the
+// same day overwrites that day's row.
+pub async fn snapshot_wallet_balances<'e, E>(
+    executor: E,
+    user_id: Option<Uuid>,
+    date: Option<NaiveDate>,
+) -> Result<u64, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+{
+    let query = format!(
+        r#"
+        INSERT INTO wallet_balance_snapshots (wallet_id, user_id, date, balance, holdings_value)
+        SELECT w.id, w.user_id, COALESCE($2, {today}), w.balance, {holdings}
+        FROM wallets w
+        JOIN users u ON u.id = w.user_id
+        WHERE w.deleted_at IS NULL AND ($1::uuid IS NULL OR w.user_id = $1)
+        ON CONFLICT (wallet_id, date) DO UPDATE
+            SET balance = EXCLUDED.balance, holdings_value = EXCLUDED.holdings_value, created_at = NOW()
+        "#,
+        today = USER_TODAY_SQL,
+        holdings = holdings_value_sql("w.id", &format!("COALESCE($2::date, {})", USER_TODAY_SQL))
+    );
+
+    let result = sqlx::query(&query)
+        .bind(user_id)
+        .bind(date)
+        .execute(executor)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+// Fills missing days from `from` to `to` with balances rebuilt from the ledger: the current
+// balance minus every transaction dated after that day. Holdings are not versioned, so past
+// days value the holdings that existed then at their current quantity. Existing snapshots are
+// left alone.
+pub async fn backfill_wallet_balances(
+    pool: &PgPool,
+    user_id: Uuid,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<u64, sqlx::Error> {
+    let query = format!(
+        r#"
+        INSERT INTO wallet_balance_snapshots (wallet_id, user_id, date, balance, holdings_value)
+        SELECT w.id, w.user_id, d.date::date,
+               w.balance - COALESCE((
+                   SELECT SUM(CASE WHEN t.transaction_type = 'income' THEN t.amount ELSE -t.amount END)
+                   FROM transactions t
+                   WHERE t.wallet_id = w.id AND t.deleted_at IS NULL AND t.date > d.date::date
+               ), 0),
+               {}
+        FROM wallets w
+        CROSS JOIN LATERAL generate_series(GREATEST($2, w.created_at::date), $3::date, INTERVAL '1 day') AS d(date)
+        WHERE w.user_id = $1 AND w.deleted_at IS NULL
+        ON CONFLICT (wallet_id, date) DO NOTHING
+        "#,
+        holdings_value_sql("w.id", "d.date::date")
+    );
+
+    let result = sqlx::query(&query)
+        .bind(user_id)
+        .bind(from)
+        .bind(to)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+// Assets and liabilities at the end of each date. Each wallet carries its latest snapshot
+// forward until it is deleted; debts count from their date, minus repayments made by then.
+pub async fn get_net_worth_history(
+    pool: &PgPool,
+    user_id: Uuid,
+    dates: &[NaiveDate],
+) -> Result<Vec<NetWorthPoint>, sqlx::Error> {
+    sqlx::query_as::<_, NetWorthPoint>(
+        r#"
+        WITH points AS (
+            SELECT DISTINCT date FROM UNNEST($2::date[]) AS p(date)
+        ),
+        wallet_totals AS (
+            SELECT p.date,
+                   COALESCE(SUM(CASE WHEN w.wallet_type = ANY($3) THEN GREATEST(s.balance, 0) ELSE s.balance END), 0)::float8 AS wallet_assets,
+                   COALESCE(SUM(CASE WHEN w.wallet_type = ANY($3) THEN GREATEST(-s.balance, 0) ELSE 0 END), 0)::float8 AS credit_liabilities,
+                   COALESCE(SUM(s.holdings_value), 0)::float8 AS investment_assets
+            FROM points p
+            LEFT JOIN wallets w ON w.user_id = $1 AND (w.deleted_at IS NULL OR w.deleted_at::date > p.date)
+            LEFT JOIN LATERAL (
+                SELECT balance, holdings_value FROM wallet_balance_snapshots
+                WHERE wallet_id = w.id AND date <= p.date
+                ORDER BY date DESC
+                LIMIT 1
+            ) s ON true
+            GROUP BY p.date
+        ),
+        debt_totals AS (
+            SELECT p.date,
+                   COALESCE(SUM(GREATEST(d.principal - r.repaid, 0)) FILTER (WHERE d.direction = 'lent'), 0)::float8 AS receivables,
+                   COALESCE(SUM(GREATEST(d.principal - r.repaid, 0)) FILTER (WHERE d.direction = 'borrowed'), 0)::float8 AS payables
+            FROM points p
+            LEFT JOIN debts d ON d.user_id = $1 AND d.deleted_at IS NULL AND d.date <= p.date
+            LEFT JOIN LATERAL (
+                SELECT COALESCE(SUM(t.amount), 0) AS repaid
+                FROM debt_repayments dr
+                JOIN transactions t ON t.id = dr.transaction_id
+                WHERE dr.debt_id = d.id AND t.deleted_at IS NULL AND t.date <= p.date
+            ) r ON true
+            GROUP BY p.date
+        )
+        SELECT w.date, w.wallet_assets, w.investment_assets, w.credit_liabilities, d.receivables, d.payables
+        FROM wallet_totals w
+        JOIN debt_totals d ON d.date = w.date
+        ORDER BY w.date
+        "#
+    )
+    .bind(user_id)
+    .bind(dates)
+    .bind(&CREDIT_WALLET_TYPES[..])
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn get_first_snapshot_date(pool: &PgPool, user_id: Uuid) -> Result<Option<NaiveDate>, sqlx::Error> {
+    sqlx::query_scalar::<_, Option<NaiveDate>>(
+        r#"SELECT MIN(date) FROM wallet_balance_snapshots WHERE user_id = $1"#
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+}
+
+// Investment queries
+// $2 narrows the result to a single holding when not NULL. Holdings on deleted wallets are hidden.
+const HOLDING_WITH_PRICE_QUERY: &str = r#"
+    SELECT h.id, h.user_id, h.wallet_id, h.asset_type, h.symbol, h.name, h.quantity, h.cost_basis,
+           h.created_at, h.updated_at, h.deleted_at,
+           w.name AS wallet_name,
+           p.price AS latest_price,
+           p.price_date
+    FROM investment_holdings h
+    JOIN wallets w ON w.id = h.wallet_id AND w.deleted_at IS NULL
+    LEFT JOIN LATERAL (
+        SELECT ap.price, ap.price_date FROM asset_prices ap
+        WHERE ap.user_id = h.user_id AND ap.asset_type = h.asset_type AND ap.symbol = h.symbol
+        ORDER BY ap.price_date DESC
+        LIMIT 1
+    ) p ON true
+    WHERE h.user_id = $1 AND ($2::uuid IS NULL OR h.id = $2) AND h.deleted_at IS NULL
+"#;
+
+pub async fn get_user_holdings(pool: &PgPool, user_id: Uuid) -> Result<Vec<HoldingWithPrice>, sqlx::Error> {
+    let query = format!("{} ORDER BY h.asset_type, h.symbol", HOLDING_WITH_PRICE_QUERY);
+
+    sqlx::query_as::<_, HoldingWithPrice>(&query)
+        .bind(user_id)
+        .bind(None::<Uuid>)
+        .fetch_all(pool)
+        .await
+}
+
+pub async fn get_holding_by_id(pool: &PgPool, id: Uuid, user_id: Uuid) -> Result<Option<HoldingWithPrice>, sqlx::Error> {
+    sqlx::query_as::<_, HoldingWithPrice>(HOLDING_WITH_PRICE_QUERY)
+        .bind(user_id)
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+// Current market value of all holdings on active wallets
+pub async fn get_investment_value(pool: &PgPool, user_id: Uuid) -> Result<f64, sqlx::Error> {
+    let query = format!(
+        "SELECT COALESCE(SUM({}), 0)::float8 FROM wallets w JOIN users u ON u.id = w.user_id WHERE w.user_id = $1 AND w.deleted_at IS NULL",
+        holdings_value_sql("w.id", USER_TODAY_SQL)
+    );
+
+    sqlx::query_scalar::<_, f64>(&query)
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+}
+
+// Records the price of a symbol for a day, replacing an earlier price for the same day
+pub async fn upsert_asset_price<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    asset_type: &str,
+    symbol: &str,
+    price: f64,
+    price_date: NaiveDate,
+    source: &str,
+) -> Result<AssetPrice, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+{
+    sqlx::query_as::<_, AssetPrice>(
+        r#"
+        INSERT INTO asset_prices (user_id, asset_type, symbol, price, price_date, source)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (user_id, asset_type, symbol, price_date)
+            DO UPDATE SET price = EXCLUDED.price, source = EXCLUDED.source, created_at = NOW()
+        RETURNING id, asset_type, symbol, price, price_date, source, created_at
+        "#
+    )
+    .bind(user_id)
+    .bind(asset_type)
+    .bind(symbol)
+    .bind(price)
+    .bind(price_date)
+    .bind(source)
+    .fetch_one(executor)
+    .await
+}
+
+// Price history, newest first, optionally for one asset type and symbol
+pub async fn get_asset_prices(
+    pool: &PgPool,
+    user_id: Uuid,
+    asset_type: Option<&str>,
+    symbol: Option<&str>,
+) -> Result<Vec<AssetPrice>, sqlx::Error> {
+    sqlx::query_as::<_, AssetPrice>(
+        r#"
+        SELECT id, asset_type, symbol, price, price_date, source, created_at
+        FROM asset_prices
+        WHERE user_id = $1 AND ($2::text IS NULL OR asset_type = $2) AND ($3::text IS NULL OR symbol = $3)
+        ORDER BY price_date DESC, symbol
+        LIMIT 500
+        "#
+    )
+    .bind(user_id)
+    .bind(asset_type)
+    .bind(symbol)
+    .fetch_all(pool)
+    .await
+}
+
+// Report queries
+// Income or expense per category between two dates, largest first. Each bucket is a top-level
+// category (or a direct child of parent_id) and collects the amounts of its whole subtree.
+// When drilling into parent_id, the parent's own transactions form a bucket of their own.
+pub async fn get_category_totals(
+    pool: &PgPool,
+    user_id: Uuid,
+    start_date: NaiveDate,
+    end_date: Option<NaiveDate>,
+    transaction_type: &str,
+    parent_id: Option<Uuid>,
+) -> Result<Vec<CategoryStat>, sqlx::Error> {
+    sqlx::query_as::<_, CategoryStat>(
+        r#"
+        WITH RECURSIVE buckets AS (
+            SELECT c.id AS bucket_id, c.id AS category_id
+            FROM categories c
+            WHERE (c.user_id = $1 OR c.user_id IS NULL)
+                AND c.deleted_at IS NULL
+                AND (
+                    c.id = $3
+                    OR c.parent_id = $3
+                    OR ($3::uuid IS NULL AND (
+                        c.parent_id IS NULL
+                        OR NOT EXISTS (SELECT 1 FROM categories p WHERE p.id = c.parent_id AND p.deleted_at IS NULL)
+                    ))
+                )
+            UNION
+            SELECT b.bucket_id, c.id
+            FROM categories c
+            JOIN buckets b ON c.parent_id = b.category_id
+            WHERE (c.user_id = $1 OR c.user_id IS NULL)
+                AND c.deleted_at IS NULL
+                AND b.bucket_id IS DISTINCT FROM $3
+        )
+        SELECT 
+            c.id,
+            c.name,
+            c.icon,
+            c.color,
+            COALESCE(SUM(t.amount)::float8, 0) as total
+        FROM transactions t
+        JOIN buckets b ON b.category_id = t.category_id
+        JOIN categories c ON c.id = b.bucket_id
+        WHERE t.user_id = $1 AND t.deleted_at IS NULL AND NOT t.is_transfer AND t.transaction_type = $5
+            AND t.date >= $2 AND ($4::date IS NULL OR t.date <= $4)
+        GROUP BY c.id, c.name, c.icon, c.color
+        ORDER BY total DESC
+        "#
+    )
+    .bind(user_id)
+    .bind(start_date)
+    .bind(parent_id)
+    .bind(end_date)
+    .bind(transaction_type)
+    .fetch_all(pool)
+    .await
+}
+
+// Only transactions on active wallets count, so totals line up with wallet balances. Transfers
+// (loans and their repayments) are not income or expense and are left out.
+// `unit` and `step` come from REPORT_GROUPINGS.
+pub async fn get_cash_flow(
+    pool: &PgPool,
+    user_id: Uuid,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    (unit, step): (&str, &str),
+    wallet_id: Option<Uuid>,
+    category_ids: Option<&[Uuid]>,
+) -> Result<Vec<CashFlowBucket>, sqlx::Error> {
+    sqlx::query_as::<_, CashFlowBucket>(
+        r#"
+        WITH buckets AS (
+            SELECT b::date AS bucket
+            FROM generate_series(date_trunc($6, $2::date::timestamp), $3::date::timestamp, $7::interval) AS b
+        ),
+        flows AS (
+            SELECT date_trunc($6, t.date::timestamp)::date AS bucket,
+                   SUM(t.amount) FILTER (WHERE t.transaction_type = 'income') AS income,
+                   SUM(t.amount) FILTER (WHERE t.transaction_type = 'expense') AS expense,
+                   COUNT(*) AS transaction_count
+            FROM transactions t
+            JOIN wallets w ON w.id = t.wallet_id AND w.deleted_at IS NULL
+            WHERE t.user_id = $1
+                AND t.deleted_at IS NULL
+                AND NOT t.is_transfer
+                AND t.date BETWEEN $2 AND $3
+                AND ($4::uuid IS NULL OR t.wallet_id = $4)
+                AND ($5::uuid[] IS NULL OR t.category_id = ANY($5))
+            GROUP BY 1
+        )
+        SELECT GREATEST(b.bucket, $2::date) AS period_start,
+               LEAST((b.bucket + $7::interval - INTERVAL '1 day')::date, $3::date) AS period_end,
+               COALESCE(f.income, 0)::float8 AS income,
+               COALESCE(f.expense, 0)::float8 AS expense,
+               COALESCE(f.transaction_count, 0) AS transaction_count
+        FROM buckets b
+        LEFT JOIN flows f ON f.bucket = b.bucket
+        ORDER BY b.bucket
+        "#
+    )
+    .bind(user_id)
+    .bind(start_date)
+    .bind(end_date)
+    .bind(wallet_id)
+    .bind(category_ids)
+    .bind(unit)
+    .bind(step)
+    .fetch_all(pool)
+    .await
+}
+
+// Combined balance of the active wallets (or one wallet) at the start of `date`: the current
+// balance minus every transaction dated on or after it
+pub async fn get_balance_before(
+    pool: &PgPool,
+    user_id: Uuid,
+    wallet_id: Option<Uuid>,
+    date: NaiveDate,
+) -> Result<f64, sqlx::Error> {
+    sqlx::query_scalar::<_, f64>(
+        r#"
+        SELECT (
+            COALESCE(SUM(w.balance), 0) - COALESCE((
+                SELECT SUM(CASE WHEN t.transaction_type = 'income' THEN t.amount ELSE -t.amount END)
+                FROM transactions t
+                JOIN wallets tw ON tw.id = t.wallet_id AND tw.deleted_at IS NULL
+                WHERE t.user_id = $1 AND t.deleted_at IS NULL AND t.date >= $3
+                    AND ($2::uuid IS NULL OR t.wallet_id = $2)
+            ), 0)
+        )::float8
+        FROM wallets w
+        WHERE w.user_id = $1 AND w.deleted_at IS NULL AND ($2::uuid IS NULL OR w.id = $2)
+        "#
+    )
+    .bind(user_id)
+    .bind(wallet_id)
+    .bind(date)
+    .fetch_one(pool)
+    .await
+}
+
+// Expense transactions between two dates with their category name, oldest first
+pub async fn get_expenses(
+    pool: &PgPool,
+    user_id: Uuid,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<Vec<ExpenseRow>, sqlx::Error> {
+    sqlx::query_as::<_, ExpenseRow>(
+        r#"
+        SELECT t.id, t.wallet_id, t.category_id, c.name AS category_name,
+               t.amount, t.description, t.date
+        FROM transactions t
+        LEFT JOIN categories c ON c.id = t.category_id
+        WHERE t.user_id = $1
+            AND t.deleted_at IS NULL
+            AND NOT t.is_transfer
+            AND t.transaction_type = 'expense'
+            AND t.date BETWEEN $2 AND $3
+        ORDER BY t.date, t.created_at
+        "#
+    )
+    .bind(user_id)
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await
+}
+
+// Statement queries
+// Active wallets that existed by end_date, with their balances at the start of start_date and
+// the end of end_date worked back from the current balance
+pub async fn get_statement_wallets(
+    pool: &PgPool,
+    user_id: Uuid,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<Vec<StatementWallet>, sqlx::Error> {
+    sqlx::query_as::<_, StatementWallet>(
+        r#"
+        SELECT w.id, w.name, w.wallet_type,
+               (w.balance - COALESCE(SUM(CASE WHEN t.transaction_type = 'income' THEN t.amount ELSE -t.amount END)
+                   FILTER (WHERE t.date >= $2), 0))::float8 AS opening_balance,
+               (w.balance - COALESCE(SUM(CASE WHEN t.transaction_type = 'income' THEN t.amount ELSE -t.amount END)
+                   FILTER (WHERE t.date > $3), 0))::float8 AS closing_balance
+        FROM wallets w
+        LEFT JOIN transactions t ON t.wallet_id = w.id AND t.deleted_at IS NULL AND t.date >= $2
+        WHERE w.user_id = $1 AND w.deleted_at IS NULL AND w.created_at::date <= $3
+        GROUP BY w.id, w.name, w.wallet_type, w.balance, w.is_default
+        ORDER BY w.is_default DESC, w.name
+        "#
+    )
+    .bind(user_id)
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await
+}
+
+// Transactions on active wallets between two dates, oldest first
+pub async fn get_statement_transactions(
+    pool: &PgPool,
+    user_id: Uuid,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    limit: i64,
+) -> Result<Vec<StatementTransaction>, sqlx::Error> {
+    sqlx::query_as::<_, StatementTransaction>(
+        r#"
+        SELECT t.id, t.date, t.transaction_type, t.amount, t.description,
+               c.name AS category_name, w.name AS wallet_name
+        FROM transactions t
+        JOIN wallets w ON w.id = t.wallet_id AND w.deleted_at IS NULL
+        LEFT JOIN categories c ON c.id = t.category_id AND c.deleted_at IS NULL
+        WHERE t.user_id = $1 AND t.deleted_at IS NULL AND t.date BETWEEN $2 AND $3
+        ORDER BY t.date, t.created_at
+        LIMIT $4
+        "#
+    )
+    .bind(user_id)
+    .bind(start_date)
+    .bind(end_date)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+// Everything on one month's statement. Totals and balances only count active wallets, the
+// same as the cash flow report.
+pub async fn get_monthly_statement(
+    pool: &PgPool,
+    user: &User,
+    year: i32,
+    month: u32,
+) -> Result<Statement, sqlx::Error> {
+    let start_date = day_of_month(year, month, 1);
+    let end_date = day_of_month(year, month, 31);
+
+    let flow = get_cash_flow(pool, user.id, start_date, end_date, ("month", "1 month"), None, None).await?;
+    let (income, expense, flow_count) = flow.iter().fold((0.0, 0.0, 0), |(income, expense, count), bucket| {
+        (income + bucket.income, expense + bucket.expense, count + bucket.transaction_count)
+    });
+    let (transfers, transfer_count) = get_transfer_totals(pool, user.id, start_date, end_date).await?;
+    let transaction_count = flow_count + transfer_count;
+    let opening_balance = get_balance_before(pool, user.id, None, start_date).await?;
+    let closing_balance = get_balance_before(pool, user.id, None, end_date + Duration::days(1)).await?;
+
+    let expense_stats = get_category_totals(pool, user.id, start_date, Some(end_date), "expense", None).await?;
+    let income_stats = get_category_totals(pool, user.id, start_date, Some(end_date), "income", None).await?;
+    let budgets = get_user_budgets_with_usage(pool, user.id, Some(month as i32), Some(year)).await?;
+    let wallets = get_statement_wallets(pool, user.id, start_date, end_date).await?;
+    let transactions =
+        get_statement_transactions(pool, user.id, start_date, end_date, MAX_STATEMENT_TRANSACTIONS).await?;
+
+    Ok(Statement {
+        user_name: user.name.clone(),
+        user_email: user.email.clone(),
+        year,
+        month,
+        start_date,
+        end_date,
+        generated_at: Utc::now(),
+        summary: StatementSummary {
+            opening_balance: round_currency(opening_balance),
+            income: round_currency(income),
+            expense: round_currency(expense),
+            net: round_currency(income - expense),
+            transfers: round_currency(transfers),
+            closing_balance: round_currency(closing_balance),
+            transaction_count,
+        },
+        expense_categories: category_breakdown(expense_stats, expense),
+        income_categories: category_breakdown(income_stats, income),
+        budgets: budgets
+            .into_iter()
+            .filter(|row| row.budget.is_active)
+            .map(StatementBudget::from)
+            .collect(),
+        wallets,
+        transactions_truncated: transaction_count > transactions.len() as i64,
+        transactions,
+    })
+}
+
+// Net amount transfers moved into active wallets between two dates, and their number
+pub async fn get_transfer_totals(
+    pool: &PgPool,
+    user_id: Uuid,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<(f64, i64), sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT COALESCE(SUM(CASE WHEN t.transaction_type = 'income' THEN t.amount ELSE -t.amount END), 0)::float8,
+               COUNT(*)
+        FROM transactions t
+        JOIN wallets w ON w.id = t.wallet_id AND w.deleted_at IS NULL
+        WHERE t.user_id = $1 AND t.deleted_at IS NULL AND t.is_transfer AND t.date BETWEEN $2 AND $3
+        "#
+    )
+    .bind(user_id)
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_one(pool)
+    .await
+}
+
+// Question queries
+// Conditions shared by the question queries; $1 is the user
+const QUESTION_FILTER_SQL: &str = r#"
+    t.user_id = $1
+    AND t.deleted_at IS NULL
+    AND NOT t.is_transfer
+    AND ($2::text IS NULL OR t.transaction_type = $2)
+    AND t.date >= $3 AND t.date <= $4
+    AND (cardinality($5::uuid[]) = 0 OR t.category_id = ANY($5))
+    AND ($6::uuid IS NULL OR t.wallet_id = $6)
+    AND ($7::text IS NULL OR strpos(lower(COALESCE(t.description, '')), lower($7)) > 0)
+"#;
+
+pub async fn get_question_totals(
+    pool: &PgPool,
+    user_id: Uuid,
+    filter: &QuestionFilter,
+) -> Result<QuestionTotals, sqlx::Error> {
+    let query = format!(
+        r#"
+        SELECT COALESCE(SUM(t.amount), 0)::FLOAT8 AS total,
+               COALESCE(SUM(CASE WHEN t.transaction_type = 'income' THEN t.amount ELSE -t.amount END), 0)::FLOAT8 AS net,
+               COUNT(*) AS count
+        FROM transactions t
+        WHERE {}
+        "#,
+        QUESTION_FILTER_SQL
+    );
+
+    sqlx::query_as::<_, QuestionTotals>(&query)
+        .bind(user_id)
+        .bind(&filter.transaction_type)
+        .bind(filter.start_date)
+        .bind(filter.end_date)
+        .bind(&filter.category_ids)
+        .bind(filter.wallet.as_ref().map(|wallet| wallet.id))
+        .bind(&filter.keyword)
+        .fetch_one(pool)
+        .await
+}
+
+pub async fn get_largest_question_transaction(
+    pool: &PgPool,
+    user_id: Uuid,
+    filter: &QuestionFilter,
+) -> Result<Option<TransactionWithCategory>, sqlx::Error> {
+    let query = format!(
+        r#"
+        SELECT t.id, t.user_id, t.wallet_id, t.category_id, t.transaction_type, t.amount, t.description, t.date, t.is_transfer, t.created_at, t.updated_at, t.deleted_at,
+               c.name AS category_name
+        FROM transactions t
+        LEFT JOIN categories c ON c.id = t.category_id AND c.deleted_at IS NULL
+        WHERE {}
+        ORDER BY t.amount DESC, t.date DESC
+        LIMIT 1
+        "#,
+        QUESTION_FILTER_SQL
+    );
+
+    sqlx::query_as::<_, TransactionWithCategory>(&query)
+        .bind(user_id)
+        .bind(&filter.transaction_type)
+        .bind(filter.start_date)
+        .bind(filter.end_date)
+        .bind(&filter.category_ids)
+        .bind(filter.wallet.as_ref().map(|wallet| wallet.id))
+        .bind(&filter.keyword)
+        .fetch_optional(pool)
+        .await
+}
+
+// Recurring transaction queries
+pub async fn get_user_recurring_transactions(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<RecurringTransaction>, sqlx::Error> {
+    sqlx::query_as::<_, RecurringTransaction>(
+        r#"
+        SELECT id, user_id, wallet_id, category_id, transaction_type, amount, description, frequency,
+               interval_count, anchor_day, next_date, end_date, auto_post, subscription_key,
+               created_at, updated_at, deleted_at
+        FROM recurring_transactions
+        WHERE user_id = $1 AND deleted_at IS NULL
+        ORDER BY next_date, description
+        "#
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn get_recurring_transaction_by_id(
+    pool: &PgPool,
+    id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<RecurringTransaction>, sqlx::Error> {
+    sqlx::query_as::<_, RecurringTransaction>(
+        r#"
+        SELECT id, user_id, wallet_id, category_id, transaction_type, amount, description, frequency,
+               interval_count, anchor_day, next_date, end_date, auto_post, subscription_key,
+               created_at, updated_at, deleted_at
+        FROM recurring_transactions
+        WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL
+        "#
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+// Bill queries
+pub async fn get_user_bills(pool: &PgPool, user_id: Uuid) -> Result<Vec<Bill>, sqlx::Error> {
+    sqlx::query_as::<_, Bill>(
+        r#"
+        SELECT id, user_id, payee, description, amount, amount_min, amount_max, wallet_id, category_id,
+               frequency, interval_count, anchor_day, next_due_date, remind_days_before, subscription_key,
+               created_at, updated_at, deleted_at
+        FROM bills
+        WHERE user_id = $1 AND deleted_at IS NULL
+        ORDER BY next_due_date NULLS LAST, payee
+        "#
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn get_bill_by_id(pool: &PgPool, id: Uuid, user_id: Uuid) -> Result<Option<Bill>, sqlx::Error> {
+    sqlx::query_as::<_, Bill>(
+        r#"
+        SELECT id, user_id, payee, description, amount, amount_min, amount_max, wallet_id, category_id,
+               frequency, interval_count, anchor_day, next_due_date, remind_days_before, subscription_key,
+               created_at, updated_at, deleted_at
+        FROM bills
+        WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL
+        "#
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+// Newest first
+pub async fn get_bill_payments(pool: &PgPool, bill_id: Uuid) -> Result<Vec<BillPayment>, sqlx::Error> {
+    sqlx::query_as::<_, BillPayment>(
+        r#"
+        SELECT id, bill_id, due_date, paid_date, amount, transaction_id, created_at
+        FROM bill_payments
+        WHERE bill_id = $1
+        ORDER BY due_date DESC
+        "#
+    )
+    .bind(bill_id)
+    .fetch_all(pool)
+    .await
+}
+
+// Owner of a bills calendar feed token
+pub async fn find_user_by_calendar_token(pool: &PgPool, token: &str) -> Result<Option<Uuid>, sqlx::Error> {
+    sqlx::query_scalar(r#"SELECT id FROM users WHERE calendar_token = $1"#)
+        .bind(token)
+        .fetch_optional(pool)
+        .await
+}
+
+// Zakat queries
+pub async fn get_zakat_settings(pool: &PgPool, user_id: Uuid) -> Result<Option<ZakatSettings>, sqlx::Error> {
+    sqlx::query_as::<_, ZakatSettings>(
+        r#"SELECT user_id, gold_price_per_gram, nisab_grams, include_receivables, deduct_liabilities, created_at, updated_at
+           FROM zakat_settings WHERE user_id = $1"#
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+// Expenses between the dates recorded as zakat: in a category or with a description
+// mentioning it
+pub async fn get_zakat_paid(
+    pool: &PgPool,
+    user_id: Uuid,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<f64, sqlx::Error> {
+    sqlx::query_scalar::<_, f64>(
+        r#"
+        SELECT COALESCE(SUM(t.amount), 0)::FLOAT8
+        FROM transactions t
+        LEFT JOIN categories c ON c.id = t.category_id
+        WHERE t.user_id = $1 AND t.deleted_at IS NULL AND t.transaction_type = 'expense'
+            AND t.date BETWEEN $2 AND $3
+            AND (c.name ILIKE '%zakat%' OR t.description ILIKE '%zakat%')
+        "#
+    )
+    .bind(user_id)
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_one(pool)
+    .await
+}
+
+// Digest queries
+// Saves the given flags, keeping the current (or default) value of those left out
+pub async fn upsert_digest_preferences(
+    pool: &PgPool,
+    user_id: Uuid,
+    weekly: Option<bool>,
+    monthly: Option<bool>,
+) -> Result<DigestPreferences, sqlx::Error> {
+    sqlx::query_as::<_, DigestPreferences>(
+        r#"
+        INSERT INTO digest_preferences (user_id, weekly, monthly, unsubscribe_token)
+        VALUES ($1, COALESCE($2, FALSE), COALESCE($3, TRUE), $4)
+        ON CONFLICT (user_id) DO UPDATE SET
+            weekly = COALESCE($2, digest_preferences.weekly),
+            monthly = COALESCE($3, digest_preferences.monthly)
+        RETURNING user_id, weekly, monthly, unsubscribe_token, created_at, updated_at
+        "#
+    )
+    .bind(user_id)
+    .bind(weekly)
+    .bind(monthly)
+    .bind(new_unsubscribe_token())
+    .fetch_one(pool)
+    .await
+}
+
+// The user's preferences, creating the default row (and its unsubscribe token) if missing
+pub async fn ensure_digest_preferences(pool: &PgPool, user_id: Uuid) -> Result<DigestPreferences, sqlx::Error> {
+    sqlx::query(
+        r#"INSERT INTO digest_preferences (user_id, unsubscribe_token) VALUES ($1, $2)
+           ON CONFLICT (user_id) DO NOTHING"#
+    )
+    .bind(user_id)
+    .bind(new_unsubscribe_token())
+    .execute(pool)
+    .await?;
+
+    sqlx::query_as::<_, DigestPreferences>(
+        r#"SELECT user_id, weekly, monthly, unsubscribe_token, created_at, updated_at
+           FROM digest_preferences WHERE user_id = $1"#
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn find_digest_preferences_by_token(
+    pool: &PgPool,
+    token: &str,
+) -> Result<Option<DigestPreferences>, sqlx::Error> {
+    sqlx::query_as::<_, DigestPreferences>(
+        r#"SELECT user_id, weekly, monthly, unsubscribe_token, created_at, updated_at
+           FROM digest_preferences WHERE unsubscribe_token = $1"#
+    )
+    .bind(token)
+    .fetch_optional(pool)
+    .await
+}
+
+// Records a digest as sent; false when this period was already sent
+pub async fn claim_digest_delivery(
+    pool: &PgPool,
+    user_id: Uuid,
+    frequency: &str,
+    period_start: NaiveDate,
+    period_end: NaiveDate,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO digest_deliveries (user_id, frequency, period_start, period_end)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (user_id, frequency, period_start) DO NOTHING
+        "#
+    )
+    .bind(user_id)
+    .bind(frequency)
+    .bind(period_start)
+    .bind(period_end)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+// Undoes a claim after sending failed, so the next run retries
+pub async fn release_digest_delivery(
+    pool: &PgPool,
+    user_id: Uuid,
+    frequency: &str,
+    period_start: NaiveDate,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(r#"DELETE FROM digest_deliveries WHERE user_id = $1 AND frequency = $2 AND period_start = $3"#)
+        .bind(user_id)
+        .bind(frequency)
+        .bind(period_start)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+// Notification queries
+// Returns false when a notification with the same dedupe key already exists
+pub async fn insert_notification<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    kind: &str,
+    title: &str,
+    body: &str,
+    data: Option<serde_json::Value>,
+    dedupe_key: Option<&str>,
+) -> Result<bool, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+{
+    let result = sqlx::query(
+        r#"
+        INSERT INTO notifications (user_id, kind, title, body, data, dedupe_key)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (user_id, dedupe_key) WHERE dedupe_key IS NOT NULL DO NOTHING
+        "#
+    )
+    .bind(user_id)
+    .bind(kind)
+    .bind(title)
+    .bind(body)
+    .bind(data.map(Json))
+    .bind(dedupe_key)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn get_user_notifications(
+    pool: &PgPool,
+    user_id: Uuid,
+    unread_only: bool,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<Notification>, sqlx::Error> {
+    sqlx::query_as::<_, Notification>(
+        r#"
+        SELECT id, kind, title, body, data, read_at, created_at
+        FROM notifications
+        WHERE user_id = $1 AND (NOT $2 OR read_at IS NULL)
+        ORDER BY created_at DESC
+        LIMIT $3 OFFSET $4
+        "#
+    )
+    .bind(user_id)
+    .bind(unread_only)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await
+}
+
+// Trash queries
+// Soft-deleted rows, most recently deleted first
+pub async fn get_deleted_wallets(pool: &PgPool, user_id: Uuid) -> Result<Vec<Wallet>, sqlx::Error> {
+    sqlx::query_as::<_, Wallet>(
+        r#"SELECT id, user_id, name, wallet_type, balance, icon, color, credit_limit, statement_closing_day, payment_due_day, is_default, created_at, updated_at, deleted_at 
+           FROM wallets WHERE user_id = $1 AND deleted_at IS NOT NULL ORDER BY deleted_at DESC"#
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn get_deleted_categories(pool: &PgPool, user_id: Uuid) -> Result<Vec<Category>, sqlx::Error> {
+    sqlx::query_as::<_, Category>(
+        r#"SELECT id, user_id, name, icon, color, category_type, parent_id, created_at, deleted_at FROM categories 
+           WHERE user_id = $1 AND deleted_at IS NOT NULL ORDER BY deleted_at DESC"#
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn get_deleted_budgets(pool: &PgPool, user_id: Uuid) -> Result<Vec<DeletedBudget>, sqlx::Error> {
+    sqlx::query_as::<_, DeletedBudget>(
+        r#"SELECT b.id, b.user_id, b.category_id, b.amount, b.month, b.year, b.is_active, b.alert_threshold, b.wallet_ids,
+                  b.created_at, b.updated_at, b.deleted_at, c.name AS category_name
+           FROM budgets b
+           LEFT JOIN categories c ON c.id = b.category_id
+           WHERE b.user_id = $1 AND b.deleted_at IS NOT NULL ORDER BY b.deleted_at DESC"#
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+// Audit log queries
+// Takes any executor so the entry can be written in the same database transaction as the change
+pub async fn insert_audit_log<'e, E, T>(
+    executor: E,
+    ctx: &AuditContext,
+    action: AuditAction,
+    before: Option<&T>,
+    after: &T,
+) -> Result<(), sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+    T: Auditable,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO audit_logs (user_id, actor_id, source_channel, entity_type, entity_id, action, before_data, after_data)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#
+    )
+    .bind(ctx.user_id)
+    .bind(ctx.actor_id())
+    .bind(ctx.channel.as_str())
+    .bind(T::ENTITY_TYPE)
+    .bind(after.entity_id())
+    .bind(action.as_str())
+    .bind(before.map(Json))
+    .bind(Json(after))
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+// Full history of one record, oldest first; also works after the record is deleted or purged
+pub async fn get_audit_history(
+    pool: &PgPool,
+    user_id: Uuid,
+    entity_type: &str,
+    entity_id: Uuid,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<AuditLog>, sqlx::Error> {
+    sqlx::query_as::<_, AuditLog>(
+        r#"SELECT id, actor_id, source_channel, action, before_data, after_data, created_at
+           FROM audit_logs
+           WHERE user_id = $1 AND entity_type = $2 AND entity_id = $3
+           ORDER BY created_at, id
+           LIMIT $4 OFFSET $5"#
+    )
+    .bind(user_id)
+    .bind(entity_type)
+    .bind(entity_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await
+}
@@ -0,0 +1,180 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header::AUTHORIZATION, HeaderMap},
+    Json,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::{
+    db,
+    error::AppError,
+    models::{
+        audit::{AuditHistoryQuery, AuditLogResponse, Auditable},
+        budget::Budget,
+        category::Category,
+        transaction::Transaction,
+        wallet::Wallet,
+    },
+    utils::jwt::verify_token,
+    AppState,
+};
+
+async fn get_user_id(state: &AppState, headers: &HeaderMap) -> Result<Uuid, AppError> {
+    let auth_header = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .ok_or(AppError::Unauthorized)?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or(AppError::Unauthorized)?;
+
+    let claims = verify_token(token, &state.config.jwt_secret)?;
+    Ok(claims.sub)
+}
+
+async fn entity_history<T: Auditable>(
+    state: &AppState,
+    headers: &HeaderMap,
+    id: Uuid,
+    query: AuditHistoryQuery,
+    resource: &str,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(state, headers).await?;
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let logs = db::get_audit_history(&state.db, user_id, T::ENTITY_TYPE, id, limit, offset).await?;
+
+    // Every record has at least its create entry, unless it predates the audit log
+    if logs.is_empty() && offset == 0 {
+        return Err(AppError::NotFound(format!("Riwayat {}", resource)));
+    }
+
+    let response: Vec<AuditLogResponse> = logs.into_iter().map(AuditLogResponse::from).collect();
+
+    Ok(Json(json!({
+        "success": true,
+        "data": response,
+        "meta": {
+            "entity_type": T::ENTITY_TYPE,
+            "entity_id": id,
+            "limit": limit,
+            "offset": offset
+        }
+    })))
+}
+
+pub async fn transaction_history(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Query(query): Query<AuditHistoryQuery>,
+) -> Result<Json<Value>, AppError> {
+    entity_history::<Transaction>(&state, &headers, id, query, "transaksi").await
+}
+
+pub async fn wallet_history(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Query(query): Query<AuditHistoryQuery>,
+) -> Result<Json<Value>, AppError> {
+    entity_history::<Wallet>(&state, &headers, id, query, "wallet").await
+}
+
+pub async fn budget_history(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Query(query): Query<AuditHistoryQuery>,
+) -> Result<Json<Value>, AppError> {
+    entity_history::<Budget>(&state, &headers, id, query, "budget").await
+}
+
+pub async fn category_history(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Query(query): Query<AuditHistoryQuery>,
+) -> Result<Json<Value>, AppError> {
+    entity_history::<Category>(&state, &headers, id, query, "kategori").await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        handlers::transaction::{create_transaction, delete_transaction, update_transaction},
+        models::{
+            audit::SOURCE_CHANNEL_HEADER,
+            transaction::{CreateTransactionRequest, UpdateTransactionRequest},
+        },
+        test_support::{auth_headers, create_user, test_state},
+    };
+    use axum::http::HeaderValue;
+    use sqlx::PgPool;
+
+    fn no_paging() -> AuditHistoryQuery {
+        AuditHistoryQuery { limit: None, offset: None }
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn transaction_history_records_every_change_in_order(pool: PgPool) {
+        let (user_id, wallet_id) = create_user(&pool).await;
+        let state = test_state(pool.clone());
+        let mut whatsapp = auth_headers(user_id);
+        whatsapp.insert(SOURCE_CHANNEL_HEADER, HeaderValue::from_static("whatsapp"));
+
+        let request = CreateTransactionRequest {
+            wallet_id: Some(wallet_id),
+            category_id: None,
+            category_name: None,
+            transaction_type: "expense".to_string(),
+            amount: 10000.0,
+            description: Some("Makan".to_string()),
+            date: None,
+        };
+        let created = create_transaction(State(state.clone()), whatsapp, Json(request)).await.unwrap();
+        let id: Uuid = created.0["data"]["id"].as_str().unwrap().parse().unwrap();
+
+        let request = UpdateTransactionRequest {
+            wallet_id: None,
+            category_id: None,
+            transaction_type: None,
+            amount: Some(12000.0),
+            description: None,
+            date: None,
+        };
+        let updated = update_transaction(State(state.clone()), auth_headers(user_id), Path(id), Json(request))
+            .await
+            .unwrap();
+        assert_eq!(updated.0["data"]["amount"], 12000.0);
+        let deleted = delete_transaction(State(state.clone()), auth_headers(user_id), Path(id)).await.unwrap();
+        assert_eq!(deleted.0["success"], true);
+
+        let history = transaction_history(State(state.clone()), auth_headers(user_id), Path(id), Query(no_paging()))
+            .await
+            .unwrap();
+        let entries = history.0["data"].as_array().unwrap();
+        let actions: Vec<(&str, &str)> = entries
+            .iter()
+            .map(|entry| (entry["action"].as_str().unwrap(), entry["source_channel"].as_str().unwrap()))
+            .collect();
+        assert_eq!(actions, [("create", "whatsapp"), ("update", "web"), ("delete", "web")]);
+        assert_eq!(entries[0]["before"], Value::Null);
+        assert_eq!(entries[0]["actor_id"], user_id.to_string());
+        assert_eq!(entries[1]["changed_fields"], json!(["amount"]));
+        assert_eq!(entries[1]["before"]["amount"], 10000.0);
+        assert_eq!(entries[1]["after"]["amount"], 12000.0);
+        assert_eq!(entries[2]["changed_fields"], json!(["deleted_at"]));
+
+        // Another user's records have no visible history
+        let (other_user_id, _) = create_user(&pool).await;
+        let result = transaction_history(State(state), auth_headers(other_user_id), Path(id), Query(no_paging())).await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+    }
+}
@@ -10,19 +10,48 @@ use validator::Validate;
 use crate::{
     db,
     error::AppError,
-    models::user::{AuthResponse, LoginRequest, RegisterRequest, User, UserResponse},
-    utils::{jwt::create_token, password::{hash_password, verify_password}},
+    models::{
+        audit::{AuditAction, AuditContext},
+        category::Category,
+        user::{AuthResponse, LoginRequest, RegisterRequest, UpdateProfileRequest, User, UserResponse},
+        wallet::Wallet,
+    },
+    utils::{
+        dates::{parse_timezone, DEFAULT_TIMEZONE},
+        jwt::create_token,
+        password::{hash_password, verify_password},
+    },
     AppState,
 };
 
+// The zone is used both by chrono-tz and by `AT TIME ZONE` in queries, so both have to know it
+async fn validate_timezone(pool: &sqlx::PgPool, timezone: &str) -> Result<(), AppError> {
+    let known_to_postgres: bool =
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM pg_timezone_names WHERE name = $1)")
+            .bind(timezone)
+            .fetch_one(pool)
+            .await?;
+
+    if parse_timezone(timezone).is_none() || !known_to_postgres {
+        return Err(AppError::ValidationError(
+            "Zona waktu tidak valid, gunakan nama IANA seperti Asia/Jakarta".to_string(),
+        ));
+    }
+    Ok(())
+}
+
 pub async fn register(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(payload): Json<RegisterRequest>,
 ) -> Result<Json<AuthResponse>, AppError> {
     payload.validate().map_err(|e| {
         AppError::ValidationError(e.to_string())
     })?;
 
+    let timezone = payload.timezone.as_deref().unwrap_or(DEFAULT_TIMEZONE);
+    validate_timezone(&state.db, timezone).await?;
+
     if db::find_user_by_email(&state.db, &payload.email).await?.is_some() {
         return Err(AppError::Conflict("Email sudah terdaftar".to_string()));
     }
@@ -33,12 +62,13 @@ pub async fn register(
 
     let password_hash = hash_password(&payload.password)?;
     let user_id = Uuid::new_v4();
+    let audit = AuditContext::from_headers(user_id, &headers)?;
     
     let user = sqlx::query_as::<_, User>(
         r#"
-        INSERT INTO users (id, email, username, name, password_hash)
-        VALUES ($1, $2, $3, $4, $5)
-        RETURNING id, email, username, name, password_hash, created_at, updated_at
+        INSERT INTO users (id, email, username, name, password_hash, timezone)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, email, username, name, password_hash, timezone, anomaly_alerts, created_at, updated_at
         "#
     )
     .bind(user_id)
@@ -46,11 +76,12 @@ pub async fn register(
     .bind(&payload.username)
     .bind(&payload.name)
     .bind(&password_hash)
+    .bind(timezone)
     .fetch_one(&state.db)
     .await?;
 
-    create_default_categories(&state.db, user_id).await?;
-    create_default_wallet(&state.db, user_id).await?;
+    create_default_categories(&state.db, &audit).await?;
+    create_default_wallet(&state.db, &audit).await?;
 
     let token = create_token(user.id, &user.email, &state.config.jwt_secret)?;
 
@@ -113,6 +144,56 @@ pub async fn me(
     })))
 }
 
+pub async fn update_me(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<UpdateProfileRequest>,
+) -> Result<Json<Value>, AppError> {
+    let auth_header = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .ok_or(AppError::Unauthorized)?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or(AppError::Unauthorized)?;
+
+    let claims = crate::utils::jwt::verify_token(token, &state.config.jwt_secret)?;
+
+    payload.validate().map_err(|e| {
+        AppError::ValidationError(e.to_string())
+    })?;
+
+    if let Some(ref timezone) = payload.timezone {
+        validate_timezone(&state.db, timezone).await?;
+    }
+
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        UPDATE users SET
+            name = COALESCE($1, name),
+            timezone = COALESCE($2, timezone),
+            anomaly_alerts = COALESCE($3, anomaly_alerts),
+            updated_at = NOW()
+        WHERE id = $4
+        RETURNING id, email, username, name, password_hash, timezone, anomaly_alerts, created_at, updated_at
+        "#
+    )
+    .bind(&payload.name)
+    .bind(&payload.timezone)
+    .bind(payload.anomaly_alerts)
+    .bind(claims.sub)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or(AppError::NotFound("User".to_string()))?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Profil berhasil diupdate!",
+        "user": UserResponse::from(user)
+    })))
+}
+
 pub async fn logout() -> Json<Value> {
     Json(json!({
         "success": true,
@@ -120,7 +201,7 @@ pub async fn logout() -> Json<Value> {
     }))
 }
 
-async fn create_default_categories(pool: &sqlx::PgPool, user_id: Uuid) -> Result<(), sqlx::Error> {
+async fn create_default_categories(pool: &sqlx::PgPool, audit: &AuditContext) -> Result<(), sqlx::Error> {
     let default_categories = vec![
         ("Gaji", "💼", "#22c55e", "income"),
         ("Freelance", "💻", "#10b981", "income"),
@@ -137,36 +218,79 @@ async fn create_default_categories(pool: &sqlx::PgPool, user_id: Uuid) -> Result
     ];
 
     for (name, icon, color, cat_type) in default_categories {
-        sqlx::query(
-            r#"INSERT INTO categories (id, user_id, name, icon, color, category_type) VALUES ($1, $2, $3, $4, $5, $6)"#
+        let category = sqlx::query_as::<_, Category>(
+            r#"INSERT INTO categories (id, user_id, name, icon, color, category_type) VALUES ($1, $2, $3, $4, $5, $6)
+               RETURNING id, user_id, name, icon, color, category_type, parent_id, created_at, deleted_at"#
         )
         .bind(Uuid::new_v4())
-        .bind(user_id)
+        .bind(audit.user_id)
         .bind(name)
         .bind(icon)
         .bind(color)
         .bind(cat_type)
-        .execute(pool)
+        .fetch_one(pool)
         .await?;
+
+        db::insert_audit_log(pool, audit, AuditAction::Create, None, &category).await?;
     }
 
     Ok(())
 }
 
-async fn create_default_wallet(pool: &sqlx::PgPool, user_id: Uuid) -> Result<(), sqlx::Error> {
-    sqlx::query(
-        r#"INSERT INTO wallets (id, user_id, name, wallet_type, balance, icon, color, is_default) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"#
+async fn create_default_wallet(pool: &sqlx::PgPool, audit: &AuditContext) -> Result<(), sqlx::Error> {
+    let wallet = sqlx::query_as::<_, Wallet>(
+        r#"INSERT INTO wallets (id, user_id, name, wallet_type, balance, icon, color, is_default) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+           RETURNING id, user_id, name, wallet_type, balance, icon, color, credit_limit, statement_closing_day, payment_due_day, is_default, created_at, updated_at, deleted_at"#
     )
     .bind(Uuid::new_v4())
-    .bind(user_id)
+    .bind(audit.user_id)
     .bind("Cash")
     .bind("cash")
     .bind(0.0_f64)
     .bind("💵")
     .bind("#22c55e")
     .bind(true) // Set as default wallet
-    .execute(pool)
+    .fetch_one(pool)
     .await?;
 
+    db::insert_audit_log(pool, audit, AuditAction::Create, None, &wallet).await?;
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{auth_headers, create_user, test_state};
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn timezone_must_be_known_to_chrono_and_postgres(pool: PgPool) {
+        assert!(validate_timezone(&pool, "Asia/Makassar").await.is_ok());
+        assert!(matches!(
+            validate_timezone(&pool, "Mars/Olympus").await,
+            Err(AppError::ValidationError(_))
+        ));
+        // Postgres accepts the posix/ aliases but chrono-tz does not
+        assert!(matches!(
+            validate_timezone(&pool, "posix/Asia/Jakarta").await,
+            Err(AppError::ValidationError(_))
+        ));
+
+        let state = test_state(pool.clone());
+        let (user_id, _) = create_user(&pool).await;
+        let update = |timezone: &str| UpdateProfileRequest {
+            name: None,
+            timezone: Some(timezone.to_string()),
+            anomaly_alerts: None,
+        };
+        let result = update_me(State(state.clone()), auth_headers(user_id), Json(update("asia/jakarta"))).await;
+        assert!(matches!(result, Err(AppError::ValidationError(_))));
+
+        let Json(body) = update_me(State(state), auth_headers(user_id), Json(update("Asia/Jayapura")))
+            .await
+            .unwrap();
+        assert_eq!(body["user"]["timezone"], "Asia/Jayapura");
+    }
+}
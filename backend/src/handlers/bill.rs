@@ -0,0 +1,677 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{
+        header::{AUTHORIZATION, CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_TYPE},
+        HeaderMap,
+    },
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Datelike, Duration, Utc};
+use rand::{distributions::Alphanumeric, Rng};
+use serde_json::{json, Value};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    db,
+    error::AppError,
+    models::{
+        audit::AuditContext,
+        bill::{
+            Bill, BillPayment, BillQuery, BillReminderQuery, BillResponse, CreateBillRequest,
+            PayBillRequest, UpdateBillRequest, BILL_FREQUENCIES,
+        },
+        transaction::{NewTransaction, TransactionResponse},
+    },
+    utils::{
+        format::format_rupiah,
+        ical::{format_date, format_utc, IcsWriter},
+        jwt::verify_token,
+    },
+    AppState,
+};
+
+const MAX_INTERVAL_COUNT: i32 = 12;
+const MAX_REMIND_DAYS_BEFORE: i32 = 30;
+const CALENDAR_TOKEN_LENGTH: usize = 40;
+// The feed lists due dates this far ahead, and at most this many per bill
+const CALENDAR_HORIZON_DAYS: i64 = 366;
+const CALENDAR_MAX_EVENTS_PER_BILL: usize = 60;
+
+async fn get_user_id(state: &AppState, headers: &HeaderMap) -> Result<Uuid, AppError> {
+    let auth_header = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .ok_or(AppError::Unauthorized)?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or(AppError::Unauthorized)?;
+
+    let claims = verify_token(token, &state.config.jwt_secret)?;
+    Ok(claims.sub)
+}
+
+fn validate_amount(amount: f64) -> Result<(), AppError> {
+    if amount <= 0.0 || !amount.is_finite() {
+        return Err(AppError::ValidationError(
+            "Jumlah harus lebih besar dari 0".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// A bill has either a fixed amount or an estimated range
+fn validate_amounts(amount: Option<f64>, amount_min: Option<f64>, amount_max: Option<f64>) -> Result<(), AppError> {
+    match (amount, amount_min, amount_max) {
+        (Some(amount), None, None) => validate_amount(amount),
+        (None, Some(min), Some(max)) => {
+            validate_amount(min)?;
+            if !max.is_finite() || max < min {
+                return Err(AppError::ValidationError(
+                    "Jumlah maksimal tidak boleh lebih kecil dari jumlah minimal".to_string(),
+                ));
+            }
+            Ok(())
+        }
+        _ => Err(AppError::ValidationError(
+            "Isi jumlah tetap, atau perkiraan jumlah minimal dan maksimal".to_string(),
+        )),
+    }
+}
+
+fn validate_remind_days(days: i32) -> Result<(), AppError> {
+    if !(0..=MAX_REMIND_DAYS_BEFORE).contains(&days) {
+        return Err(AppError::ValidationError(format!(
+            "Pengingat harus antara 0-{} hari sebelum jatuh tempo",
+            MAX_REMIND_DAYS_BEFORE
+        )));
+    }
+    Ok(())
+}
+
+async fn ensure_wallet_exists(state: &AppState, user_id: Uuid, wallet_id: Uuid) -> Result<(), AppError> {
+    db::get_wallet_by_id(&state.db, wallet_id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Wallet".to_string()))?;
+    Ok(())
+}
+
+async fn ensure_category_exists(state: &AppState, user_id: Uuid, category_id: Uuid) -> Result<(), AppError> {
+    let category_exists: bool = sqlx::query_scalar(
+        r#"SELECT EXISTS(SELECT 1 FROM categories WHERE id = $1 AND (user_id = $2 OR user_id IS NULL) AND deleted_at IS NULL)"#
+    )
+    .bind(category_id)
+    .bind(user_id)
+    .fetch_one(&state.db)
+    .await?;
+
+    if !category_exists {
+        return Err(AppError::NotFound("Category".to_string()));
+    }
+    Ok(())
+}
+
+fn amount_label(bill: &Bill) -> String {
+    match (bill.amount, bill.amount_min, bill.amount_max) {
+        (Some(amount), _, _) => format_rupiah(amount),
+        (None, Some(min), Some(max)) => format!("{} - {} (perkiraan)", format_rupiah(min), format_rupiah(max)),
+        _ => "-".to_string(),
+    }
+}
+
+pub async fn list_bills(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<BillQuery>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let bills: Vec<BillResponse> = db::get_user_bills(&state.db, user_id)
+        .await?
+        .into_iter()
+        .map(|bill| BillResponse::new(bill, today))
+        .collect();
+
+    let overdue_count = bills.iter().filter(|bill| bill.status == "overdue").count();
+    let due_soon_count = bills.iter().filter(|bill| bill.status == "due_soon").count();
+    let annual_amount: f64 = bills.iter().map(|bill| bill.annual_amount).sum();
+
+    let bills: Vec<BillResponse> = bills
+        .into_iter()
+        .filter(|bill| query.status.as_ref().is_none_or(|status| &bill.status == status))
+        .collect();
+
+    Ok(Json(json!({
+        "success": true,
+        "data": bills,
+        "meta": {
+            "overdue_count": overdue_count,
+            "due_soon_count": due_soon_count,
+            "annual_amount": annual_amount,
+            "monthly_amount": annual_amount / 12.0
+        }
+    })))
+}
+
+pub async fn list_bill_reminders(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<BillReminderQuery>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let days = query.days.unwrap_or(7);
+    if !(0..=366).contains(&days) {
+        return Err(AppError::ValidationError(
+            "Days harus antara 0-366".to_string(),
+        ));
+    }
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let reminders: Vec<BillResponse> = db::get_user_bills(&state.db, user_id)
+        .await?
+        .into_iter()
+        .map(|bill| BillResponse::new(bill, today))
+        .filter(|bill| bill.days_until_due.is_some_and(|until_due| until_due <= days))
+        .collect();
+
+    let overdue_count = reminders.iter().filter(|bill| bill.status == "overdue").count();
+    let expected_total: f64 = reminders.iter().map(|bill| bill.expected_amount).sum();
+
+    Ok(Json(json!({
+        "success": true,
+        "data": reminders,
+        "meta": {
+            "days": days,
+            "overdue_count": overdue_count,
+            "expected_total": expected_total
+        }
+    })))
+}
+
+// Validates and stores a new bill. Also used when a detected subscription is converted.
+pub(crate) async fn insert_bill(
+    state: &AppState,
+    user_id: Uuid,
+    payload: &CreateBillRequest,
+    subscription_key: Option<&str>,
+) -> Result<Bill, AppError> {
+    payload.validate().map_err(|e| {
+        AppError::ValidationError(e.to_string())
+    })?;
+
+    validate_amounts(payload.amount, payload.amount_min, payload.amount_max)?;
+
+    if !BILL_FREQUENCIES.contains(&payload.frequency.as_str()) {
+        return Err(AppError::ValidationError(
+            "Frekuensi harus salah satu dari: once, weekly, monthly, yearly".to_string(),
+        ));
+    }
+
+    let interval_count = payload.interval_count.unwrap_or(1);
+    if !(1..=MAX_INTERVAL_COUNT).contains(&interval_count) {
+        return Err(AppError::ValidationError(format!(
+            "Interval harus antara 1-{}",
+            MAX_INTERVAL_COUNT
+        )));
+    }
+
+    let remind_days_before = payload.remind_days_before.unwrap_or(3);
+    validate_remind_days(remind_days_before)?;
+
+    if let Some(wallet_id) = payload.wallet_id {
+        ensure_wallet_exists(state, user_id, wallet_id).await?;
+    }
+    if let Some(category_id) = payload.category_id {
+        ensure_category_exists(state, user_id, category_id).await?;
+    }
+
+    let bill = sqlx::query_as::<_, Bill>(
+        r#"
+        INSERT INTO bills (id, user_id, payee, description, amount, amount_min, amount_max, wallet_id, category_id,
+            frequency, interval_count, anchor_day, next_due_date, remind_days_before, subscription_key)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+        RETURNING id, user_id, payee, description, amount, amount_min, amount_max, wallet_id, category_id,
+            frequency, interval_count, anchor_day, next_due_date, remind_days_before, subscription_key, created_at, updated_at, deleted_at
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(&payload.payee)
+    .bind(&payload.description)
+    .bind(payload.amount)
+    .bind(payload.amount_min)
+    .bind(payload.amount_max)
+    .bind(payload.wallet_id)
+    .bind(payload.category_id)
+    .bind(&payload.frequency)
+    .bind(interval_count)
+    .bind(payload.due_date.day() as i16)
+    .bind(payload.due_date)
+    .bind(remind_days_before)
+    .bind(subscription_key)
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(bill)
+}
+
+pub async fn create_bill(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateBillRequest>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let bill = insert_bill(&state, user_id, &payload, None).await?;
+    let today = db::get_user_today(&state.db, user_id).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Tagihan berhasil ditambahkan!",
+        "data": BillResponse::new(bill, today)
+    })))
+}
+
+pub async fn get_bill(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let bill = db::get_bill_by_id(&state.db, id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Bill".to_string()))?;
+    let payments = db::get_bill_payments(&state.db, id).await?;
+    let today = db::get_user_today(&state.db, user_id).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "bill": BillResponse::new(bill, today),
+            "payments": payments
+        }
+    })))
+}
+
+pub async fn update_bill(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateBillRequest>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    payload.validate().map_err(|e| {
+        AppError::ValidationError(e.to_string())
+    })?;
+
+    if let Some(days) = payload.remind_days_before {
+        validate_remind_days(days)?;
+    }
+    if let Some(wallet_id) = payload.wallet_id {
+        ensure_wallet_exists(&state, user_id, wallet_id).await?;
+    }
+    if let Some(category_id) = payload.category_id {
+        ensure_category_exists(&state, user_id, category_id).await?;
+    }
+
+    let existing = db::get_bill_by_id(&state.db, id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Bill".to_string()))?;
+
+    let range_changed = payload.amount_min.is_some() || payload.amount_max.is_some();
+    let (amount, amount_min, amount_max) = match (payload.amount, range_changed) {
+        (Some(_), true) => {
+            return Err(AppError::ValidationError(
+                "Isi jumlah tetap, atau perkiraan jumlah minimal dan maksimal".to_string(),
+            ));
+        }
+        (Some(amount), false) => (Some(amount), None, None),
+        (None, true) => (
+            None,
+            payload.amount_min.or(existing.amount_min),
+            payload.amount_max.or(existing.amount_max),
+        ),
+        (None, false) => (existing.amount, existing.amount_min, existing.amount_max),
+    };
+    validate_amounts(amount, amount_min, amount_max)?;
+
+    // A paid one-off bill can be reopened by giving it a new due date
+    let next_due_date = payload.next_due_date.or(existing.next_due_date);
+    let anchor_day = payload
+        .next_due_date
+        .map(|date| date.day() as i16)
+        .unwrap_or(existing.anchor_day);
+
+    let updated = sqlx::query_as::<_, Bill>(
+        r#"
+        UPDATE bills SET
+            payee = COALESCE($1, payee),
+            description = COALESCE($2, description),
+            amount = $3,
+            amount_min = $4,
+            amount_max = $5,
+            wallet_id = COALESCE($6, wallet_id),
+            category_id = COALESCE($7, category_id),
+            next_due_date = $8,
+            anchor_day = $9,
+            remind_days_before = COALESCE($10, remind_days_before),
+            updated_at = NOW()
+        WHERE id = $11 AND user_id = $12 AND deleted_at IS NULL
+        RETURNING id, user_id, payee, description, amount, amount_min, amount_max, wallet_id, category_id,
+            frequency, interval_count, anchor_day, next_due_date, remind_days_before, subscription_key, created_at, updated_at, deleted_at
+        "#
+    )
+    .bind(&payload.payee)
+    .bind(&payload.description)
+    .bind(amount)
+    .bind(amount_min)
+    .bind(amount_max)
+    .bind(payload.wallet_id)
+    .bind(payload.category_id)
+    .bind(next_due_date)
+    .bind(anchor_day)
+    .bind(payload.remind_days_before)
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or(AppError::NotFound("Bill".to_string()))?;
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Tagihan berhasil diupdate!",
+        "data": BillResponse::new(updated, today)
+    })))
+}
+
+pub async fn delete_bill(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    // Payments already made stay in the ledger
+    let result = sqlx::query(
+        r#"UPDATE bills SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL"#
+    )
+    .bind(id)
+    .bind(user_id)
+    .execute(&state.db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Bill".to_string()));
+    }
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Tagihan berhasil dihapus! Pembayaran yang sudah tercatat tetap tersimpan."
+    })))
+}
+
+// Pays the oldest open due date: books the expense on the wallet, records the payment and
+// moves the bill to its next due date
+pub async fn pay_bill(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<PayBillRequest>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+    let audit = AuditContext::from_headers(user_id, &headers)?;
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let paid_date = payload.date.unwrap_or(today);
+
+    let mut db_tx = state.db.begin().await?;
+
+    // Locked so a double tap cannot pay the same due date twice
+    let bill = sqlx::query_as::<_, Bill>(
+        r#"
+        SELECT b.id, b.user_id, b.payee, b.description, b.amount, b.amount_min, b.amount_max, b.wallet_id,
+               c.id AS category_id, b.frequency, b.interval_count, b.anchor_day, b.next_due_date,
+               b.remind_days_before, b.subscription_key, b.created_at, b.updated_at, b.deleted_at
+        FROM bills b
+        LEFT JOIN categories c ON c.id = b.category_id AND c.deleted_at IS NULL
+        WHERE b.id = $1 AND b.user_id = $2 AND b.deleted_at IS NULL
+        FOR UPDATE OF b
+        "#
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(&mut *db_tx)
+    .await?
+    .ok_or(AppError::NotFound("Bill".to_string()))?;
+
+    let due_date = bill.next_due_date.ok_or(AppError::Conflict(
+        "Tagihan ini sudah lunas".to_string(),
+    ))?;
+
+    let amount = match (payload.amount, bill.amount) {
+        (Some(amount), _) | (None, Some(amount)) => amount,
+        (None, None) => {
+            return Err(AppError::ValidationError(
+                "Jumlah wajib diisi untuk tagihan dengan perkiraan jumlah".to_string(),
+            ));
+        }
+    };
+    validate_amount(amount)?;
+
+    let wallet_id = payload.wallet_id.or(bill.wallet_id).ok_or(AppError::ValidationError(
+        "Pilih dompet untuk membayar tagihan".to_string(),
+    ))?;
+    let wallet_exists: bool = sqlx::query_scalar(
+        r#"SELECT EXISTS(SELECT 1 FROM wallets WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL)"#
+    )
+    .bind(wallet_id)
+    .bind(user_id)
+    .fetch_one(&mut *db_tx)
+    .await?;
+    if !wallet_exists {
+        return Err(AppError::NotFound("Wallet".to_string()));
+    }
+
+    let description = bill
+        .description
+        .clone()
+        .unwrap_or_else(|| format!("Tagihan {}", bill.payee));
+
+    let transaction = db::record_wallet_transaction(
+        &mut db_tx,
+        &audit,
+        &NewTransaction {
+            wallet_id,
+            category_id: bill.category_id,
+            transaction_type: "expense",
+            amount,
+            description: &description,
+            date: paid_date,
+            is_transfer: false,
+        },
+    )
+    .await?;
+
+    let payment = sqlx::query_as::<_, BillPayment>(
+        r#"
+        INSERT INTO bill_payments (id, bill_id, due_date, paid_date, amount, transaction_id)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, bill_id, due_date, paid_date, amount, transaction_id, created_at
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(bill.id)
+    .bind(due_date)
+    .bind(paid_date)
+    .bind(amount)
+    .bind(transaction.id)
+    .fetch_one(&mut *db_tx)
+    .await?;
+
+    let updated = sqlx::query_as::<_, Bill>(
+        r#"
+        UPDATE bills SET next_due_date = $1, updated_at = NOW()
+        WHERE id = $2
+        RETURNING id, user_id, payee, description, amount, amount_min, amount_max, wallet_id, category_id,
+            frequency, interval_count, anchor_day, next_due_date, remind_days_before, subscription_key, created_at, updated_at, deleted_at
+        "#
+    )
+    .bind(bill.due_after(due_date))
+    .bind(bill.id)
+    .fetch_one(&mut *db_tx)
+    .await?;
+
+    db_tx.commit().await?;
+
+    let transaction = TransactionResponse::from_with_category(transaction, &state.db).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Tagihan berhasil dibayar!",
+        "data": {
+            "bill": BillResponse::new(updated, today),
+            "payment": payment,
+            "transaction": transaction
+        }
+    })))
+}
+
+// Creates or replaces the secret of the calendar feed; the old feed URL stops working
+pub async fn rotate_calendar_token(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let token: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(CALENDAR_TOKEN_LENGTH)
+        .map(char::from)
+        .collect();
+
+    sqlx::query(r#"UPDATE users SET calendar_token = $1, updated_at = NOW() WHERE id = $2"#)
+        .bind(&token)
+        .bind(user_id)
+        .execute(&state.db)
+        .await?;
+
+    let url = format!("{}/api/bills/calendar/{}.ics", state.config.public_url, token);
+    // webcal:// makes calendar apps subscribe instead of downloading the file once
+    let webcal_url = url
+        .replacen("https://", "webcal://", 1)
+        .replacen("http://", "webcal://", 1);
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Link kalender tagihan berhasil dibuat! Link lama tidak berlaku lagi.",
+        "data": {
+            "token": token,
+            "url": url,
+            "webcal_url": webcal_url
+        }
+    })))
+}
+
+pub async fn revoke_calendar_token(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    sqlx::query(r#"UPDATE users SET calendar_token = NULL, updated_at = NOW() WHERE id = $1"#)
+        .bind(user_id)
+        .execute(&state.db)
+        .await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Link kalender tagihan berhasil dinonaktifkan!"
+    })))
+}
+
+// iCalendar feed of open due dates for calendar apps. Calendar apps cannot send a login,
+// so the token in the URL authenticates the request.
+pub async fn get_bills_calendar(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<Response, AppError> {
+    let token = token.strip_suffix(".ics").unwrap_or(&token);
+    let user_id = db::find_user_by_calendar_token(&state.db, token)
+        .await?
+        .ok_or(AppError::NotFound("Calendar".to_string()))?;
+    let user = db::find_user_by_id(&state.db, user_id)
+        .await?
+        .ok_or(AppError::NotFound("User".to_string()))?;
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let until = today + Duration::days(CALENDAR_HORIZON_DAYS);
+    let bills = db::get_user_bills(&state.db, user_id).await?;
+    let stamp = format_utc(Utc::now());
+
+    let mut ics = IcsWriter::new();
+    ics.begin("VCALENDAR")
+        .property("VERSION", "2.0")
+        .property("PRODID", "-//FinTrack//Tagihan//ID")
+        .property("CALSCALE", "GREGORIAN")
+        .property("METHOD", "PUBLISH")
+        .text("X-WR-CALNAME", "Tagihan FinTrack")
+        .text("X-WR-TIMEZONE", &user.timezone)
+        .property("REFRESH-INTERVAL;VALUE=DURATION", "PT6H")
+        .property("X-PUBLISHED-TTL", "PT6H");
+
+    for bill in &bills {
+        let amount = amount_label(bill);
+        for due_date in bill.due_dates_until(until, CALENDAR_MAX_EVENTS_PER_BILL) {
+            let summary = if due_date < today {
+                format!("Terlambat: {} ({})", bill.payee, amount)
+            } else {
+                format!("Tagihan {} ({})", bill.payee, amount)
+            };
+            let mut details = format!("Jumlah: {}", amount);
+            if let Some(description) = &bill.description {
+                details = format!("{}\n{}", description, details);
+            }
+
+            ics.begin("VEVENT")
+                .property("UID", &format!("bill-{}-{}@fintrack", bill.id, format_date(due_date)))
+                .property("DTSTAMP", &stamp)
+                .property("DTSTART;VALUE=DATE", &format_date(due_date))
+                .property("DTEND;VALUE=DATE", &format_date(due_date + Duration::days(1)))
+                .text("SUMMARY", &summary)
+                .text("DESCRIPTION", &details)
+                .property("TRANSP", "TRANSPARENT");
+
+            // Reminder at 09:00 on the day the app would remind about the bill
+            let trigger = match bill.remind_days_before {
+                0 => "PT9H".to_string(),
+                days => format!("-P{}DT15H", days - 1),
+            };
+            ics.begin("VALARM")
+                .property("ACTION", "DISPLAY")
+                .text("DESCRIPTION", &summary)
+                .property("TRIGGER", &trigger)
+                .end("VALARM");
+
+            ics.end("VEVENT");
+        }
+    }
+    ics.end("VCALENDAR");
+
+    Ok((
+        [
+            (CONTENT_TYPE, "text/calendar; charset=utf-8"),
+            (CONTENT_DISPOSITION, "inline; filename=\"fintrack-tagihan.ics\""),
+            (CACHE_CONTROL, "private, max-age=900"),
+        ],
+        ics.finish(),
+    )
+        .into_response())
+}
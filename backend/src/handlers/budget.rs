@@ -4,19 +4,32 @@ use axum::{
     http::{header::AUTHORIZATION, HeaderMap},
     Json,
 };
+use chrono::{Datelike, NaiveDate};
 use serde_json::{json, Value};
+use std::collections::HashMap;
 use uuid::Uuid;
 
 use crate::{
     db,
     error::AppError,
-    models::budget::{
-        Budget, BudgetResponse, CopyBudgetRequest, CreateBudgetRequest, UpdateBudgetRequest,
+    models::{
+        audit::{AuditAction, AuditContext},
+        budget::{
+            Budget, BudgetHistoryQuery, BudgetHistoryResponse, BudgetPeriodUsage, BudgetResponse,
+            CategoryBudgetTrend, CopyBudgetRequest, CreateBudgetRequest, UpdateBudgetRequest,
+        },
     },
     utils::jwt::verify_token,
     AppState,
 };
 
+// A period counts as under budget when usage is at or below this percentage
+const UNDER_BUDGET_USAGE_PERCENTAGE: f64 = 50.0;
+// A category is flagged when at least this share of its budgeted periods (and at least
+// TREND_MIN_PERIODS of them) are over, or under, budget
+const TREND_RATIO: f64 = 0.75;
+const TREND_MIN_PERIODS: i32 = 3;
+
 async fn get_user_id(state: &AppState, headers: &HeaderMap) -> Result<Uuid, AppError> {
     let auth_header = headers
         .get(AUTHORIZATION)
@@ -31,6 +44,36 @@ async fn get_user_id(state: &AppState, headers: &HeaderMap) -> Result<Uuid, AppE
     Ok(claims.sub)
 }
 
+// Sorts and de-duplicates a wallet scope so equal scopes compare equal in the unique index,
+// and checks every wallet belongs to the user. An empty list means all wallets (None).
+async fn normalize_wallet_ids(
+    state: &AppState,
+    user_id: Uuid,
+    wallet_ids: Vec<Uuid>,
+) -> Result<Option<Vec<Uuid>>, AppError> {
+    let mut wallet_ids = wallet_ids;
+    wallet_ids.sort();
+    wallet_ids.dedup();
+
+    if wallet_ids.is_empty() {
+        return Ok(None);
+    }
+
+    let owned: i64 = sqlx::query_scalar(
+        r#"SELECT COUNT(*) FROM wallets WHERE id = ANY($1) AND user_id = $2 AND deleted_at IS NULL"#
+    )
+    .bind(&wallet_ids)
+    .bind(user_id)
+    .fetch_one(&state.db)
+    .await?;
+
+    if owned != wallet_ids.len() as i64 {
+        return Err(AppError::NotFound("Wallet".to_string()));
+    }
+
+    Ok(Some(wallet_ids))
+}
+
 #[derive(Debug, serde::Deserialize)]
 pub struct BudgetQueryParams {
     pub month: Option<i32>,
@@ -44,91 +87,9 @@ pub async fn list_budgets(
 ) -> Result<Json<Value>, AppError> {
     let user_id = get_user_id(&state, &headers).await?;
 
-    let budgets = db::get_user_budgets(&state.db, user_id, params.month, params.year).await?;
-
-    // Get budget usage for each budget
-    let mut budget_responses = Vec::new();
-    for budget in budgets {
-        let (used_amount, category_name) = if let Some(category_id) = budget.category_id {
-            // Get category name (only if not deleted)
-            let category: Option<(String,)> = sqlx::query_as(
-                r#"SELECT name FROM categories WHERE id = $1 AND deleted_at IS NULL"#
-            )
-            .bind(category_id)
-            .fetch_optional(&state.db)
-            .await?;
-
-            // Get used amount for this category
-            let used: Option<(f64,)> = sqlx::query_as(
-                r#"
-                SELECT COALESCE(SUM(amount), 0) as used
-                FROM transactions
-                WHERE user_id = $1
-                    AND category_id = $2
-                    AND transaction_type = 'expense'
-                    AND EXTRACT(MONTH FROM date) = $3
-                    AND EXTRACT(YEAR FROM date) = $4
-                "#
-            )
-            .bind(user_id)
-            .bind(category_id)
-            .bind(budget.month)
-            .bind(budget.year)
-            .fetch_optional(&state.db)
-            .await?;
-
-            (used.map(|u| u.0).unwrap_or(0.0), category.map(|c| c.0))
-        } else {
-            // Total budget - sum all expenses for the month
-            let used: Option<(f64,)> = sqlx::query_as(
-                r#"
-                SELECT COALESCE(SUM(amount), 0) as used
-                FROM transactions
-                WHERE user_id = $1
-                    AND transaction_type = 'expense'
-                    AND EXTRACT(MONTH FROM date) = $2
-                    AND EXTRACT(YEAR FROM date) = $3
-                "#
-            )
-            .bind(user_id)
-            .bind(budget.month)
-            .bind(budget.year)
-            .fetch_optional(&state.db)
-            .await?;
-
-            (used.map(|u| u.0).unwrap_or(0.0), None)
-        };
-
-        let remaining_amount = budget.amount - used_amount;
-        let usage_percentage = if budget.amount > 0.0 {
-            (used_amount / budget.amount) * 100.0
-        } else {
-            0.0
-        };
-        let is_over_budget = used_amount > budget.amount;
-        let should_alert = budget
-            .alert_threshold
-            .map(|threshold| usage_percentage >= threshold as f64)
-            .unwrap_or(false);
-
-        budget_responses.push(BudgetResponse {
-            id: budget.id,
-            category_id: budget.category_id,
-            category_name,
-            amount: budget.amount,
-            month: budget.month,
-            year: budget.year,
-            is_active: budget.is_active,
-            alert_threshold: budget.alert_threshold,
-            used_amount: Some(used_amount),
-            remaining_amount: Some(remaining_amount),
-            usage_percentage: Some(usage_percentage),
-            is_over_budget: Some(is_over_budget),
-            should_alert: Some(should_alert),
-            created_at: budget.created_at,
-            updated_at: budget.updated_at,
-        });
-    }
+    // Usage and category names come back from one aggregate query instead of two per budget
+    let budgets = db::get_user_budgets_with_usage(&state.db, user_id, params.month, params.year).await?;
+    let budget_responses: Vec<BudgetResponse> = budgets.into_iter().map(BudgetResponse::from).collect();
 
     Ok(Json(json!({
         "success": true,
@@ -141,11 +102,12 @@ pub async fn create_budget(
     headers: HeaderMap,
     payload: Result<Json<CreateBudgetRequest>, JsonRejection>,
 ) -> Result<Json<Value>, AppError> {
-    let payload = payload.map_err(|e| AppError::from(e))?;
+    let payload = payload.map_err(AppError::from)?;
     let payload = payload.0;
     
     tracing::debug!("Received budget payload: {:?}", payload);
     let user_id = get_user_id(&state, &headers).await?;
+    let audit = AuditContext::from_headers(user_id, &headers)?;
 
     // Validate amount manually first
     if payload.amount <= 0.0 || payload.amount.is_nan() || payload.amount.is_infinite() {
@@ -169,26 +131,33 @@ pub async fn create_budget(
 
     // Validate alert_threshold if provided
     if let Some(threshold) = payload.alert_threshold {
-        if threshold < 0 || threshold > 100 {
+        if !(0..=100).contains(&threshold) {
             return Err(AppError::ValidationError(
                 "Alert threshold harus antara 0-100".to_string(),
             ));
         }
     }
 
-    // Check if budget already exists for this user, category, month, year (not deleted)
+    let wallet_ids = match payload.wallet_ids {
+        Some(ids) => normalize_wallet_ids(&state, user_id, ids).await?,
+        None => None,
+    };
+
+    // Check if budget already exists for this user, category, wallets, month, year (not deleted)
     let existing: Option<(Uuid,)> = sqlx::query_as(
         r#"
         SELECT id FROM budgets
         WHERE user_id = $1
             AND (category_id IS NULL AND $2::uuid IS NULL OR category_id = $2)
-            AND month = $3
-            AND year = $4
+            AND wallet_ids IS NOT DISTINCT FROM $3::uuid[]
+            AND month = $4
+            AND year = $5
             AND deleted_at IS NULL
         "#
     )
     .bind(user_id)
-    .bind(&payload.category_id)
+    .bind(payload.category_id)
+    .bind(&wallet_ids)
     .bind(payload.month)
     .bind(payload.year)
     .fetch_optional(&state.db)
@@ -196,7 +165,7 @@ pub async fn create_budget(
 
     if existing.is_some() {
         return Err(AppError::ValidationError(
-            "Budget untuk kategori dan periode ini sudah ada".to_string(),
+            "Budget untuk kategori, wallet dan periode ini sudah ada".to_string(),
         ));
     }
 
@@ -204,24 +173,31 @@ pub async fn create_budget(
     let is_active = payload.is_active.unwrap_or(true);
     let alert_threshold = payload.alert_threshold.unwrap_or(80);
 
+    let mut db_tx = state.db.begin().await?;
+
     let budget = sqlx::query_as::<_, Budget>(
         r#"
-        INSERT INTO budgets (id, user_id, category_id, amount, month, year, is_active, alert_threshold)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-        RETURNING id, user_id, category_id, amount, month, year, is_active, alert_threshold, created_at, updated_at, deleted_at
+        INSERT INTO budgets (id, user_id, category_id, amount, month, year, is_active, alert_threshold, wallet_ids)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        RETURNING id, user_id, category_id, amount, month, year, is_active, alert_threshold, wallet_ids, created_at, updated_at, deleted_at
         "#
     )
     .bind(budget_id)
     .bind(user_id)
-    .bind(&payload.category_id)
+    .bind(payload.category_id)
     .bind(payload.amount)
     .bind(payload.month)
     .bind(payload.year)
     .bind(is_active)
     .bind(alert_threshold)
-    .fetch_one(&state.db)
+    .bind(&wallet_ids)
+    .fetch_one(&mut *db_tx)
     .await?;
 
+    db::insert_audit_log(&mut *db_tx, &audit, AuditAction::Create, None, &budget).await?;
+
+    db_tx.commit().await?;
+
     Ok(Json(json!({
         "success": true,
         "message": "Budget berhasil dibuat!",
@@ -234,13 +210,17 @@ pub async fn create_budget(
             year: budget.year,
             is_active: budget.is_active,
             alert_threshold: budget.alert_threshold,
+            wallet_ids: budget.wallet_ids,
             used_amount: Some(0.0),
             remaining_amount: Some(budget.amount),
             usage_percentage: Some(0.0),
             is_over_budget: Some(false),
             should_alert: Some(false),
+            committed_amount: None,
+            projected_remaining_amount: None,
             created_at: budget.created_at,
             updated_at: budget.updated_at,
+            deleted_at: None,
         }
     })))
 }
@@ -252,89 +232,13 @@ pub async fn get_budget(
 ) -> Result<Json<Value>, AppError> {
     let user_id = get_user_id(&state, &headers).await?;
 
-    let budget = db::get_budget_by_id(&state.db, id, user_id)
+    let budget = db::get_budget_with_usage(&state.db, id, user_id)
         .await?
         .ok_or(AppError::NotFound("Budget".to_string()))?;
 
-    // Calculate usage
-    let (used_amount, category_name) = if let Some(category_id) = budget.category_id {
-        let category: Option<(String,)> = sqlx::query_as(
-            r#"SELECT name FROM categories WHERE id = $1 AND deleted_at IS NULL"#
-        )
-        .bind(category_id)
-        .fetch_optional(&state.db)
-        .await?;
-
-        let used: Option<(f64,)> = sqlx::query_as(
-            r#"
-            SELECT COALESCE(SUM(amount), 0) as used
-            FROM transactions
-            WHERE user_id = $1
-                AND category_id = $2
-                AND transaction_type = 'expense'
-                AND EXTRACT(MONTH FROM date) = $3
-                AND EXTRACT(YEAR FROM date) = $4
-            "#
-        )
-        .bind(user_id)
-        .bind(category_id)
-        .bind(budget.month)
-        .bind(budget.year)
-        .fetch_optional(&state.db)
-        .await?;
-
-        (used.map(|u| u.0).unwrap_or(0.0), category.map(|c| c.0))
-    } else {
-        let used: Option<(f64,)> = sqlx::query_as(
-            r#"
-            SELECT COALESCE(SUM(amount), 0) as used
-            FROM transactions
-            WHERE user_id = $1
-                AND transaction_type = 'expense'
-                AND EXTRACT(MONTH FROM date) = $2
-                AND EXTRACT(YEAR FROM date) = $3
-            "#
-        )
-        .bind(user_id)
-        .bind(budget.month)
-        .bind(budget.year)
-        .fetch_optional(&state.db)
-        .await?;
-
-        (used.map(|u| u.0).unwrap_or(0.0), None)
-    };
-
-    let remaining_amount = budget.amount - used_amount;
-    let usage_percentage = if budget.amount > 0.0 {
-        (used_amount / budget.amount) * 100.0
-    } else {
-        0.0
-    };
-    let is_over_budget = used_amount > budget.amount;
-    let should_alert = budget
-        .alert_threshold
-        .map(|threshold| usage_percentage >= threshold as f64)
-        .unwrap_or(false);
-
     Ok(Json(json!({
         "success": true,
-        "data": BudgetResponse {
-            id: budget.id,
-            category_id: budget.category_id,
-            category_name,
-            amount: budget.amount,
-            month: budget.month,
-            year: budget.year,
-            is_active: budget.is_active,
-            alert_threshold: budget.alert_threshold,
-            used_amount: Some(used_amount),
-            remaining_amount: Some(remaining_amount),
-            usage_percentage: Some(usage_percentage),
-            is_over_budget: Some(is_over_budget),
-            should_alert: Some(should_alert),
-            created_at: budget.created_at,
-            updated_at: budget.updated_at,
-        }
+        "data": BudgetResponse::from(budget)
     })))
 }
 
@@ -345,6 +249,7 @@ pub async fn update_budget(
     Json(payload): Json<UpdateBudgetRequest>,
 ) -> Result<Json<Value>, AppError> {
     let user_id = get_user_id(&state, &headers).await?;
+    let audit = AuditContext::from_headers(user_id, &headers)?;
 
     if let Some(amount) = payload.amount {
         if amount <= 0.0 {
@@ -355,7 +260,7 @@ pub async fn update_budget(
     }
 
     if let Some(month) = payload.month {
-        if month < 1 || month > 12 {
+        if !(1..=12).contains(&month) {
             return Err(AppError::ValidationError(
                 "Month harus antara 1-12".to_string(),
             ));
@@ -363,7 +268,7 @@ pub async fn update_budget(
     }
 
     if let Some(year) = payload.year {
-        if year < 2000 || year > 3000 {
+        if !(2000..=3000).contains(&year) {
             return Err(AppError::ValidationError(
                 "Year harus valid".to_string(),
             ));
@@ -379,23 +284,33 @@ pub async fn update_budget(
     let new_category_id = payload.category_id.or(budget.category_id);
     let new_month = payload.month.unwrap_or(budget.month);
     let new_year = payload.year.unwrap_or(budget.year);
+    let new_wallet_ids = match payload.wallet_ids {
+        Some(ids) => normalize_wallet_ids(&state, user_id, ids).await?,
+        None => budget.wallet_ids.clone(),
+    };
 
-    // Check for conflicts if category, month, or year changed
-    if new_category_id != budget.category_id || new_month != budget.month || new_year != budget.year {
+    // Check for conflicts if category, wallets, month, or year changed
+    if new_category_id != budget.category_id
+        || new_wallet_ids != budget.wallet_ids
+        || new_month != budget.month
+        || new_year != budget.year
+    {
         let existing: Option<(Uuid,)> = sqlx::query_as(
             r#"
             SELECT id FROM budgets
             WHERE user_id = $1
                 AND id != $2
                 AND (category_id IS NULL AND $3::uuid IS NULL OR category_id = $3)
-                AND month = $4
-                AND year = $5
+                AND wallet_ids IS NOT DISTINCT FROM $4::uuid[]
+                AND month = $5
+                AND year = $6
                 AND deleted_at IS NULL
             "#
         )
         .bind(user_id)
         .bind(id)
-        .bind(&new_category_id)
+        .bind(new_category_id)
+        .bind(&new_wallet_ids)
         .bind(new_month)
         .bind(new_year)
         .fetch_optional(&state.db)
@@ -403,11 +318,13 @@ pub async fn update_budget(
 
         if existing.is_some() {
             return Err(AppError::ValidationError(
-                "Budget untuk kategori dan periode ini sudah ada".to_string(),
+                "Budget untuk kategori, wallet dan periode ini sudah ada".to_string(),
             ));
         }
     }
 
+    let mut db_tx = state.db.begin().await?;
+
     let updated_budget = sqlx::query_as::<_, Budget>(
         r#"
         UPDATE budgets SET
@@ -417,42 +334,32 @@ pub async fn update_budget(
             year = COALESCE($4, year),
             is_active = COALESCE($5, is_active),
             alert_threshold = COALESCE($6, alert_threshold),
+            wallet_ids = $7,
             updated_at = NOW()
-        WHERE id = $7 AND user_id = $8 AND deleted_at IS NULL
-        RETURNING id, user_id, category_id, amount, month, year, is_active, alert_threshold, created_at, updated_at, deleted_at
+        WHERE id = $8 AND user_id = $9 AND deleted_at IS NULL
+        RETURNING id, user_id, category_id, amount, month, year, is_active, alert_threshold, wallet_ids, created_at, updated_at, deleted_at
         "#
     )
-    .bind(&new_category_id)
+    .bind(new_category_id)
     .bind(payload.amount)
     .bind(payload.month)
     .bind(payload.year)
     .bind(payload.is_active)
     .bind(payload.alert_threshold)
+    .bind(&new_wallet_ids)
     .bind(id)
     .bind(user_id)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *db_tx)
     .await?;
 
+    db::insert_audit_log(&mut *db_tx, &audit, AuditAction::Update, Some(&budget), &updated_budget).await?;
+
+    db_tx.commit().await?;
+
     Ok(Json(json!({
         "success": true,
         "message": "Budget berhasil diupdate!",
-        "data": BudgetResponse {
-            id: updated_budget.id,
-            category_id: updated_budget.category_id,
-            category_name: None,
-            amount: updated_budget.amount,
-            month: updated_budget.month,
-            year: updated_budget.year,
-            is_active: updated_budget.is_active,
-            alert_threshold: updated_budget.alert_threshold,
-            used_amount: None,
-            remaining_amount: None,
-            usage_percentage: None,
-            is_over_budget: None,
-            should_alert: None,
-            created_at: updated_budget.created_at,
-            updated_at: updated_budget.updated_at,
-        }
+        "data": BudgetResponse::without_usage(updated_budget, None)
     })))
 }
 
@@ -462,36 +369,141 @@ pub async fn delete_budget(
     Path(id): Path<Uuid>,
 ) -> Result<Json<Value>, AppError> {
     let user_id = get_user_id(&state, &headers).await?;
+    let audit = AuditContext::from_headers(user_id, &headers)?;
 
     // Verify budget exists and is not already deleted
-    let budget_exists: bool = sqlx::query_scalar(
-        r#"SELECT EXISTS(SELECT 1 FROM budgets WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL)"#
+    let before = db::get_budget_by_id(&state.db, id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Budget".to_string()))?;
+
+    let mut db_tx = state.db.begin().await?;
+
+    // Soft delete: set deleted_at timestamp
+    let budget = sqlx::query_as::<_, Budget>(
+        r#"UPDATE budgets SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL
+           RETURNING id, user_id, category_id, amount, month, year, is_active, alert_threshold, wallet_ids, created_at, updated_at, deleted_at"#
     )
     .bind(id)
     .bind(user_id)
-    .fetch_one(&state.db)
-    .await?;
+    .fetch_optional(&mut *db_tx)
+    .await?
+    .ok_or(AppError::NotFound("Budget".to_string()))?;
 
-    if !budget_exists {
-        return Err(AppError::NotFound("Budget".to_string()));
-    }
+    db::insert_audit_log(&mut *db_tx, &audit, AuditAction::Delete, Some(&before), &budget).await?;
 
-    // Soft delete: set deleted_at timestamp
-    let result = sqlx::query(
-        r#"UPDATE budgets SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL"#
+    db_tx.commit().await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Budget berhasil dihapus!"
+    })))
+}
+
+pub async fn list_deleted_budgets(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let budgets = db::get_deleted_budgets(&state.db, user_id).await?;
+    let response: Vec<BudgetResponse> = budgets.into_iter().map(BudgetResponse::from).collect();
+
+    Ok(Json(json!({
+        "success": true,
+        "data": response,
+        "meta": {
+            "retention_days": state.config.trash_retention_days
+        }
+    })))
+}
+
+pub async fn restore_budget(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+    let audit = AuditContext::from_headers(user_id, &headers)?;
+
+    let budget = sqlx::query_as::<_, Budget>(
+        r#"
+        SELECT id, user_id, category_id, amount, month, year, is_active, alert_threshold, wallet_ids, created_at, updated_at, deleted_at
+        FROM budgets WHERE id = $1 AND user_id = $2 AND deleted_at IS NOT NULL
+        "#
     )
     .bind(id)
     .bind(user_id)
-    .execute(&state.db)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or(AppError::NotFound("Budget".to_string()))?;
+
+    if let Some(category_id) = budget.category_id {
+        let category_active: bool = sqlx::query_scalar(
+            r#"SELECT EXISTS(SELECT 1 FROM categories WHERE id = $1 AND deleted_at IS NULL)"#
+        )
+        .bind(category_id)
+        .fetch_one(&state.db)
+        .await?;
+
+        if !category_active {
+            return Err(AppError::Conflict(
+                "Kategori budget ini sudah dihapus. Pulihkan kategorinya terlebih dahulu.".to_string(),
+            ));
+        }
+    }
+
+    // Same uniqueness rule as create_budget: one active budget per scope and period
+    let existing: Option<(Uuid,)> = sqlx::query_as(
+        r#"
+        SELECT id FROM budgets
+        WHERE user_id = $1
+            AND id != $2
+            AND (category_id IS NULL AND $3::uuid IS NULL OR category_id = $3)
+            AND wallet_ids IS NOT DISTINCT FROM $4::uuid[]
+            AND month = $5
+            AND year = $6
+            AND deleted_at IS NULL
+        "#
+    )
+    .bind(user_id)
+    .bind(id)
+    .bind(budget.category_id)
+    .bind(&budget.wallet_ids)
+    .bind(budget.month)
+    .bind(budget.year)
+    .fetch_optional(&state.db)
     .await?;
 
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound("Budget".to_string()));
+    if existing.is_some() {
+        return Err(AppError::Conflict(
+            "Budget untuk kategori, wallet dan periode ini sudah ada".to_string(),
+        ));
     }
 
+    let mut db_tx = state.db.begin().await?;
+
+    let after = sqlx::query_as::<_, Budget>(
+        r#"UPDATE budgets SET deleted_at = NULL, updated_at = NOW() WHERE id = $1 AND user_id = $2 AND deleted_at IS NOT NULL
+           RETURNING id, user_id, category_id, amount, month, year, is_active, alert_threshold, wallet_ids, created_at, updated_at, deleted_at"#
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(&mut *db_tx)
+    .await?
+    .ok_or(AppError::NotFound("Budget".to_string()))?;
+
+    db::insert_audit_log(&mut *db_tx, &audit, AuditAction::Restore, Some(&budget), &after).await?;
+
+    db_tx.commit().await?;
+
+    let restored = db::get_budget_with_usage(&state.db, id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Budget".to_string()))?;
+
     Ok(Json(json!({
         "success": true,
-        "message": "Budget berhasil dihapus!"
+        "message": "Budget berhasil dipulihkan!",
+        "data": BudgetResponse::from(restored)
     })))
 }
 
@@ -500,10 +512,11 @@ pub async fn copy_budget(
     headers: HeaderMap,
     payload: Result<Json<CopyBudgetRequest>, JsonRejection>,
 ) -> Result<Json<Value>, AppError> {
-    let payload = payload.map_err(|e| AppError::from(e))?;
+    let payload = payload.map_err(AppError::from)?;
     let payload = payload.0;
     
     let user_id = get_user_id(&state, &headers).await?;
+    let audit = AuditContext::from_headers(user_id, &headers)?;
 
     // Validate manually
     if payload.source_month < 1 || payload.source_month > 12 {
@@ -566,27 +579,31 @@ pub async fn copy_budget(
     }
 
     // Copy each budget
+    let mut db_tx = state.db.begin().await?;
     let mut copied_budgets = Vec::new();
     for source_budget in source_budgets {
         let new_budget_id = Uuid::new_v4();
         let copied_budget = sqlx::query_as::<_, Budget>(
             r#"
-            INSERT INTO budgets (id, user_id, category_id, amount, month, year, is_active, alert_threshold)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-            RETURNING id, user_id, category_id, amount, month, year, is_active, alert_threshold, created_at, updated_at, deleted_at
+            INSERT INTO budgets (id, user_id, category_id, amount, month, year, is_active, alert_threshold, wallet_ids)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING id, user_id, category_id, amount, month, year, is_active, alert_threshold, wallet_ids, created_at, updated_at, deleted_at
             "#
         )
         .bind(new_budget_id)
         .bind(user_id)
-        .bind(&source_budget.category_id)
+        .bind(source_budget.category_id)
         .bind(source_budget.amount)
         .bind(payload.target_month)
         .bind(payload.target_year)
         .bind(source_budget.is_active)
         .bind(source_budget.alert_threshold)
-        .fetch_one(&state.db)
+        .bind(&source_budget.wallet_ids)
+        .fetch_one(&mut *db_tx)
         .await?;
 
+        db::insert_audit_log(&mut *db_tx, &audit, AuditAction::Create, None, &copied_budget).await?;
+
         copied_budgets.push(BudgetResponse {
             id: copied_budget.id,
             category_id: copied_budget.category_id,
@@ -596,16 +613,22 @@ pub async fn copy_budget(
             year: copied_budget.year,
             is_active: copied_budget.is_active,
             alert_threshold: copied_budget.alert_threshold,
+            wallet_ids: copied_budget.wallet_ids,
             used_amount: Some(0.0),
             remaining_amount: Some(copied_budget.amount),
             usage_percentage: Some(0.0),
             is_over_budget: Some(false),
             should_alert: Some(false),
+            committed_amount: None,
+            projected_remaining_amount: None,
             created_at: copied_budget.created_at,
             updated_at: copied_budget.updated_at,
+            deleted_at: None,
         });
     }
 
+    db_tx.commit().await?;
+
     Ok(Json(json!({
         "success": true,
         "message": format!(
@@ -619,3 +642,494 @@ pub async fn copy_budget(
     })))
 }
 
+
+// Returns `count` consecutive (year, month) periods ending at end_year/end_month, oldest first
+fn period_range(end_year: i32, end_month: i32, count: i32) -> Vec<(i32, i32)> {
+    let end_index = end_year * 12 + (end_month - 1);
+    (0..count)
+        .rev()
+        .map(|offset| {
+            let index = end_index - offset;
+            (index / 12, index % 12 + 1)
+        })
+        .collect()
+}
+
+fn period_status(budgeted: f64, actual: f64) -> &'static str {
+    if actual > budgeted {
+        "over"
+    } else if budgeted > 0.0 && (actual / budgeted) * 100.0 <= UNDER_BUDGET_USAGE_PERCENTAGE {
+        "under"
+    } else {
+        "on_track"
+    }
+}
+
+pub async fn budget_history(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<BudgetHistoryQuery>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let periods = params.periods.unwrap_or(6);
+    if !(1..=24).contains(&periods) {
+        return Err(AppError::ValidationError(
+            "Periods harus antara 1-24".to_string(),
+        ));
+    }
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let end_month = params.end_month.unwrap_or(today.month() as i32);
+    let end_year = params.end_year.unwrap_or(today.year());
+    if !(1..=12).contains(&end_month) {
+        return Err(AppError::ValidationError(
+            "Month harus antara 1-12".to_string(),
+        ));
+    }
+    if !(2000..=3000).contains(&end_year) {
+        return Err(AppError::ValidationError(
+            "Year harus valid (2000-3000)".to_string(),
+        ));
+    }
+
+    let category_name = if let Some(category_id) = params.category_id {
+        let category: Option<(String,)> = sqlx::query_as(
+            r#"SELECT name FROM categories WHERE id = $1 AND (user_id = $2 OR user_id IS NULL) AND deleted_at IS NULL"#
+        )
+        .bind(category_id)
+        .bind(user_id)
+        .fetch_optional(&state.db)
+        .await?;
+
+        Some(category.ok_or(AppError::NotFound("Category".to_string()))?.0)
+    } else {
+        None
+    };
+
+    let range = period_range(end_year, end_month, periods);
+    let (first_year, first_month) = range[0];
+    let from = NaiveDate::from_ymd_opt(first_year, first_month as u32, 1).unwrap();
+    let to = NaiveDate::from_ymd_opt(end_year, end_month as u32, 1).unwrap();
+
+    let budgets = db::get_user_budgets_with_usage_in_range(&state.db, user_id, from, to).await?;
+    let expenses = db::get_monthly_expenses(&state.db, user_id, params.category_id, from, to).await?;
+
+    let actual_by_period: HashMap<(i32, i32), f64> = expenses
+        .into_iter()
+        .map(|(year, month, total)| ((year, month), total))
+        .collect();
+    // History and trends cover budgets across all wallets; wallet-scoped budgets are
+    // separate caps and would double count the same spending
+    let budgets: Vec<_> = budgets
+        .into_iter()
+        .filter(|row| row.budget.wallet_ids.is_none())
+        .collect();
+    let budget_by_period: HashMap<(i32, i32), f64> = budgets
+        .iter()
+        .filter(|row| row.budget.category_id == params.category_id)
+        .map(|row| ((row.budget.year, row.budget.month), row.budget.amount))
+        .collect();
+
+    let mut history = Vec::new();
+    let (mut total_budgeted, mut total_actual) = (0.0, 0.0);
+    let (mut over_count, mut under_count) = (0, 0);
+    for (year, month) in range {
+        let actual = actual_by_period.get(&(year, month)).copied().unwrap_or(0.0);
+        let budgeted = budget_by_period.get(&(year, month)).copied();
+
+        let status = match budgeted {
+            Some(amount) => {
+                total_budgeted += amount;
+                total_actual += actual;
+                period_status(amount, actual)
+            }
+            None => "no_budget",
+        };
+        match status {
+            "over" => over_count += 1,
+            "under" => under_count += 1,
+            _ => {}
+        }
+
+        history.push(BudgetPeriodUsage {
+            month,
+            year,
+            budgeted,
+            actual,
+            variance: budgeted.map(|amount| amount - actual),
+            usage_percentage: budgeted
+                .filter(|amount| *amount > 0.0)
+                .map(|amount| (actual / amount) * 100.0),
+            status: status.to_string(),
+        });
+    }
+
+    // Flag category budgets that keep ending up over, or well under, their amount
+    let mut trends: HashMap<Uuid, CategoryBudgetTrend> = HashMap::new();
+    for row in &budgets {
+        let Some(category_id) = row.budget.category_id else {
+            continue;
+        };
+        let trend = trends.entry(category_id).or_insert_with(|| CategoryBudgetTrend {
+            category_id,
+            category_name: row.category_name.clone(),
+            periods_with_budget: 0,
+            over_count: 0,
+            under_count: 0,
+            total_budgeted: 0.0,
+            total_actual: 0.0,
+            trend: String::new(),
+        });
+        trend.periods_with_budget += 1;
+        trend.total_budgeted += row.budget.amount;
+        trend.total_actual += row.used_amount;
+        match period_status(row.budget.amount, row.used_amount) {
+            "over" => trend.over_count += 1,
+            "under" => trend.under_count += 1,
+            _ => {}
+        }
+    }
+
+    let mut flagged_categories: Vec<CategoryBudgetTrend> = trends
+        .into_values()
+        .filter_map(|mut trend| {
+            if trend.periods_with_budget < TREND_MIN_PERIODS {
+                return None;
+            }
+            let required = trend.periods_with_budget as f64 * TREND_RATIO;
+            if trend.over_count as f64 >= required {
+                trend.trend = "consistently_over".to_string();
+            } else if trend.under_count as f64 >= required {
+                trend.trend = "consistently_under".to_string();
+            } else {
+                return None;
+            }
+            Some(trend)
+        })
+        .collect();
+    flagged_categories.sort_by(|a, b| {
+        (b.total_actual - b.total_budgeted)
+            .abs()
+            .total_cmp(&(a.total_actual - a.total_budgeted).abs())
+    });
+
+    Ok(Json(json!({
+        "success": true,
+        "data": BudgetHistoryResponse {
+            category_id: params.category_id,
+            category_name,
+            periods: history,
+            total_budgeted,
+            total_actual,
+            total_variance: total_budgeted - total_actual,
+            over_count,
+            under_count,
+            flagged_categories,
+        }
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{auth_headers, count_queries, create_user, create_wallet, test_state};
+    use sqlx::PgPool;
+
+    const MONTH: i32 = 3;
+    const YEAR: i32 = 2025;
+
+    // A user with `count` category budgets for MONTH/YEAR, each with two expenses against it
+    async fn seed_budgets(pool: &PgPool, count: usize) -> (Uuid, Vec<Uuid>) {
+        let (user_id, wallet_id) = create_user(pool).await;
+        let mut budget_ids = Vec::new();
+        for i in 0..count {
+            let category_id: Uuid = sqlx::query_scalar(
+                "INSERT INTO categories (user_id, name, category_type) VALUES ($1, $2, 'expense') RETURNING id",
+            )
+            .bind(user_id)
+            .bind(format!("Kategori {}", i))
+            .fetch_one(pool)
+            .await
+            .unwrap();
+            budget_ids.push(
+                sqlx::query_scalar(
+                    "INSERT INTO budgets (user_id, category_id, amount, month, year) VALUES ($1, $2, 100000, $3, $4) RETURNING id",
+                )
+                .bind(user_id)
+                .bind(category_id)
+                .bind(MONTH)
+                .bind(YEAR)
+                .fetch_one(pool)
+                .await
+                .unwrap(),
+            );
+            sqlx::query(
+                r#"INSERT INTO transactions (user_id, wallet_id, category_id, transaction_type, amount, date)
+                   SELECT $1, $2, $3, 'expense', 10000, make_date($4, $5, d) FROM generate_series(1, 2) AS d"#,
+            )
+            .bind(user_id)
+            .bind(wallet_id)
+            .bind(category_id)
+            .bind(YEAR)
+            .bind(MONTH)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+        (user_id, budget_ids)
+    }
+
+    async fn list_budget_queries(pool: &PgPool, count: usize) -> usize {
+        let (user_id, _) = seed_budgets(pool, count).await;
+        let state = test_state(pool.clone());
+        let params = BudgetQueryParams { month: Some(MONTH), year: Some(YEAR) };
+
+        let (response, queries) = count_queries(list_budgets(State(state), auth_headers(user_id), Query(params))).await;
+        let budgets = response.unwrap().0["data"].as_array().unwrap().clone();
+        assert_eq!(budgets.len(), count);
+        assert!(budgets.iter().all(|budget| budget["used_amount"] == 20000.0));
+        queries
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn list_budgets_query_count_is_constant(pool: PgPool) {
+        let one = list_budget_queries(&pool, 1).await;
+        let many = list_budget_queries(&pool, 40).await;
+        assert_eq!(one, 1);
+        assert_eq!(many, one);
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn get_budget_query_count_is_constant(pool: PgPool) {
+        let (user_id, budget_ids) = seed_budgets(&pool, 40).await;
+        let state = test_state(pool.clone());
+
+        for id in [budget_ids[0], budget_ids[39]] {
+            let (response, queries) =
+                count_queries(get_budget(State(state.clone()), auth_headers(user_id), Path(id))).await;
+            assert_eq!(response.unwrap().0["data"]["used_amount"], 20000.0);
+            assert_eq!(queries, 1);
+        }
+    }
+
+    #[test]
+    fn period_range_crosses_year_boundary() {
+        assert_eq!(period_range(2025, 2, 3), vec![(2024, 12), (2025, 1), (2025, 2)]);
+        assert_eq!(period_range(2025, 12, 1), vec![(2025, 12)]);
+    }
+
+    #[test]
+    fn period_status_thresholds() {
+        assert_eq!(period_status(100000.0, 100001.0), "over");
+        assert_eq!(period_status(100000.0, 100000.0), "on_track");
+        assert_eq!(period_status(100000.0, 50001.0), "on_track");
+        assert_eq!(period_status(100000.0, 50000.0), "under");
+        assert_eq!(period_status(100000.0, 0.0), "under");
+        assert_eq!(period_status(0.0, 0.0), "on_track");
+        assert_eq!(period_status(0.0, 1.0), "over");
+    }
+
+    // A category with a 100000 budget in each of the given months of YEAR, and `spent` in expenses
+    // in each month (index-aligned with `months`)
+    async fn seed_category_history(
+        pool: &PgPool,
+        user_id: Uuid,
+        wallet_id: Uuid,
+        name: &str,
+        months: &[i32],
+        spent: &[f64],
+    ) -> Uuid {
+        let category_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO categories (user_id, name, category_type) VALUES ($1, $2, 'expense') RETURNING id",
+        )
+        .bind(user_id)
+        .bind(name)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        for (month, amount) in months.iter().zip(spent) {
+            sqlx::query(
+                "INSERT INTO budgets (user_id, category_id, amount, month, year) VALUES ($1, $2, 100000, $3, $4)",
+            )
+            .bind(user_id)
+            .bind(category_id)
+            .bind(month)
+            .bind(YEAR)
+            .execute(pool)
+            .await
+            .unwrap();
+            sqlx::query(
+                r#"INSERT INTO transactions (user_id, wallet_id, category_id, transaction_type, amount, date)
+                   VALUES ($1, $2, $3, 'expense', $4, make_date($5, $6, 10))"#,
+            )
+            .bind(user_id)
+            .bind(wallet_id)
+            .bind(category_id)
+            .bind(amount)
+            .bind(YEAR)
+            .bind(month)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+        category_id
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn budget_history_reports_variance_and_flags_trends(pool: PgPool) {
+        let (user_id, wallet_id) = create_user(&pool).await;
+        let over = seed_category_history(
+            &pool, user_id, wallet_id, "Makan", &[1, 2, 3, 4], &[120000.0, 130000.0, 110000.0, 90000.0],
+        )
+        .await;
+        let under = seed_category_history(
+            &pool, user_id, wallet_id, "Hiburan", &[1, 2, 3, 4], &[10000.0, 20000.0, 30000.0, 40000.0],
+        )
+        .await;
+        // Over in half of its periods: not consistent enough to flag
+        seed_category_history(
+            &pool, user_id, wallet_id, "Transport", &[1, 2, 3, 4], &[150000.0, 150000.0, 80000.0, 80000.0],
+        )
+        .await;
+        // Always over, but in fewer than TREND_MIN_PERIODS periods
+        seed_category_history(&pool, user_id, wallet_id, "Belanja", &[3, 4], &[200000.0, 200000.0]).await;
+
+        let params = BudgetHistoryQuery {
+            category_id: Some(over),
+            periods: Some(5),
+            end_month: Some(4),
+            end_year: Some(YEAR),
+        };
+        let response = budget_history(State(test_state(pool.clone())), auth_headers(user_id), Query(params))
+            .await
+            .unwrap();
+        let data = &response.0["data"];
+
+        let periods = data["periods"].as_array().unwrap();
+        let statuses: Vec<&str> = periods.iter().map(|p| p["status"].as_str().unwrap()).collect();
+        assert_eq!(statuses, ["no_budget", "over", "over", "over", "on_track"]);
+        assert_eq!((periods[0]["year"].as_i64(), periods[0]["month"].as_i64()), (Some(2024), Some(12)));
+        assert_eq!(periods[2]["variance"], -30000.0);
+        assert_eq!(periods[4]["usage_percentage"], 90.0);
+        assert_eq!(data["total_budgeted"], 400000.0);
+        assert_eq!(data["total_actual"], 450000.0);
+        assert_eq!(data["total_variance"], -50000.0);
+        assert_eq!(data["over_count"], 3);
+        assert_eq!(data["under_count"], 0);
+
+        // Sorted by how far actual spending is from the budget, largest first
+        let flagged = data["flagged_categories"].as_array().unwrap();
+        assert_eq!(flagged.len(), 2);
+        assert_eq!(flagged[0]["category_id"], under.to_string());
+        assert_eq!(flagged[0]["trend"], "consistently_under");
+        assert_eq!(flagged[0]["under_count"], 4);
+        assert_eq!(flagged[1]["category_id"], over.to_string());
+        assert_eq!(flagged[1]["trend"], "consistently_over");
+        assert_eq!(flagged[1]["over_count"], 3);
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn budget_history_rejects_out_of_range_periods(pool: PgPool) {
+        let (user_id, _) = create_user(&pool).await;
+        for periods in [0, 25] {
+            let params = BudgetHistoryQuery { category_id: None, periods: Some(periods), end_month: None, end_year: None };
+            let result = budget_history(State(test_state(pool.clone())), auth_headers(user_id), Query(params)).await;
+            assert!(matches!(result, Err(AppError::ValidationError(_))));
+        }
+    }
+
+    fn budget_request(category_id: Option<Uuid>, wallet_ids: Option<Vec<Uuid>>) -> CreateBudgetRequest {
+        CreateBudgetRequest {
+            category_id,
+            amount: 100000.0,
+            month: MONTH,
+            year: YEAR,
+            is_active: None,
+            alert_threshold: None,
+            wallet_ids,
+        }
+    }
+
+    async fn create(state: &AppState, user_id: Uuid, request: CreateBudgetRequest) -> Result<(), AppError> {
+        create_budget(State(state.clone()), auth_headers(user_id), Ok(Json(request))).await.map(|_| ())
+    }
+
+    async fn add_expense(pool: &PgPool, user_id: Uuid, wallet_id: Uuid, amount: f64) {
+        sqlx::query(
+            r#"INSERT INTO transactions (user_id, wallet_id, transaction_type, amount, date)
+               VALUES ($1, $2, 'expense', $3, make_date($4, $5, 1))"#,
+        )
+        .bind(user_id)
+        .bind(wallet_id)
+        .bind(amount)
+        .bind(YEAR)
+        .bind(MONTH)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn wallet_scoped_budget_only_counts_its_wallets(pool: PgPool) {
+        let (user_id, cash) = create_user(&pool).await;
+        let card = create_wallet(&pool, user_id, "Kartu Kredit", "credit-card").await;
+        let ewallet = create_wallet(&pool, user_id, "E-Wallet", "e-wallet").await;
+        add_expense(&pool, user_id, cash, 10000.0).await;
+        add_expense(&pool, user_id, card, 20000.0).await;
+        add_expense(&pool, user_id, ewallet, 40000.0).await;
+
+        let state = test_state(pool.clone());
+        let scopes = [None, Some(vec![card]), Some(vec![ewallet, card, card])];
+        for wallet_ids in scopes {
+            create(&state, user_id, budget_request(None, wallet_ids)).await.unwrap();
+        }
+
+        let params = BudgetQueryParams { month: Some(MONTH), year: Some(YEAR) };
+        let response = list_budgets(State(state), auth_headers(user_id), Query(params)).await.unwrap();
+        let mut usage: Vec<(Option<usize>, f64)> = response.0["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|budget| {
+                let wallets = budget["wallet_ids"].as_array().map(|ids| ids.len());
+                (wallets, budget["used_amount"].as_f64().unwrap())
+            })
+            .collect();
+        usage.sort_by(|a, b| a.1.total_cmp(&b.1));
+        // The wallet group is stored de-duplicated
+        assert_eq!(usage, [(Some(1), 20000.0), (Some(2), 60000.0), (None, 70000.0)]);
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn budget_scope_uniqueness_ignores_wallet_order(pool: PgPool) {
+        let (user_id, cash) = create_user(&pool).await;
+        let card = create_wallet(&pool, user_id, "Kartu Kredit", "credit-card").await;
+        let state = test_state(pool.clone());
+
+        create(&state, user_id, budget_request(None, Some(vec![cash, card]))).await.unwrap();
+
+        let result = create(&state, user_id, budget_request(None, Some(vec![card, cash]))).await;
+        assert!(matches!(result, Err(AppError::ValidationError(_))));
+
+        // A different wallet scope, or all wallets, is a separate budget
+        create(&state, user_id, budget_request(None, Some(vec![card]))).await.unwrap();
+        create(&state, user_id, budget_request(None, Some(vec![]))).await.unwrap();
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn budget_scope_rejects_other_users_wallets(pool: PgPool) {
+        let (user_id, _) = create_user(&pool).await;
+        let (_, other_wallet) = create_user(&pool).await;
+
+        let result = create(&test_state(pool.clone()), user_id, budget_request(None, Some(vec![other_wallet]))).await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+    }
+}
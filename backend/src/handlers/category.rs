@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::{header::AUTHORIZATION, HeaderMap},
     Json,
 };
@@ -10,7 +10,13 @@ use validator::Validate;
 use crate::{
     db,
     error::AppError,
-    models::category::{Category, CategoryResponse, CreateCategoryRequest, UpdateCategoryRequest},
+    models::{
+        audit::{AuditAction, AuditContext},
+        category::{
+            Category, CategoryMergeResult, CategoryQuery, CategoryResponse, CreateCategoryRequest,
+            DeleteCategoryQuery, MergeCategoryRequest, UpdateCategoryRequest,
+        },
+    },
     utils::jwt::verify_token,
     AppState,
 };
@@ -29,14 +35,55 @@ async fn get_user_id(state: &AppState, headers: &HeaderMap) -> Result<Uuid, AppE
     Ok(claims.sub)
 }
 
+// Checks that parent_id can be the parent of a category of category_type. When updating an
+// existing category, also rejects parents inside its own subtree, which would create a cycle.
+async fn validate_parent(
+    state: &AppState,
+    user_id: Uuid,
+    parent_id: Uuid,
+    category_type: &str,
+    category_id: Option<Uuid>,
+) -> Result<(), AppError> {
+    let parent_type: Option<String> = sqlx::query_scalar(
+        r#"SELECT category_type FROM categories WHERE id = $1 AND (user_id = $2 OR user_id IS NULL) AND deleted_at IS NULL"#
+    )
+    .bind(parent_id)
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    let parent_type = parent_type.ok_or(AppError::NotFound("Kategori induk".to_string()))?;
+    if parent_type != category_type {
+        return Err(AppError::ValidationError(
+            "Kategori induk harus memiliki tipe yang sama".to_string(),
+        ));
+    }
+
+    if let Some(category_id) = category_id {
+        let subtree = db::get_category_subtree_ids(&state.db, user_id, category_id).await?;
+        if subtree.contains(&parent_id) {
+            return Err(AppError::ValidationError(
+                "Kategori tidak bisa menjadi sub-kategori dari dirinya sendiri atau turunannya".to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
 pub async fn list_categories(
     State(state): State<AppState>,
     headers: HeaderMap,
+    Query(query): Query<CategoryQuery>,
 ) -> Result<Json<Value>, AppError> {
     let user_id = get_user_id(&state, &headers).await?;
 
     let categories = db::get_user_categories(&state.db, user_id).await?;
-    let response: Vec<CategoryResponse> = categories.into_iter().map(CategoryResponse::from).collect();
+    let response: Vec<CategoryResponse> = if query.flat.unwrap_or(false) {
+        categories.into_iter().map(CategoryResponse::from).collect()
+    } else {
+        CategoryResponse::tree(categories)
+    };
 
     Ok(Json(json!({
         "success": true,
@@ -50,17 +97,24 @@ pub async fn create_category(
     Json(payload): Json<CreateCategoryRequest>,
 ) -> Result<Json<Value>, AppError> {
     let user_id = get_user_id(&state, &headers).await?;
+    let audit = AuditContext::from_headers(user_id, &headers)?;
 
     payload.validate().map_err(|e| {
         AppError::ValidationError(e.to_string())
     })?;
 
+    if let Some(parent_id) = payload.parent_id {
+        validate_parent(&state, user_id, parent_id, &payload.category_type, None).await?;
+    }
+
     let category_id = Uuid::new_v4();
+    let mut db_tx = state.db.begin().await?;
+
     let category = sqlx::query_as::<_, Category>(
         r#"
-        INSERT INTO categories (id, user_id, name, icon, color, category_type)
-        VALUES ($1, $2, $3, $4, $5, $6)
-        RETURNING id, user_id, name, icon, color, category_type, created_at, deleted_at
+        INSERT INTO categories (id, user_id, name, icon, color, category_type, parent_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id, user_id, name, icon, color, category_type, parent_id, created_at, deleted_at
         "#
     )
     .bind(category_id)
@@ -69,9 +123,14 @@ pub async fn create_category(
     .bind(&payload.icon)
     .bind(&payload.color)
     .bind(&payload.category_type)
-    .fetch_one(&state.db)
+    .bind(payload.parent_id)
+    .fetch_one(&mut *db_tx)
     .await?;
 
+    db::insert_audit_log(&mut *db_tx, &audit, AuditAction::Create, None, &category).await?;
+
+    db_tx.commit().await?;
+
     Ok(Json(json!({
         "success": true,
         "message": "Kategori berhasil dibuat!",
@@ -86,31 +145,53 @@ pub async fn update_category(
     Json(payload): Json<UpdateCategoryRequest>,
 ) -> Result<Json<Value>, AppError> {
     let user_id = get_user_id(&state, &headers).await?;
+    let audit = AuditContext::from_headers(user_id, &headers)?;
 
     payload.validate().map_err(|e| {
         AppError::ValidationError(e.to_string())
     })?;
 
     // Check if category exists and belongs to user
-    let category_exists: bool = sqlx::query_scalar(
-        r#"SELECT EXISTS(SELECT 1 FROM categories WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL)"#
+    let before = sqlx::query_as::<_, Category>(
+        r#"SELECT id, user_id, name, icon, color, category_type, parent_id, created_at, deleted_at
+           FROM categories WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL"#
     )
     .bind(id)
     .bind(user_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or(AppError::NotFound("Category".to_string()))?;
+
+    let parent_id = payload.parent_id.unwrap_or(before.parent_id);
+
+    if let Some(parent_id) = parent_id {
+        validate_parent(&state, user_id, parent_id, &payload.category_type, Some(id)).await?;
+    }
+
+    // Sub-categories must keep the same type as their parent
+    let has_other_type_children: bool = sqlx::query_scalar(
+        r#"SELECT EXISTS(SELECT 1 FROM categories WHERE parent_id = $1 AND category_type <> $2 AND deleted_at IS NULL)"#
+    )
+    .bind(id)
+    .bind(&payload.category_type)
     .fetch_one(&state.db)
     .await?;
 
-    if !category_exists {
-        return Err(AppError::NotFound("Category".to_string()));
+    if has_other_type_children {
+        return Err(AppError::ValidationError(
+            "Tipe kategori tidak bisa diubah karena sub-kategorinya memiliki tipe berbeda".to_string(),
+        ));
     }
 
+    let mut db_tx = state.db.begin().await?;
+
     // Update category
     let category = sqlx::query_as::<_, Category>(
         r#"
         UPDATE categories 
-        SET name = $3, icon = $4, color = $5, category_type = $6, updated_at = NOW()
+        SET name = $3, icon = $4, color = $5, category_type = $6, parent_id = $7, updated_at = NOW()
         WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL
-        RETURNING id, user_id, name, icon, color, category_type, created_at, deleted_at
+        RETURNING id, user_id, name, icon, color, category_type, parent_id, created_at, deleted_at
         "#
     )
     .bind(id)
@@ -119,42 +200,266 @@ pub async fn update_category(
     .bind(&payload.icon)
     .bind(&payload.color)
     .bind(&payload.category_type)
+    .bind(parent_id)
+    .fetch_optional(&mut *db_tx)
+    .await?
+    .ok_or(AppError::NotFound("Category".to_string()))?;
+
+    db::insert_audit_log(&mut *db_tx, &audit, AuditAction::Update, Some(&before), &category).await?;
+
+    db_tx.commit().await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Kategori berhasil diupdate!",
+        "data": CategoryResponse::from(category)
+    })))
+}
+
+// Moves transactions, budgets and sub-categories from source_id to target_id and soft
+// deletes the source, all in one database transaction. A source budget that collides with
+// a target budget for the same wallets and period is added onto the target budget's amount.
+// Every touched row gets an audit entry; the bulk moves snapshot rows with to_jsonb.
+async fn merge_category(
+    state: &AppState,
+    audit: &AuditContext,
+    source_id: Uuid,
+    target_id: Uuid,
+) -> Result<CategoryMergeResult, AppError> {
+    let user_id = audit.user_id;
+
+    if source_id == target_id {
+        return Err(AppError::ValidationError(
+            "Kategori tujuan harus berbeda dengan kategori asal".to_string(),
+        ));
+    }
+
+    let source = sqlx::query_as::<_, Category>(
+        r#"SELECT id, user_id, name, icon, color, category_type, parent_id, created_at, deleted_at
+           FROM categories WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL"#
+    )
+    .bind(source_id)
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or(AppError::NotFound("Category".to_string()))?;
+
+    let target_type: Option<String> = sqlx::query_scalar(
+        r#"SELECT category_type FROM categories WHERE id = $1 AND (user_id = $2 OR user_id IS NULL) AND deleted_at IS NULL"#
+    )
+    .bind(target_id)
+    .bind(user_id)
     .fetch_optional(&state.db)
     .await?;
+    let target_type = target_type.ok_or(AppError::NotFound("Kategori tujuan".to_string()))?;
 
-    match category {
-        Some(cat) => Ok(Json(json!({
-            "success": true,
-            "message": "Kategori berhasil diupdate!",
-            "data": CategoryResponse::from(cat)
-        }))),
-        None => Err(AppError::NotFound("Category".to_string())),
+    if source.category_type != target_type {
+        return Err(AppError::ValidationError(
+            "Kategori tujuan harus memiliki tipe yang sama".to_string(),
+        ));
     }
+
+    // Moving sub-categories under one of their own descendants would create a cycle
+    let subtree = db::get_category_subtree_ids(&state.db, user_id, source_id).await?;
+    if subtree.contains(&target_id) {
+        return Err(AppError::ValidationError(
+            "Kategori tidak bisa digabung ke sub-kategorinya sendiri".to_string(),
+        ));
+    }
+
+    let mut tx = state.db.begin().await?;
+
+    // Each statement joins the table to itself as `old` to capture the row before the update,
+    // and the row count of the audit insert equals the number of rows changed
+    let transactions_moved = sqlx::query(
+        r#"
+        WITH moved AS (
+            UPDATE transactions t SET category_id = $3, updated_at = NOW()
+            FROM transactions old
+            WHERE old.id = t.id AND t.category_id = $2 AND t.user_id = $1
+            RETURNING t.id, to_jsonb(old) AS before_data, to_jsonb(t) AS after_data
+        )
+        INSERT INTO audit_logs (user_id, actor_id, source_channel, entity_type, entity_id, action, before_data, after_data)
+        SELECT $1, $1, $4, 'transaction', id, 'update', before_data, after_data FROM moved
+        "#
+    )
+    .bind(user_id)
+    .bind(source_id)
+    .bind(target_id)
+    .bind(audit.channel.as_str())
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    // Fold colliding budgets into the target budget, then retire them
+    sqlx::query(
+        r#"
+        WITH folded AS (
+            UPDATE budgets target SET amount = target.amount + source.amount, updated_at = NOW()
+            FROM budgets source, budgets old
+            WHERE old.id = target.id
+                AND source.user_id = $1 AND source.category_id = $2 AND source.deleted_at IS NULL
+                AND target.user_id = $1 AND target.category_id = $3 AND target.deleted_at IS NULL
+                AND target.month = source.month
+                AND target.year = source.year
+                AND target.wallet_ids IS NOT DISTINCT FROM source.wallet_ids
+            RETURNING target.id, to_jsonb(old) AS before_data, to_jsonb(target) AS after_data
+        )
+        INSERT INTO audit_logs (user_id, actor_id, source_channel, entity_type, entity_id, action, before_data, after_data)
+        SELECT $1, $1, $4, 'budget', id, 'update', before_data, after_data FROM folded
+        "#
+    )
+    .bind(user_id)
+    .bind(source_id)
+    .bind(target_id)
+    .bind(audit.channel.as_str())
+    .execute(&mut *tx)
+    .await?;
+
+    let budgets_merged = sqlx::query(
+        r#"
+        WITH merged AS (
+            UPDATE budgets source SET deleted_at = NOW(), updated_at = NOW()
+            FROM budgets old
+            WHERE old.id = source.id
+                AND source.user_id = $1 AND source.category_id = $2 AND source.deleted_at IS NULL
+                AND EXISTS (
+                    SELECT 1 FROM budgets target
+                    WHERE target.user_id = $1 AND target.category_id = $3 AND target.deleted_at IS NULL
+                        AND target.month = source.month
+                        AND target.year = source.year
+                        AND target.wallet_ids IS NOT DISTINCT FROM source.wallet_ids
+                )
+            RETURNING source.id, to_jsonb(old) AS before_data, to_jsonb(source) AS after_data
+        )
+        INSERT INTO audit_logs (user_id, actor_id, source_channel, entity_type, entity_id, action, before_data, after_data)
+        SELECT $1, $1, $4, 'budget', id, 'delete', before_data, after_data FROM merged
+        "#
+    )
+    .bind(user_id)
+    .bind(source_id)
+    .bind(target_id)
+    .bind(audit.channel.as_str())
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    let budgets_moved = sqlx::query(
+        r#"
+        WITH moved AS (
+            UPDATE budgets b SET category_id = $3, updated_at = NOW()
+            FROM budgets old
+            WHERE old.id = b.id AND b.user_id = $1 AND b.category_id = $2 AND b.deleted_at IS NULL
+            RETURNING b.id, to_jsonb(old) AS before_data, to_jsonb(b) AS after_data
+        )
+        INSERT INTO audit_logs (user_id, actor_id, source_channel, entity_type, entity_id, action, before_data, after_data)
+        SELECT $1, $1, $4, 'budget', id, 'update', before_data, after_data FROM moved
+        "#
+    )
+    .bind(user_id)
+    .bind(source_id)
+    .bind(target_id)
+    .bind(audit.channel.as_str())
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    let subcategories_moved = sqlx::query(
+        r#"
+        WITH moved AS (
+            UPDATE categories c SET parent_id = $3, updated_at = NOW()
+            FROM categories old
+            WHERE old.id = c.id AND c.user_id = $1 AND c.parent_id = $2 AND c.deleted_at IS NULL
+            RETURNING c.id, to_jsonb(old) AS before_data, to_jsonb(c) AS after_data
+        )
+        INSERT INTO audit_logs (user_id, actor_id, source_channel, entity_type, entity_id, action, before_data, after_data)
+        SELECT $1, $1, $4, 'category', id, 'update', before_data, after_data FROM moved
+        "#
+    )
+    .bind(user_id)
+    .bind(source_id)
+    .bind(target_id)
+    .bind(audit.channel.as_str())
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    let deleted_source = sqlx::query_as::<_, Category>(
+        r#"UPDATE categories SET deleted_at = NOW() WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL
+           RETURNING id, user_id, name, icon, color, category_type, parent_id, created_at, deleted_at"#
+    )
+    .bind(source_id)
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    db::insert_audit_log(&mut *tx, audit, AuditAction::Delete, Some(&source), &deleted_source).await?;
+
+    tx.commit().await?;
+
+    Ok(CategoryMergeResult {
+        transactions_moved,
+        budgets_moved,
+        budgets_merged,
+        subcategories_moved,
+    })
+}
+
+pub async fn merge_categories(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<MergeCategoryRequest>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+    let audit = AuditContext::from_headers(user_id, &headers)?;
+
+    let result = merge_category(&state, &audit, id, payload.target_id).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Kategori berhasil digabungkan!",
+        "data": result
+    })))
 }
 
 pub async fn delete_category(
     State(state): State<AppState>,
     headers: HeaderMap,
     Path(id): Path<Uuid>,
+    Query(query): Query<DeleteCategoryQuery>,
 ) -> Result<Json<Value>, AppError> {
     let user_id = get_user_id(&state, &headers).await?;
+    let audit = AuditContext::from_headers(user_id, &headers)?;
+
+    if let Some(target_id) = query.reassign_to {
+        let result = merge_category(&state, &audit, id, target_id).await?;
+
+        return Ok(Json(json!({
+            "success": true,
+            "message": format!(
+                "Kategori berhasil dihapus! {} transaksi dan {} budget dipindahkan.",
+                result.transactions_moved,
+                result.budgets_moved + result.budgets_merged
+            ),
+            "data": result
+        })));
+    }
 
     // Check if category exists and is not already deleted
-    let category_exists: bool = sqlx::query_scalar(
-        r#"SELECT EXISTS(SELECT 1 FROM categories WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL)"#
+    let before = sqlx::query_as::<_, Category>(
+        r#"SELECT id, user_id, name, icon, color, category_type, parent_id, created_at, deleted_at
+           FROM categories WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL"#
     )
     .bind(id)
     .bind(user_id)
-    .fetch_one(&state.db)
-    .await?;
-
-    if !category_exists {
-        return Err(AppError::NotFound("Category".to_string()));
-    }
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or(AppError::NotFound("Category".to_string()))?;
 
     // Check usage in transactions
     let transaction_count: (i64,) = sqlx::query_as(
-        r#"SELECT COUNT(*) FROM transactions WHERE category_id = $1 AND user_id = $2"#
+        r#"SELECT COUNT(*) FROM transactions WHERE category_id = $1 AND user_id = $2 AND deleted_at IS NULL"#
     )
     .bind(id)
     .bind(user_id)
@@ -173,26 +478,387 @@ pub async fn delete_category(
     // If category is used, return error with details
     if transaction_count.0 > 0 || budget_count.0 > 0 {
         return Err(AppError::Conflict(format!(
-            "Kategori ini digunakan di {} transaksi dan {} budget. Hapus atau update data terkait terlebih dahulu.",
+            "Kategori ini digunakan di {} transaksi dan {} budget. Pindahkan dengan reassign_to, atau hapus/update data terkait terlebih dahulu.",
             transaction_count.0, budget_count.0
         )));
     }
 
-    // Soft delete: set deleted_at timestamp
-    let result = sqlx::query(
-        r#"UPDATE categories SET deleted_at = NOW() WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL"#
+    // Check for sub-categories
+    let child_count: (i64,) = sqlx::query_as(
+        r#"SELECT COUNT(*) FROM categories WHERE parent_id = $1 AND deleted_at IS NULL"#
     )
     .bind(id)
-    .bind(user_id)
-    .execute(&state.db)
+    .fetch_one(&state.db)
     .await?;
 
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound("Category".to_string()));
+    if child_count.0 > 0 {
+        return Err(AppError::Conflict(format!(
+            "Kategori ini memiliki {} sub-kategori. Pindahkan dengan reassign_to, atau pindahkan/hapus sub-kategori terlebih dahulu.",
+            child_count.0
+        )));
     }
 
+    let mut db_tx = state.db.begin().await?;
+
+    // Soft delete: set deleted_at timestamp
+    let category = sqlx::query_as::<_, Category>(
+        r#"UPDATE categories SET deleted_at = NOW() WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL
+           RETURNING id, user_id, name, icon, color, category_type, parent_id, created_at, deleted_at"#
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(&mut *db_tx)
+    .await?
+    .ok_or(AppError::NotFound("Category".to_string()))?;
+
+    db::insert_audit_log(&mut *db_tx, &audit, AuditAction::Delete, Some(&before), &category).await?;
+
+    db_tx.commit().await?;
+
     Ok(Json(json!({
         "success": true,
         "message": "Kategori berhasil dihapus!"
     })))
 }
+
+pub async fn list_deleted_categories(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let categories = db::get_deleted_categories(&state.db, user_id).await?;
+    let response: Vec<CategoryResponse> = categories.into_iter().map(CategoryResponse::from).collect();
+
+    Ok(Json(json!({
+        "success": true,
+        "data": response,
+        "meta": {
+            "retention_days": state.config.trash_retention_days
+        }
+    })))
+}
+
+pub async fn restore_category(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+    let audit = AuditContext::from_headers(user_id, &headers)?;
+
+    let mut db_tx = state.db.begin().await?;
+
+    let before = sqlx::query_as::<_, Category>(
+        r#"SELECT id, user_id, name, icon, color, category_type, parent_id, created_at, deleted_at
+           FROM categories WHERE id = $1 AND user_id = $2 AND deleted_at IS NOT NULL FOR UPDATE"#
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(&mut *db_tx)
+    .await?
+    .ok_or(AppError::NotFound("Category".to_string()))?;
+
+    // A category whose parent is still deleted (or gone) comes back as top-level
+    let category = sqlx::query_as::<_, Category>(
+        r#"
+        UPDATE categories c SET
+            deleted_at = NULL,
+            parent_id = CASE
+                WHEN EXISTS(SELECT 1 FROM categories p WHERE p.id = c.parent_id AND p.deleted_at IS NULL)
+                THEN c.parent_id
+                ELSE NULL
+            END,
+            updated_at = NOW()
+        WHERE c.id = $1 AND c.user_id = $2 AND c.deleted_at IS NOT NULL
+        RETURNING id, user_id, name, icon, color, category_type, parent_id, created_at, deleted_at
+        "#
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(&mut *db_tx)
+    .await?
+    .ok_or(AppError::NotFound("Category".to_string()))?;
+
+    db::insert_audit_log(&mut *db_tx, &audit, AuditAction::Restore, Some(&before), &category).await?;
+
+    db_tx.commit().await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Kategori berhasil dipulihkan!",
+        "data": CategoryResponse::from(category)
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{auth_headers, create_user, test_state};
+    use chrono::NaiveDate;
+    use sqlx::PgPool;
+
+    async fn create(
+        state: &AppState,
+        user_id: Uuid,
+        name: &str,
+        category_type: &str,
+        parent_id: Option<Uuid>,
+    ) -> Result<Uuid, AppError> {
+        let request = CreateCategoryRequest {
+            name: name.to_string(),
+            icon: None,
+            color: None,
+            category_type: category_type.to_string(),
+            parent_id,
+        };
+        let response = create_category(State(state.clone()), auth_headers(user_id), Json(request)).await?;
+        Ok(response.0["data"]["id"].as_str().unwrap().parse().unwrap())
+    }
+
+    async fn set_parent(
+        state: &AppState,
+        user_id: Uuid,
+        id: Uuid,
+        parent_id: Option<Uuid>,
+    ) -> Result<(), AppError> {
+        let request = UpdateCategoryRequest {
+            name: "Kategori".to_string(),
+            icon: None,
+            color: None,
+            category_type: "expense".to_string(),
+            parent_id: Some(parent_id),
+        };
+        update_category(State(state.clone()), auth_headers(user_id), Path(id), Json(request))
+            .await
+            .map(|_| ())
+    }
+
+    async fn add_expense(pool: &PgPool, user_id: Uuid, wallet_id: Uuid, category_id: Uuid, amount: f64) {
+        sqlx::query(
+            r#"INSERT INTO transactions (user_id, wallet_id, category_id, transaction_type, amount, date)
+               VALUES ($1, $2, $3, 'expense', $4, '2025-03-10')"#,
+        )
+        .bind(user_id)
+        .bind(wallet_id)
+        .bind(category_id)
+        .bind(amount)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn category_totals(pool: &PgPool, user_id: Uuid, parent_id: Option<Uuid>) -> Vec<(Uuid, f64)> {
+        let start = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        db::get_category_totals(pool, user_id, start, None, "expense", parent_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|stat| (stat.id, stat.total))
+            .collect()
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn validate_parent_prevents_cycles(pool: PgPool) {
+        let (user_id, _) = create_user(&pool).await;
+        let state = test_state(pool.clone());
+        let food = create(&state, user_id, "Makanan", "expense", None).await.unwrap();
+        let dining = create(&state, user_id, "Restoran", "expense", Some(food)).await.unwrap();
+        let cafe = create(&state, user_id, "Kafe", "expense", Some(dining)).await.unwrap();
+
+        for parent_id in [food, dining, cafe] {
+            let result = set_parent(&state, user_id, food, Some(parent_id)).await;
+            assert!(matches!(result, Err(AppError::ValidationError(_))), "parent {}", parent_id);
+        }
+
+        // Moving a category up the tree, or to the top level, is fine
+        set_parent(&state, user_id, cafe, Some(food)).await.unwrap();
+        set_parent(&state, user_id, dining, None).await.unwrap();
+        set_parent(&state, user_id, food, Some(dining)).await.unwrap();
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn validate_parent_requires_same_type_and_visible_parent(pool: PgPool) {
+        let (user_id, _) = create_user(&pool).await;
+        let (other_user_id, _) = create_user(&pool).await;
+        let state = test_state(pool.clone());
+        let salary = create(&state, user_id, "Gaji", "income", None).await.unwrap();
+        let foreign = create(&state, other_user_id, "Makanan", "expense", None).await.unwrap();
+
+        let result = create(&state, user_id, "Restoran", "expense", Some(salary)).await;
+        assert!(matches!(result, Err(AppError::ValidationError(_))));
+        let result = create(&state, user_id, "Restoran", "expense", Some(foreign)).await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn spending_rolls_up_into_parent_categories(pool: PgPool) {
+        let (user_id, wallet_id) = create_user(&pool).await;
+        let state = test_state(pool.clone());
+        let food = create(&state, user_id, "Makanan", "expense", None).await.unwrap();
+        let dining = create(&state, user_id, "Restoran", "expense", Some(food)).await.unwrap();
+        let cafe = create(&state, user_id, "Kafe", "expense", Some(dining)).await.unwrap();
+        let groceries = create(&state, user_id, "Groceries", "expense", Some(food)).await.unwrap();
+        add_expense(&pool, user_id, wallet_id, food, 1000.0).await;
+        add_expense(&pool, user_id, wallet_id, dining, 2000.0).await;
+        add_expense(&pool, user_id, wallet_id, cafe, 4000.0).await;
+        add_expense(&pool, user_id, wallet_id, groceries, 8000.0).await;
+
+        assert_eq!(category_totals(&pool, user_id, None).await, [(food, 15000.0)]);
+        // One level down, each child includes its own descendants and the parent only its own spending
+        assert_eq!(
+            category_totals(&pool, user_id, Some(food)).await,
+            [(groceries, 8000.0), (dining, 6000.0), (food, 1000.0)]
+        );
+
+        sqlx::query("INSERT INTO budgets (user_id, category_id, amount, month, year) VALUES ($1, $2, 20000, 3, 2025)")
+            .bind(user_id)
+            .bind(food)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let budgets = db::get_user_budgets_with_usage(&pool, user_id, Some(3), Some(2025)).await.unwrap();
+        assert_eq!(budgets[0].used_amount, 15000.0);
+    }
+
+    async fn add_budget(pool: &PgPool, user_id: Uuid, category_id: Uuid, amount: f64, month: i32) {
+        sqlx::query("INSERT INTO budgets (user_id, category_id, amount, month, year) VALUES ($1, $2, $3, $4, 2025)")
+            .bind(user_id)
+            .bind(category_id)
+            .bind(amount)
+            .bind(month)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn merge_moves_transactions_budgets_and_subcategories(pool: PgPool) {
+        let (user_id, wallet_id) = create_user(&pool).await;
+        let state = test_state(pool.clone());
+        let source = create(&state, user_id, "Jajan", "expense", None).await.unwrap();
+        let child = create(&state, user_id, "Kopi", "expense", Some(source)).await.unwrap();
+        let target = create(&state, user_id, "Makanan", "expense", None).await.unwrap();
+        add_expense(&pool, user_id, wallet_id, source, 1000.0).await;
+        add_expense(&pool, user_id, wallet_id, source, 2000.0).await;
+        add_budget(&pool, user_id, source, 50000.0, 3).await;
+        add_budget(&pool, user_id, source, 60000.0, 4).await;
+        add_budget(&pool, user_id, target, 100000.0, 3).await;
+
+        let request = MergeCategoryRequest { target_id: target };
+        let response = merge_categories(State(state), auth_headers(user_id), Path(source), Json(request))
+            .await
+            .unwrap();
+        let data = &response.0["data"];
+        assert_eq!(data["transactions_moved"], 2);
+        assert_eq!(data["budgets_merged"], 1);
+        assert_eq!(data["budgets_moved"], 1);
+        assert_eq!(data["subcategories_moved"], 1);
+
+        // The colliding March budget is folded into the target's, April's simply moves over
+        let budgets: Vec<(i32, f64)> = sqlx::query_as(
+            "SELECT month, amount FROM budgets WHERE category_id = $1 AND deleted_at IS NULL ORDER BY month",
+        )
+        .bind(target)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(budgets, [(3, 150000.0), (4, 60000.0)]);
+
+        let moved: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM transactions WHERE category_id = $1")
+            .bind(target)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(moved, 2);
+        let child_parent: Option<Uuid> = sqlx::query_scalar("SELECT parent_id FROM categories WHERE id = $1")
+            .bind(child)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(child_parent, Some(target));
+        let source_deleted: bool = sqlx::query_scalar("SELECT deleted_at IS NOT NULL FROM categories WHERE id = $1")
+            .bind(source)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert!(source_deleted);
+
+        // Two transactions, three budgets, one sub-category and the source itself
+        let audit_entries: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM audit_logs WHERE user_id = $1 AND action <> 'create'")
+                .bind(user_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(audit_entries, 7);
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn merge_rejects_invalid_targets(pool: PgPool) {
+        let (user_id, _) = create_user(&pool).await;
+        let state = test_state(pool.clone());
+        let source = create(&state, user_id, "Jajan", "expense", None).await.unwrap();
+        let child = create(&state, user_id, "Kopi", "expense", Some(source)).await.unwrap();
+        let income = create(&state, user_id, "Bonus", "income", None).await.unwrap();
+
+        for target_id in [source, child, income] {
+            let request = MergeCategoryRequest { target_id };
+            let result =
+                merge_categories(State(state.clone()), auth_headers(user_id), Path(source), Json(request)).await;
+            assert!(matches!(result, Err(AppError::ValidationError(_))), "target {}", target_id);
+        }
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn delete_used_category_requires_reassign_to(pool: PgPool) {
+        let (user_id, wallet_id) = create_user(&pool).await;
+        let state = test_state(pool.clone());
+        let source = create(&state, user_id, "Jajan", "expense", None).await.unwrap();
+        let target = create(&state, user_id, "Makanan", "expense", None).await.unwrap();
+        add_expense(&pool, user_id, wallet_id, source, 1000.0).await;
+
+        let query = DeleteCategoryQuery { reassign_to: None };
+        let result = delete_category(State(state.clone()), auth_headers(user_id), Path(source), Query(query)).await;
+        assert!(matches!(result, Err(AppError::Conflict(_))));
+
+        let query = DeleteCategoryQuery { reassign_to: Some(target) };
+        let response = delete_category(State(state), auth_headers(user_id), Path(source), Query(query))
+            .await
+            .unwrap();
+        assert_eq!(response.0["data"]["transactions_moved"], 1);
+        assert_eq!(category_totals(&pool, user_id, None).await, [(target, 1000.0)]);
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn restore_category_under_deleted_parent_becomes_top_level(pool: PgPool) {
+        let (user_id, _) = create_user(&pool).await;
+        let state = test_state(pool.clone());
+        let parent = create(&state, user_id, "Makanan", "expense", None).await.unwrap();
+        let child = create(&state, user_id, "Kopi", "expense", Some(parent)).await.unwrap();
+        for id in [child, parent] {
+            let query = DeleteCategoryQuery { reassign_to: None };
+            let response = delete_category(State(state.clone()), auth_headers(user_id), Path(id), Query(query))
+                .await
+                .unwrap();
+            assert_eq!(response.0["success"], true);
+        }
+
+        let trash = list_deleted_categories(State(state.clone()), auth_headers(user_id)).await.unwrap();
+        assert_eq!(trash.0["data"].as_array().unwrap().len(), 2);
+
+        let restored = restore_category(State(state.clone()), auth_headers(user_id), Path(child)).await.unwrap();
+        assert_eq!(restored.0["data"]["parent_id"], Value::Null);
+
+        // Restoring twice finds nothing in the trash
+        let result = restore_category(State(state), auth_headers(user_id), Path(child)).await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+    }
+}
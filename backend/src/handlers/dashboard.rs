@@ -1,14 +1,32 @@
 use axum::{
-    extract::State,
+    extract::{Query, State},
     http::{header::AUTHORIZATION, HeaderMap},
     Json,
 };
-use chrono::{Datelike, Utc};
+use chrono::{Datelike, Duration, NaiveDate};
 use serde::Serialize;
 use serde_json::{json, Value};
 use uuid::Uuid;
 
-use crate::{error::AppError, utils::jwt::verify_token, AppState};
+use crate::{
+    db,
+    error::AppError,
+    models::{
+        health::{compute_health, HealthInputs, HealthQuery, DEFAULT_HEALTH_MONTHS},
+        net_worth::{BalanceBackfillRequest, NetWorthPointResponse, NetWorthQuery},
+        wallet::{CREDIT_WALLET_TYPES, INVESTMENT_WALLET_TYPE},
+    },
+    utils::{
+        dates::{day_of_month, shift_months},
+        jwt::verify_token,
+    },
+    AppState,
+};
+
+// Upper bound on points in one net worth history response
+const MAX_NET_WORTH_POINTS: usize = 400;
+// How far back a balance backfill may reach
+const MAX_BACKFILL_DAYS: i64 = 3650;
 
 async fn get_user_id(state: &AppState, headers: &HeaderMap) -> Result<Uuid, AppError> {
     let auth_header = headers
@@ -33,6 +51,13 @@ pub struct DashboardSummary {
     pub this_month_expense: f64,
     pub wallet_count: i64,
     pub transaction_count: i64,
+    pub receivables: f64, // open piutang
+    pub payables: f64,    // open hutang
+    pub investment_value: f64, // market value of investment holdings
+    // Wallet balances, investments and receivables, minus credit wallet debt and payables
+    pub total_assets: f64,
+    pub total_liabilities: f64,
+    pub net_worth: f64,
 }
 
 pub async fn get_summary(
@@ -49,24 +74,24 @@ pub async fn get_summary(
     .await?;
 
     let total_income: (f64,) = sqlx::query_as(
-        r#"SELECT COALESCE(SUM(amount)::float8, 0) FROM transactions WHERE user_id = $1 AND transaction_type = 'income'"#
+        r#"SELECT COALESCE(SUM(amount)::float8, 0) FROM transactions WHERE user_id = $1 AND deleted_at IS NULL AND NOT is_transfer AND transaction_type = 'income'"#
     )
     .bind(user_id)
     .fetch_one(&state.db)
     .await?;
 
     let total_expense: (f64,) = sqlx::query_as(
-        r#"SELECT COALESCE(SUM(amount)::float8, 0) FROM transactions WHERE user_id = $1 AND transaction_type = 'expense'"#
+        r#"SELECT COALESCE(SUM(amount)::float8, 0) FROM transactions WHERE user_id = $1 AND deleted_at IS NULL AND NOT is_transfer AND transaction_type = 'expense'"#
     )
     .bind(user_id)
     .fetch_one(&state.db)
     .await?;
 
-    let now = Utc::now();
-    let first_day = chrono::NaiveDate::from_ymd_opt(now.year(), now.month(), 1).unwrap();
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let first_day = today.with_day(1).unwrap();
     
     let this_month_income: (f64,) = sqlx::query_as(
-        r#"SELECT COALESCE(SUM(amount)::float8, 0) FROM transactions WHERE user_id = $1 AND transaction_type = 'income' AND date >= $2"#
+        r#"SELECT COALESCE(SUM(amount)::float8, 0) FROM transactions WHERE user_id = $1 AND deleted_at IS NULL AND NOT is_transfer AND transaction_type = 'income' AND date >= $2"#
     )
     .bind(user_id)
     .bind(first_day)
@@ -74,7 +99,7 @@ pub async fn get_summary(
     .await?;
 
     let this_month_expense: (f64,) = sqlx::query_as(
-        r#"SELECT COALESCE(SUM(amount)::float8, 0) FROM transactions WHERE user_id = $1 AND transaction_type = 'expense' AND date >= $2"#
+        r#"SELECT COALESCE(SUM(amount)::float8, 0) FROM transactions WHERE user_id = $1 AND deleted_at IS NULL AND NOT is_transfer AND transaction_type = 'expense' AND date >= $2"#
     )
     .bind(user_id)
     .bind(first_day)
@@ -89,12 +114,30 @@ pub async fn get_summary(
     .await?;
 
     let transaction_count: (i64,) = sqlx::query_as(
-        r#"SELECT COUNT(*) FROM transactions WHERE user_id = $1"#
+        r#"SELECT COUNT(*) FROM transactions WHERE user_id = $1 AND deleted_at IS NULL"#
     )
     .bind(user_id)
     .fetch_one(&state.db)
     .await?;
 
+    let debt_totals = db::get_debt_totals(&state.db, user_id).await?;
+
+    // Credit wallets paid in advance hold money; owed credit balances are liabilities
+    let (wallet_assets, credit_liabilities): (f64, f64) = sqlx::query_as(
+        r#"
+        SELECT COALESCE(SUM(CASE WHEN wallet_type = ANY($2) THEN GREATEST(balance, 0) ELSE balance END), 0)::float8,
+               COALESCE(SUM(CASE WHEN wallet_type = ANY($2) THEN GREATEST(-balance, 0) ELSE 0 END), 0)::float8
+        FROM wallets WHERE user_id = $1 AND deleted_at IS NULL
+        "#
+    )
+    .bind(user_id)
+    .bind(&CREDIT_WALLET_TYPES[..])
+    .fetch_one(&state.db)
+    .await?;
+    let investment_value = db::get_investment_value(&state.db, user_id).await?;
+    let total_assets = wallet_assets + investment_value + debt_totals.receivable;
+    let total_liabilities = credit_liabilities + debt_totals.payable;
+
     let summary = DashboardSummary {
         total_balance: total_balance.0,
         total_income: total_income.0,
@@ -103,6 +146,12 @@ pub async fn get_summary(
         this_month_expense: this_month_expense.0,
         wallet_count: wallet_count.0,
         transaction_count: transaction_count.0,
+        receivables: debt_totals.receivable,
+        payables: debt_totals.payable,
+        investment_value,
+        total_assets,
+        total_liabilities,
+        net_worth: total_assets - total_liabilities,
     };
 
     Ok(Json(json!({
@@ -111,11 +160,96 @@ pub async fn get_summary(
     })))
 }
 
+// Savings rate, expense-to-income, emergency fund, debt-to-income and credit utilization
+// combined into one score, each with an explanation
+pub async fn get_financial_health(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<HealthQuery>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let months = params.months.unwrap_or(DEFAULT_HEALTH_MONTHS);
+    if !(1..=12).contains(&months) {
+        return Err(AppError::ValidationError(
+            "Months harus antara 1-12".to_string(),
+        ));
+    }
+
+    // Completed months only, so a half-finished month does not skew the averages
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let first_day = today.with_day(1).unwrap();
+    let start_date = shift_months(first_day, -months, 1);
+    let end_date = first_day - Duration::days(1);
+
+    let (income, expense): (f64, f64) = sqlx::query_as(
+        r#"
+        SELECT COALESCE(SUM(CASE WHEN transaction_type = 'income' THEN amount ELSE 0 END), 0)::float8,
+               COALESCE(SUM(CASE WHEN transaction_type = 'expense' THEN amount ELSE 0 END), 0)::float8
+        FROM transactions
+        WHERE user_id = $1 AND deleted_at IS NULL AND NOT is_transfer AND date >= $2 AND date <= $3
+        "#
+    )
+    .bind(user_id)
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_one(&state.db)
+    .await?;
+
+    let (liquid_balance, credit_used, credit_limit): (f64, f64, f64) = sqlx::query_as(
+        r#"
+        SELECT COALESCE(SUM(CASE WHEN wallet_type <> ALL($2) AND wallet_type <> $3 THEN GREATEST(balance, 0) ELSE 0 END), 0)::float8,
+               COALESCE(SUM(CASE WHEN wallet_type = ANY($2) AND credit_limit > 0 THEN GREATEST(-balance, 0) ELSE 0 END), 0)::float8,
+               COALESCE(SUM(CASE WHEN wallet_type = ANY($2) AND credit_limit > 0 THEN credit_limit ELSE 0 END), 0)::float8
+        FROM wallets WHERE user_id = $1 AND deleted_at IS NULL
+        "#
+    )
+    .bind(user_id)
+    .bind(&CREDIT_WALLET_TYPES[..])
+    .bind(INVESTMENT_WALLET_TYPE)
+    .fetch_one(&state.db)
+    .await?;
+
+    let next_month = shift_months(first_day, 1, 1);
+    let installments_due: f64 = db::get_installment_commitments(
+        &state.db,
+        user_id,
+        next_month,
+        shift_months(next_month, 1, 1),
+    )
+    .await?
+    .iter()
+    .fold(0.0, |total, commitment| total + commitment.amount);
+    let payables_due =
+        db::get_payables_due(&state.db, user_id, shift_months(next_month, 1, 1) - Duration::days(1)).await?;
+
+    let health = compute_health(
+        &HealthInputs {
+            months,
+            income,
+            expense,
+            liquid_balance,
+            monthly_debt_payments: installments_due + payables_due,
+            payables_due,
+            credit_used,
+            credit_limit,
+        },
+        start_date,
+        end_date,
+    );
+
+    Ok(Json(json!({
+        "success": true,
+        "data": health
+    })))
+}
+
 pub async fn get_monthly_stats(
     State(state): State<AppState>,
     headers: HeaderMap,
 ) -> Result<Json<Value>, AppError> {
     let user_id = get_user_id(&state, &headers).await?;
+    let today = db::get_user_today(&state.db, user_id).await?;
 
     let stats: Vec<(i32, i32, f64, f64)> = sqlx::query_as(
         r#"
@@ -125,13 +259,14 @@ pub async fn get_monthly_stats(
             COALESCE(SUM(CASE WHEN transaction_type = 'income' THEN amount ELSE 0 END)::float8, 0) as income,
             COALESCE(SUM(CASE WHEN transaction_type = 'expense' THEN amount ELSE 0 END)::float8, 0) as expense
         FROM transactions 
-        WHERE user_id = $1 AND date >= NOW() - INTERVAL '12 months'
+        WHERE user_id = $1 AND deleted_at IS NULL AND NOT is_transfer AND date >= $2
         GROUP BY EXTRACT(MONTH FROM date), EXTRACT(YEAR FROM date)
         ORDER BY year DESC, month DESC
         LIMIT 12
         "#
     )
     .bind(user_id)
+    .bind(shift_months(today, -12, today.day() as i32))
     .fetch_all(&state.db)
     .await?;
 
@@ -150,46 +285,282 @@ pub async fn get_monthly_stats(
     })))
 }
 
+#[derive(Debug, serde::Deserialize)]
+pub struct CategoryStatsQuery {
+    // Break down this category's spending by its sub-categories instead of top-level categories
+    pub parent_id: Option<Uuid>,
+}
+
 pub async fn get_by_category(
     State(state): State<AppState>,
     headers: HeaderMap,
+    Query(params): Query<CategoryStatsQuery>,
 ) -> Result<Json<Value>, AppError> {
     let user_id = get_user_id(&state, &headers).await?;
 
-    let now = Utc::now();
-    let first_day = chrono::NaiveDate::from_ymd_opt(now.year(), now.month(), 1).unwrap();
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let first_day = today.with_day(1).unwrap();
 
-    let stats: Vec<(String, Option<String>, Option<String>, f64)> = sqlx::query_as(
-        r#"
-        SELECT 
-            c.name,
-            c.icon,
-            c.color,
-            COALESCE(SUM(t.amount)::float8, 0) as total
-        FROM transactions t
-        JOIN categories c ON t.category_id = c.id AND c.deleted_at IS NULL
-        WHERE t.user_id = $1 AND t.transaction_type = 'expense' AND t.date >= $2
-        GROUP BY c.id, c.name, c.icon, c.color
-        ORDER BY total DESC
-        LIMIT 10
-        "#
-    )
-    .bind(user_id)
-    .bind(first_day)
-    .fetch_all(&state.db)
-    .await?;
+    let mut stats = db::get_category_totals(&state.db, user_id, first_day, None, "expense", params.parent_id).await?;
+    stats.truncate(10);
 
-    let formatted: Vec<_> = stats.into_iter().map(|(name, icon, color, total)| {
-        json!({
-            "name": name,
-            "icon": icon,
-            "color": color,
-            "total": total
-        })
-    }).collect();
+    Ok(Json(json!({
+        "success": true,
+        "data": stats
+    })))
+}
+
+// Dates to report for a net worth history: each day, each week's Sunday or each month's last
+// day inside the range, always ending with end_date itself
+fn net_worth_points(start_date: NaiveDate, end_date: NaiveDate, interval: &str) -> Vec<NaiveDate> {
+    let mut points = Vec::new();
+    let mut point = match interval {
+        "day" => start_date,
+        "week" => start_date + Duration::days(6 - start_date.weekday().num_days_from_monday() as i64),
+        _ => day_of_month(start_date.year(), start_date.month(), 31),
+    };
+
+    while point < end_date && points.len() <= MAX_NET_WORTH_POINTS {
+        points.push(point);
+        point = match interval {
+            "day" => point + Duration::days(1),
+            "week" => point + Duration::days(7),
+            _ => shift_months(point, 1, 31),
+        };
+    }
+    points.push(end_date);
+    points
+}
+
+pub async fn get_net_worth_history(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<NetWorthQuery>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let interval = params.interval.as_deref().unwrap_or("month");
+    if !["day", "week", "month"].contains(&interval) {
+        return Err(AppError::ValidationError(
+            "Interval harus 'day', 'week' atau 'month'".to_string(),
+        ));
+    }
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let end_date = params.end_date.unwrap_or(today);
+    let start_date = params.start_date.unwrap_or(match interval {
+        "day" => end_date - Duration::days(30),
+        "week" => end_date - Duration::weeks(26),
+        _ => shift_months(end_date, -11, 1),
+    });
+
+    if start_date > end_date {
+        return Err(AppError::ValidationError(
+            "start_date tidak boleh setelah end_date".to_string(),
+        ));
+    }
+
+    let points = net_worth_points(start_date, end_date, interval);
+    if points.len() > MAX_NET_WORTH_POINTS {
+        return Err(AppError::ValidationError(format!(
+            "Rentang terlalu panjang untuk interval '{}' (maksimal {} titik)",
+            interval, MAX_NET_WORTH_POINTS
+        )));
+    }
+
+    // Make today's point reflect the live balances instead of the last hourly snapshot
+    if end_date >= today {
+        db::snapshot_wallet_balances(&state.db, Some(user_id), Some(today)).await?;
+    }
+
+    let history: Vec<NetWorthPointResponse> = db::get_net_worth_history(&state.db, user_id, &points)
+        .await?
+        .into_iter()
+        .map(NetWorthPointResponse::from)
+        .collect();
+
+    let first_snapshot_date = db::get_first_snapshot_date(&state.db, user_id).await?;
+
+    let (first, last) = (history.first(), history.last());
+    let change = match (first, last) {
+        (Some(first), Some(last)) => last.net_worth - first.net_worth,
+        _ => 0.0,
+    };
+    let change_percentage = first
+        .filter(|first| first.net_worth != 0.0)
+        .map(|first| change / first.net_worth.abs() * 100.0);
 
     Ok(Json(json!({
         "success": true,
-        "data": formatted
+        "data": history,
+        "meta": {
+            "interval": interval,
+            "start_date": start_date,
+            "end_date": end_date,
+            // Points before this date have no balance data; POST /api/dashboard/net-worth/backfill fills them
+            "first_snapshot_date": first_snapshot_date,
+            "change": change,
+            "change_percentage": change_percentage
+        }
+    })))
+}
+
+// Rebuilds missing daily balances from the transaction ledger
+pub async fn backfill_net_worth(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    payload: Option<Json<BalanceBackfillRequest>>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let start_date = payload
+        .and_then(|Json(payload)| payload.start_date)
+        .unwrap_or(today - Duration::days(365));
+
+    if start_date < today - Duration::days(MAX_BACKFILL_DAYS) {
+        return Err(AppError::ValidationError(format!(
+            "start_date maksimal {} hari ke belakang",
+            MAX_BACKFILL_DAYS
+        )));
+    }
+    if start_date >= today {
+        return Err(AppError::ValidationError(
+            "start_date harus sebelum hari ini".to_string(),
+        ));
+    }
+
+    let inserted = db::backfill_wallet_balances(&state.db, user_id, start_date, today - Duration::days(1)).await?;
+    db::snapshot_wallet_balances(&state.db, Some(user_id), Some(today)).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": format!("{} saldo harian berhasil dibuat dari riwayat transaksi", inserted),
+        "data": {
+            "start_date": start_date,
+            "end_date": today,
+            "snapshots_created": inserted
+        }
     })))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{create_user, create_wallet};
+    use sqlx::PgPool;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn net_worth_points_per_interval() {
+        assert_eq!(
+            net_worth_points(date(2025, 3, 1), date(2025, 3, 4), "day"),
+            [date(2025, 3, 1), date(2025, 3, 2), date(2025, 3, 3), date(2025, 3, 4)]
+        );
+        // Sundays, starting from the first one on or after the start date
+        assert_eq!(
+            net_worth_points(date(2025, 3, 5), date(2025, 3, 20), "week"),
+            [date(2025, 3, 9), date(2025, 3, 16), date(2025, 3, 20)]
+        );
+        assert_eq!(
+            net_worth_points(date(2025, 1, 15), date(2025, 3, 10), "month"),
+            [date(2025, 1, 31), date(2025, 2, 28), date(2025, 3, 10)]
+        );
+        // end_date is never repeated when it falls on a point
+        assert_eq!(
+            net_worth_points(date(2025, 1, 15), date(2025, 3, 31), "month"),
+            [date(2025, 1, 31), date(2025, 2, 28), date(2025, 3, 31)]
+        );
+        assert_eq!(net_worth_points(date(2025, 3, 1), date(2025, 3, 1), "week"), [date(2025, 3, 1)]);
+    }
+
+    async fn add_transaction(
+        pool: &PgPool,
+        user_id: Uuid,
+        wallet_id: Uuid,
+        transaction_type: &str,
+        amount: f64,
+        date: NaiveDate,
+    ) {
+        sqlx::query(
+            r#"INSERT INTO transactions (user_id, wallet_id, transaction_type, amount, date)
+               VALUES ($1, $2, $3, $4, $5)"#,
+        )
+        .bind(user_id)
+        .bind(wallet_id)
+        .bind(transaction_type)
+        .bind(amount)
+        .bind(date)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn add_debt(pool: &PgPool, user_id: Uuid, direction: &str, principal: f64, date: NaiveDate) {
+        sqlx::query(
+            "INSERT INTO debts (user_id, counterparty, direction, principal, date) VALUES ($1, 'Budi', $2, $3, $4)",
+        )
+        .bind(user_id)
+        .bind(direction)
+        .bind(principal)
+        .bind(date)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn net_worth_history_from_backfilled_balances(pool: PgPool) {
+        let (user_id, cash) = create_user(&pool).await;
+        let card = create_wallet(&pool, user_id, "Kartu Kredit", "credit-card").await;
+        // Current balances, after the transactions below
+        sqlx::query(
+            r#"UPDATE wallets SET created_at = '2025-01-01',
+                   balance = CASE WHEN id = $1 THEN 700000 ELSE -200000 END
+               WHERE user_id = $2"#,
+        )
+        .bind(cash)
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        add_transaction(&pool, user_id, cash, "income", 1_000_000.0, date(2025, 3, 1)).await;
+        add_transaction(&pool, user_id, cash, "expense", 300_000.0, date(2025, 3, 10)).await;
+        add_transaction(&pool, user_id, card, "expense", 200_000.0, date(2025, 3, 5)).await;
+        add_debt(&pool, user_id, "lent", 100_000.0, date(2025, 3, 3)).await;
+        add_debt(&pool, user_id, "borrowed", 50_000.0, date(2025, 3, 20)).await;
+
+        let inserted = db::backfill_wallet_balances(&pool, user_id, date(2025, 2, 28), date(2025, 3, 31)).await.unwrap();
+        assert_eq!(inserted, 2 * 32);
+        // Existing days are left alone
+        let inserted = db::backfill_wallet_balances(&pool, user_id, date(2025, 2, 1), date(2025, 3, 31)).await.unwrap();
+        assert_eq!(inserted, 2 * 27);
+
+        let points = [date(2025, 2, 28), date(2025, 3, 5), date(2025, 3, 31), date(2025, 4, 30)];
+        let history: Vec<NetWorthPointResponse> = db::get_net_worth_history(&pool, user_id, &points)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(NetWorthPointResponse::from)
+            .collect();
+        let summary: Vec<(f64, f64, f64, f64, f64)> = history
+            .iter()
+            .map(|p| (p.wallet_assets, p.credit_liabilities, p.receivables, p.payables, p.net_worth))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (0.0, 0.0, 0.0, 0.0, 0.0),
+                (1_000_000.0, 200_000.0, 100_000.0, 0.0, 900_000.0),
+                (700_000.0, 200_000.0, 100_000.0, 50_000.0, 550_000.0),
+                // No snapshot yet: the latest one carries forward
+                (700_000.0, 200_000.0, 100_000.0, 50_000.0, 550_000.0),
+            ]
+        );
+        assert_eq!(db::get_first_snapshot_date(&pool, user_id).await.unwrap(), Some(date(2025, 2, 1)));
+    }
+}
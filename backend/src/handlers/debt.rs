@@ -0,0 +1,592 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header::AUTHORIZATION, HeaderMap},
+    Json,
+};
+use serde_json::{json, Value};
+use sqlx::PgConnection;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    db,
+    error::AppError,
+    models::{
+        audit::AuditContext,
+        debt::{
+            CreateDebtRequest, CreateRepaymentRequest, Debt, DebtQuery, DebtReminderQuery,
+            DebtResponse, UpdateDebtRequest, DEBT_DIRECTIONS,
+        },
+        transaction::{NewTransaction, TransactionResponse},
+    },
+    utils::jwt::verify_token,
+    AppState,
+};
+
+async fn get_user_id(state: &AppState, headers: &HeaderMap) -> Result<Uuid, AppError> {
+    let auth_header = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .ok_or(AppError::Unauthorized)?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or(AppError::Unauthorized)?;
+
+    let claims = verify_token(token, &state.config.jwt_secret)?;
+    Ok(claims.sub)
+}
+
+fn validate_amount(amount: f64, message: &str) -> Result<(), AppError> {
+    if amount <= 0.0 || !amount.is_finite() {
+        return Err(AppError::ValidationError(message.to_string()));
+    }
+    Ok(())
+}
+
+// The given wallet if it is active, otherwise the user's default wallet
+async fn resolve_wallet(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    wallet_id: Option<Uuid>,
+) -> Result<Uuid, AppError> {
+    match wallet_id {
+        Some(wallet_id) => {
+            let wallet_exists: bool = sqlx::query_scalar(
+                r#"SELECT EXISTS(SELECT 1 FROM wallets WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL)"#
+            )
+            .bind(wallet_id)
+            .bind(user_id)
+            .fetch_one(&mut *conn)
+            .await?;
+
+            if !wallet_exists {
+                return Err(AppError::NotFound("Wallet".to_string()));
+            }
+            Ok(wallet_id)
+        }
+        None => {
+            let default_wallet: Option<Uuid> = sqlx::query_scalar(
+                r#"SELECT id FROM wallets WHERE user_id = $1 AND is_default = true AND deleted_at IS NULL LIMIT 1"#
+            )
+            .bind(user_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+
+            default_wallet.ok_or(AppError::ValidationError(
+                "Pilih wallet untuk transaksi ini".to_string(),
+            ))
+        }
+    }
+}
+
+pub async fn list_debts(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<DebtQuery>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    if let Some(direction) = query.direction.as_deref() {
+        if !DEBT_DIRECTIONS.contains(&direction) {
+            return Err(AppError::ValidationError(
+                "Direction harus 'lent' atau 'borrowed'".to_string(),
+            ));
+        }
+    }
+
+    if let Some(status) = query.status.as_deref() {
+        if !["active", "overdue", "settled"].contains(&status) {
+            return Err(AppError::ValidationError(
+                "Status harus 'active', 'overdue' atau 'settled'".to_string(),
+            ));
+        }
+    }
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let debts: Vec<DebtResponse> = db::get_user_debts(&state.db, user_id)
+        .await?
+        .into_iter()
+        .map(|row| DebtResponse::new(row, today))
+        .collect();
+
+    let total_outstanding = |direction: &str| -> f64 {
+        debts
+            .iter()
+            .filter(|debt| debt.direction == direction)
+            .map(|debt| debt.outstanding_amount)
+            .sum()
+    };
+    let total_receivable = total_outstanding("lent");
+    let total_payable = total_outstanding("borrowed");
+
+    let response: Vec<DebtResponse> = debts
+        .into_iter()
+        .filter(|debt| query.direction.as_deref().is_none_or(|direction| debt.direction == direction))
+        .filter(|debt| query.status.as_deref().is_none_or(|status| debt.status == status))
+        .collect();
+
+    Ok(Json(json!({
+        "success": true,
+        "data": response,
+        "meta": {
+            "total_receivable": total_receivable,
+            "total_payable": total_payable,
+            "net": total_receivable - total_payable
+        }
+    })))
+}
+
+pub async fn create_debt(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateDebtRequest>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+    let audit = AuditContext::from_headers(user_id, &headers)?;
+
+    payload.validate().map_err(|e| {
+        AppError::ValidationError(e.to_string())
+    })?;
+
+    if !DEBT_DIRECTIONS.contains(&payload.direction.as_str()) {
+        return Err(AppError::ValidationError(
+            "Direction harus 'lent' atau 'borrowed'".to_string(),
+        ));
+    }
+    validate_amount(payload.principal, "Jumlah pinjaman harus lebih besar dari 0")?;
+
+    let date = match payload.date {
+        Some(date) => date,
+        None => db::get_user_today(&state.db, user_id).await?,
+    };
+    if payload.due_date.is_some_and(|due_date| due_date < date) {
+        return Err(AppError::ValidationError(
+            "Tanggal jatuh tempo tidak boleh sebelum tanggal pinjaman".to_string(),
+        ));
+    }
+
+    let mut db_tx = state.db.begin().await?;
+
+    // Lending takes money out of the wallet, borrowing puts it in
+    let transaction_id = match payload.wallet_id {
+        Some(wallet_id) => {
+            let wallet_id = resolve_wallet(&mut db_tx, user_id, Some(wallet_id)).await?;
+            let (transaction_type, description) = if payload.direction == "lent" {
+                ("expense", format!("Pinjaman ke {}", payload.counterparty))
+            } else {
+                ("income", format!("Pinjaman dari {}", payload.counterparty))
+            };
+
+            // Lending and borrowing move money without being income or expense
+            let transaction = db::record_wallet_transaction(
+                &mut db_tx,
+                &audit,
+                &NewTransaction {
+                    wallet_id,
+                    category_id: None,
+                    transaction_type,
+                    amount: payload.principal,
+                    description: &description,
+                    date,
+                    is_transfer: true,
+                },
+            )
+            .await?;
+            Some(transaction.id)
+        }
+        None => None,
+    };
+
+    let debt = sqlx::query_as::<_, Debt>(
+        r#"
+        INSERT INTO debts (id, user_id, counterparty, direction, principal, description, date, due_date, transaction_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        RETURNING id, user_id, counterparty, direction, principal, description, date, due_date, transaction_id, created_at, updated_at, deleted_at
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(&payload.counterparty)
+    .bind(&payload.direction)
+    .bind(payload.principal)
+    .bind(&payload.description)
+    .bind(date)
+    .bind(payload.due_date)
+    .bind(transaction_id)
+    .fetch_one(&mut *db_tx)
+    .await?;
+
+    db_tx.commit().await?;
+
+    let created = db::get_debt_by_id(&state.db, debt.id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Debt".to_string()))?;
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    Ok(Json(json!({
+        "success": true,
+        "message": if debt.direction == "lent" {
+            "Piutang berhasil dicatat!"
+        } else {
+            "Hutang berhasil dicatat!"
+        },
+        "data": DebtResponse::new(created, today)
+    })))
+}
+
+pub async fn get_debt(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let debt = db::get_debt_by_id(&state.db, id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Debt".to_string()))?;
+
+    let repayments: Vec<TransactionResponse> = db::get_debt_repayments(&state.db, id, user_id)
+        .await?
+        .into_iter()
+        .map(TransactionResponse::from)
+        .collect();
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "debt": DebtResponse::new(debt, today),
+            "repayments": repayments
+        }
+    })))
+}
+
+pub async fn update_debt(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateDebtRequest>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    payload.validate().map_err(|e| {
+        AppError::ValidationError(e.to_string())
+    })?;
+
+    let mut db_tx = state.db.begin().await?;
+
+    // Lock the debt so a concurrent repayment cannot slip under the principal check
+    sqlx::query(r#"SELECT id FROM debts WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL FOR UPDATE"#)
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&mut *db_tx)
+        .await?
+        .ok_or(AppError::NotFound("Debt".to_string()))?;
+
+    let current = db::get_debt_by_id(&mut *db_tx, id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Debt".to_string()))?;
+
+    if let Some(principal) = payload.principal {
+        validate_amount(principal, "Jumlah pinjaman harus lebih besar dari 0")?;
+
+        // The wallet transaction holds the same amount; it would silently disagree
+        if current.debt.transaction_id.is_some() && principal != current.debt.principal {
+            return Err(AppError::ValidationError(
+                "Pokok pinjaman tidak bisa diubah karena sudah tercatat sebagai transaksi wallet".to_string(),
+            ));
+        }
+
+        if principal < current.repaid_amount {
+            return Err(AppError::ValidationError(
+                "Pokok pinjaman tidak boleh lebih kecil dari jumlah yang sudah dibayar".to_string(),
+            ));
+        }
+    }
+
+    let due_date = payload.due_date.unwrap_or(current.debt.due_date);
+    if due_date.is_some_and(|due_date| due_date < current.debt.date) {
+        return Err(AppError::ValidationError(
+            "Tanggal jatuh tempo tidak boleh sebelum tanggal pinjaman".to_string(),
+        ));
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE debts SET
+            counterparty = COALESCE($1, counterparty),
+            principal = COALESCE($2, principal),
+            description = COALESCE($3, description),
+            due_date = $4,
+            updated_at = NOW()
+        WHERE id = $5 AND user_id = $6 AND deleted_at IS NULL
+        "#
+    )
+    .bind(&payload.counterparty)
+    .bind(payload.principal)
+    .bind(&payload.description)
+    .bind(due_date)
+    .bind(id)
+    .bind(user_id)
+    .execute(&mut *db_tx)
+    .await?;
+
+    db_tx.commit().await?;
+
+    let updated = db::get_debt_by_id(&state.db, id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Debt".to_string()))?;
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    Ok(Json(json!({
+        "success": true,
+        "message": "Data pinjaman berhasil diupdate!",
+        "data": DebtResponse::new(updated, today)
+    })))
+}
+
+pub async fn delete_debt(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    // Soft delete; the wallet transactions of the loan and its repayments are kept
+    let result = sqlx::query(
+        r#"UPDATE debts SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL"#
+    )
+    .bind(id)
+    .bind(user_id)
+    .execute(&state.db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Debt".to_string()));
+    }
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Data pinjaman berhasil dihapus! Transaksi wallet terkait tetap tersimpan."
+    })))
+}
+
+pub async fn create_repayment(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<CreateRepaymentRequest>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+    let audit = AuditContext::from_headers(user_id, &headers)?;
+
+    validate_amount(payload.amount, "Jumlah pembayaran harus lebih besar dari 0")?;
+
+    let date = match payload.date {
+        Some(date) => date,
+        None => db::get_user_today(&state.db, user_id).await?,
+    };
+
+    let mut db_tx = state.db.begin().await?;
+
+    // Lock the debt so concurrent repayments cannot overpay it
+    sqlx::query(r#"SELECT id FROM debts WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL FOR UPDATE"#)
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&mut *db_tx)
+        .await?
+        .ok_or(AppError::NotFound("Debt".to_string()))?;
+
+    let debt = db::get_debt_by_id(&mut *db_tx, id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Debt".to_string()))?;
+
+    let outstanding = debt.debt.principal - debt.repaid_amount;
+    if outstanding <= 0.0 {
+        return Err(AppError::Conflict("Pinjaman ini sudah lunas".to_string()));
+    }
+    if payload.amount > outstanding {
+        return Err(AppError::ValidationError(format!(
+            "Jumlah pembayaran melebihi sisa pinjaman ({})",
+            outstanding
+        )));
+    }
+
+    let wallet_id = resolve_wallet(&mut db_tx, user_id, payload.wallet_id).await?;
+
+    // Repayments of money lent come into the wallet, repayments of money borrowed go out
+    let (transaction_type, default_description) = if debt.debt.direction == "lent" {
+        ("income", format!("Pelunasan piutang dari {}", debt.debt.counterparty))
+    } else {
+        ("expense", format!("Pembayaran hutang ke {}", debt.debt.counterparty))
+    };
+    let description = payload.description.unwrap_or(default_description);
+
+    let transaction = db::record_wallet_transaction(
+        &mut db_tx,
+        &audit,
+        &NewTransaction {
+            wallet_id,
+            category_id: None,
+            transaction_type,
+            amount: payload.amount,
+            description: &description,
+            date,
+            is_transfer: true,
+        },
+    )
+    .await?;
+
+    sqlx::query(r#"INSERT INTO debt_repayments (debt_id, transaction_id) VALUES ($1, $2)"#)
+        .bind(id)
+        .bind(transaction.id)
+        .execute(&mut *db_tx)
+        .await?;
+
+    db_tx.commit().await?;
+
+    let updated = db::get_debt_by_id(&state.db, id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Debt".to_string()))?;
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let updated = DebtResponse::new(updated, today);
+
+    Ok(Json(json!({
+        "success": true,
+        "message": if updated.status == "settled" {
+            "Pembayaran berhasil dicatat! Pinjaman sudah lunas."
+        } else {
+            "Pembayaran berhasil dicatat!"
+        },
+        "data": {
+            "debt": updated,
+            "transaction": TransactionResponse::from(transaction)
+        }
+    })))
+}
+
+// Open debts that are overdue or due within `days` days, soonest first
+pub async fn list_debt_reminders(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<DebtReminderQuery>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let days = query.days.unwrap_or(7);
+    if !(0..=366).contains(&days) {
+        return Err(AppError::ValidationError(
+            "Days harus antara 0-366".to_string(),
+        ));
+    }
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let mut reminders: Vec<DebtResponse> = db::get_user_debts(&state.db, user_id)
+        .await?
+        .into_iter()
+        .map(|row| DebtResponse::new(row, today))
+        .filter(|debt| debt.status != "settled")
+        .filter(|debt| debt.days_until_due.is_some_and(|until_due| until_due <= days))
+        .collect();
+    reminders.sort_by_key(|debt| debt.due_date);
+
+    let overdue_count = reminders.iter().filter(|debt| debt.status == "overdue").count();
+
+    Ok(Json(json!({
+        "success": true,
+        "data": reminders,
+        "meta": {
+            "days": days,
+            "overdue_count": overdue_count
+        }
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{auth_headers, create_user, test_state};
+    use chrono::NaiveDate;
+    use sqlx::PgPool;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn update(value: Value) -> UpdateDebtRequest {
+        serde_json::from_value(value).unwrap()
+    }
+
+    async fn patch(state: &AppState, user_id: Uuid, id: Uuid, value: Value) -> Result<Json<Value>, AppError> {
+        update_debt(State(state.clone()), auth_headers(user_id), Path(id), Json(update(value))).await
+    }
+
+    async fn lend(state: &AppState, user_id: Uuid, wallet_id: Option<Uuid>) -> Uuid {
+        let request = CreateDebtRequest {
+            counterparty: "Budi".to_string(),
+            direction: "lent".to_string(),
+            principal: 500_000.0,
+            description: None,
+            date: Some(date(2025, 3, 1)),
+            due_date: Some(date(2025, 4, 1)),
+            wallet_id,
+        };
+        let response = create_debt(State(state.clone()), auth_headers(user_id), Json(request)).await.unwrap();
+        response.0["data"]["id"].as_str().unwrap().parse().unwrap()
+    }
+
+    #[test]
+    fn update_request_tells_missing_and_null_due_date_apart() {
+        assert_eq!(update(json!({})).due_date, None);
+        assert_eq!(update(json!({"due_date": null})).due_date, Some(None));
+        assert_eq!(update(json!({"due_date": "2025-05-01"})).due_date, Some(Some(date(2025, 5, 1))));
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn update_debt_keeps_or_clears_the_due_date(pool: PgPool) {
+        let (user_id, _) = create_user(&pool).await;
+        let state = test_state(pool.clone());
+        let id = lend(&state, user_id, None).await;
+
+        let response = patch(&state, user_id, id, json!({"counterparty": "Budi S."})).await.unwrap();
+        assert_eq!(response.0["data"]["due_date"], "2025-04-01");
+
+        let response = patch(&state, user_id, id, json!({"due_date": null})).await.unwrap();
+        assert_eq!(response.0["data"]["due_date"], Value::Null);
+        assert_eq!(response.0["data"]["counterparty"], "Budi S.");
+
+        let result = patch(&state, user_id, id, json!({"due_date": "2025-02-01"})).await;
+        assert!(matches!(result, Err(AppError::ValidationError(_))));
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn update_debt_principal_checks(pool: PgPool) {
+        let (user_id, wallet_id) = create_user(&pool).await;
+        let state = test_state(pool.clone());
+        let unbooked = lend(&state, user_id, None).await;
+        let booked = lend(&state, user_id, Some(wallet_id)).await;
+
+        let repayment = CreateRepaymentRequest {
+            amount: 200_000.0,
+            wallet_id: Some(wallet_id),
+            date: None,
+            description: None,
+        };
+        let response = create_repayment(State(state.clone()), auth_headers(user_id), Path(unbooked), Json(repayment))
+            .await
+            .unwrap();
+        assert_eq!(response.0["data"]["debt"]["repaid_amount"], 200_000.0);
+
+        let result = patch(&state, user_id, unbooked, json!({"principal": 150_000.0})).await;
+        assert!(matches!(result, Err(AppError::ValidationError(_))));
+        let response = patch(&state, user_id, unbooked, json!({"principal": 300_000.0})).await.unwrap();
+        assert_eq!(response.0["data"]["outstanding_amount"], 100_000.0);
+
+        // The principal of a loan booked on a wallet is fixed by its transaction
+        let result = patch(&state, user_id, booked, json!({"principal": 600_000.0})).await;
+        assert!(matches!(result, Err(AppError::ValidationError(_))));
+    }
+}
@@ -0,0 +1,219 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{
+        header::{AUTHORIZATION, CONTENT_TYPE},
+        HeaderMap,
+    },
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::{
+    db,
+    error::AppError,
+    jobs::email_digest::build_digest_email,
+    models::digest::{digest_period, DigestQuery, UpdateDigestPreferencesRequest, DIGEST_FREQUENCIES},
+    utils::{html::escape_html, jwt::verify_token},
+    AppState,
+};
+
+async fn get_user_id(state: &AppState, headers: &HeaderMap) -> Result<Uuid, AppError> {
+    let auth_header = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .ok_or(AppError::Unauthorized)?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or(AppError::Unauthorized)?;
+
+    let claims = verify_token(token, &state.config.jwt_secret)?;
+    Ok(claims.sub)
+}
+
+fn validate_frequency(frequency: Option<&str>) -> Result<Option<&str>, AppError> {
+    match frequency {
+        Some(frequency) if !DIGEST_FREQUENCIES.contains(&frequency) => Err(AppError::ValidationError(format!(
+            "Frekuensi harus salah satu dari: {}",
+            DIGEST_FREQUENCIES.join(", ")
+        ))),
+        _ => Ok(frequency),
+    }
+}
+
+fn frequency_label(frequency: Option<&str>) -> &'static str {
+    match frequency {
+        Some("weekly") => "ringkasan mingguan",
+        Some("monthly") => "ringkasan bulanan",
+        _ => "ringkasan email",
+    }
+}
+
+fn html_page(title: &str, body: &str) -> Response {
+    let page = format!(
+        "<!DOCTYPE html>\n<html lang=\"id\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{title}</title>\n</head>\n\
+         <body style=\"margin:0;padding:48px 24px;color:#222;font-family:Helvetica,Arial,sans-serif\">\n\
+         <div style=\"max-width:480px;margin:0 auto;text-align:center\">\n<h1 style=\"font-size:20px\">{title}</h1>\n{body}\n</div>\n\
+         </body>\n</html>\n",
+        title = escape_html(title),
+        body = body,
+    );
+    ([(CONTENT_TYPE, "text/html; charset=utf-8")], page).into_response()
+}
+
+pub async fn get_digest_preferences(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let preferences = db::ensure_digest_preferences(&state.db, user_id).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": preferences,
+        "meta": {
+            "frequencies": DIGEST_FREQUENCIES
+        }
+    })))
+}
+
+pub async fn update_digest_preferences(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<UpdateDigestPreferencesRequest>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let preferences = db::upsert_digest_preferences(&state.db, user_id, payload.weekly, payload.monthly).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Pengaturan ringkasan email berhasil disimpan",
+        "data": preferences
+    })))
+}
+
+// The digest for the latest finished period, rendered as it would be emailed
+pub async fn preview_digest(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<DigestQuery>,
+) -> Result<Response, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+    let frequency = validate_frequency(params.frequency.as_deref())?.unwrap_or("monthly");
+
+    let user = db::find_user_by_id(&state.db, user_id)
+        .await?
+        .ok_or(AppError::NotFound("User".to_string()))?;
+    let preferences = db::ensure_digest_preferences(&state.db, user_id).await?;
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let (start_date, end_date) = digest_period(frequency, today);
+
+    let email = build_digest_email(
+        &state.db,
+        &user,
+        &preferences,
+        frequency,
+        start_date,
+        end_date,
+        &state.config.public_url,
+    )
+    .await?;
+
+    Ok(([(CONTENT_TYPE, "text/html; charset=utf-8")], email.html).into_response())
+}
+
+// Sends the latest digest right away. Does not count as the scheduled delivery.
+pub async fn send_test_digest(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<DigestQuery>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+    let frequency = validate_frequency(params.frequency.as_deref())?.unwrap_or("monthly");
+
+    let user = db::find_user_by_id(&state.db, user_id)
+        .await?
+        .ok_or(AppError::NotFound("User".to_string()))?;
+    let preferences = db::ensure_digest_preferences(&state.db, user_id).await?;
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let (start_date, end_date) = digest_period(frequency, today);
+
+    let email = build_digest_email(
+        &state.db,
+        &user,
+        &preferences,
+        frequency,
+        start_date,
+        end_date,
+        &state.config.public_url,
+    )
+    .await?;
+    state.mailer.send(&email).await.map_err(|e| {
+        tracing::error!("❌ Test digest for user {} failed: {}", user_id, e);
+        AppError::InternalError("Gagal mengirim email".to_string())
+    })?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": format!("Email ringkasan dikirim ke {}", user.email),
+        "data": {
+            "frequency": frequency,
+            "start_date": start_date,
+            "end_date": end_date,
+            "subject": email.subject
+        }
+    })))
+}
+
+// Unsubscribe links are public, authorised by the token. The page asks for confirmation so
+// that link scanners opening the URL do not unsubscribe anyone; mail clients use the
+// one-click POST directly.
+pub async fn get_unsubscribe_page(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    Query(params): Query<DigestQuery>,
+) -> Result<Response, AppError> {
+    let frequency = validate_frequency(params.frequency.as_deref())?;
+    db::find_digest_preferences_by_token(&state.db, &token)
+        .await?
+        .ok_or(AppError::NotFound("Langganan".to_string()))?;
+
+    let action = match frequency {
+        Some(frequency) => format!("?frequency={}", frequency),
+        None => String::new(),
+    };
+    let body = format!(
+        "<p>Berhenti menerima {} FinTrack?</p>\n\
+         <form method=\"post\" action=\"{}\">\n\
+         <button type=\"submit\" style=\"padding:8px 16px;font-size:14px\">Berhenti berlangganan</button>\n</form>",
+        frequency_label(frequency),
+        escape_html(&action),
+    );
+    Ok(html_page("Berhenti berlangganan", &body))
+}
+
+pub async fn unsubscribe(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    Query(params): Query<DigestQuery>,
+) -> Result<Response, AppError> {
+    let frequency = validate_frequency(params.frequency.as_deref())?;
+    let preferences = db::find_digest_preferences_by_token(&state.db, &token)
+        .await?
+        .ok_or(AppError::NotFound("Langganan".to_string()))?;
+
+    let weekly = matches!(frequency, None | Some("weekly")).then_some(false);
+    let monthly = matches!(frequency, None | Some("monthly")).then_some(false);
+    db::upsert_digest_preferences(&state.db, preferences.user_id, weekly, monthly).await?;
+
+    let body = format!(
+        "<p>Anda tidak akan menerima {} FinTrack lagi. Langganan dapat diaktifkan kembali di pengaturan aplikasi.</p>",
+        frequency_label(frequency)
+    );
+    Ok(html_page("Berhasil berhenti berlangganan", &body))
+}
@@ -0,0 +1,461 @@
+use axum::{
+    extract::{Path, State},
+    http::{header::AUTHORIZATION, HeaderMap},
+    Json,
+};
+use chrono::{Datelike, NaiveDate};
+use serde_json::{json, Value};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    db,
+    error::AppError,
+    models::goal::{
+        CreateContributionRequest, CreateGoalRequest, Goal, GoalContribution,
+        GoalMonthlyContribution, GoalResponse, UpdateGoalRequest, GOAL_HISTORY_MONTHS,
+    },
+    utils::{dates::shift_months, jwt::verify_token},
+    AppState,
+};
+
+async fn get_user_id(state: &AppState, headers: &HeaderMap) -> Result<Uuid, AppError> {
+    let auth_header = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .ok_or(AppError::Unauthorized)?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or(AppError::Unauthorized)?;
+
+    let claims = verify_token(token, &state.config.jwt_secret)?;
+    Ok(claims.sub)
+}
+
+fn validate_target_amount(amount: f64) -> Result<(), AppError> {
+    if amount <= 0.0 || !amount.is_finite() {
+        return Err(AppError::ValidationError(
+            "Target harus lebih besar dari 0".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+async fn ensure_wallet_exists(state: &AppState, user_id: Uuid, wallet_id: Uuid) -> Result<(), AppError> {
+    db::get_wallet_by_id(&state.db, wallet_id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Wallet".to_string()))?;
+    Ok(())
+}
+
+// First day of the oldest month in the projection window
+fn history_start(today: NaiveDate) -> NaiveDate {
+    shift_months(today.with_day(1).unwrap(), -(GOAL_HISTORY_MONTHS - 1), 1)
+}
+
+async fn load_goal(state: &AppState, id: Uuid, user_id: Uuid) -> Result<(GoalResponse, Vec<GoalMonthlyContribution>), AppError> {
+    let goal = db::get_goal_by_id(&state.db, id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Goal".to_string()))?;
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let history = db::get_goal_monthly_contributions(&state.db, user_id, Some(id), history_start(today)).await?;
+
+    Ok((GoalResponse::new(goal, &history, today), history))
+}
+
+pub async fn list_goals(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let goals = db::get_user_goals(&state.db, user_id).await?;
+    let history = db::get_goal_monthly_contributions(&state.db, user_id, None, history_start(today)).await?;
+
+    let response: Vec<GoalResponse> = goals
+        .into_iter()
+        .map(|goal| {
+            let goal_history: Vec<GoalMonthlyContribution> = history
+                .iter()
+                .filter(|entry| entry.goal_id == goal.goal.id)
+                .copied()
+                .collect();
+            GoalResponse::new(goal, &goal_history, today)
+        })
+        .collect();
+
+    let total_saved: f64 = response.iter().map(|goal| goal.saved_amount).sum();
+    let total_target: f64 = response.iter().map(|goal| goal.target_amount).sum();
+
+    Ok(Json(json!({
+        "success": true,
+        "data": response,
+        "meta": {
+            "total_saved": total_saved,
+            "total_target": total_target
+        }
+    })))
+}
+
+pub async fn create_goal(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateGoalRequest>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    payload.validate().map_err(|e| {
+        AppError::ValidationError(e.to_string())
+    })?;
+
+    validate_target_amount(payload.target_amount)?;
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    if payload.target_date.is_some_and(|target_date| target_date < today) {
+        return Err(AppError::ValidationError(
+            "Tanggal target tidak boleh di masa lalu".to_string(),
+        ));
+    }
+
+    if let Some(wallet_id) = payload.wallet_id {
+        ensure_wallet_exists(&state, user_id, wallet_id).await?;
+    }
+
+    let goal = sqlx::query_as::<_, Goal>(
+        r#"
+        INSERT INTO goals (id, user_id, name, target_amount, target_date, wallet_id, icon, color)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING id, user_id, name, target_amount, target_date, wallet_id, icon, color, created_at, updated_at, deleted_at
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(&payload.name)
+    .bind(payload.target_amount)
+    .bind(payload.target_date)
+    .bind(payload.wallet_id)
+    .bind(&payload.icon)
+    .bind(&payload.color)
+    .fetch_one(&state.db)
+    .await?;
+
+    let (response, _) = load_goal(&state, goal.id, user_id).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Goal berhasil dibuat!",
+        "data": response
+    })))
+}
+
+pub async fn get_goal(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let (goal, history) = load_goal(&state, id, user_id).await?;
+    let contributions: Vec<GoalContribution> = if goal.wallet_id.is_none() {
+        db::get_goal_contributions(&state.db, id).await?
+    } else {
+        Vec::new()
+    };
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "goal": goal,
+            "monthly_history": history,
+            "contributions": contributions
+        }
+    })))
+}
+
+pub async fn update_goal(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateGoalRequest>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    payload.validate().map_err(|e| {
+        AppError::ValidationError(e.to_string())
+    })?;
+
+    if let Some(target_amount) = payload.target_amount {
+        validate_target_amount(target_amount)?;
+    }
+
+    let unlink_wallet = payload.unlink_wallet.unwrap_or(false);
+    if unlink_wallet && payload.wallet_id.is_some() {
+        return Err(AppError::ValidationError(
+            "Pilih salah satu: wallet_id atau unlink_wallet".to_string(),
+        ));
+    }
+
+    if let Some(wallet_id) = payload.wallet_id {
+        ensure_wallet_exists(&state, user_id, wallet_id).await?;
+    }
+
+    let result = sqlx::query(
+        r#"
+        UPDATE goals SET
+            name = COALESCE($1, name),
+            target_amount = COALESCE($2, target_amount),
+            target_date = COALESCE($3, target_date),
+            wallet_id = CASE WHEN $4 THEN NULL ELSE COALESCE($5, wallet_id) END,
+            icon = COALESCE($6, icon),
+            color = COALESCE($7, color),
+            updated_at = NOW()
+        WHERE id = $8 AND user_id = $9 AND deleted_at IS NULL
+        "#
+    )
+    .bind(&payload.name)
+    .bind(payload.target_amount)
+    .bind(payload.target_date)
+    .bind(unlink_wallet)
+    .bind(payload.wallet_id)
+    .bind(&payload.icon)
+    .bind(&payload.color)
+    .bind(id)
+    .bind(user_id)
+    .execute(&state.db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Goal".to_string()));
+    }
+
+    let (response, _) = load_goal(&state, id, user_id).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Goal berhasil diupdate!",
+        "data": response
+    })))
+}
+
+pub async fn delete_goal(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let result = sqlx::query(
+        r#"UPDATE goals SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL"#
+    )
+    .bind(id)
+    .bind(user_id)
+    .execute(&state.db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Goal".to_string()));
+    }
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Goal berhasil dihapus!"
+    })))
+}
+
+pub async fn add_contribution(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<CreateContributionRequest>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    if payload.amount == 0.0 || !payload.amount.is_finite() {
+        return Err(AppError::ValidationError(
+            "Jumlah kontribusi tidak boleh 0".to_string(),
+        ));
+    }
+
+    let goal = db::get_goal_by_id(&state.db, id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Goal".to_string()))?;
+
+    // The balance of the linked wallet is the progress; money goes in through its transactions
+    if goal.goal.wallet_id.is_some() {
+        return Err(AppError::ValidationError(
+            "Goal ini mengikuti saldo wallet, tambahkan transaksi ke wallet tersebut".to_string(),
+        ));
+    }
+
+    if payload.amount < 0.0 && goal.saved_amount + payload.amount < 0.0 {
+        return Err(AppError::ValidationError(
+            "Penarikan melebihi jumlah yang sudah terkumpul".to_string(),
+        ));
+    }
+
+    let date = match payload.date {
+        Some(date) => date,
+        None => db::get_user_today(&state.db, user_id).await?,
+    };
+
+    let contribution = sqlx::query_as::<_, GoalContribution>(
+        r#"
+        INSERT INTO goal_contributions (id, goal_id, amount, date, note)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, goal_id, amount, date, note, created_at
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(id)
+    .bind(payload.amount)
+    .bind(date)
+    .bind(&payload.note)
+    .fetch_one(&state.db)
+    .await?;
+
+    let (response, _) = load_goal(&state, id, user_id).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": if response.status == "achieved" {
+            "Kontribusi berhasil dicatat! Target goal sudah tercapai 🎉"
+        } else {
+            "Kontribusi berhasil dicatat!"
+        },
+        "data": {
+            "goal": response,
+            "contribution": contribution
+        }
+    })))
+}
+
+pub async fn delete_contribution(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, contribution_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let result = sqlx::query(
+        r#"
+        DELETE FROM goal_contributions gc
+        USING goals g
+        WHERE gc.id = $1 AND gc.goal_id = $2 AND g.id = gc.goal_id AND g.user_id = $3 AND g.deleted_at IS NULL
+        "#
+    )
+    .bind(contribution_id)
+    .bind(id)
+    .bind(user_id)
+    .execute(&state.db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Contribution".to_string()));
+    }
+
+    let (response, _) = load_goal(&state, id, user_id).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Kontribusi berhasil dihapus!",
+        "data": response
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{auth_headers, create_user, test_state};
+    use sqlx::PgPool;
+
+    fn goal_request(target_amount: f64, wallet_id: Option<Uuid>) -> CreateGoalRequest {
+        CreateGoalRequest {
+            name: "Dana darurat".to_string(),
+            target_amount,
+            target_date: None,
+            wallet_id,
+            icon: None,
+            color: None,
+        }
+    }
+
+    async fn create(state: &AppState, user_id: Uuid, request: CreateGoalRequest) -> Value {
+        let Json(body) = create_goal(State(state.clone()), auth_headers(user_id), Json(request))
+            .await
+            .unwrap();
+        body["data"].clone()
+    }
+
+    async fn contribute(state: &AppState, user_id: Uuid, goal_id: Uuid, amount: f64) -> Result<Value, AppError> {
+        let request = CreateContributionRequest { amount, date: None, note: None };
+        add_contribution(State(state.clone()), auth_headers(user_id), Path(goal_id), Json(request))
+            .await
+            .map(|Json(body)| body)
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn manual_goal_tracks_contributions_and_withdrawals(pool: PgPool) {
+        let state = test_state(pool.clone());
+        let (user_id, _) = create_user(&pool).await;
+        let goal = create(&state, user_id, goal_request(1_000_000.0, None)).await;
+        let goal_id: Uuid = goal["id"].as_str().unwrap().parse().unwrap();
+        assert_eq!(goal["saved_amount"], 0.0);
+
+        let body = contribute(&state, user_id, goal_id, 400_000.0).await.unwrap();
+        assert_eq!(body["data"]["goal"]["saved_amount"], 400_000.0);
+        assert_eq!(body["data"]["goal"]["progress_percentage"], 40.0);
+
+        let result = contribute(&state, user_id, goal_id, -500_000.0).await;
+        assert!(matches!(result, Err(AppError::ValidationError(_))));
+        let body = contribute(&state, user_id, goal_id, -100_000.0).await.unwrap();
+        assert_eq!(body["data"]["goal"]["saved_amount"], 300_000.0);
+
+        let body = contribute(&state, user_id, goal_id, 700_000.0).await.unwrap();
+        assert_eq!(body["data"]["goal"]["status"], "achieved");
+        assert_eq!(body["data"]["goal"]["remaining_amount"], 0.0);
+
+        let contribution_id: Uuid = body["data"]["contribution"]["id"].as_str().unwrap().parse().unwrap();
+        let Json(body) = delete_contribution(State(state.clone()), auth_headers(user_id), Path((goal_id, contribution_id)))
+            .await
+            .unwrap();
+        assert_eq!(body["data"]["saved_amount"], 300_000.0);
+        assert_eq!(body["data"]["status"], "in_progress");
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn wallet_goal_follows_the_wallet_balance(pool: PgPool) {
+        let state = test_state(pool.clone());
+        let (user_id, _) = create_user(&pool).await;
+        let savings = crate::test_support::create_wallet(&pool, user_id, "Tabungan", "bank").await;
+        sqlx::query("UPDATE wallets SET balance = 2500000 WHERE id = $1")
+            .bind(savings)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let goal = create(&state, user_id, goal_request(10_000_000.0, Some(savings))).await;
+        assert_eq!(goal["saved_amount"], 2_500_000.0);
+        assert_eq!(goal["wallet_name"], "Tabungan");
+        assert_eq!(goal["progress_percentage"], 25.0);
+
+        let goal_id: Uuid = goal["id"].as_str().unwrap().parse().unwrap();
+        let result = contribute(&state, user_id, goal_id, 100_000.0).await;
+        assert!(matches!(result, Err(AppError::ValidationError(_))));
+
+        // Other users' wallets cannot be linked
+        let (other_user, other_wallet) = create_user(&pool).await;
+        let result = create_goal(
+            State(state.clone()),
+            auth_headers(other_user),
+            Json(goal_request(1_000_000.0, Some(savings))),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+        create(&state, other_user, goal_request(1_000_000.0, Some(other_wallet))).await;
+    }
+}
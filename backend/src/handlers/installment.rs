@@ -0,0 +1,460 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header::AUTHORIZATION, HeaderMap},
+    Json,
+};
+use chrono::{Datelike, NaiveDate};
+use serde_json::{json, Value};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    db,
+    error::AppError,
+    jobs::installment_posting,
+    models::{
+        installment::{
+            CommitmentQuery, CreateInstallmentPlanRequest, InstallmentPlan, InstallmentPlanQuery,
+            InstallmentPlanResponse, MonthlyCommitment, UpdateInstallmentPlanRequest, round_currency,
+        },
+        wallet::is_credit_wallet_type,
+    },
+    utils::{dates::shift_months, jwt::verify_token},
+    AppState,
+};
+
+const MIN_TENOR_MONTHS: i32 = 2;
+const MAX_TENOR_MONTHS: i32 = 36;
+// Flat monthly rate; paylater providers charge up to a few percent per month
+const MAX_INTEREST_RATE: f64 = 10.0;
+
+async fn get_user_id(state: &AppState, headers: &HeaderMap) -> Result<Uuid, AppError> {
+    let auth_header = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .ok_or(AppError::Unauthorized)?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or(AppError::Unauthorized)?;
+
+    let claims = verify_token(token, &state.config.jwt_secret)?;
+    Ok(claims.sub)
+}
+
+// Equal monthly amounts of principal plus flat interest; the last installment absorbs the
+// rounding difference so the schedule adds up to the exact total
+fn installment_amounts(principal: f64, interest_rate: f64, tenor_months: i32) -> Vec<f64> {
+    let total = principal + principal * interest_rate / 100.0 * tenor_months as f64;
+    let monthly = round_currency(total / tenor_months as f64);
+    let last = round_currency(total - monthly * (tenor_months - 1) as f64);
+
+    let mut amounts = vec![monthly; tenor_months as usize - 1];
+    amounts.push(last);
+    amounts
+}
+
+async fn ensure_category_exists(state: &AppState, user_id: Uuid, category_id: Uuid) -> Result<(), AppError> {
+    let category_exists: bool = sqlx::query_scalar(
+        r#"SELECT EXISTS(SELECT 1 FROM categories WHERE id = $1 AND (user_id = $2 OR user_id IS NULL) AND deleted_at IS NULL)"#
+    )
+    .bind(category_id)
+    .bind(user_id)
+    .fetch_one(&state.db)
+    .await?;
+
+    if !category_exists {
+        return Err(AppError::NotFound("Category".to_string()));
+    }
+    Ok(())
+}
+
+pub async fn list_installment_plans(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<InstallmentPlanQuery>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    if let Some(status) = query.status.as_deref() {
+        if !["active", "completed"].contains(&status) {
+            return Err(AppError::ValidationError(
+                "Status harus 'active' atau 'completed'".to_string(),
+            ));
+        }
+    }
+
+    let plans: Vec<InstallmentPlanResponse> = db::get_user_installment_plans(&state.db, user_id)
+        .await?
+        .into_iter()
+        .map(InstallmentPlanResponse::from)
+        .filter(|plan| query.status.as_deref().is_none_or(|status| plan.status == status))
+        .filter(|plan| query.wallet_id.is_none_or(|wallet_id| plan.wallet_id == wallet_id))
+        .collect();
+
+    let total_remaining = round_currency(plans.iter().map(|plan| plan.remaining_amount).sum());
+
+    Ok(Json(json!({
+        "success": true,
+        "data": plans,
+        "meta": {
+            "total_remaining": total_remaining
+        }
+    })))
+}
+
+pub async fn create_installment_plan(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateInstallmentPlanRequest>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    payload.validate().map_err(|e| {
+        AppError::ValidationError(e.to_string())
+    })?;
+
+    if payload.principal <= 0.0 || !payload.principal.is_finite() {
+        return Err(AppError::ValidationError(
+            "Harga pembelian harus lebih besar dari 0".to_string(),
+        ));
+    }
+
+    if !(MIN_TENOR_MONTHS..=MAX_TENOR_MONTHS).contains(&payload.tenor_months) {
+        return Err(AppError::ValidationError(format!(
+            "Tenor harus antara {}-{} bulan",
+            MIN_TENOR_MONTHS, MAX_TENOR_MONTHS
+        )));
+    }
+
+    let interest_rate = payload.interest_rate.unwrap_or(0.0);
+    if !(0.0..=MAX_INTEREST_RATE).contains(&interest_rate) {
+        return Err(AppError::ValidationError(format!(
+            "Bunga per bulan harus antara 0-{}%",
+            MAX_INTEREST_RATE
+        )));
+    }
+
+    let wallet = db::get_wallet_by_id(&state.db, payload.wallet_id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Wallet".to_string()))?;
+
+    if !is_credit_wallet_type(&wallet.wallet_type) {
+        return Err(AppError::ValidationError(
+            "Cicilan hanya bisa dibuat untuk wallet kartu kredit atau paylater".to_string(),
+        ));
+    }
+
+    if let Some(category_id) = payload.category_id {
+        ensure_category_exists(&state, user_id, category_id).await?;
+    }
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let purchase_date = payload.purchase_date.unwrap_or(today);
+    let first_due_date = payload.first_due_date.unwrap_or(purchase_date);
+    if first_due_date < purchase_date {
+        return Err(AppError::ValidationError(
+            "Tanggal cicilan pertama tidak boleh sebelum tanggal pembelian".to_string(),
+        ));
+    }
+
+    let amounts = installment_amounts(payload.principal, interest_rate, payload.tenor_months);
+    let numbers: Vec<i32> = (1..=payload.tenor_months).collect();
+    let due_dates: Vec<NaiveDate> = (0..payload.tenor_months)
+        .map(|offset| shift_months(first_due_date, offset, first_due_date.day() as i32))
+        .collect();
+
+    let mut db_tx = state.db.begin().await?;
+
+    let plan = sqlx::query_as::<_, InstallmentPlan>(
+        r#"
+        INSERT INTO installment_plans (id, user_id, wallet_id, category_id, description, principal, interest_rate, tenor_months, purchase_date)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        RETURNING id, user_id, wallet_id, category_id, description, principal, interest_rate, tenor_months, purchase_date, created_at, updated_at, deleted_at
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(payload.wallet_id)
+    .bind(payload.category_id)
+    .bind(&payload.description)
+    .bind(payload.principal)
+    .bind(interest_rate)
+    .bind(payload.tenor_months)
+    .bind(purchase_date)
+    .fetch_one(&mut *db_tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO installments (plan_id, installment_number, due_date, amount)
+        SELECT $1, * FROM UNNEST($2::int[], $3::date[], $4::float8[])
+        "#
+    )
+    .bind(plan.id)
+    .bind(&numbers)
+    .bind(&due_dates)
+    .bind(&amounts)
+    .execute(&mut *db_tx)
+    .await?;
+
+    // A purchase registered after the fact books the installments that are already due
+    installment_posting::post_plan_installments(&mut db_tx, plan.id, Some(today)).await?;
+
+    db_tx.commit().await?;
+
+    let created = db::get_installment_plan_by_id(&state.db, plan.id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Installment plan".to_string()))?;
+    let schedule = db::get_installment_schedule(&state.db, plan.id).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Cicilan berhasil dibuat!",
+        "data": {
+            "plan": InstallmentPlanResponse::from(created),
+            "schedule": schedule
+        }
+    })))
+}
+
+pub async fn get_installment_plan(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let plan = db::get_installment_plan_by_id(&state.db, id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Installment plan".to_string()))?;
+    let schedule = db::get_installment_schedule(&state.db, id).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "plan": InstallmentPlanResponse::from(plan),
+            "schedule": schedule
+        }
+    })))
+}
+
+pub async fn update_installment_plan(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateInstallmentPlanRequest>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    payload.validate().map_err(|e| {
+        AppError::ValidationError(e.to_string())
+    })?;
+
+    if let Some(category_id) = payload.category_id {
+        ensure_category_exists(&state, user_id, category_id).await?;
+    }
+
+    // Installments already posted keep their transaction as it is
+    let result = sqlx::query(
+        r#"
+        UPDATE installment_plans SET
+            description = COALESCE($1, description),
+            category_id = COALESCE($2, category_id),
+            updated_at = NOW()
+        WHERE id = $3 AND user_id = $4 AND deleted_at IS NULL
+        "#
+    )
+    .bind(&payload.description)
+    .bind(payload.category_id)
+    .bind(id)
+    .bind(user_id)
+    .execute(&state.db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Installment plan".to_string()));
+    }
+
+    let updated = db::get_installment_plan_by_id(&state.db, id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Installment plan".to_string()))?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Cicilan berhasil diupdate!",
+        "data": InstallmentPlanResponse::from(updated)
+    })))
+}
+
+pub async fn delete_installment_plan(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    // Stops future postings; installments already posted stay in the ledger
+    let result = sqlx::query(
+        r#"UPDATE installment_plans SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL"#
+    )
+    .bind(id)
+    .bind(user_id)
+    .execute(&state.db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Installment plan".to_string()));
+    }
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Cicilan berhasil dibatalkan! Cicilan yang sudah tercatat tetap tersimpan."
+    })))
+}
+
+// Installments still to be posted per month, starting with the current month. Months without
+// installments are included with zero so the result lines up with a budget calendar.
+pub async fn list_commitments(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<CommitmentQuery>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let months = query.months.unwrap_or(6);
+    if !(1..=MAX_TENOR_MONTHS).contains(&months) {
+        return Err(AppError::ValidationError(format!(
+            "Months harus antara 1-{}",
+            MAX_TENOR_MONTHS
+        )));
+    }
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let from = today.with_day(1).unwrap();
+    let to = shift_months(from, months, 1);
+
+    let rows = db::get_installment_commitments(&state.db, user_id, from, to).await?;
+
+    let commitments: Vec<MonthlyCommitment> = (0..months)
+        .map(|offset| {
+            let month_start = shift_months(from, offset, 1);
+            let (year, month) = (month_start.year(), month_start.month() as i32);
+            rows.iter()
+                .find(|row| row.year == year && row.month == month)
+                .copied()
+                .unwrap_or(MonthlyCommitment {
+                    month,
+                    year,
+                    amount: 0.0,
+                    installment_count: 0,
+                })
+        })
+        .collect();
+
+    let total = round_currency(commitments.iter().map(|commitment| commitment.amount).sum());
+
+    Ok(Json(json!({
+        "success": true,
+        "data": commitments,
+        "meta": {
+            "months": months,
+            "total": total
+        }
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{auth_headers, create_user, create_wallet, test_state};
+    use chrono::Duration;
+    use sqlx::PgPool;
+
+    fn total(amounts: &[f64]) -> f64 {
+        round_currency(amounts.iter().sum())
+    }
+
+    #[test]
+    fn installment_amounts_add_up_to_principal_plus_interest() {
+        let amounts = installment_amounts(1_200_000.0, 2.0, 12);
+        assert_eq!(amounts, vec![124_000.0; 12]);
+        assert_eq!(total(&amounts), 1_488_000.0);
+
+        let amounts = installment_amounts(2_500_000.0, 1.75, 6);
+        assert_eq!(amounts.len(), 6);
+        assert_eq!(total(&amounts), 2_762_500.0);
+    }
+
+    #[test]
+    fn installment_amounts_for_one_month() {
+        assert_eq!(installment_amounts(500_000.0, 1.5, 1), vec![507_500.0]);
+        assert_eq!(installment_amounts(500_000.0, 0.0, 1), vec![500_000.0]);
+    }
+
+    #[test]
+    fn last_installment_absorbs_rounding() {
+        assert_eq!(installment_amounts(1_000_000.0, 0.0, 3), vec![333_333.33, 333_333.33, 333_333.34]);
+        assert_eq!(installment_amounts(100.0, 0.0, 6), vec![16.67, 16.67, 16.67, 16.67, 16.67, 16.65]);
+        for tenor in 2..=36 {
+            let amounts = installment_amounts(999_999.99, 2.5, tenor);
+            let expected = round_currency(999_999.99 + 999_999.99 * 0.025 * tenor as f64);
+            assert_eq!(total(&amounts), expected, "tenor {}", tenor);
+        }
+    }
+
+    async fn create(state: &AppState, user_id: Uuid, request: CreateInstallmentPlanRequest) -> Uuid {
+        let response = create_installment_plan(State(state.clone()), auth_headers(user_id), Json(request))
+            .await
+            .unwrap();
+        response.0["data"]["plan"]["id"].as_str().unwrap().parse().unwrap()
+    }
+
+    async fn unposted_count(pool: &PgPool, plan_id: Uuid) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM installments WHERE plan_id = $1 AND posted_at IS NULL")
+            .bind(plan_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn creating_a_plan_only_books_its_own_due_installments(pool: PgPool) {
+        let (user_id, _) = create_user(&pool).await;
+        let wallet_id = create_wallet(&pool, user_id, "Kartu Kredit", "credit-card").await;
+        let state = test_state(pool.clone());
+        let today = db::get_user_today(&pool, user_id).await.unwrap();
+
+        let request = |description: &str, purchase_date: NaiveDate| CreateInstallmentPlanRequest {
+            wallet_id,
+            category_id: None,
+            description: description.to_string(),
+            principal: 3_000_000.0,
+            interest_rate: None,
+            tenor_months: 3,
+            purchase_date: Some(purchase_date),
+            first_due_date: None,
+        };
+
+        // A plan whose installments fell due while the posting job was not running
+        let waiting = create(&state, user_id, request("Laptop", today + Duration::days(1))).await;
+        sqlx::query("UPDATE installments SET due_date = due_date - 200 WHERE plan_id = $1")
+            .bind(waiting)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // Registered after the fact: the first installment is due today
+        let new = create(&state, user_id, request("Ponsel", today)).await;
+
+        assert_eq!(unposted_count(&pool, new).await, 2);
+        assert_eq!(unposted_count(&pool, waiting).await, 3);
+        let balance: f64 = sqlx::query_scalar("SELECT balance FROM wallets WHERE id = $1")
+            .bind(wallet_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(balance, -1_000_000.0);
+    }
+}
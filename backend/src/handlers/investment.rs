@@ -0,0 +1,572 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header::AUTHORIZATION, HeaderMap},
+    Json,
+};
+use chrono::NaiveDate;
+use serde_json::{json, Value};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    db,
+    error::AppError,
+    models::{
+        investment::{
+            CreateHoldingRequest, CreatePriceRequest, Holding, HoldingQuery, HoldingResponse,
+            PriceQuery, UpdateHoldingRequest, ASSET_TYPES,
+        },
+        wallet::INVESTMENT_WALLET_TYPE,
+    },
+    utils::jwt::verify_token,
+    AppState,
+};
+
+// Rows accepted by one price import
+const MAX_IMPORT_ROWS: usize = 1000;
+
+async fn get_user_id(state: &AppState, headers: &HeaderMap) -> Result<Uuid, AppError> {
+    let auth_header = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .ok_or(AppError::Unauthorized)?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or(AppError::Unauthorized)?;
+
+    let claims = verify_token(token, &state.config.jwt_secret)?;
+    Ok(claims.sub)
+}
+
+fn validate_asset_type(asset_type: &str) -> Result<(), AppError> {
+    if !ASSET_TYPES.contains(&asset_type) {
+        return Err(AppError::ValidationError(format!(
+            "Tipe aset harus salah satu dari: {}",
+            ASSET_TYPES.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+// Symbols are matched case-insensitively, e.g. "bbca" and "BBCA"
+fn normalize_symbol(symbol: &str) -> Result<String, AppError> {
+    let symbol = symbol.trim().to_uppercase();
+    if symbol.is_empty() || symbol.chars().count() > 30 {
+        return Err(AppError::ValidationError(
+            "Kode aset wajib diisi (maksimal 30 karakter)".to_string(),
+        ));
+    }
+    Ok(symbol)
+}
+
+fn validate_non_negative(value: f64, field: &str) -> Result<(), AppError> {
+    if value < 0.0 || !value.is_finite() {
+        return Err(AppError::ValidationError(format!(
+            "{} tidak boleh negatif",
+            field
+        )));
+    }
+    Ok(())
+}
+
+pub async fn list_holdings(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<HoldingQuery>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    if let Some(asset_type) = query.asset_type.as_deref() {
+        validate_asset_type(asset_type)?;
+    }
+
+    let holdings: Vec<HoldingResponse> = db::get_user_holdings(&state.db, user_id)
+        .await?
+        .into_iter()
+        .map(HoldingResponse::from)
+        .filter(|holding| query.wallet_id.is_none_or(|wallet_id| holding.wallet_id == wallet_id))
+        .filter(|holding| query.asset_type.as_deref().is_none_or(|asset_type| holding.asset_type == asset_type))
+        .collect();
+
+    let total_cost: f64 = holdings.iter().map(|holding| holding.cost_basis).sum();
+    let total_market_value: f64 = holdings.iter().map(|holding| holding.market_value).sum();
+    let total_unrealized_gain = total_market_value - total_cost;
+
+    let by_asset_type: Vec<Value> = ASSET_TYPES
+        .iter()
+        .filter_map(|asset_type| {
+            let group: Vec<&HoldingResponse> = holdings
+                .iter()
+                .filter(|holding| holding.asset_type == *asset_type)
+                .collect();
+            if group.is_empty() {
+                return None;
+            }
+
+            let cost: f64 = group.iter().map(|holding| holding.cost_basis).sum();
+            let market_value: f64 = group.iter().map(|holding| holding.market_value).sum();
+            Some(json!({
+                "asset_type": asset_type,
+                "cost_basis": cost,
+                "market_value": market_value,
+                "unrealized_gain": market_value - cost,
+                "allocation_percentage": if total_market_value > 0.0 {
+                    market_value / total_market_value * 100.0
+                } else {
+                    0.0
+                }
+            }))
+        })
+        .collect();
+
+    Ok(Json(json!({
+        "success": true,
+        "data": holdings,
+        "meta": {
+            "total_cost": total_cost,
+            "total_market_value": total_market_value,
+            "total_unrealized_gain": total_unrealized_gain,
+            "total_unrealized_gain_percentage": (total_cost > 0.0)
+                .then(|| total_unrealized_gain / total_cost * 100.0),
+            "by_asset_type": by_asset_type
+        }
+    })))
+}
+
+pub async fn create_holding(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateHoldingRequest>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    payload.validate().map_err(|e| {
+        AppError::ValidationError(e.to_string())
+    })?;
+
+    validate_asset_type(&payload.asset_type)?;
+    let symbol = normalize_symbol(&payload.symbol)?;
+    validate_non_negative(payload.quantity, "Jumlah unit")?;
+
+    let cost_basis = match (payload.cost_basis, payload.average_price) {
+        (Some(_), Some(_)) => {
+            return Err(AppError::ValidationError(
+                "Isi salah satu: cost_basis atau average_price".to_string(),
+            ))
+        }
+        (Some(cost_basis), None) => cost_basis,
+        (None, Some(average_price)) => {
+            validate_non_negative(average_price, "Harga rata-rata")?;
+            average_price * payload.quantity
+        }
+        (None, None) => {
+            return Err(AppError::ValidationError(
+                "Modal pembelian (cost_basis atau average_price) wajib diisi".to_string(),
+            ))
+        }
+    };
+    validate_non_negative(cost_basis, "Modal pembelian")?;
+
+    let wallet = db::get_wallet_by_id(&state.db, payload.wallet_id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Wallet".to_string()))?;
+
+    if wallet.wallet_type != INVESTMENT_WALLET_TYPE {
+        return Err(AppError::ValidationError(
+            "Aset investasi hanya bisa ditambahkan ke wallet bertipe investment".to_string(),
+        ));
+    }
+
+    let exists: bool = sqlx::query_scalar(
+        r#"SELECT EXISTS(SELECT 1 FROM investment_holdings WHERE wallet_id = $1 AND asset_type = $2 AND symbol = $3 AND deleted_at IS NULL)"#
+    )
+    .bind(payload.wallet_id)
+    .bind(&payload.asset_type)
+    .bind(&symbol)
+    .fetch_one(&state.db)
+    .await?;
+
+    if exists {
+        return Err(AppError::Conflict(
+            "Aset ini sudah ada di wallet tersebut, update jumlah dan modalnya".to_string(),
+        ));
+    }
+
+    let holding = sqlx::query_as::<_, Holding>(
+        r#"
+        INSERT INTO investment_holdings (id, user_id, wallet_id, asset_type, symbol, name, quantity, cost_basis)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING id, user_id, wallet_id, asset_type, symbol, name, quantity, cost_basis, created_at, updated_at, deleted_at
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(payload.wallet_id)
+    .bind(&payload.asset_type)
+    .bind(&symbol)
+    .bind(&payload.name)
+    .bind(payload.quantity)
+    .bind(cost_basis)
+    .fetch_one(&state.db)
+    .await?;
+
+    let created = db::get_holding_by_id(&state.db, holding.id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Holding".to_string()))?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Aset investasi berhasil ditambahkan!",
+        "data": HoldingResponse::from(created)
+    })))
+}
+
+pub async fn get_holding(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let holding = db::get_holding_by_id(&state.db, id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Holding".to_string()))?;
+
+    let prices = db::get_asset_prices(
+        &state.db,
+        user_id,
+        Some(&holding.holding.asset_type),
+        Some(&holding.holding.symbol),
+    )
+    .await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "holding": HoldingResponse::from(holding),
+            "prices": prices
+        }
+    })))
+}
+
+pub async fn update_holding(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateHoldingRequest>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    payload.validate().map_err(|e| {
+        AppError::ValidationError(e.to_string())
+    })?;
+
+    if let Some(quantity) = payload.quantity {
+        validate_non_negative(quantity, "Jumlah unit")?;
+    }
+    if let Some(cost_basis) = payload.cost_basis {
+        validate_non_negative(cost_basis, "Modal pembelian")?;
+    }
+
+    let result = sqlx::query(
+        r#"
+        UPDATE investment_holdings SET
+            name = COALESCE($1, name),
+            quantity = COALESCE($2, quantity),
+            cost_basis = COALESCE($3, cost_basis),
+            updated_at = NOW()
+        WHERE id = $4 AND user_id = $5 AND deleted_at IS NULL
+        "#
+    )
+    .bind(&payload.name)
+    .bind(payload.quantity)
+    .bind(payload.cost_basis)
+    .bind(id)
+    .bind(user_id)
+    .execute(&state.db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Holding".to_string()));
+    }
+
+    let updated = db::get_holding_by_id(&state.db, id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Holding".to_string()))?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Aset investasi berhasil diupdate!",
+        "data": HoldingResponse::from(updated)
+    })))
+}
+
+pub async fn delete_holding(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let result = sqlx::query(
+        r#"UPDATE investment_holdings SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL"#
+    )
+    .bind(id)
+    .bind(user_id)
+    .execute(&state.db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Holding".to_string()));
+    }
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Aset investasi berhasil dihapus!"
+    })))
+}
+
+pub async fn list_prices(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<PriceQuery>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    if let Some(asset_type) = query.asset_type.as_deref() {
+        validate_asset_type(asset_type)?;
+    }
+    let symbol = query.symbol.as_deref().map(normalize_symbol).transpose()?;
+
+    let prices = db::get_asset_prices(&state.db, user_id, query.asset_type.as_deref(), symbol.as_deref()).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": prices
+    })))
+}
+
+pub async fn create_price(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<CreatePriceRequest>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    validate_asset_type(&payload.asset_type)?;
+    let symbol = normalize_symbol(&payload.symbol)?;
+    validate_non_negative(payload.price, "Harga")?;
+
+    let date = match payload.date {
+        Some(date) => date,
+        None => db::get_user_today(&state.db, user_id).await?,
+    };
+
+    let price = db::upsert_asset_price(
+        &state.db,
+        user_id,
+        &payload.asset_type,
+        &symbol,
+        payload.price,
+        date,
+        "manual",
+    )
+    .await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Harga berhasil disimpan!",
+        "data": price
+    })))
+}
+
+// One price row of an import: asset_type,symbol,price[,date]
+fn parse_price_row(line: &str, today: NaiveDate) -> Result<(String, String, f64, NaiveDate), String> {
+    let fields: Vec<&str> = line.split(',').map(|field| field.trim()).collect();
+    if !(3..=4).contains(&fields.len()) {
+        return Err("Format harus asset_type,symbol,price[,date]".to_string());
+    }
+
+    let asset_type = fields[0].to_lowercase();
+    if !ASSET_TYPES.contains(&asset_type.as_str()) {
+        return Err(format!("Tipe aset '{}' tidak dikenal", fields[0]));
+    }
+
+    let symbol = normalize_symbol(fields[1]).map_err(|_| "Kode aset tidak valid".to_string())?;
+
+    let price: f64 = fields[2]
+        .parse()
+        .ok()
+        .filter(|price: &f64| *price >= 0.0 && price.is_finite())
+        .ok_or_else(|| format!("Harga '{}' tidak valid", fields[2]))?;
+
+    let date = match fields.get(3).filter(|date| !date.is_empty()) {
+        Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| format!("Tanggal '{}' harus berformat YYYY-MM-DD", date))?,
+        None => today,
+    };
+
+    Ok((asset_type, symbol, price, date))
+}
+
+// Imports prices from a CSV body with the columns asset_type,symbol,price[,date]. A header row
+// and lines starting with '#' are skipped. Valid rows are saved even when others fail.
+pub async fn import_prices(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+
+    for (index, line) in body.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || (index == 0 && line.to_lowercase().starts_with("asset_type")) {
+            continue;
+        }
+
+        match parse_price_row(line, today) {
+            Ok(row) => rows.push(row),
+            Err(error) => errors.push(json!({ "line": index + 1, "error": error })),
+        }
+    }
+
+    if rows.is_empty() && errors.is_empty() {
+        return Err(AppError::ValidationError(
+            "File harga kosong".to_string(),
+        ));
+    }
+    if rows.len() > MAX_IMPORT_ROWS {
+        return Err(AppError::ValidationError(format!(
+            "Maksimal {} baris per import",
+            MAX_IMPORT_ROWS
+        )));
+    }
+
+    let mut db_tx = state.db.begin().await?;
+    for (asset_type, symbol, price, date) in &rows {
+        db::upsert_asset_price(&mut *db_tx, user_id, asset_type, symbol, *price, *date, "import").await?;
+    }
+    db_tx.commit().await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": format!("{} harga berhasil diimport", rows.len()),
+        "data": {
+            "imported": rows.len(),
+            "errors": errors
+        }
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{auth_headers, create_user, create_wallet, test_state};
+    use sqlx::PgPool;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn parse_price_row_accepts_optional_date() {
+        let today = date(2025, 3, 10);
+        assert_eq!(
+            parse_price_row("stock, bbca , 9800", today),
+            Ok(("stock".to_string(), "BBCA".to_string(), 9800.0, today))
+        );
+        assert_eq!(
+            parse_price_row("Gold,ANTM,1500000.5,2025-03-01", today),
+            Ok(("gold".to_string(), "ANTM".to_string(), 1_500_000.5, date(2025, 3, 1)))
+        );
+        assert_eq!(parse_price_row("crypto,BTC,1000,", today).unwrap().3, today);
+    }
+
+    #[test]
+    fn parse_price_row_rejects_bad_fields() {
+        let today = date(2025, 3, 10);
+        for line in [
+            "stock,BBCA",
+            "stock,BBCA,9800,2025-03-01,extra",
+            "bond,FR0098,100",
+            "stock, ,9800",
+            "stock,BBCA,abc",
+            "stock,BBCA,-1",
+            "stock,BBCA,NaN",
+            "stock,BBCA,9800,01/03/2025",
+        ] {
+            assert!(parse_price_row(line, today).is_err(), "{}", line);
+        }
+    }
+
+    fn holding_request(
+        wallet_id: Uuid,
+        asset_type: &str,
+        symbol: &str,
+        quantity: f64,
+        average_price: f64,
+    ) -> CreateHoldingRequest {
+        CreateHoldingRequest {
+            wallet_id,
+            asset_type: asset_type.to_string(),
+            symbol: symbol.to_string(),
+            name: None,
+            quantity,
+            cost_basis: None,
+            average_price: Some(average_price),
+        }
+    }
+
+    async fn create(state: &AppState, user_id: Uuid, request: CreateHoldingRequest) -> Result<Json<Value>, AppError> {
+        create_holding(State(state.clone()), auth_headers(user_id), Json(request)).await
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn imported_prices_value_holdings_and_net_worth(pool: PgPool) {
+        let (user_id, cash) = create_user(&pool).await;
+        let wallet_id = create_wallet(&pool, user_id, "Portofolio", INVESTMENT_WALLET_TYPE).await;
+        let state = test_state(pool.clone());
+
+        let result = create(&state, user_id, holding_request(cash, "stock", "BBCA", 100.0, 9_000.0)).await;
+        assert!(matches!(result, Err(AppError::ValidationError(_))));
+        for request in [
+            holding_request(wallet_id, "stock", "bbca", 100.0, 9_000.0),
+            holding_request(wallet_id, "gold", "ANTM", 10.0, 1_000_000.0),
+        ] {
+            let response = create(&state, user_id, request).await.unwrap();
+            assert_eq!(response.0["data"]["unrealized_gain"], 0.0);
+        }
+        let result = create(&state, user_id, holding_request(wallet_id, "stock", "BBCA", 1.0, 1.0)).await;
+        assert!(matches!(result, Err(AppError::Conflict(_))));
+
+        let body = "asset_type,symbol,price,date\n\
+                    stock,bbca,9500,2025-03-01\n\
+                    # harga terbaru\n\
+                    stock,BBCA,9800\n\
+                    gold,ANTM,abc\n";
+        let response = import_prices(State(state.clone()), auth_headers(user_id), body.to_string()).await.unwrap();
+        assert_eq!(response.0["data"]["imported"], 2);
+        assert_eq!(response.0["data"]["errors"][0]["line"], 5);
+
+        let query = HoldingQuery { wallet_id: None, asset_type: None };
+        let response = list_holdings(State(state), auth_headers(user_id), Query(query)).await.unwrap();
+        let meta = &response.0["meta"];
+        // BBCA at its latest price, gold still at cost
+        assert_eq!(meta["total_cost"], 10_900_000.0);
+        assert_eq!(meta["total_market_value"], 10_980_000.0);
+        assert_eq!(meta["total_unrealized_gain"], 80_000.0);
+
+        let today = db::get_user_today(&pool, user_id).await.unwrap();
+        db::snapshot_wallet_balances(&pool, Some(user_id), Some(today)).await.unwrap();
+        let points = db::get_net_worth_history(&pool, user_id, &[date(2025, 3, 1), today]).await.unwrap();
+        // The holdings did not exist yet in March 2025
+        assert_eq!(points[0].investment_assets, 0.0);
+        assert_eq!(points[1].investment_assets, 10_980_000.0);
+    }
+}
@@ -1,8 +1,20 @@
+pub mod audit;
 pub mod auth;
+pub mod bill;
 pub mod category;
 pub mod dashboard;
+pub mod debt;
+pub mod digest;
+pub mod goal;
 pub mod health;
+pub mod installment;
+pub mod investment;
+pub mod notification;
+pub mod recurring;
+pub mod report;
+pub mod subscription;
 pub mod transaction;
 pub mod wallet;
 pub mod budget;
 
+pub mod zakat;
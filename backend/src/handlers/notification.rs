@@ -0,0 +1,113 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header::AUTHORIZATION, HeaderMap},
+    Json,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::{
+    db,
+    error::AppError,
+    models::notification::NotificationQuery,
+    utils::jwt::verify_token,
+    AppState,
+};
+
+async fn get_user_id(state: &AppState, headers: &HeaderMap) -> Result<Uuid, AppError> {
+    let auth_header = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .ok_or(AppError::Unauthorized)?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or(AppError::Unauthorized)?;
+
+    let claims = verify_token(token, &state.config.jwt_secret)?;
+    Ok(claims.sub)
+}
+
+pub async fn list_notifications(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<NotificationQuery>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let notifications = db::get_user_notifications(
+        &state.db,
+        user_id,
+        query.unread.unwrap_or(false),
+        limit,
+        offset,
+    )
+    .await?;
+
+    let unread_count: i64 = sqlx::query_scalar(
+        r#"SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND read_at IS NULL"#
+    )
+    .bind(user_id)
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": notifications,
+        "meta": {
+            "unread_count": unread_count,
+            "limit": limit,
+            "offset": offset
+        }
+    })))
+}
+
+pub async fn mark_notification_read(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let result = sqlx::query(
+        r#"UPDATE notifications SET read_at = COALESCE(read_at, NOW()) WHERE id = $1 AND user_id = $2"#
+    )
+    .bind(id)
+    .bind(user_id)
+    .execute(&state.db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Notification".to_string()));
+    }
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Notifikasi ditandai sudah dibaca"
+    })))
+}
+
+pub async fn mark_all_notifications_read(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let result = sqlx::query(
+        r#"UPDATE notifications SET read_at = NOW() WHERE user_id = $1 AND read_at IS NULL"#
+    )
+    .bind(user_id)
+    .execute(&state.db)
+    .await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Semua notifikasi ditandai sudah dibaca",
+        "data": {
+            "updated": result.rows_affected()
+        }
+    })))
+}
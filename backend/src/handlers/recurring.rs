@@ -0,0 +1,322 @@
+use axum::{
+    extract::{Path, State},
+    http::{header::AUTHORIZATION, HeaderMap},
+    Json,
+};
+use chrono::Datelike;
+use serde_json::{json, Value};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    db,
+    error::AppError,
+    jobs::recurring_posting,
+    models::recurring::{
+        CreateRecurringRequest, RecurringResponse, RecurringTransaction, UpdateRecurringRequest,
+        RECURRING_FREQUENCIES,
+    },
+    utils::jwt::verify_token,
+    AppState,
+};
+
+const MAX_INTERVAL_COUNT: i32 = 12;
+
+async fn get_user_id(state: &AppState, headers: &HeaderMap) -> Result<Uuid, AppError> {
+    let auth_header = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .ok_or(AppError::Unauthorized)?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or(AppError::Unauthorized)?;
+
+    let claims = verify_token(token, &state.config.jwt_secret)?;
+    Ok(claims.sub)
+}
+
+fn validate_amount(amount: f64) -> Result<(), AppError> {
+    if amount <= 0.0 || !amount.is_finite() {
+        return Err(AppError::ValidationError(
+            "Jumlah harus lebih besar dari 0".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+async fn ensure_wallet_exists(state: &AppState, user_id: Uuid, wallet_id: Uuid) -> Result<(), AppError> {
+    db::get_wallet_by_id(&state.db, wallet_id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Wallet".to_string()))?;
+    Ok(())
+}
+
+async fn ensure_category_exists(state: &AppState, user_id: Uuid, category_id: Uuid) -> Result<(), AppError> {
+    let category_exists: bool = sqlx::query_scalar(
+        r#"SELECT EXISTS(SELECT 1 FROM categories WHERE id = $1 AND (user_id = $2 OR user_id IS NULL) AND deleted_at IS NULL)"#
+    )
+    .bind(category_id)
+    .bind(user_id)
+    .fetch_one(&state.db)
+    .await?;
+
+    if !category_exists {
+        return Err(AppError::NotFound("Category".to_string()));
+    }
+    Ok(())
+}
+
+// Validates and stores a new schedule, then books the occurrences that are already due.
+// Also used when a detected subscription is converted.
+pub(crate) async fn insert_recurring(
+    state: &AppState,
+    user_id: Uuid,
+    payload: &CreateRecurringRequest,
+    subscription_key: Option<&str>,
+) -> Result<RecurringTransaction, AppError> {
+    payload.validate().map_err(|e| {
+        AppError::ValidationError(e.to_string())
+    })?;
+
+    if payload.transaction_type != "income" && payload.transaction_type != "expense" {
+        return Err(AppError::ValidationError(
+            "Tipe transaksi harus income atau expense".to_string(),
+        ));
+    }
+
+    validate_amount(payload.amount)?;
+
+    if !RECURRING_FREQUENCIES.contains(&payload.frequency.as_str()) {
+        return Err(AppError::ValidationError(
+            "Frekuensi harus salah satu dari: weekly, monthly, yearly".to_string(),
+        ));
+    }
+
+    let interval_count = payload.interval_count.unwrap_or(1);
+    if !(1..=MAX_INTERVAL_COUNT).contains(&interval_count) {
+        return Err(AppError::ValidationError(format!(
+            "Interval harus antara 1-{}",
+            MAX_INTERVAL_COUNT
+        )));
+    }
+
+    ensure_wallet_exists(state, user_id, payload.wallet_id).await?;
+    if let Some(category_id) = payload.category_id {
+        ensure_category_exists(state, user_id, category_id).await?;
+    }
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let start_date = payload.start_date.unwrap_or(today);
+    if payload.end_date.is_some_and(|end_date| end_date < start_date) {
+        return Err(AppError::ValidationError(
+            "Tanggal berakhir tidak boleh sebelum tanggal mulai".to_string(),
+        ));
+    }
+
+    let mut db_tx = state.db.begin().await?;
+
+    let recurring = sqlx::query_as::<_, RecurringTransaction>(
+        r#"
+        INSERT INTO recurring_transactions (id, user_id, wallet_id, category_id, transaction_type, amount, description,
+            frequency, interval_count, anchor_day, next_date, end_date, auto_post, subscription_key)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+        RETURNING id, user_id, wallet_id, category_id, transaction_type, amount, description, frequency,
+            interval_count, anchor_day, next_date, end_date, auto_post, subscription_key, created_at, updated_at, deleted_at
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(payload.wallet_id)
+    .bind(payload.category_id)
+    .bind(&payload.transaction_type)
+    .bind(payload.amount)
+    .bind(&payload.description)
+    .bind(&payload.frequency)
+    .bind(interval_count)
+    .bind(start_date.day() as i16)
+    .bind(start_date)
+    .bind(payload.end_date)
+    .bind(payload.auto_post.unwrap_or(true))
+    .bind(subscription_key)
+    .fetch_one(&mut *db_tx)
+    .await?;
+
+    // A schedule that started in the past books the occurrences that are already due
+    recurring_posting::post_due_recurring(&mut db_tx, Some(user_id), Some(today)).await?;
+
+    db_tx.commit().await?;
+
+    db::get_recurring_transaction_by_id(&state.db, recurring.id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Recurring transaction".to_string()))
+}
+
+pub async fn list_recurring(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let recurring: Vec<RecurringResponse> = db::get_user_recurring_transactions(&state.db, user_id)
+        .await?
+        .into_iter()
+        .map(RecurringResponse::from)
+        .collect();
+
+    // Yearly cost of the active expense schedules, e.g. all subscriptions together
+    let annual_expense: f64 = recurring
+        .iter()
+        .filter(|item| item.status == "active" && item.transaction_type == "expense")
+        .map(|item| item.annual_amount)
+        .sum();
+
+    Ok(Json(json!({
+        "success": true,
+        "data": recurring,
+        "meta": {
+            "annual_expense": annual_expense
+        }
+    })))
+}
+
+pub async fn create_recurring(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateRecurringRequest>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let recurring = insert_recurring(&state, user_id, &payload, None).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Transaksi rutin berhasil dibuat!",
+        "data": RecurringResponse::from(recurring)
+    })))
+}
+
+pub async fn get_recurring(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let recurring = db::get_recurring_transaction_by_id(&state.db, id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Recurring transaction".to_string()))?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": RecurringResponse::from(recurring)
+    })))
+}
+
+pub async fn update_recurring(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateRecurringRequest>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    payload.validate().map_err(|e| {
+        AppError::ValidationError(e.to_string())
+    })?;
+
+    if let Some(amount) = payload.amount {
+        validate_amount(amount)?;
+    }
+    if let Some(wallet_id) = payload.wallet_id {
+        ensure_wallet_exists(&state, user_id, wallet_id).await?;
+    }
+    if let Some(category_id) = payload.category_id {
+        ensure_category_exists(&state, user_id, category_id).await?;
+    }
+
+    let existing = db::get_recurring_transaction_by_id(&state.db, id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Recurring transaction".to_string()))?;
+
+    let next_date = payload.next_date.unwrap_or(existing.next_date);
+    let end_date = payload.end_date.or(existing.end_date);
+    if end_date.is_some_and(|end_date| end_date < next_date) {
+        return Err(AppError::ValidationError(
+            "Tanggal berakhir tidak boleh sebelum tanggal berikutnya".to_string(),
+        ));
+    }
+
+    let mut db_tx = state.db.begin().await?;
+
+    // Occurrences already booked keep their transactions as they are
+    sqlx::query(
+        r#"
+        UPDATE recurring_transactions SET
+            wallet_id = COALESCE($1, wallet_id),
+            category_id = COALESCE($2, category_id),
+            amount = COALESCE($3, amount),
+            description = COALESCE($4, description),
+            next_date = $5,
+            anchor_day = CASE WHEN $6 THEN $7 ELSE anchor_day END,
+            end_date = $8,
+            auto_post = COALESCE($9, auto_post),
+            updated_at = NOW()
+        WHERE id = $10 AND user_id = $11
+        "#
+    )
+    .bind(payload.wallet_id)
+    .bind(payload.category_id)
+    .bind(payload.amount)
+    .bind(&payload.description)
+    .bind(next_date)
+    .bind(payload.next_date.is_some())
+    .bind(next_date.day() as i16)
+    .bind(end_date)
+    .bind(payload.auto_post)
+    .bind(id)
+    .bind(user_id)
+    .execute(&mut *db_tx)
+    .await?;
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    recurring_posting::post_due_recurring(&mut db_tx, Some(user_id), Some(today)).await?;
+
+    db_tx.commit().await?;
+
+    let updated = db::get_recurring_transaction_by_id(&state.db, id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Recurring transaction".to_string()))?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Transaksi rutin berhasil diupdate!",
+        "data": RecurringResponse::from(updated)
+    })))
+}
+
+pub async fn delete_recurring(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    // Stops future occurrences; transactions already booked stay in the ledger
+    let result = sqlx::query(
+        r#"UPDATE recurring_transactions SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL"#
+    )
+    .bind(id)
+    .bind(user_id)
+    .execute(&state.db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Recurring transaction".to_string()));
+    }
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Transaksi rutin berhasil dihapus! Transaksi yang sudah tercatat tetap tersimpan."
+    })))
+}
@@ -0,0 +1,1030 @@
+use axum::{
+    extract::{Query, State},
+    http::{
+        header::{AUTHORIZATION, CONTENT_DISPOSITION, CONTENT_TYPE},
+        HeaderMap,
+    },
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Datelike, NaiveDate};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::{
+    db,
+    error::AppError,
+    jobs::anomaly_detection,
+    models::{
+        anomaly::{AnomalyQuery, DEFAULT_ANOMALY_HISTORY_MONTHS, DEFAULT_ANOMALY_THRESHOLD},
+        installment::round_currency,
+        question::{answer_sentence, interpret_question, QuestionQuery},
+        report::{
+            CashFlowBucketResponse, CashFlowQuery, CategoryComparison, CategoryStat,
+            ComparisonPeriod, ComparisonQuery, REPORT_GROUPINGS,
+        },
+        statement::{StatementQuery, STATEMENT_FORMATS},
+        transaction::TransactionResponse,
+    },
+    utils::{
+        dates::{day_of_month, shift_months},
+        jwt::verify_token,
+    },
+    AppState,
+};
+
+// Upper bound on the number of periods in one report
+const MAX_REPORT_BUCKETS: i64 = 1000;
+// Top movers returned in each direction by default
+const DEFAULT_TOP_MOVERS: usize = 5;
+const MAX_QUESTION_LENGTH: usize = 300;
+
+async fn get_user_id(state: &AppState, headers: &HeaderMap) -> Result<Uuid, AppError> {
+    let auth_header = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .ok_or(AppError::Unauthorized)?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or(AppError::Unauthorized)?;
+
+    let claims = verify_token(token, &state.config.jwt_secret)?;
+    Ok(claims.sub)
+}
+
+// Rough number of periods between two dates, only used to reject oversized reports
+fn estimate_buckets(group_by: &str, start_date: NaiveDate, end_date: NaiveDate) -> i64 {
+    let days = (end_date - start_date).num_days() + 1;
+    let months = ((end_date.year() - start_date.year()) * 12 + end_date.month() as i32
+        - start_date.month() as i32
+        + 1) as i64;
+    match group_by {
+        "day" => days,
+        "week" => days / 7 + 1,
+        "month" => months,
+        "quarter" => months / 3 + 1,
+        _ => (end_date.year() - start_date.year() + 1) as i64,
+    }
+}
+
+pub async fn get_cash_flow(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<CashFlowQuery>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let group_by = query.group_by.as_deref().unwrap_or("month");
+    let (_, unit, step) = REPORT_GROUPINGS
+        .iter()
+        .find(|(name, _, _)| *name == group_by)
+        .copied()
+        .ok_or_else(|| {
+            AppError::ValidationError(
+                "group_by harus salah satu dari: day, week, month, quarter, year".to_string(),
+            )
+        })?;
+
+    // Defaults to the last 12 months including the current one
+    let end_date = match query.end_date {
+        Some(date) => date,
+        None => db::get_user_today(&state.db, user_id).await?,
+    };
+    let start_date = query
+        .start_date
+        .unwrap_or_else(|| shift_months(end_date.with_day(1).unwrap(), -11, 1));
+
+    if start_date > end_date {
+        return Err(AppError::ValidationError(
+            "start_date tidak boleh setelah end_date".to_string(),
+        ));
+    }
+
+    if estimate_buckets(group_by, start_date, end_date) > MAX_REPORT_BUCKETS {
+        return Err(AppError::ValidationError(format!(
+            "Rentang terlalu panjang, maksimal {} periode",
+            MAX_REPORT_BUCKETS
+        )));
+    }
+
+    if let Some(wallet_id) = query.wallet_id {
+        db::get_wallet_by_id(&state.db, wallet_id, user_id)
+            .await?
+            .ok_or(AppError::NotFound("Wallet".to_string()))?;
+    }
+
+    let category_ids = match query.category_id {
+        Some(category_id) => {
+            let category_exists: bool = sqlx::query_scalar(
+                r#"SELECT EXISTS(SELECT 1 FROM categories WHERE id = $1 AND (user_id = $2 OR user_id IS NULL) AND deleted_at IS NULL)"#
+            )
+            .bind(category_id)
+            .bind(user_id)
+            .fetch_one(&state.db)
+            .await?;
+
+            if !category_exists {
+                return Err(AppError::NotFound("Category".to_string()));
+            }
+            Some(db::get_category_subtree_ids(&state.db, user_id, category_id).await?)
+        }
+        None => None,
+    };
+
+    let buckets = db::get_cash_flow(
+        &state.db,
+        user_id,
+        start_date,
+        end_date,
+        (unit, step),
+        query.wallet_id,
+        category_ids.as_deref(),
+    )
+    .await?;
+
+    // Without a category filter the running balance follows the wallet balances; a category
+    // only covers part of the money flow, so it is accumulated from zero instead
+    let (opening_balance, running_balance_basis) = if category_ids.is_none() {
+        let balance = db::get_balance_before(&state.db, user_id, query.wallet_id, start_date).await?;
+        (round_currency(balance), "wallet_balance")
+    } else {
+        (0.0, "cumulative_net")
+    };
+
+    let mut running_balance = opening_balance;
+    let data: Vec<CashFlowBucketResponse> = buckets
+        .into_iter()
+        .map(|bucket| {
+            let net = round_currency(bucket.income - bucket.expense);
+            running_balance = round_currency(running_balance + net);
+            CashFlowBucketResponse {
+                period_start: bucket.period_start,
+                period_end: bucket.period_end,
+                income: bucket.income,
+                expense: bucket.expense,
+                net,
+                running_balance,
+                transaction_count: bucket.transaction_count,
+            }
+        })
+        .collect();
+
+    let total_income = round_currency(data.iter().map(|bucket| bucket.income).sum());
+    let total_expense = round_currency(data.iter().map(|bucket| bucket.expense).sum());
+
+    Ok(Json(json!({
+        "success": true,
+        "data": data,
+        "meta": {
+            "start_date": start_date,
+            "end_date": end_date,
+            "group_by": group_by,
+            "wallet_id": query.wallet_id,
+            "category_id": query.category_id,
+            "opening_balance": opening_balance,
+            "closing_balance": running_balance,
+            "running_balance_basis": running_balance_basis,
+            "total_income": total_income,
+            "total_expense": total_expense,
+            "net": round_currency(total_income - total_expense)
+        }
+    })))
+}
+
+fn percentage_change(current: f64, previous: f64) -> Option<f64> {
+    if previous > 0.0 {
+        Some(round_currency((current - previous) / previous * 100.0))
+    } else {
+        None
+    }
+}
+
+// (current_start, current_end, previous_start, previous_end) for the requested comparison
+fn comparison_periods(
+    query: &ComparisonQuery,
+    mode: &str,
+    today: NaiveDate,
+) -> Result<(NaiveDate, NaiveDate, NaiveDate, NaiveDate), AppError> {
+    if mode == "custom" {
+        let (Some(current_start), Some(current_end), Some(previous_start), Some(previous_end)) = (
+            query.current_start,
+            query.current_end,
+            query.previous_start,
+            query.previous_end,
+        ) else {
+            return Err(AppError::ValidationError(
+                "Mode custom membutuhkan current_start, current_end, previous_start dan previous_end".to_string(),
+            ));
+        };
+
+        if current_start > current_end || previous_start > previous_end {
+            return Err(AppError::ValidationError(
+                "Tanggal mulai tidak boleh setelah tanggal akhir".to_string(),
+            ));
+        }
+        return Ok((current_start, current_end, previous_start, previous_end));
+    }
+
+    let offset = match mode {
+        "mom" => -1,
+        "yoy" => -12,
+        _ => {
+            return Err(AppError::ValidationError(
+                "Mode harus salah satu dari: mom, yoy, custom".to_string(),
+            ))
+        }
+    };
+
+    let month = query.month.unwrap_or(today.month() as i32);
+    let year = query.year.unwrap_or(today.year());
+    if !(1..=12).contains(&month) {
+        return Err(AppError::ValidationError(
+            "Month harus antara 1-12".to_string(),
+        ));
+    }
+    if !(2000..=3000).contains(&year) {
+        return Err(AppError::ValidationError(
+            "Year harus antara 2000-3000".to_string(),
+        ));
+    }
+
+    let current_start = day_of_month(year, month as u32, 1);
+    let previous_start = shift_months(current_start, offset, 1);
+
+    // A month still in progress is compared with the same days of the earlier month
+    let month_end = day_of_month(year, month as u32, 31);
+    let (current_end, previous_end) = if current_start <= today && today < month_end {
+        (today, shift_months(today, offset, today.day() as i32))
+    } else {
+        (month_end, shift_months(previous_start, 0, 31))
+    };
+
+    Ok((current_start, current_end, previous_start, previous_end))
+}
+
+async fn get_period_total(
+    state: &AppState,
+    user_id: Uuid,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    transaction_type: &str,
+) -> Result<f64, AppError> {
+    let total: f64 = sqlx::query_scalar(
+        r#"
+        SELECT COALESCE(SUM(amount)::float8, 0) FROM transactions
+        WHERE user_id = $1 AND deleted_at IS NULL AND NOT is_transfer AND transaction_type = $2 AND date BETWEEN $3 AND $4
+        "#
+    )
+    .bind(user_id)
+    .bind(transaction_type)
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(total)
+}
+
+pub async fn get_period_comparison(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ComparisonQuery>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let mode = query.mode.as_deref().unwrap_or("mom");
+    let transaction_type = query.transaction_type.as_deref().unwrap_or("expense");
+    if transaction_type != "expense" && transaction_type != "income" {
+        return Err(AppError::ValidationError(
+            "Tipe transaksi harus income atau expense".to_string(),
+        ));
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_TOP_MOVERS).clamp(1, 50);
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let (current_start, current_end, previous_start, previous_end) =
+        comparison_periods(&query, mode, today)?;
+
+    let current = db::get_category_totals(
+        &state.db,
+        user_id,
+        current_start,
+        Some(current_end),
+        transaction_type,
+        query.parent_id,
+    )
+    .await?;
+    let previous = db::get_category_totals(
+        &state.db,
+        user_id,
+        previous_start,
+        Some(previous_end),
+        transaction_type,
+        query.parent_id,
+    )
+    .await?;
+
+    // Pair each category with its previous total; categories only seen in the previous period
+    // come last with a current total of zero
+    let mut previous_by_id: HashMap<Uuid, f64> =
+        previous.iter().map(|stat| (stat.id, stat.total)).collect();
+    let mut pairs: Vec<(CategoryStat, f64)> = current
+        .into_iter()
+        .map(|stat| {
+            let previous_total = previous_by_id.remove(&stat.id).unwrap_or(0.0);
+            (stat, previous_total)
+        })
+        .collect();
+    pairs.extend(
+        previous
+            .into_iter()
+            .filter(|stat| previous_by_id.contains_key(&stat.id))
+            .map(|stat| {
+                let previous_total = stat.total;
+                (CategoryStat { total: 0.0, ..stat }, previous_total)
+            }),
+    );
+
+    let mut categories: Vec<CategoryComparison> = pairs
+        .into_iter()
+        .map(|(stat, previous_total)| {
+            let change_amount = round_currency(stat.total - previous_total);
+            let status = if previous_total == 0.0 {
+                "new"
+            } else if stat.total == 0.0 {
+                "disappeared"
+            } else if change_amount > 0.0 {
+                "increased"
+            } else if change_amount < 0.0 {
+                "decreased"
+            } else {
+                "unchanged"
+            };
+
+            CategoryComparison {
+                category_id: stat.id,
+                name: stat.name,
+                icon: stat.icon,
+                color: stat.color,
+                current_total: stat.total,
+                previous_total,
+                change_amount,
+                change_percentage: percentage_change(stat.total, previous_total),
+                status: status.to_string(),
+            }
+        })
+        .collect();
+
+    categories.sort_by(|a, b| b.change_amount.abs().total_cmp(&a.change_amount.abs()));
+
+    let mut top_increases: Vec<CategoryComparison> = categories
+        .iter()
+        .filter(|category| category.change_amount > 0.0)
+        .cloned()
+        .collect();
+    top_increases.sort_by(|a, b| b.change_amount.total_cmp(&a.change_amount));
+    top_increases.truncate(limit);
+
+    let mut top_decreases: Vec<CategoryComparison> = categories
+        .iter()
+        .filter(|category| category.change_amount < 0.0)
+        .cloned()
+        .collect();
+    top_decreases.sort_by(|a, b| a.change_amount.total_cmp(&b.change_amount));
+    top_decreases.truncate(limit);
+
+    let new_categories: Vec<&CategoryComparison> =
+        categories.iter().filter(|category| category.status == "new").collect();
+    let disappeared_categories: Vec<&CategoryComparison> = categories
+        .iter()
+        .filter(|category| category.status == "disappeared")
+        .collect();
+
+    // Overall totals include transactions without a category
+    let current_total = get_period_total(&state, user_id, current_start, current_end, transaction_type).await?;
+    let previous_total = get_period_total(&state, user_id, previous_start, previous_end, transaction_type).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "current": ComparisonPeriod { start_date: current_start, end_date: current_end, total: current_total },
+            "previous": ComparisonPeriod { start_date: previous_start, end_date: previous_end, total: previous_total },
+            "change_amount": round_currency(current_total - previous_total),
+            "change_percentage": percentage_change(current_total, previous_total),
+            "categories": categories,
+            "new_categories": new_categories,
+            "disappeared_categories": disappeared_categories,
+            "top_movers": {
+                "increases": top_increases,
+                "decreases": top_decreases
+            }
+        },
+        "meta": {
+            "mode": mode,
+            "transaction_type": transaction_type,
+            "parent_id": query.parent_id
+        }
+    })))
+}
+
+pub async fn get_anomalies(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<AnomalyQuery>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let months = query.months.unwrap_or(DEFAULT_ANOMALY_HISTORY_MONTHS);
+    if !(2..=24).contains(&months) {
+        return Err(AppError::ValidationError(
+            "Months harus antara 2-24".to_string(),
+        ));
+    }
+
+    let threshold = query.threshold.unwrap_or(DEFAULT_ANOMALY_THRESHOLD);
+    if !threshold.is_finite() || threshold <= 0.0 {
+        return Err(AppError::ValidationError(
+            "Threshold harus lebih besar dari 0".to_string(),
+        ));
+    }
+
+    // Defaults to the current month up to today
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let end_date = query.end_date.unwrap_or(today);
+    let start_date = query.start_date.unwrap_or_else(|| end_date.with_day(1).unwrap());
+
+    if start_date > end_date {
+        return Err(AppError::ValidationError(
+            "start_date tidak boleh setelah end_date".to_string(),
+        ));
+    }
+    if (end_date - start_date).num_days() > 366 {
+        return Err(AppError::ValidationError(
+            "Rentang maksimal 1 tahun".to_string(),
+        ));
+    }
+
+    let report = anomaly_detection::find_anomalies(
+        &state.db,
+        user_id,
+        start_date,
+        end_date,
+        months,
+        threshold,
+    )
+    .await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": report,
+        "meta": {
+            "start_date": start_date,
+            "end_date": end_date,
+            "months": months,
+            "threshold": threshold
+        }
+    })))
+}
+
+// Answers a spending question typed in Indonesian or English, e.g. "berapa pengeluaran makanan
+// bulan lalu?", together with the filter it was read as
+pub async fn ask_question(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<QuestionQuery>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let question = query.q.as_deref().map(str::trim).unwrap_or("");
+    if question.is_empty() {
+        return Err(AppError::ValidationError(
+            "Pertanyaan wajib diisi".to_string(),
+        ));
+    }
+    if question.chars().count() > MAX_QUESTION_LENGTH {
+        return Err(AppError::ValidationError(format!(
+            "Pertanyaan maksimal {} karakter",
+            MAX_QUESTION_LENGTH
+        )));
+    }
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let categories = db::get_user_categories(&state.db, user_id).await?;
+    let wallets = db::get_user_wallets(&state.db, user_id).await?;
+
+    let filter = interpret_question(question, today, &categories, &wallets).ok_or_else(|| {
+        AppError::ValidationError(
+            "Pertanyaan belum bisa dipahami. Coba misalnya \"berapa pengeluaran makanan bulan lalu?\" atau \"total transport minggu ini\"".to_string(),
+        )
+    })?;
+
+    let totals = db::get_question_totals(&state.db, user_id, &filter).await?;
+    let largest = if filter.metric == "largest" {
+        db::get_largest_question_transaction(&state.db, user_id, &filter).await?
+    } else {
+        None
+    };
+
+    let value = match filter.metric.as_str() {
+        "count" => totals.count as f64,
+        "average" if totals.count > 0 => round_currency(totals.total / totals.count as f64),
+        "average" => 0.0,
+        "largest" => largest.as_ref().map(|row| row.transaction.amount).unwrap_or(0.0),
+        "net" => totals.net,
+        _ => totals.total,
+    };
+
+    let answer = answer_sentence(
+        &filter,
+        value,
+        totals.count,
+        largest.as_ref().map(|row| {
+            (
+                row.transaction.description.as_deref().unwrap_or("-"),
+                row.transaction.date,
+            )
+        }),
+    );
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "question": question,
+            "answer": answer,
+            "value": value,
+            "transaction_count": totals.count,
+            "transaction": largest.map(TransactionResponse::from),
+            "filter": filter
+        }
+    })))
+}
+
+// One month's statement as JSON, an HTML page or a PDF download
+pub async fn get_monthly_statement(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<StatementQuery>,
+) -> Result<Response, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let format = params.format.as_deref().unwrap_or("json");
+    if !STATEMENT_FORMATS.contains(&format) {
+        return Err(AppError::ValidationError(format!(
+            "Format harus salah satu dari: {}",
+            STATEMENT_FORMATS.join(", ")
+        )));
+    }
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let month = params.month.unwrap_or(today.month() as i32);
+    let year = params.year.unwrap_or(today.year());
+    if !(1..=12).contains(&month) {
+        return Err(AppError::ValidationError(
+            "Month harus antara 1-12".to_string(),
+        ));
+    }
+    if !(2000..=3000).contains(&year) {
+        return Err(AppError::ValidationError(
+            "Year harus antara 2000-3000".to_string(),
+        ));
+    }
+    if day_of_month(year, month as u32, 1) > today {
+        return Err(AppError::ValidationError(
+            "Laporan belum tersedia untuk bulan yang akan datang".to_string(),
+        ));
+    }
+
+    let user = db::find_user_by_id(&state.db, user_id)
+        .await?
+        .ok_or(AppError::NotFound("User".to_string()))?;
+    let statement = db::get_monthly_statement(&state.db, &user, year, month as u32).await?;
+
+    match format {
+        "html" => Ok(([(CONTENT_TYPE, "text/html; charset=utf-8")], statement.to_html()).into_response()),
+        "pdf" => {
+            let pdf = statement
+                .to_pdf()
+                .map_err(|e| AppError::InternalError(format!("Gagal membuat PDF: {}", e)))?;
+            Ok((
+                [
+                    (CONTENT_TYPE, "application/pdf".to_string()),
+                    (
+                        CONTENT_DISPOSITION,
+                        format!("attachment; filename=\"{}\"", statement.file_name("pdf")),
+                    ),
+                ],
+                pdf,
+            )
+                .into_response())
+        }
+        _ => Ok(Json(json!({
+            "success": true,
+            "data": statement
+        }))
+        .into_response()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{auth_headers, create_user, test_state};
+    use sqlx::PgPool;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn estimate_buckets_per_grouping() {
+        let (start, end) = (date(2025, 1, 1), date(2025, 12, 31));
+        assert_eq!(estimate_buckets("day", start, end), 365);
+        assert_eq!(estimate_buckets("week", start, end), 53);
+        assert_eq!(estimate_buckets("month", start, end), 12);
+        assert_eq!(estimate_buckets("quarter", start, end), 5);
+        assert_eq!(estimate_buckets("year", start, date(2027, 1, 1)), 3);
+    }
+
+    async fn create_category(pool: &PgPool, user_id: Uuid, name: &str, parent_id: Option<Uuid>) -> Uuid {
+        sqlx::query_scalar(
+            "INSERT INTO categories (user_id, name, category_type, parent_id) VALUES ($1, $2, 'expense', $3) RETURNING id",
+        )
+        .bind(user_id)
+        .bind(name)
+        .bind(parent_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn add_transaction(
+        pool: &PgPool,
+        user_id: Uuid,
+        wallet_id: Uuid,
+        transaction_type: &str,
+        amount: f64,
+        date: NaiveDate,
+        category_id: Option<Uuid>,
+    ) {
+        sqlx::query(
+            r#"INSERT INTO transactions (user_id, wallet_id, category_id, transaction_type, amount, date)
+               VALUES ($1, $2, $3, $4, $5, $6)"#,
+        )
+        .bind(user_id)
+        .bind(wallet_id)
+        .bind(category_id)
+        .bind(transaction_type)
+        .bind(amount)
+        .bind(date)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    fn cash_flow_query(start_date: NaiveDate, end_date: NaiveDate, group_by: &str) -> CashFlowQuery {
+        CashFlowQuery {
+            start_date: Some(start_date),
+            end_date: Some(end_date),
+            group_by: Some(group_by.to_string()),
+            wallet_id: None,
+            category_id: None,
+        }
+    }
+
+    async fn cash_flow(state: &AppState, user_id: Uuid, query: CashFlowQuery) -> Result<Value, AppError> {
+        get_cash_flow(State(state.clone()), auth_headers(user_id), Query(query))
+            .await
+            .map(|Json(body)| body)
+    }
+
+    // (period_start, period_end, income, expense, net, running_balance, transaction_count)
+    fn buckets(body: &Value) -> Vec<(String, String, f64, f64, f64, f64, i64)> {
+        body["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|bucket| {
+                (
+                    bucket["period_start"].as_str().unwrap().to_string(),
+                    bucket["period_end"].as_str().unwrap().to_string(),
+                    bucket["income"].as_f64().unwrap(),
+                    bucket["expense"].as_f64().unwrap(),
+                    bucket["net"].as_f64().unwrap(),
+                    bucket["running_balance"].as_f64().unwrap(),
+                    bucket["transaction_count"].as_i64().unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    fn bucket(
+        period_start: &str,
+        period_end: &str,
+        income: f64,
+        expense: f64,
+        net: f64,
+        running_balance: f64,
+        transaction_count: i64,
+    ) -> (String, String, f64, f64, f64, f64, i64) {
+        (
+            period_start.to_string(),
+            period_end.to_string(),
+            income,
+            expense,
+            net,
+            running_balance,
+            transaction_count,
+        )
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn cash_flow_fills_empty_buckets_and_tracks_the_balance(pool: PgPool) {
+        let state = test_state(pool.clone());
+        let (user_id, cash) = create_user(&pool).await;
+        let food = create_category(&pool, user_id, "Makanan", None).await;
+        let snacks = create_category(&pool, user_id, "Jajan", Some(food)).await;
+
+        add_transaction(&pool, user_id, cash, "income", 500_000.0, date(2024, 12, 10), None).await;
+        add_transaction(&pool, user_id, cash, "income", 1_000_000.0, date(2025, 1, 5), None).await;
+        add_transaction(&pool, user_id, cash, "expense", 200_000.0, date(2025, 1, 20), Some(food)).await;
+        add_transaction(&pool, user_id, cash, "expense", 100_000.0, date(2025, 3, 2), Some(snacks)).await;
+        // The stored balance already includes every transaction above
+        sqlx::query("UPDATE wallets SET balance = 1200000 WHERE id = $1")
+            .bind(cash)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let body = cash_flow(&state, user_id, cash_flow_query(date(2025, 1, 1), date(2025, 3, 31), "month"))
+            .await
+            .unwrap();
+        assert_eq!(
+            buckets(&body),
+            [
+                bucket("2025-01-01", "2025-01-31", 1_000_000.0, 200_000.0, 800_000.0, 1_300_000.0, 2),
+                bucket("2025-02-01", "2025-02-28", 0.0, 0.0, 0.0, 1_300_000.0, 0),
+                bucket("2025-03-01", "2025-03-31", 0.0, 100_000.0, -100_000.0, 1_200_000.0, 1),
+            ]
+        );
+        // Opening balance plus the flows ends at the current balance
+        assert_eq!(body["meta"]["opening_balance"], 500_000.0);
+        assert_eq!(body["meta"]["closing_balance"], 1_200_000.0);
+        assert_eq!(body["meta"]["net"], 700_000.0);
+        assert_eq!(body["meta"]["running_balance_basis"], "wallet_balance");
+
+        // Partial weeks are clipped to the requested range
+        let body = cash_flow(&state, user_id, cash_flow_query(date(2025, 1, 1), date(2025, 1, 8), "week"))
+            .await
+            .unwrap();
+        assert_eq!(
+            buckets(&body),
+            [
+                bucket("2025-01-01", "2025-01-05", 1_000_000.0, 0.0, 1_000_000.0, 1_500_000.0, 1),
+                bucket("2025-01-06", "2025-01-08", 0.0, 0.0, 0.0, 1_500_000.0, 0),
+            ]
+        );
+
+        // A category filter includes its sub-categories and accumulates from zero
+        let query = CashFlowQuery {
+            category_id: Some(food),
+            ..cash_flow_query(date(2025, 1, 1), date(2025, 12, 31), "quarter")
+        };
+        let body = cash_flow(&state, user_id, query).await.unwrap();
+        assert_eq!(
+            buckets(&body),
+            [
+                bucket("2025-01-01", "2025-03-31", 0.0, 300_000.0, -300_000.0, -300_000.0, 2),
+                bucket("2025-04-01", "2025-06-30", 0.0, 0.0, 0.0, -300_000.0, 0),
+                bucket("2025-07-01", "2025-09-30", 0.0, 0.0, 0.0, -300_000.0, 0),
+                bucket("2025-10-01", "2025-12-31", 0.0, 0.0, 0.0, -300_000.0, 0),
+            ]
+        );
+        assert_eq!(body["meta"]["opening_balance"], 0.0);
+        assert_eq!(body["meta"]["running_balance_basis"], "cumulative_net");
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn cash_flow_rejects_invalid_ranges(pool: PgPool) {
+        let state = test_state(pool.clone());
+        let (user_id, _) = create_user(&pool).await;
+
+        let result = cash_flow(&state, user_id, cash_flow_query(date(2025, 1, 1), date(2025, 3, 31), "hour")).await;
+        assert!(matches!(result, Err(AppError::ValidationError(_))));
+        let result = cash_flow(&state, user_id, cash_flow_query(date(2025, 3, 1), date(2025, 1, 1), "month")).await;
+        assert!(matches!(result, Err(AppError::ValidationError(_))));
+        let result = cash_flow(&state, user_id, cash_flow_query(date(2022, 1, 1), date(2025, 1, 1), "day")).await;
+        assert!(matches!(result, Err(AppError::ValidationError(_))));
+
+        let query = CashFlowQuery {
+            wallet_id: Some(Uuid::new_v4()),
+            ..cash_flow_query(date(2025, 1, 1), date(2025, 3, 31), "month")
+        };
+        assert!(matches!(cash_flow(&state, user_id, query).await, Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn percentage_change_needs_a_previous_total() {
+        assert_eq!(percentage_change(150.0, 100.0), Some(50.0));
+        assert_eq!(percentage_change(0.0, 80.0), Some(-100.0));
+        assert_eq!(percentage_change(100.0, 300.0), Some(-66.67));
+        assert_eq!(percentage_change(100.0, 0.0), None);
+    }
+
+    fn comparison_query(mode: &str) -> ComparisonQuery {
+        ComparisonQuery {
+            mode: Some(mode.to_string()),
+            month: None,
+            year: None,
+            current_start: None,
+            current_end: None,
+            previous_start: None,
+            previous_end: None,
+            transaction_type: None,
+            parent_id: None,
+            limit: None,
+        }
+    }
+
+    #[test]
+    fn comparison_periods_per_mode() {
+        let mom = comparison_query("mom");
+        // The month in progress is compared with the same days of the previous month
+        assert_eq!(
+            comparison_periods(&mom, "mom", date(2025, 3, 15)).unwrap(),
+            (date(2025, 3, 1), date(2025, 3, 15), date(2025, 2, 1), date(2025, 2, 15))
+        );
+        assert_eq!(
+            comparison_periods(&mom, "mom", date(2025, 3, 30)).unwrap(),
+            (date(2025, 3, 1), date(2025, 3, 30), date(2025, 2, 1), date(2025, 2, 28))
+        );
+        assert_eq!(
+            comparison_periods(&mom, "mom", date(2025, 3, 31)).unwrap(),
+            (date(2025, 3, 1), date(2025, 3, 31), date(2025, 2, 1), date(2025, 2, 28))
+        );
+
+        // Finished months are compared in full
+        let yoy = ComparisonQuery { month: Some(2), year: Some(2024), ..comparison_query("yoy") };
+        assert_eq!(
+            comparison_periods(&yoy, "yoy", date(2025, 3, 15)).unwrap(),
+            (date(2024, 2, 1), date(2024, 2, 29), date(2023, 2, 1), date(2023, 2, 28))
+        );
+
+        let custom = ComparisonQuery {
+            current_start: Some(date(2025, 3, 1)),
+            current_end: Some(date(2025, 3, 10)),
+            previous_start: Some(date(2024, 12, 1)),
+            previous_end: Some(date(2024, 12, 10)),
+            ..comparison_query("custom")
+        };
+        assert_eq!(
+            comparison_periods(&custom, "custom", date(2025, 3, 15)).unwrap(),
+            (date(2025, 3, 1), date(2025, 3, 10), date(2024, 12, 1), date(2024, 12, 10))
+        );
+
+        let incomplete = ComparisonQuery { previous_end: None, ..custom };
+        let inverted = ComparisonQuery {
+            current_start: Some(date(2025, 3, 11)),
+            current_end: Some(date(2025, 3, 10)),
+            previous_start: Some(date(2024, 12, 1)),
+            previous_end: Some(date(2024, 12, 10)),
+            ..comparison_query("custom")
+        };
+        let bad_month = ComparisonQuery { month: Some(13), ..comparison_query("mom") };
+        for (query, mode) in [(&incomplete, "custom"), (&inverted, "custom"), (&bad_month, "mom"), (&mom, "week")] {
+            assert!(matches!(
+                comparison_periods(query, mode, date(2025, 3, 15)),
+                Err(AppError::ValidationError(_))
+            ));
+        }
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn period_comparison_flags_new_disappeared_and_top_movers(pool: PgPool) {
+        let state = test_state(pool.clone());
+        let (user_id, cash) = create_user(&pool).await;
+        let food = create_category(&pool, user_id, "Makanan", None).await;
+        let snacks = create_category(&pool, user_id, "Jajan", Some(food)).await;
+        let transport = create_category(&pool, user_id, "Transport", None).await;
+        let health = create_category(&pool, user_id, "Kesehatan", None).await;
+
+        // February
+        add_transaction(&pool, user_id, cash, "expense", 100_000.0, date(2025, 2, 3), Some(food)).await;
+        add_transaction(&pool, user_id, cash, "expense", 50_000.0, date(2025, 2, 10), Some(transport)).await;
+        add_transaction(&pool, user_id, cash, "expense", 10_000.0, date(2025, 2, 20), None).await;
+        // March; the sub-category rolls up into its parent
+        add_transaction(&pool, user_id, cash, "expense", 120_000.0, date(2025, 3, 4), Some(food)).await;
+        add_transaction(&pool, user_id, cash, "expense", 30_000.0, date(2025, 3, 5), Some(snacks)).await;
+        add_transaction(&pool, user_id, cash, "expense", 80_000.0, date(2025, 3, 6), Some(health)).await;
+        add_transaction(&pool, user_id, cash, "income", 5_000_000.0, date(2025, 3, 1), None).await;
+
+        let query = ComparisonQuery { month: Some(3), year: Some(2025), limit: Some(1), ..comparison_query("mom") };
+        let Json(body) = get_period_comparison(State(state), auth_headers(user_id), Query(query))
+            .await
+            .unwrap();
+        let data = &body["data"];
+
+        // Overall totals include the uncategorized expense
+        assert_eq!(data["current"]["total"], 230_000.0);
+        assert_eq!(data["previous"]["total"], 160_000.0);
+        assert_eq!(data["change_amount"], 70_000.0);
+        assert_eq!(data["change_percentage"], 43.75);
+
+        // (category_id, current_total, previous_total, change_amount, change_percentage, status)
+        type Row<'a> = (Uuid, f64, f64, f64, Option<f64>, &'a str);
+        let categories: Vec<Row> = data["categories"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|category| {
+                (
+                    category["category_id"].as_str().unwrap().parse().unwrap(),
+                    category["current_total"].as_f64().unwrap(),
+                    category["previous_total"].as_f64().unwrap(),
+                    category["change_amount"].as_f64().unwrap(),
+                    category["change_percentage"].as_f64(),
+                    category["status"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        // Largest absolute change first
+        assert_eq!(
+            categories,
+            [
+                (health, 80_000.0, 0.0, 80_000.0, None, "new"),
+                (food, 150_000.0, 100_000.0, 50_000.0, Some(50.0), "increased"),
+                (transport, 0.0, 50_000.0, -50_000.0, Some(-100.0), "disappeared"),
+            ]
+        );
+
+        let id = |value: &Value| value["category_id"].as_str().unwrap().parse::<Uuid>().unwrap();
+        let ids = |key: &str| -> Vec<Uuid> { data[key].as_array().unwrap().iter().map(id).collect() };
+        assert_eq!(ids("new_categories"), [health]);
+        assert_eq!(ids("disappeared_categories"), [transport]);
+        let movers = &data["top_movers"];
+        assert_eq!(movers["increases"].as_array().unwrap().iter().map(id).collect::<Vec<_>>(), [health]);
+        assert_eq!(movers["decreases"].as_array().unwrap().iter().map(id).collect::<Vec<_>>(), [transport]);
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn statement_balances_reconcile_with_the_flows(pool: PgPool) {
+        let (user_id, cash) = create_user(&pool).await;
+        let card = crate::test_support::create_wallet(&pool, user_id, "Kartu Kredit", "credit-card").await;
+
+        add_transaction(&pool, user_id, cash, "income", 1_000_000.0, date(2025, 2, 20), None).await;
+        add_transaction(&pool, user_id, cash, "income", 500_000.0, date(2025, 3, 1), None).await;
+        add_transaction(&pool, user_id, card, "expense", 200_000.0, date(2025, 3, 12), None).await;
+        add_transaction(&pool, user_id, cash, "expense", 50_000.0, date(2025, 4, 2), None).await;
+        // Money lent and partly repaid in March
+        for (transaction_type, amount) in [("expense", 100_000.0), ("income", 30_000.0)] {
+            sqlx::query(
+                r#"INSERT INTO transactions (user_id, wallet_id, transaction_type, amount, date, is_transfer)
+                   VALUES ($1, $2, $3, $4, '2025-03-15', TRUE)"#,
+            )
+            .bind(user_id)
+            .bind(cash)
+            .bind(transaction_type)
+            .bind(amount)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        // Current balances, after every transaction above
+        sqlx::query(
+            r#"UPDATE wallets SET created_at = '2025-01-01',
+                   balance = CASE WHEN id = $1 THEN 1380000 ELSE -200000 END
+               WHERE user_id = $2"#,
+        )
+        .bind(cash)
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let user = db::find_user_by_id(&pool, user_id).await.unwrap().unwrap();
+        let statement = db::get_monthly_statement(&pool, &user, 2025, 3).await.unwrap();
+        let summary = &statement.summary;
+
+        assert_eq!(
+            (summary.opening_balance, summary.income, summary.expense, summary.transfers, summary.closing_balance),
+            (1_000_000.0, 500_000.0, 200_000.0, -70_000.0, 1_230_000.0)
+        );
+        assert_eq!(summary.transaction_count, 4);
+        // Opening balance plus the month's flows is the closing balance
+        assert_eq!(summary.opening_balance + summary.net + summary.transfers, summary.closing_balance);
+        assert_eq!(statement.wallets.len(), 2);
+        let wallet_opening: f64 = statement.wallets.iter().map(|wallet| wallet.opening_balance).sum();
+        let wallet_closing: f64 = statement.wallets.iter().map(|wallet| wallet.closing_balance).sum();
+        assert_eq!((wallet_opening, wallet_closing), (summary.opening_balance, summary.closing_balance));
+
+        assert_eq!(statement.expense_categories.len(), 1);
+        assert_eq!(statement.expense_categories[0].name, "Tanpa kategori");
+        assert_eq!(statement.transactions.len(), 4);
+        assert!(!statement.transactions_truncated);
+    }
+}
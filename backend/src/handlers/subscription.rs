@@ -0,0 +1,200 @@
+use axum::{
+    extract::{Query, State},
+    http::{header::AUTHORIZATION, HeaderMap},
+    Json,
+};
+use chrono::{Datelike, NaiveDate};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::{
+    db,
+    error::AppError,
+    handlers::{bill::insert_bill, recurring::insert_recurring},
+    models::{
+        bill::{BillResponse, CreateBillRequest},
+        recurring::{CreateRecurringRequest, RecurringResponse},
+        subscription::{
+            detect_subscriptions, ConvertSubscriptionRequest, DetectedSubscription,
+            SubscriptionQuery, SUBSCRIPTION_HISTORY_MONTHS, SUBSCRIPTION_TARGETS,
+        },
+    },
+    utils::{dates::shift_months, jwt::verify_token},
+    AppState,
+};
+
+async fn get_user_id(state: &AppState, headers: &HeaderMap) -> Result<Uuid, AppError> {
+    let auth_header = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .ok_or(AppError::Unauthorized)?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or(AppError::Unauthorized)?;
+
+    let claims = verify_token(token, &state.config.jwt_secret)?;
+    Ok(claims.sub)
+}
+
+// Subscriptions found in the expense history, linked to the recurring transactions and bills
+// that were created from them
+async fn load_subscriptions(
+    state: &AppState,
+    user_id: Uuid,
+    today: NaiveDate,
+) -> Result<Vec<DetectedSubscription>, AppError> {
+    let start_date = shift_months(today.with_day(1).unwrap(), -SUBSCRIPTION_HISTORY_MONTHS, 1);
+    let expenses = db::get_expenses(&state.db, user_id, start_date, today).await?;
+
+    let recurring_ids: HashMap<String, Uuid> = db::get_user_recurring_transactions(&state.db, user_id)
+        .await?
+        .into_iter()
+        .filter_map(|recurring| recurring.subscription_key.map(|key| (key, recurring.id)))
+        .collect();
+    let bill_ids: HashMap<String, Uuid> = db::get_user_bills(&state.db, user_id)
+        .await?
+        .into_iter()
+        .filter_map(|bill| bill.subscription_key.map(|key| (key, bill.id)))
+        .collect();
+
+    Ok(detect_subscriptions(&expenses, today)
+        .into_iter()
+        .map(|subscription| DetectedSubscription {
+            recurring_id: recurring_ids.get(&subscription.key).copied(),
+            bill_id: bill_ids.get(&subscription.key).copied(),
+            ..subscription
+        })
+        .collect())
+}
+
+pub async fn list_subscriptions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<SubscriptionQuery>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let include_inactive = query.include_inactive.unwrap_or(false);
+    let subscriptions: Vec<DetectedSubscription> = load_subscriptions(&state, user_id, today)
+        .await?
+        .into_iter()
+        .filter(|subscription| include_inactive || subscription.status == "active")
+        .collect();
+
+    let annual_cost: f64 = subscriptions
+        .iter()
+        .filter(|subscription| subscription.status == "active")
+        .map(|subscription| subscription.annual_cost)
+        .sum();
+
+    Ok(Json(json!({
+        "success": true,
+        "data": subscriptions,
+        "meta": {
+            "total_annual_cost": annual_cost,
+            "total_monthly_cost": annual_cost / 12.0
+        }
+    })))
+}
+
+// Turns a detected subscription into a recurring transaction starting at its next expected
+// charge, booked automatically or only reminded about (auto_post = false), or into a bill
+// with payment reminders (target = "bill")
+pub async fn convert_subscription(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<ConvertSubscriptionRequest>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let target = payload.target.as_deref().unwrap_or("recurring");
+    if !SUBSCRIPTION_TARGETS.contains(&target) {
+        return Err(AppError::ValidationError(format!(
+            "Target harus salah satu dari: {}",
+            SUBSCRIPTION_TARGETS.join(", ")
+        )));
+    }
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let subscription = load_subscriptions(&state, user_id, today)
+        .await?
+        .into_iter()
+        .find(|subscription| subscription.key == payload.key)
+        .ok_or(AppError::NotFound("Subscription".to_string()))?;
+
+    if subscription.recurring_id.is_some() {
+        return Err(AppError::Conflict(
+            "Langganan ini sudah dijadikan transaksi rutin".to_string(),
+        ));
+    }
+    if subscription.bill_id.is_some() {
+        return Err(AppError::Conflict(
+            "Langganan ini sudah dijadikan tagihan".to_string(),
+        ));
+    }
+
+    // An overdue charge starts the schedule today rather than booking the missed one
+    let start_date = subscription.next_expected_date.max(today);
+    let wallet_id = payload.wallet_id.unwrap_or(subscription.wallet_id);
+    let amount = payload.amount.unwrap_or(subscription.amount);
+
+    if target == "bill" {
+        let bill = insert_bill(
+            &state,
+            user_id,
+            &CreateBillRequest {
+                payee: subscription.name.chars().take(100).collect(),
+                description: None,
+                amount: Some(amount),
+                amount_min: None,
+                amount_max: None,
+                wallet_id: Some(wallet_id),
+                category_id: subscription.category_id,
+                frequency: subscription.frequency.clone(),
+                interval_count: Some(1),
+                due_date: start_date,
+                remind_days_before: None,
+            },
+            Some(&subscription.key),
+        )
+        .await?;
+
+        return Ok(Json(json!({
+            "success": true,
+            "message": "Langganan berhasil dijadikan tagihan!",
+            "data": BillResponse::new(bill, today)
+        })));
+    }
+
+    let recurring = insert_recurring(
+        &state,
+        user_id,
+        &CreateRecurringRequest {
+            wallet_id,
+            category_id: subscription.category_id,
+            transaction_type: "expense".to_string(),
+            amount,
+            description: subscription.name.chars().take(255).collect(),
+            frequency: subscription.frequency.clone(),
+            interval_count: Some(1),
+            start_date: Some(start_date),
+            end_date: None,
+            auto_post: payload.auto_post,
+        },
+        Some(&subscription.key),
+    )
+    .await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": if recurring.auto_post {
+            "Langganan berhasil dijadikan transaksi rutin!"
+        } else {
+            "Pengingat langganan berhasil dibuat!"
+        },
+        "data": RecurringResponse::from(recurring)
+    })))
+}
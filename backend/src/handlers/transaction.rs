@@ -3,14 +3,19 @@ use axum::{
     http::{header::AUTHORIZATION, HeaderMap},
     Json,
 };
-use chrono::Utc;
 use serde_json::{json, Value};
 use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
+    db,
     error::AppError,
-    models::transaction::{CreateTransactionRequest, Transaction, TransactionQuery, TransactionResponse, UpdateTransactionRequest},
+    models::{
+        audit::{AuditAction, AuditContext},
+        category::Category,
+        transaction::{CreateTransactionRequest, Transaction, TransactionQuery, TransactionResponse, UpdateTransactionRequest},
+        wallet::Wallet,
+    },
     utils::jwt::verify_token,
     AppState,
 };
@@ -29,6 +34,17 @@ async fn get_user_id(state: &AppState, headers: &HeaderMap) -> Result<Uuid, AppE
     Ok(claims.sub)
 }
 
+// Loan disbursements and repayments are tied to a debt's balance, so they are only changed
+// through the debt endpoints
+fn reject_transfer(transaction: &Transaction) -> Result<(), AppError> {
+    if transaction.is_transfer {
+        return Err(AppError::ValidationError(
+            "Transaksi ini bagian dari hutang/piutang. Ubah lewat /api/debts.".to_string(),
+        ));
+    }
+    Ok(())
+}
+
 pub async fn list_transactions(
     State(state): State<AppState>,
     headers: HeaderMap,
@@ -39,38 +55,11 @@ pub async fn list_transactions(
     let limit = query.limit.unwrap_or(50);
     let offset = query.offset.unwrap_or(0);
 
-    let transactions = sqlx::query_as::<_, Transaction>(
-        r#"
-        SELECT id, user_id, wallet_id, category_id, transaction_type, amount, description, date, created_at, updated_at
-        FROM transactions 
-        WHERE user_id = $1
-            AND ($2::uuid IS NULL OR wallet_id = $2)
-            AND ($3::uuid IS NULL OR category_id = $3)
-            AND ($4::text IS NULL OR transaction_type = $4)
-            AND ($5::date IS NULL OR date >= $5)
-            AND ($6::date IS NULL OR date <= $6)
-        ORDER BY date DESC, created_at DESC
-        LIMIT $7 OFFSET $8
-        "#
-    )
-    .bind(user_id)
-    .bind(query.wallet_id)
-    .bind(query.category_id)
-    .bind(&query.transaction_type)
-    .bind(query.start_date)
-    .bind(query.end_date)
-    .bind(limit)
-    .bind(offset)
-    .fetch_all(&state.db)
-    .await?;
-
-    let mut response = Vec::new();
-    for tx in transactions {
-        response.push(TransactionResponse::from_with_category(tx, &state.db).await?);
-    }
+    let transactions = db::get_user_transactions(&state.db, user_id, &query, limit, offset).await?;
+    let response: Vec<TransactionResponse> = transactions.into_iter().map(TransactionResponse::from).collect();
 
     let total: (i64,) = sqlx::query_as(
-        r#"SELECT COUNT(*) FROM transactions WHERE user_id = $1"#
+        r#"SELECT COUNT(*) FROM transactions WHERE user_id = $1 AND deleted_at IS NULL"#
     )
     .bind(user_id)
     .fetch_one(&state.db)
@@ -93,13 +82,19 @@ pub async fn create_transaction(
     Json(payload): Json<CreateTransactionRequest>,
 ) -> Result<Json<Value>, AppError> {
     let user_id = get_user_id(&state, &headers).await?;
+    let audit = AuditContext::from_headers(user_id, &headers)?;
 
     payload.validate().map_err(|e| {
         AppError::ValidationError(e.to_string())
     })?;
 
     let tx_id = Uuid::new_v4();
-    let date = payload.date.unwrap_or_else(|| Utc::now().date_naive());
+    let date = match payload.date {
+        Some(date) => date,
+        None => db::get_user_today(&state.db, user_id).await?,
+    };
+
+    let mut db_tx = state.db.begin().await?;
 
     // Get or create default wallet if wallet_id is not provided
     let wallet_id = if let Some(w_id) = payload.wallet_id {
@@ -109,7 +104,7 @@ pub async fn create_transaction(
         )
         .bind(w_id)
         .bind(user_id)
-        .fetch_one(&state.db)
+        .fetch_one(&mut *db_tx)
         .await?;
 
         if !wallet_exists {
@@ -122,18 +117,18 @@ pub async fn create_transaction(
             r#"SELECT id FROM wallets WHERE user_id = $1 AND is_default = true AND deleted_at IS NULL LIMIT 1"#
         )
         .bind(user_id)
-        .fetch_optional(&state.db)
+        .fetch_optional(&mut *db_tx)
         .await?;
 
         if let Some(w_id) = default_wallet {
             w_id
         } else {
             // No default wallet found: create cash wallet as default
-            let new_wallet_id = Uuid::new_v4();
-            sqlx::query(
-                r#"INSERT INTO wallets (id, user_id, name, wallet_type, balance, icon, color, is_default) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"#
+            let new_wallet = sqlx::query_as::<_, Wallet>(
+                r#"INSERT INTO wallets (id, user_id, name, wallet_type, balance, icon, color, is_default) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                   RETURNING id, user_id, name, wallet_type, balance, icon, color, credit_limit, statement_closing_day, payment_due_day, is_default, created_at, updated_at, deleted_at"#
             )
-            .bind(new_wallet_id)
+            .bind(Uuid::new_v4())
             .bind(user_id)
             .bind("Cash")
             .bind("cash")
@@ -141,9 +136,11 @@ pub async fn create_transaction(
             .bind("💵")
             .bind("#22c55e")
             .bind(true)
-            .execute(&state.db)
+            .fetch_one(&mut *db_tx)
             .await?;
-            new_wallet_id
+
+            db::insert_audit_log(&mut *db_tx, &audit, AuditAction::Create, None, &new_wallet).await?;
+            new_wallet.id
         }
     };
 
@@ -155,7 +152,7 @@ pub async fn create_transaction(
         )
         .bind(cat_id)
         .bind(user_id)
-        .fetch_one(&state.db)
+        .fetch_one(&mut *db_tx)
         .await?;
 
         if !cat_exists {
@@ -170,24 +167,26 @@ pub async fn create_transaction(
         .bind(cat_name)
         .bind(&payload.transaction_type)
         .bind(user_id)
-        .fetch_optional(&state.db)
+        .fetch_optional(&mut *db_tx)
         .await?;
 
         if let Some(cat_id) = existing_cat {
             Some(cat_id)
         } else {
             // Create new category
-            let new_cat_id = Uuid::new_v4();
-            sqlx::query(
-                r#"INSERT INTO categories (id, user_id, name, category_type) VALUES ($1, $2, $3, $4)"#
+            let new_category = sqlx::query_as::<_, Category>(
+                r#"INSERT INTO categories (id, user_id, name, category_type) VALUES ($1, $2, $3, $4)
+                   RETURNING id, user_id, name, icon, color, category_type, parent_id, created_at, deleted_at"#
             )
-            .bind(new_cat_id)
+            .bind(Uuid::new_v4())
             .bind(user_id)
             .bind(cat_name)
             .bind(&payload.transaction_type)
-            .execute(&state.db)
+            .fetch_one(&mut *db_tx)
             .await?;
-            Some(new_cat_id)
+
+            db::insert_audit_log(&mut *db_tx, &audit, AuditAction::Create, None, &new_category).await?;
+            Some(new_category.id)
         }
     } else {
         None
@@ -197,7 +196,7 @@ pub async fn create_transaction(
         r#"
         INSERT INTO transactions (id, user_id, wallet_id, category_id, transaction_type, amount, description, date)
         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-        RETURNING id, user_id, wallet_id, category_id, transaction_type, amount, description, date, created_at, updated_at
+        RETURNING id, user_id, wallet_id, category_id, transaction_type, amount, description, date, is_transfer, created_at, updated_at, deleted_at
         "#
     )
     .bind(tx_id)
@@ -208,7 +207,7 @@ pub async fn create_transaction(
     .bind(payload.amount)
     .bind(&payload.description)
     .bind(date)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *db_tx)
     .await?;
 
     // Update wallet balance
@@ -224,9 +223,13 @@ pub async fn create_transaction(
     .bind(balance_change)
     .bind(wallet_id)
     .bind(user_id)
-    .execute(&state.db)
+    .execute(&mut *db_tx)
     .await?;
 
+    db::insert_audit_log(&mut *db_tx, &audit, AuditAction::Create, None, &transaction).await?;
+
+    db_tx.commit().await?;
+
     let response = TransactionResponse::from_with_category(transaction, &state.db).await?;
 
     Ok(Json(json!({
@@ -243,19 +246,11 @@ pub async fn get_transaction(
 ) -> Result<Json<Value>, AppError> {
     let user_id = get_user_id(&state, &headers).await?;
 
-    let transaction = sqlx::query_as::<_, Transaction>(
-        r#"
-        SELECT id, user_id, wallet_id, category_id, transaction_type, amount, description, date, created_at, updated_at
-        FROM transactions WHERE id = $1 AND user_id = $2
-        "#
-    )
-    .bind(id)
-    .bind(user_id)
-    .fetch_optional(&state.db)
-    .await?
-    .ok_or(AppError::NotFound("Transaction".to_string()))?;
+    let transaction = db::get_transaction_by_id(&state.db, id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Transaction".to_string()))?;
 
-    let response = TransactionResponse::from_with_category(transaction, &state.db).await?;
+    let response = TransactionResponse::from(transaction);
 
     Ok(Json(json!({
         "success": true,
@@ -270,20 +265,25 @@ pub async fn update_transaction(
     Json(payload): Json<UpdateTransactionRequest>,
 ) -> Result<Json<Value>, AppError> {
     let user_id = get_user_id(&state, &headers).await?;
+    let audit = AuditContext::from_headers(user_id, &headers)?;
+
+    let mut db_tx = state.db.begin().await?;
 
     // Get old transaction data before update
     let old_transaction = sqlx::query_as::<_, Transaction>(
         r#"
-        SELECT id, user_id, wallet_id, category_id, transaction_type, amount, description, date, created_at, updated_at
-        FROM transactions WHERE id = $1 AND user_id = $2
+        SELECT id, user_id, wallet_id, category_id, transaction_type, amount, description, date, is_transfer, created_at, updated_at, deleted_at
+        FROM transactions WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL FOR UPDATE
         "#
     )
     .bind(id)
     .bind(user_id)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *db_tx)
     .await?
     .ok_or(AppError::NotFound("Transaction".to_string()))?;
 
+    reject_transfer(&old_transaction)?;
+
     // Reverse the old balance change
     let old_balance_change = if old_transaction.transaction_type == "income" {
         -old_transaction.amount
@@ -297,7 +297,7 @@ pub async fn update_transaction(
     .bind(old_balance_change)
     .bind(old_transaction.wallet_id)
     .bind(user_id)
-    .execute(&state.db)
+    .execute(&mut *db_tx)
     .await?;
 
     // Verify new wallet exists and belongs to user (if wallet_id is being changed)
@@ -308,20 +308,11 @@ pub async fn update_transaction(
             )
             .bind(new_wallet_id)
             .bind(user_id)
-            .fetch_one(&state.db)
+            .fetch_one(&mut *db_tx)
             .await?;
 
+            // Returning early rolls back the reversed balance change
             if !wallet_exists {
-                // Revert the old balance change since we're failing
-                let revert_change = -old_balance_change;
-                sqlx::query(
-                    r#"UPDATE wallets SET balance = balance + $1, updated_at = NOW() WHERE id = $2 AND user_id = $3"#
-                )
-                .bind(revert_change)
-                .bind(old_transaction.wallet_id)
-                .bind(user_id)
-                .execute(&state.db)
-                .await?;
                 return Err(AppError::NotFound("Wallet".to_string()));
             }
         }
@@ -338,8 +329,8 @@ pub async fn update_transaction(
             description = COALESCE($5, description),
             date = COALESCE($6, date),
             updated_at = NOW()
-        WHERE id = $7 AND user_id = $8
-        RETURNING id, user_id, wallet_id, category_id, transaction_type, amount, description, date, created_at, updated_at
+        WHERE id = $7 AND user_id = $8 AND deleted_at IS NULL
+        RETURNING id, user_id, wallet_id, category_id, transaction_type, amount, description, date, is_transfer, created_at, updated_at, deleted_at
         "#
     )
     .bind(payload.wallet_id)
@@ -350,7 +341,7 @@ pub async fn update_transaction(
     .bind(payload.date)
     .bind(id)
     .bind(user_id)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *db_tx)
     .await?
     .ok_or(AppError::NotFound("Transaction".to_string()))?;
 
@@ -372,9 +363,13 @@ pub async fn update_transaction(
     .bind(new_balance_change)
     .bind(final_wallet_id)
     .bind(user_id)
-    .execute(&state.db)
+    .execute(&mut *db_tx)
     .await?;
 
+    db::insert_audit_log(&mut *db_tx, &audit, AuditAction::Update, Some(&old_transaction), &transaction).await?;
+
+    db_tx.commit().await?;
+
     let response = TransactionResponse::from_with_category(transaction, &state.db).await?;
 
     Ok(Json(json!({
@@ -390,14 +385,24 @@ pub async fn delete_transaction(
     Path(id): Path<Uuid>,
 ) -> Result<Json<Value>, AppError> {
     let user_id = get_user_id(&state, &headers).await?;
+    let audit = AuditContext::from_headers(user_id, &headers)?;
+
+    let before = db::get_transaction_by_id(&state.db, id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Transaction".to_string()))?;
+
+    reject_transfer(&before.transaction)?;
 
+    let mut db_tx = state.db.begin().await?;
+
+    // Soft delete: keep the row so the deletion can be undone
     let transaction = sqlx::query_as::<_, Transaction>(
-        r#"SELECT id, user_id, wallet_id, category_id, transaction_type, amount, description, date, created_at, updated_at 
-           FROM transactions WHERE id = $1 AND user_id = $2"#
+        r#"UPDATE transactions SET deleted_at = NOW() WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL
+           RETURNING id, user_id, wallet_id, category_id, transaction_type, amount, description, date, is_transfer, created_at, updated_at, deleted_at"#
     )
     .bind(id)
     .bind(user_id)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *db_tx)
     .await?
     .ok_or(AppError::NotFound("Transaction".to_string()))?;
 
@@ -414,19 +419,348 @@ pub async fn delete_transaction(
     .bind(balance_change)
     .bind(transaction.wallet_id)
     .bind(user_id)
-    .execute(&state.db)
+    .execute(&mut *db_tx)
     .await?;
 
-    sqlx::query(
-        r#"DELETE FROM transactions WHERE id = $1 AND user_id = $2"#
+    db::insert_audit_log(&mut *db_tx, &audit, AuditAction::Delete, Some(&before.transaction), &transaction).await?;
+
+    db_tx.commit().await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Transaksi berhasil dihapus!",
+        "data": {
+            "id": transaction.id,
+            "deleted_at": transaction.deleted_at
+        }
+    })))
+}
+
+pub async fn list_deleted_transactions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let transactions = db::get_deleted_transactions(&state.db, user_id).await?;
+    let response: Vec<TransactionResponse> = transactions.into_iter().map(TransactionResponse::from).collect();
+
+    Ok(Json(json!({
+        "success": true,
+        "data": response,
+        "meta": {
+            "retention_days": state.config.trash_retention_days
+        }
+    })))
+}
+
+pub async fn restore_transaction(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+    let audit = AuditContext::from_headers(user_id, &headers)?;
+
+    let mut db_tx = state.db.begin().await?;
+
+    let transaction = sqlx::query_as::<_, Transaction>(
+        r#"SELECT id, user_id, wallet_id, category_id, transaction_type, amount, description, date, is_transfer, created_at, updated_at, deleted_at
+           FROM transactions WHERE id = $1 AND user_id = $2 AND deleted_at IS NOT NULL FOR UPDATE"#
     )
     .bind(id)
     .bind(user_id)
-    .execute(&state.db)
+    .fetch_optional(&mut *db_tx)
+    .await?
+    .ok_or(AppError::NotFound("Transaction".to_string()))?;
+
+    reject_transfer(&transaction)?;
+
+    let wallet_active: bool = sqlx::query_scalar(
+        r#"SELECT EXISTS(SELECT 1 FROM wallets WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL)"#
+    )
+    .bind(transaction.wallet_id)
+    .bind(user_id)
+    .fetch_one(&mut *db_tx)
     .await?;
 
+    if !wallet_active {
+        return Err(AppError::Conflict(
+            "Wallet transaksi ini sudah dihapus. Pulihkan wallet terlebih dahulu.".to_string(),
+        ));
+    }
+
+    let after = sqlx::query_as::<_, Transaction>(
+        r#"UPDATE transactions SET deleted_at = NULL WHERE id = $1 AND user_id = $2
+           RETURNING id, user_id, wallet_id, category_id, transaction_type, amount, description, date, is_transfer, created_at, updated_at, deleted_at"#
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_one(&mut *db_tx)
+    .await?;
+
+    db::insert_audit_log(&mut *db_tx, &audit, AuditAction::Restore, Some(&transaction), &after).await?;
+
+    // Re-apply the balance change
+    let balance_change = if transaction.transaction_type == "income" {
+        transaction.amount
+    } else {
+        -transaction.amount
+    };
+
+    sqlx::query(
+        r#"UPDATE wallets SET balance = balance + $1, updated_at = NOW() WHERE id = $2 AND user_id = $3"#
+    )
+    .bind(balance_change)
+    .bind(transaction.wallet_id)
+    .bind(user_id)
+    .execute(&mut *db_tx)
+    .await?;
+
+    db_tx.commit().await?;
+
+    let restored = db::get_transaction_by_id(&state.db, id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Transaction".to_string()))?;
+
     Ok(Json(json!({
         "success": true,
-        "message": "Transaksi berhasil dihapus!"
+        "message": "Transaksi berhasil dipulihkan!",
+        "data": TransactionResponse::from(restored)
     })))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        handlers::debt::create_debt,
+        models::debt::CreateDebtRequest,
+        test_support::{auth_headers, count_queries, create_user, test_state},
+    };
+    use chrono::NaiveDate;
+    use sqlx::PgPool;
+
+    // A user with `count` expenses, each in its own category; returns the user and one transaction
+    async fn seed_transactions(pool: &PgPool, count: i32) -> (Uuid, Uuid) {
+        let (user_id, wallet_id) = create_user(pool).await;
+        let transaction_id: Uuid = sqlx::query_scalar(
+            r#"
+            WITH new_categories AS (
+                INSERT INTO categories (user_id, name, category_type)
+                SELECT $1, 'Kategori ' || i, 'expense' FROM generate_series(1, $3) AS i
+                RETURNING id
+            )
+            INSERT INTO transactions (user_id, wallet_id, category_id, transaction_type, amount)
+            SELECT $1, $2, id, 'expense', 10000 FROM new_categories
+            RETURNING id
+            "#,
+        )
+        .bind(user_id)
+        .bind(wallet_id)
+        .bind(count)
+        .fetch_all(pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .next_back()
+        .unwrap();
+        (user_id, transaction_id)
+    }
+
+    async fn list_transaction_queries(pool: &PgPool, count: i32) -> usize {
+        let (user_id, _) = seed_transactions(pool, count).await;
+        let state = test_state(pool.clone());
+        let query = TransactionQuery {
+            wallet_id: None,
+            category_id: None,
+            transaction_type: None,
+            start_date: None,
+            end_date: None,
+            limit: Some(100),
+            offset: None,
+        };
+
+        let (response, queries) =
+            count_queries(list_transactions(State(state), auth_headers(user_id), Query(query))).await;
+        let transactions = response.unwrap().0["data"].as_array().unwrap().clone();
+        assert_eq!(transactions.len(), count as usize);
+        assert!(transactions.iter().all(|transaction| transaction["category_name"].is_string()));
+        queries
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn list_transactions_query_count_is_constant(pool: PgPool) {
+        let one = list_transaction_queries(&pool, 1).await;
+        let many = list_transaction_queries(&pool, 80).await;
+        // The page and the total count
+        assert_eq!(one, 2);
+        assert_eq!(many, one);
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn get_transaction_resolves_category_in_one_query(pool: PgPool) {
+        let (user_id, transaction_id) = seed_transactions(&pool, 5).await;
+        let state = test_state(pool.clone());
+
+        let (response, queries) =
+            count_queries(get_transaction(State(state), auth_headers(user_id), Path(transaction_id))).await;
+        assert!(response.unwrap().0["data"]["category_name"].is_string());
+        assert_eq!(queries, 1);
+    }
+
+    fn expense(wallet_id: Uuid, amount: f64) -> CreateTransactionRequest {
+        CreateTransactionRequest {
+            wallet_id: Some(wallet_id),
+            category_id: None,
+            category_name: None,
+            transaction_type: "expense".to_string(),
+            amount,
+            description: None,
+            date: NaiveDate::from_ymd_opt(2025, 3, 10),
+        }
+    }
+
+    async fn create(state: &AppState, user_id: Uuid, request: CreateTransactionRequest) -> Uuid {
+        let response = create_transaction(State(state.clone()), auth_headers(user_id), Json(request))
+            .await
+            .unwrap();
+        response.0["data"]["id"].as_str().unwrap().parse().unwrap()
+    }
+
+    async fn wallet_balance(pool: &PgPool, wallet_id: Uuid) -> f64 {
+        sqlx::query_scalar("SELECT balance FROM wallets WHERE id = $1")
+            .bind(wallet_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn budget_usage(pool: &PgPool, user_id: Uuid) -> f64 {
+        db::get_user_budgets_with_usage(pool, user_id, Some(3), Some(2025)).await.unwrap()[0].used_amount
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn delete_and_restore_reverse_and_reapply_the_balance(pool: PgPool) {
+        let (user_id, wallet_id) = create_user(&pool).await;
+        sqlx::query("UPDATE wallets SET balance = 100000 WHERE id = $1")
+            .bind(wallet_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO budgets (user_id, amount, month, year) VALUES ($1, 50000, 3, 2025)")
+            .bind(user_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let state = test_state(pool.clone());
+
+        let id = create(&state, user_id, expense(wallet_id, 25000.0)).await;
+        assert_eq!(wallet_balance(&pool, wallet_id).await, 75000.0);
+        assert_eq!(budget_usage(&pool, user_id).await, 25000.0);
+
+        let deleted = delete_transaction(State(state.clone()), auth_headers(user_id), Path(id)).await.unwrap();
+        assert!(deleted.0["data"]["deleted_at"].is_string());
+        assert_eq!(wallet_balance(&pool, wallet_id).await, 100000.0);
+        assert_eq!(budget_usage(&pool, user_id).await, 0.0);
+        let result = get_transaction(State(state.clone()), auth_headers(user_id), Path(id)).await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+        let trash = list_deleted_transactions(State(state.clone()), auth_headers(user_id)).await.unwrap();
+        assert_eq!(trash.0["data"][0]["id"], id.to_string());
+
+        // A second delete finds nothing to delete, so the balance is only reversed once
+        let result = delete_transaction(State(state.clone()), auth_headers(user_id), Path(id)).await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+        assert_eq!(wallet_balance(&pool, wallet_id).await, 100000.0);
+
+        let restored = restore_transaction(State(state.clone()), auth_headers(user_id), Path(id)).await.unwrap();
+        assert_eq!(restored.0["data"]["deleted_at"], Value::Null);
+        assert_eq!(wallet_balance(&pool, wallet_id).await, 75000.0);
+        assert_eq!(budget_usage(&pool, user_id).await, 25000.0);
+
+        let result = restore_transaction(State(state), auth_headers(user_id), Path(id)).await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+        assert_eq!(wallet_balance(&pool, wallet_id).await, 75000.0);
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn restore_transaction_needs_an_active_wallet(pool: PgPool) {
+        let (user_id, wallet_id) = create_user(&pool).await;
+        let state = test_state(pool.clone());
+        let id = create(&state, user_id, expense(wallet_id, 25000.0)).await;
+        let deleted = delete_transaction(State(state.clone()), auth_headers(user_id), Path(id)).await.unwrap();
+        assert_eq!(deleted.0["success"], true);
+        sqlx::query("UPDATE wallets SET deleted_at = NOW() WHERE id = $1")
+            .bind(wallet_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let result = restore_transaction(State(state), auth_headers(user_id), Path(id)).await;
+        assert!(matches!(result, Err(AppError::Conflict(_))));
+    }
+
+    // A loan paid out of the wallet; returns the disbursement transaction
+    async fn lend(state: &AppState, user_id: Uuid, wallet_id: Uuid) -> Uuid {
+        let request = CreateDebtRequest {
+            counterparty: "Budi".to_string(),
+            direction: "lent".to_string(),
+            principal: 500_000.0,
+            description: None,
+            date: None,
+            due_date: None,
+            wallet_id: Some(wallet_id),
+        };
+        let response = create_debt(State(state.clone()), auth_headers(user_id), Json(request)).await.unwrap();
+        response.0["data"]["transaction_id"].as_str().unwrap().parse().unwrap()
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn transfers_cannot_be_updated_or_deleted(pool: PgPool) {
+        let (user_id, wallet_id) = create_user(&pool).await;
+        let state = test_state(pool.clone());
+        let id = lend(&state, user_id, wallet_id).await;
+        assert_eq!(wallet_balance(&pool, wallet_id).await, -500_000.0);
+
+        let request = UpdateTransactionRequest {
+            wallet_id: None,
+            category_id: None,
+            transaction_type: None,
+            amount: Some(100_000.0),
+            description: None,
+            date: None,
+        };
+        let result = update_transaction(State(state.clone()), auth_headers(user_id), Path(id), Json(request)).await;
+        assert!(matches!(result, Err(AppError::ValidationError(_))));
+
+        let result = delete_transaction(State(state.clone()), auth_headers(user_id), Path(id)).await;
+        assert!(matches!(result, Err(AppError::ValidationError(_))));
+
+        let transaction = get_transaction(State(state), auth_headers(user_id), Path(id)).await.unwrap();
+        assert_eq!(transaction.0["data"]["amount"], 500_000.0);
+        assert_eq!(wallet_balance(&pool, wallet_id).await, -500_000.0);
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn trashed_transfers_cannot_be_restored(pool: PgPool) {
+        let (user_id, wallet_id) = create_user(&pool).await;
+        let state = test_state(pool.clone());
+        let id = lend(&state, user_id, wallet_id).await;
+        // Trashed before transfers were protected
+        sqlx::query("UPDATE transactions SET deleted_at = NOW() WHERE id = $1")
+            .bind(id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let result = restore_transaction(State(state), auth_headers(user_id), Path(id)).await;
+        assert!(matches!(result, Err(AppError::ValidationError(_))));
+        assert_eq!(wallet_balance(&pool, wallet_id).await, -500_000.0);
+    }
+}
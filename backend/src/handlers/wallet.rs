@@ -1,20 +1,36 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::{header::AUTHORIZATION, HeaderMap},
     Json,
 };
+use chrono::{Datelike, NaiveDate};
 use serde_json::{json, Value};
+use sqlx::PgConnection;
 use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
     db,
     error::AppError,
-    models::wallet::{CreateWalletRequest, UpdateWalletRequest, Wallet, WalletResponse},
-    utils::jwt::verify_token,
+    models::{
+        audit::{AuditAction, AuditContext},
+        wallet::{
+            is_credit_wallet_type, CreateWalletRequest, INVESTMENT_WALLET_TYPE, CreditActivity, CreditStatementResponse,
+            DueDateQuery, UpcomingDueResponse, UpdateWalletRequest, Wallet, WalletResponse,
+        },
+    },
+    utils::{
+        dates::{day_of_month, shift_months},
+        jwt::verify_token,
+    },
     AppState,
 };
 
+// Minimum payment of a credit statement: this percentage of the statement balance, but at
+// least MINIMUM_PAYMENT_FLOOR, and never more than the statement balance itself
+const MINIMUM_PAYMENT_PERCENTAGE: f64 = 10.0;
+const MINIMUM_PAYMENT_FLOOR: f64 = 50_000.0;
+
 async fn get_user_id(state: &AppState, headers: &HeaderMap) -> Result<Uuid, AppError> {
     let auth_header = headers
         .get(AUTHORIZATION)
@@ -29,6 +45,146 @@ async fn get_user_id(state: &AppState, headers: &HeaderMap) -> Result<Uuid, AppE
     Ok(claims.sub)
 }
 
+// Clears is_default on the user's other wallets, recording each one in the audit log
+async fn unset_other_defaults(
+    conn: &mut PgConnection,
+    audit: &AuditContext,
+    except_id: Option<Uuid>,
+) -> Result<(), AppError> {
+    let defaults = sqlx::query_as::<_, Wallet>(
+        r#"SELECT id, user_id, name, wallet_type, balance, icon, color, credit_limit, statement_closing_day, payment_due_day, is_default, created_at, updated_at, deleted_at
+           FROM wallets WHERE user_id = $1 AND id IS DISTINCT FROM $2 AND is_default = true AND deleted_at IS NULL FOR UPDATE"#
+    )
+    .bind(audit.user_id)
+    .bind(except_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for before in defaults {
+        let after = sqlx::query_as::<_, Wallet>(
+            r#"UPDATE wallets SET is_default = false, updated_at = NOW() WHERE id = $1
+               RETURNING id, user_id, name, wallet_type, balance, icon, color, credit_limit, statement_closing_day, payment_due_day, is_default, created_at, updated_at, deleted_at"#
+        )
+        .bind(before.id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        db::insert_audit_log(&mut *conn, audit, AuditAction::Update, Some(&before), &after).await?;
+    }
+
+    Ok(())
+}
+
+fn validate_statement_cycle(
+    wallet_type: &str,
+    statement_closing_day: Option<i32>,
+    payment_due_day: Option<i32>,
+) -> Result<(), AppError> {
+    if statement_closing_day.is_none() && payment_due_day.is_none() {
+        return Ok(());
+    }
+
+    if !is_credit_wallet_type(wallet_type) {
+        return Err(AppError::ValidationError(
+            "Tanggal cetak tagihan dan jatuh tempo hanya untuk wallet credit-card atau paylater".to_string(),
+        ));
+    }
+
+    let valid_day = |day: Option<i32>| day.is_none_or(|d| (1..=31).contains(&d));
+    if !valid_day(statement_closing_day) || !valid_day(payment_due_day) {
+        return Err(AppError::ValidationError(
+            "Tanggal cetak tagihan dan jatuh tempo harus antara 1-31".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+// First payment_due_day strictly after a statement closing date
+fn due_date_after(statement_date: NaiveDate, payment_due_day: i32) -> NaiveDate {
+    let same_month = day_of_month(statement_date.year(), statement_date.month(), payment_due_day);
+    if same_month > statement_date {
+        same_month
+    } else {
+        shift_months(statement_date, 1, payment_due_day)
+    }
+}
+
+// Latest statement closing date before today. A statement closes at the end of its closing day,
+// so on the closing day itself the previous statement is still the latest one.
+fn last_statement_date(today: NaiveDate, statement_closing_day: i32) -> NaiveDate {
+    let this_month = day_of_month(today.year(), today.month(), statement_closing_day);
+    if this_month < today {
+        this_month
+    } else {
+        shift_months(today, -1, statement_closing_day)
+    }
+}
+
+// Amount owed on a credit wallet balance; a positive balance (overpayment) owes nothing
+fn amount_owed(balance: f64) -> f64 {
+    if balance < 0.0 {
+        -balance
+    } else {
+        0.0
+    }
+}
+
+fn minimum_payment(statement_balance: f64) -> f64 {
+    if statement_balance <= 0.0 {
+        return 0.0;
+    }
+    (statement_balance * MINIMUM_PAYMENT_PERCENTAGE / 100.0)
+        .max(MINIMUM_PAYMENT_FLOOR)
+        .min(statement_balance)
+}
+
+// Balances of credit wallets are negative while money is owed. The balance on the statement date
+// is rebuilt from the current balance by undoing activity dated after it.
+fn credit_statement(
+    wallet: &Wallet,
+    activity: &CreditActivity,
+    statement_date: NaiveDate,
+    today: NaiveDate,
+) -> Option<CreditStatementResponse> {
+    let statement_closing_day = wallet.statement_closing_day?;
+    let payment_due_day = wallet.payment_due_day?;
+
+    let outstanding_balance = amount_owed(wallet.balance);
+    let statement_balance = amount_owed(wallet.balance + activity.charges - activity.payments);
+    let minimum = minimum_payment(statement_balance);
+    let remaining_due = (statement_balance - activity.payments).max(0.0);
+
+    let credit_limit = wallet.credit_limit.filter(|limit| *limit > 0.0);
+    let due_date = due_date_after(statement_date, payment_due_day);
+    let next_statement_date = shift_months(statement_date, 1, statement_closing_day);
+
+    Some(CreditStatementResponse {
+        wallet_id: wallet.id,
+        wallet_name: wallet.name.clone(),
+        wallet_type: wallet.wallet_type.clone(),
+        credit_limit: wallet.credit_limit,
+        outstanding_balance,
+        available_credit: credit_limit.map(|limit| limit + wallet.balance),
+        utilization_percentage: credit_limit.map(|limit| outstanding_balance / limit * 100.0),
+        statement_closing_day,
+        payment_due_day,
+        period_start: shift_months(statement_date, -1, statement_closing_day).succ_opt().unwrap(),
+        statement_date,
+        due_date,
+        statement_balance,
+        minimum_payment: minimum,
+        paid_since_statement: activity.payments,
+        remaining_due,
+        remaining_minimum_payment: (minimum - activity.payments).max(0.0),
+        unbilled_charges: activity.charges,
+        next_statement_date,
+        next_due_date: due_date_after(next_statement_date, payment_due_day),
+        days_until_due: (due_date - today).num_days(),
+        is_overdue: remaining_due > 0.0 && due_date < today,
+    })
+}
+
 pub async fn list_wallets(
     State(state): State<AppState>,
     headers: HeaderMap,
@@ -50,17 +206,20 @@ pub async fn create_wallet(
     Json(payload): Json<CreateWalletRequest>,
 ) -> Result<Json<Value>, AppError> {
     let user_id = get_user_id(&state, &headers).await?;
+    let audit = AuditContext::from_headers(user_id, &headers)?;
 
     payload.validate().map_err(|e| {
         AppError::ValidationError(e.to_string())
     })?;
 
+    let mut db_tx = state.db.begin().await?;
+
     // Check if user has any wallets
     let wallet_count: (i64,) = sqlx::query_as(
         r#"SELECT COUNT(*) FROM wallets WHERE user_id = $1 AND deleted_at IS NULL"#
     )
     .bind(user_id)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *db_tx)
     .await?;
 
     let wallet_id = Uuid::new_v4();
@@ -75,12 +234,7 @@ pub async fn create_wallet(
         true
     } else if payload.is_default.unwrap_or(false) {
         // User wants this as default: unset other defaults first
-        sqlx::query(
-            r#"UPDATE wallets SET is_default = false, updated_at = NOW() WHERE user_id = $1 AND is_default = true AND deleted_at IS NULL"#
-        )
-        .bind(user_id)
-        .execute(&state.db)
-        .await?;
+        unset_other_defaults(&mut db_tx, &audit, None).await?;
         true
     } else {
         false
@@ -93,11 +247,13 @@ pub async fn create_wallet(
         payload.wallet_type
     };
 
+    validate_statement_cycle(&wallet_type, payload.statement_closing_day, payload.payment_due_day)?;
+
     let wallet = sqlx::query_as::<_, Wallet>(
         r#"
-        INSERT INTO wallets (id, user_id, name, wallet_type, balance, icon, color, credit_limit, is_default)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
-        RETURNING id, user_id, name, wallet_type, balance, icon, color, credit_limit, is_default, created_at, updated_at, deleted_at
+        INSERT INTO wallets (id, user_id, name, wallet_type, balance, icon, color, credit_limit, statement_closing_day, payment_due_day, is_default)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        RETURNING id, user_id, name, wallet_type, balance, icon, color, credit_limit, statement_closing_day, payment_due_day, is_default, created_at, updated_at, deleted_at
         "#
     )
     .bind(wallet_id)
@@ -107,11 +263,17 @@ pub async fn create_wallet(
     .bind(balance)
     .bind(&payload.icon)
     .bind(&payload.color)
-    .bind(&payload.credit_limit)
+    .bind(payload.credit_limit)
+    .bind(payload.statement_closing_day)
+    .bind(payload.payment_due_day)
     .bind(is_default)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *db_tx)
     .await?;
 
+    db::insert_audit_log(&mut *db_tx, &audit, AuditAction::Create, None, &wallet).await?;
+
+    db_tx.commit().await?;
+
     Ok(Json(json!({
         "success": true,
         "message": if wallet_count.0 == 0 {
@@ -147,20 +309,55 @@ pub async fn update_wallet(
     Json(payload): Json<UpdateWalletRequest>,
 ) -> Result<Json<Value>, AppError> {
     let user_id = get_user_id(&state, &headers).await?;
+    let audit = AuditContext::from_headers(user_id, &headers)?;
 
-    db::get_wallet_by_id(&state.db, id, user_id)
-        .await?
-        .ok_or(AppError::NotFound("Wallet".to_string()))?;
+    let mut db_tx = state.db.begin().await?;
 
-    // If setting this wallet as default, unset other defaults first
-    if let Some(true) = payload.is_default {
-        sqlx::query(
-            r#"UPDATE wallets SET is_default = false, updated_at = NOW() WHERE user_id = $1 AND id != $2 AND is_default = true AND deleted_at IS NULL"#
+    let before = sqlx::query_as::<_, Wallet>(
+        r#"SELECT id, user_id, name, wallet_type, balance, icon, color, credit_limit, statement_closing_day, payment_due_day, is_default, created_at, updated_at, deleted_at
+           FROM wallets WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL FOR UPDATE"#
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(&mut *db_tx)
+    .await?
+    .ok_or(AppError::NotFound("Wallet".to_string()))?;
+
+    // Statement cycle days only apply to credit wallets and are cleared when a wallet stops being one
+    let wallet_type = match payload.wallet_type.as_deref() {
+        Some(wallet_type) if !wallet_type.is_empty() => wallet_type,
+        _ => before.wallet_type.as_str(),
+    };
+    validate_statement_cycle(wallet_type, payload.statement_closing_day, payload.payment_due_day)?;
+
+    // Holdings only make sense on investment wallets
+    if before.wallet_type == INVESTMENT_WALLET_TYPE && wallet_type != INVESTMENT_WALLET_TYPE {
+        let has_holdings: bool = sqlx::query_scalar(
+            r#"SELECT EXISTS(SELECT 1 FROM investment_holdings WHERE wallet_id = $1 AND deleted_at IS NULL)"#
         )
-        .bind(user_id)
         .bind(id)
-        .execute(&state.db)
+        .fetch_one(&mut *db_tx)
         .await?;
+
+        if has_holdings {
+            return Err(AppError::Conflict(
+                "Wallet investasi masih memiliki aset, hapus aset terlebih dahulu".to_string(),
+            ));
+        }
+    }
+
+    let (statement_closing_day, payment_due_day) = if is_credit_wallet_type(wallet_type) {
+        (
+            payload.statement_closing_day.or(before.statement_closing_day),
+            payload.payment_due_day.or(before.payment_due_day),
+        )
+    } else {
+        (None, None)
+    };
+
+    // If setting this wallet as default, unset other defaults first
+    if let Some(true) = payload.is_default {
+        unset_other_defaults(&mut db_tx, &audit, Some(id)).await?;
     }
 
     // Update wallet - always update icon and color if provided
@@ -175,9 +372,11 @@ pub async fn update_wallet(
             color = COALESCE($5, color),
             credit_limit = COALESCE($6, credit_limit),
             is_default = COALESCE($7, is_default),
+            statement_closing_day = $8,
+            payment_due_day = $9,
             updated_at = NOW()
-        WHERE id = $8 AND user_id = $9 AND deleted_at IS NULL
-        RETURNING id, user_id, name, wallet_type, balance, icon, color, credit_limit, is_default, created_at, updated_at, deleted_at
+        WHERE id = $10 AND user_id = $11 AND deleted_at IS NULL
+        RETURNING id, user_id, name, wallet_type, balance, icon, color, credit_limit, statement_closing_day, payment_due_day, is_default, created_at, updated_at, deleted_at
         "#
     )
     .bind(&payload.name)
@@ -187,11 +386,17 @@ pub async fn update_wallet(
     .bind(&payload.color)
     .bind(payload.credit_limit)
     .bind(payload.is_default)
+    .bind(statement_closing_day)
+    .bind(payment_due_day)
     .bind(id)
     .bind(user_id)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *db_tx)
     .await?;
 
+    db::insert_audit_log(&mut *db_tx, &audit, AuditAction::Update, Some(&before), &wallet).await?;
+
+    db_tx.commit().await?;
+
     Ok(Json(json!({
         "success": true,
         "message": "Wallet berhasil diupdate!",
@@ -205,35 +410,39 @@ pub async fn delete_wallet(
     Path(id): Path<Uuid>,
 ) -> Result<Json<Value>, AppError> {
     let user_id = get_user_id(&state, &headers).await?;
+    let audit = AuditContext::from_headers(user_id, &headers)?;
 
     // Check if wallet exists and is not already deleted
-    let wallet = db::get_wallet_by_id(&state.db, id, user_id).await?;
-    if wallet.is_none() {
-        return Err(AppError::NotFound("Wallet".to_string()));
-    }
+    let before = db::get_wallet_by_id(&state.db, id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Wallet".to_string()))?;
 
     // Check if wallet has transactions
     let transaction_count: (i64,) = sqlx::query_as(
-        r#"SELECT COUNT(*) FROM transactions WHERE wallet_id = $1 AND user_id = $2"#
+        r#"SELECT COUNT(*) FROM transactions WHERE wallet_id = $1 AND user_id = $2 AND deleted_at IS NULL"#
     )
     .bind(id)
     .bind(user_id)
     .fetch_one(&state.db)
     .await?;
 
+    let mut db_tx = state.db.begin().await?;
+
     // Soft delete: set deleted_at timestamp instead of hard delete
     // This preserves transaction history
-    let result = sqlx::query(
-        r#"UPDATE wallets SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL"#
+    let wallet = sqlx::query_as::<_, Wallet>(
+        r#"UPDATE wallets SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL
+           RETURNING id, user_id, name, wallet_type, balance, icon, color, credit_limit, statement_closing_day, payment_due_day, is_default, created_at, updated_at, deleted_at"#
     )
     .bind(id)
     .bind(user_id)
-    .execute(&state.db)
-    .await?;
+    .fetch_optional(&mut *db_tx)
+    .await?
+    .ok_or(AppError::NotFound("Wallet".to_string()))?;
 
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound("Wallet".to_string()));
-    }
+    db::insert_audit_log(&mut *db_tx, &audit, AuditAction::Delete, Some(&before), &wallet).await?;
+
+    db_tx.commit().await?;
 
     Ok(Json(json!({
         "success": true,
@@ -243,3 +452,317 @@ pub async fn delete_wallet(
         )
     })))
 }
+
+pub async fn list_deleted_wallets(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let wallets = db::get_deleted_wallets(&state.db, user_id).await?;
+    let response: Vec<WalletResponse> = wallets.into_iter().map(WalletResponse::from).collect();
+
+    Ok(Json(json!({
+        "success": true,
+        "data": response,
+        "meta": {
+            "retention_days": state.config.trash_retention_days
+        }
+    })))
+}
+
+pub async fn restore_wallet(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+    let audit = AuditContext::from_headers(user_id, &headers)?;
+
+    let mut db_tx = state.db.begin().await?;
+
+    let before = sqlx::query_as::<_, Wallet>(
+        r#"SELECT id, user_id, name, wallet_type, balance, icon, color, credit_limit, statement_closing_day, payment_due_day, is_default, created_at, updated_at, deleted_at
+           FROM wallets WHERE id = $1 AND user_id = $2 AND deleted_at IS NOT NULL FOR UPDATE"#
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(&mut *db_tx)
+    .await?
+    .ok_or(AppError::NotFound("Wallet".to_string()))?;
+
+    // Only one default wallet per user: restore as non-default if another default exists
+    let wallet = sqlx::query_as::<_, Wallet>(
+        r#"
+        UPDATE wallets SET
+            deleted_at = NULL,
+            is_default = is_default AND NOT EXISTS(
+                SELECT 1 FROM wallets WHERE user_id = $2 AND is_default = true AND deleted_at IS NULL
+            ),
+            updated_at = NOW()
+        WHERE id = $1 AND user_id = $2 AND deleted_at IS NOT NULL
+        RETURNING id, user_id, name, wallet_type, balance, icon, color, credit_limit, statement_closing_day, payment_due_day, is_default, created_at, updated_at, deleted_at
+        "#
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(&mut *db_tx)
+    .await?
+    .ok_or(AppError::NotFound("Wallet".to_string()))?;
+
+    db::insert_audit_log(&mut *db_tx, &audit, AuditAction::Restore, Some(&before), &wallet).await?;
+
+    db_tx.commit().await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Wallet berhasil dipulihkan!",
+        "data": WalletResponse::from(wallet)
+    })))
+}
+
+pub async fn get_credit_statement(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let wallet = db::get_wallet_by_id(&state.db, id, user_id)
+        .await?
+        .ok_or(AppError::NotFound("Wallet".to_string()))?;
+
+    if !is_credit_wallet_type(&wallet.wallet_type) {
+        return Err(AppError::ValidationError(
+            "Tagihan hanya tersedia untuk wallet credit-card atau paylater".to_string(),
+        ));
+    }
+
+    let statement_closing_day = match (wallet.statement_closing_day, wallet.payment_due_day) {
+        (Some(closing_day), Some(_)) => closing_day,
+        _ => {
+            return Err(AppError::ValidationError(
+                "Atur tanggal cetak tagihan dan jatuh tempo wallet ini terlebih dahulu".to_string(),
+            ))
+        }
+    };
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let statement_date = last_statement_date(today, statement_closing_day);
+
+    let activity = db::get_credit_activity_since(&state.db, user_id, &[(wallet.id, statement_date)])
+        .await?
+        .into_iter()
+        .next()
+        .unwrap_or(CreditActivity { wallet_id: wallet.id, charges: 0.0, payments: 0.0 });
+
+    let statement = credit_statement(&wallet, &activity, statement_date, today);
+
+    Ok(Json(json!({
+        "success": true,
+        "data": statement
+    })))
+}
+
+// Next payment per credit wallet: the latest statement while it is unpaid (including overdue
+// ones), otherwise an estimate for the open statement based on the current outstanding balance
+pub async fn list_upcoming_due_dates(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<DueDateQuery>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let days = query.days.unwrap_or(30);
+    if !(1..=366).contains(&days) {
+        return Err(AppError::ValidationError(
+            "Days harus antara 1-366".to_string(),
+        ));
+    }
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let wallets: Vec<(Wallet, NaiveDate)> = db::get_user_wallets(&state.db, user_id)
+        .await?
+        .into_iter()
+        .filter(|wallet| is_credit_wallet_type(&wallet.wallet_type))
+        .filter_map(|wallet| {
+            let closing_day = wallet.statement_closing_day?;
+            wallet.payment_due_day?;
+            Some((wallet, last_statement_date(today, closing_day)))
+        })
+        .collect();
+
+    let cutoffs: Vec<(Uuid, NaiveDate)> = wallets.iter().map(|(wallet, date)| (wallet.id, *date)).collect();
+    let activity = db::get_credit_activity_since(&state.db, user_id, &cutoffs).await?;
+
+    let mut upcoming: Vec<UpcomingDueResponse> = wallets
+        .iter()
+        .filter_map(|(wallet, statement_date)| {
+            let activity = activity.iter().find(|a| a.wallet_id == wallet.id)?;
+            let statement = credit_statement(wallet, activity, *statement_date, today)?;
+
+            let due = if statement.remaining_due > 0.0 {
+                UpcomingDueResponse {
+                    wallet_id: wallet.id,
+                    wallet_name: wallet.name.clone(),
+                    statement_date: statement.statement_date,
+                    due_date: statement.due_date,
+                    days_until_due: statement.days_until_due,
+                    amount_due: statement.remaining_due,
+                    minimum_payment: statement.remaining_minimum_payment,
+                    is_estimate: false,
+                    is_overdue: statement.is_overdue,
+                }
+            } else {
+                UpcomingDueResponse {
+                    wallet_id: wallet.id,
+                    wallet_name: wallet.name.clone(),
+                    statement_date: statement.next_statement_date,
+                    due_date: statement.next_due_date,
+                    days_until_due: (statement.next_due_date - today).num_days(),
+                    amount_due: statement.outstanding_balance,
+                    minimum_payment: minimum_payment(statement.outstanding_balance),
+                    is_estimate: true,
+                    is_overdue: false,
+                }
+            };
+
+            (due.amount_due > 0.0 && (due.is_overdue || due.days_until_due <= days)).then_some(due)
+        })
+        .collect();
+
+    upcoming.sort_by_key(|due| due.due_date);
+
+    Ok(Json(json!({
+        "success": true,
+        "data": upcoming,
+        "meta": {
+            "days": days
+        }
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn credit_card(balance: f64, statement_closing_day: Option<i32>, payment_due_day: Option<i32>) -> Wallet {
+        Wallet {
+            id: Uuid::nil(),
+            user_id: Uuid::nil(),
+            name: "Kartu Kredit".to_string(),
+            wallet_type: "credit-card".to_string(),
+            balance,
+            icon: None,
+            color: None,
+            credit_limit: Some(10_000_000.0),
+            statement_closing_day,
+            payment_due_day,
+            is_default: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            deleted_at: None,
+        }
+    }
+
+    #[test]
+    fn statement_cycle_only_for_credit_wallets() {
+        assert!(validate_statement_cycle("cash", None, None).is_ok());
+        assert!(validate_statement_cycle("paylater", Some(31), Some(1)).is_ok());
+        assert!(validate_statement_cycle("credit-card", Some(25), None).is_ok());
+        assert!(validate_statement_cycle("cash", Some(25), Some(10)).is_err());
+        assert!(validate_statement_cycle("credit-card", Some(0), Some(10)).is_err());
+        assert!(validate_statement_cycle("credit-card", Some(25), Some(32)).is_err());
+    }
+
+    #[test]
+    fn last_statement_date_clamps_closing_days_to_month_end() {
+        assert_eq!(last_statement_date(date(2025, 3, 15), 31), date(2025, 2, 28));
+        assert_eq!(last_statement_date(date(2024, 3, 15), 30), date(2024, 2, 29));
+        assert_eq!(last_statement_date(date(2025, 3, 1), 29), date(2025, 2, 28));
+        // April has no 31st, so its statement closes on the 30th
+        assert_eq!(last_statement_date(date(2025, 4, 30), 31), date(2025, 3, 31));
+        assert_eq!(last_statement_date(date(2025, 5, 1), 31), date(2025, 4, 30));
+    }
+
+    #[test]
+    fn last_statement_date_on_the_closing_day() {
+        // The statement closing today is not out yet
+        assert_eq!(last_statement_date(date(2025, 3, 25), 25), date(2025, 2, 25));
+        assert_eq!(last_statement_date(date(2025, 3, 26), 25), date(2025, 3, 25));
+        assert_eq!(last_statement_date(date(2025, 1, 10), 10), date(2024, 12, 10));
+    }
+
+    #[test]
+    fn due_date_after_statement() {
+        assert_eq!(due_date_after(date(2025, 1, 5), 20), date(2025, 1, 20));
+        assert_eq!(due_date_after(date(2025, 1, 25), 10), date(2025, 2, 10));
+        assert_eq!(due_date_after(date(2025, 1, 15), 15), date(2025, 2, 15));
+        assert_eq!(due_date_after(date(2025, 1, 31), 31), date(2025, 2, 28));
+        assert_eq!(due_date_after(date(2025, 12, 20), 5), date(2026, 1, 5));
+    }
+
+    #[test]
+    fn minimum_payment_has_a_floor() {
+        assert_eq!(minimum_payment(0.0), 0.0);
+        assert_eq!(minimum_payment(-100.0), 0.0);
+        assert_eq!(minimum_payment(3_000_000.0), 300_000.0);
+        assert_eq!(minimum_payment(200_000.0), 50_000.0);
+        // Never more than the statement balance
+        assert_eq!(minimum_payment(30_000.0), 30_000.0);
+    }
+
+    #[test]
+    fn credit_statement_rebuilds_the_statement_balance() {
+        let wallet = credit_card(-3_200_000.0, Some(25), Some(10));
+        let activity = CreditActivity { wallet_id: wallet.id, charges: 700_000.0, payments: 500_000.0 };
+        let today = date(2025, 3, 5);
+        let statement_date = last_statement_date(today, 25);
+
+        let statement = credit_statement(&wallet, &activity, statement_date, today).unwrap();
+        assert_eq!(statement.period_start, date(2025, 1, 26));
+        assert_eq!(statement.statement_date, date(2025, 2, 25));
+        assert_eq!(statement.due_date, date(2025, 3, 10));
+        assert_eq!(statement.next_statement_date, date(2025, 3, 25));
+        assert_eq!(statement.next_due_date, date(2025, 4, 10));
+        assert_eq!(statement.days_until_due, 5);
+        assert_eq!(statement.outstanding_balance, 3_200_000.0);
+        assert_eq!(statement.statement_balance, 3_000_000.0);
+        assert_eq!(statement.minimum_payment, 300_000.0);
+        assert_eq!(statement.remaining_due, 2_500_000.0);
+        assert_eq!(statement.remaining_minimum_payment, 0.0);
+        assert_eq!(statement.available_credit, Some(6_800_000.0));
+        assert_eq!(statement.utilization_percentage, Some(32.0));
+        assert!(!statement.is_overdue);
+
+        let late = credit_statement(&wallet, &activity, statement_date, date(2025, 3, 12)).unwrap();
+        assert_eq!(late.days_until_due, -2);
+        assert!(late.is_overdue);
+    }
+
+    #[test]
+    fn credit_statement_period_at_month_end() {
+        let wallet = credit_card(-1_000_000.0, Some(31), Some(15));
+        let activity = CreditActivity { wallet_id: wallet.id, charges: 0.0, payments: 0.0 };
+        let today = date(2025, 3, 10);
+
+        let statement = credit_statement(&wallet, &activity, last_statement_date(today, 31), today).unwrap();
+        assert_eq!(statement.period_start, date(2025, 2, 1));
+        assert_eq!(statement.statement_date, date(2025, 2, 28));
+        assert_eq!(statement.due_date, date(2025, 3, 15));
+        assert_eq!(statement.next_statement_date, date(2025, 3, 31));
+    }
+
+    #[test]
+    fn credit_statement_needs_a_cycle() {
+        let activity = CreditActivity { wallet_id: Uuid::nil(), charges: 0.0, payments: 0.0 };
+        let today = date(2025, 3, 10);
+        assert!(credit_statement(&credit_card(0.0, Some(25), None), &activity, today, today).is_none());
+        assert!(credit_statement(&credit_card(0.0, None, Some(10)), &activity, today, today).is_none());
+    }
+}
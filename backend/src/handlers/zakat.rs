@@ -0,0 +1,272 @@
+use axum::{
+    extract::{Query, State},
+    http::{header::AUTHORIZATION, HeaderMap},
+    Json,
+};
+use chrono::{Datelike, NaiveDate};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::{
+    db,
+    error::AppError,
+    models::zakat::{
+        evaluate_haul, haul_dates, haul_summary, zakat_amount, HaulStatus, UpdateZakatSettingsRequest,
+        ZakatReportQuery, ZakatSettings, ZakatableWealth, DEFAULT_NISAB_GRAMS, HAUL_DAYS, ZAKAT_RATE,
+    },
+    utils::{dates::day_of_month, jwt::verify_token},
+    AppState,
+};
+
+const MAX_NISAB_GRAMS: f64 = 1000.0;
+const MIN_REPORT_YEAR: i32 = 2000;
+
+async fn get_user_id(state: &AppState, headers: &HeaderMap) -> Result<Uuid, AppError> {
+    let auth_header = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .ok_or(AppError::Unauthorized)?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or(AppError::Unauthorized)?;
+
+    let claims = verify_token(token, &state.config.jwt_secret)?;
+    Ok(claims.sub)
+}
+
+async fn require_settings(state: &AppState, user_id: Uuid) -> Result<ZakatSettings, AppError> {
+    db::get_zakat_settings(&state.db, user_id).await?.ok_or_else(|| {
+        AppError::ValidationError(
+            "Atur harga emas per gram terlebih dahulu di pengaturan zakat".to_string(),
+        )
+    })
+}
+
+// Daily zakatable wealth over the haul ending at `as_of`, and the haul verdict. Days before
+// the first balance snapshot carry no data.
+async fn evaluate(
+    state: &AppState,
+    user_id: Uuid,
+    settings: &ZakatSettings,
+    as_of: NaiveDate,
+) -> Result<(Vec<ZakatableWealth>, HaulStatus, Option<NaiveDate>), AppError> {
+    let history = db::get_net_worth_history(&state.db, user_id, &haul_dates(as_of)).await?;
+    let wealth: Vec<ZakatableWealth> = history
+        .iter()
+        .map(|point| settings.zakatable_wealth(point))
+        .collect();
+
+    let first_snapshot_date = db::get_first_snapshot_date(&state.db, user_id).await?;
+    let haul = evaluate_haul(
+        &wealth,
+        settings.nisab(),
+        as_of,
+        first_snapshot_date.unwrap_or(as_of),
+    );
+
+    Ok((wealth, haul, first_snapshot_date))
+}
+
+pub async fn get_zakat_settings(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    let settings = db::get_zakat_settings(&state.db, user_id).await?;
+    let nisab = settings.as_ref().map(ZakatSettings::nisab);
+
+    Ok(Json(json!({
+        "success": true,
+        "data": settings,
+        "meta": {
+            "nisab": nisab,
+            "default_nisab_grams": DEFAULT_NISAB_GRAMS,
+            "zakat_rate": ZAKAT_RATE,
+            "haul_days": HAUL_DAYS
+        }
+    })))
+}
+
+pub async fn update_zakat_settings(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<UpdateZakatSettingsRequest>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+
+    if let Some(price) = payload.gold_price_per_gram {
+        if price <= 0.0 || !price.is_finite() {
+            return Err(AppError::ValidationError(
+                "Harga emas per gram harus lebih besar dari 0".to_string(),
+            ));
+        }
+    }
+    if let Some(grams) = payload.nisab_grams {
+        if grams <= 0.0 || grams > MAX_NISAB_GRAMS || !grams.is_finite() {
+            return Err(AppError::ValidationError(format!(
+                "Nisab harus antara 0 dan {} gram",
+                MAX_NISAB_GRAMS
+            )));
+        }
+    }
+
+    let existing = db::get_zakat_settings(&state.db, user_id).await?;
+    let gold_price_per_gram = payload
+        .gold_price_per_gram
+        .or(existing.as_ref().map(|settings| settings.gold_price_per_gram))
+        .ok_or_else(|| AppError::ValidationError("Harga emas per gram wajib diisi".to_string()))?;
+
+    let settings = sqlx::query_as::<_, ZakatSettings>(
+        r#"
+        INSERT INTO zakat_settings (user_id, gold_price_per_gram, nisab_grams, include_receivables, deduct_liabilities)
+        VALUES ($1, $2, COALESCE($3, $6), COALESCE($4, TRUE), COALESCE($5, TRUE))
+        ON CONFLICT (user_id) DO UPDATE SET
+            gold_price_per_gram = EXCLUDED.gold_price_per_gram,
+            nisab_grams = COALESCE($3, zakat_settings.nisab_grams),
+            include_receivables = COALESCE($4, zakat_settings.include_receivables),
+            deduct_liabilities = COALESCE($5, zakat_settings.deduct_liabilities)
+        RETURNING user_id, gold_price_per_gram, nisab_grams, include_receivables, deduct_liabilities, created_at, updated_at
+        "#
+    )
+    .bind(user_id)
+    .bind(gold_price_per_gram)
+    .bind(payload.nisab_grams)
+    .bind(payload.include_receivables)
+    .bind(payload.deduct_liabilities)
+    .bind(DEFAULT_NISAB_GRAMS)
+    .fetch_one(&state.db)
+    .await?;
+
+    let nisab = settings.nisab();
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Pengaturan zakat berhasil disimpan",
+        "data": settings,
+        "meta": {
+            "nisab": nisab
+        }
+    })))
+}
+
+// Whether wealth is above nisab today, how long it has stayed there and the zakat due once it
+// has for a full haul
+pub async fn get_zakat_status(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+    let settings = require_settings(&state, user_id).await?;
+
+    // Make today's point reflect the live balances instead of the last hourly snapshot
+    let today = db::get_user_today(&state.db, user_id).await?;
+    db::snapshot_wallet_balances(&state.db, Some(user_id), Some(today)).await?;
+
+    let (wealth, haul, first_snapshot_date) = evaluate(&state, user_id, &settings, today).await?;
+    let current = wealth
+        .last()
+        .cloned()
+        .ok_or_else(|| AppError::InternalError("Riwayat saldo kosong".to_string()))?;
+    let nisab = settings.nisab();
+    let above_nisab = current.total >= nisab;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "date": today,
+            "gold_price_per_gram": settings.gold_price_per_gram,
+            "nisab_grams": settings.nisab_grams,
+            "nisab": nisab,
+            "wealth": current,
+            "above_nisab": above_nisab,
+            "haul": haul,
+            "zakat_rate": ZAKAT_RATE,
+            // Payable now only once the haul is complete
+            "zakat_due": if haul.status == "complete" { zakat_amount(current.total) } else { 0.0 },
+            "estimated_zakat": if above_nisab { zakat_amount(current.total) } else { 0.0 },
+            "summary": haul_summary(&haul, current.total, nisab)
+        },
+        "meta": {
+            // Days before this date have no balance data; POST /api/dashboard/net-worth/backfill fills them
+            "first_snapshot_date": first_snapshot_date
+        }
+    })))
+}
+
+// Month-end wealth for a calendar year, the haul as of the year's end (or today) and the
+// zakat recorded as paid during the year. Nisab uses the current gold price throughout.
+pub async fn get_zakat_report(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<ZakatReportQuery>,
+) -> Result<Json<Value>, AppError> {
+    let user_id = get_user_id(&state, &headers).await?;
+    let settings = require_settings(&state, user_id).await?;
+
+    let today = db::get_user_today(&state.db, user_id).await?;
+    let year = params.year.unwrap_or(today.year());
+    if !(MIN_REPORT_YEAR..=today.year()).contains(&year) {
+        return Err(AppError::ValidationError(format!(
+            "Tahun harus antara {} dan {}",
+            MIN_REPORT_YEAR,
+            today.year()
+        )));
+    }
+
+    let start_date = day_of_month(year, 1, 1);
+    let as_of = day_of_month(year, 12, 31).min(today);
+    if as_of == today {
+        db::snapshot_wallet_balances(&state.db, Some(user_id), Some(today)).await?;
+    }
+
+    let nisab = settings.nisab();
+    let month_ends: Vec<NaiveDate> = (1..=as_of.month())
+        .map(|month| day_of_month(year, month, 31).min(as_of))
+        .collect();
+    let months: Vec<Value> = db::get_net_worth_history(&state.db, user_id, &month_ends)
+        .await?
+        .iter()
+        .map(|point| {
+            let wealth = settings.zakatable_wealth(point);
+            json!({
+                "month": point.date.month(),
+                "date": point.date,
+                "wealth": wealth.total,
+                "above_nisab": wealth.total >= nisab
+            })
+        })
+        .collect();
+
+    let (wealth, haul, first_snapshot_date) = evaluate(&state, user_id, &settings, as_of).await?;
+    let current = wealth
+        .last()
+        .cloned()
+        .ok_or_else(|| AppError::InternalError("Riwayat saldo kosong".to_string()))?;
+    let zakat_due = if haul.status == "complete" { zakat_amount(current.total) } else { 0.0 };
+    let zakat_paid = db::get_zakat_paid(&state.db, user_id, start_date, as_of).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "year": year,
+            "start_date": start_date,
+            "end_date": as_of,
+            "gold_price_per_gram": settings.gold_price_per_gram,
+            "nisab_grams": settings.nisab_grams,
+            "nisab": nisab,
+            "months": months,
+            "wealth": current,
+            "haul": haul,
+            "zakat_due": zakat_due,
+            // Expenses in a zakat category or mentioning zakat in the description
+            "zakat_paid": zakat_paid,
+            "outstanding": (zakat_due - zakat_paid).max(0.0),
+            "summary": haul_summary(&haul, current.total, nisab)
+        },
+        "meta": {
+            "first_snapshot_date": first_snapshot_date
+        }
+    })))
+}
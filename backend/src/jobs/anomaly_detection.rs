@@ -0,0 +1,244 @@
+// Background job that looks for unusual spending among recently added expenses and leaves a
+// notification for users who have anomaly alerts turned on
+
+use chrono::{Datelike, Duration as DateDuration, NaiveDate};
+use serde_json::json;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::db;
+use crate::models::anomaly::{
+    AnomalyReport, CategoryAnomaly, TransactionAnomaly, DEFAULT_ANOMALY_HISTORY_MONTHS,
+    DEFAULT_ANOMALY_THRESHOLD,
+};
+use crate::utils::dates::{parse_timezone, shift_months, today_in, DEFAULT_TIMEZONE};
+use crate::utils::format::format_rupiah;
+use crate::utils::stats::RobustStats;
+
+const DETECTION_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+// Expenses added within this many hours are checked on each run
+const RECENT_HOURS: i32 = 12;
+// Days of expenses the job checks for each user
+const NOTIFY_WINDOW_DAYS: i64 = 7;
+// Fewer past transactions (or months with spending) than this is too little to judge
+const MIN_TRANSACTION_HISTORY: usize = 5;
+const MIN_CATEGORY_MONTHS: usize = 3;
+
+fn month_index(date: NaiveDate) -> i32 {
+    date.year() * 12 + date.month0() as i32
+}
+
+fn round_score(score: f64) -> f64 {
+    (score * 100.0).round() / 100.0
+}
+
+pub fn spawn(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(DETECTION_INTERVAL);
+        loop {
+            interval.tick().await;
+            match notify_anomalies(&pool).await {
+                Ok(0) => {}
+                Ok(count) => tracing::info!("🔎 Anomaly detection: {} notifications", count),
+                Err(e) => tracing::error!("❌ Anomaly detection failed: {:?}", e),
+            }
+        }
+    });
+}
+
+// Expenses between start_date and end_date that stand out against the same category's history
+// over the `months` months before. Transactions are compared with the category's single
+// transactions, and each month's category total with the monthly totals before it.
+pub async fn find_anomalies(
+    pool: &PgPool,
+    user_id: Uuid,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    months: i32,
+    threshold: f64,
+) -> Result<AnomalyReport, sqlx::Error> {
+    let history_start = shift_months(start_date.with_day(1).unwrap(), -months, 1);
+
+    let rows = db::get_expenses(pool, user_id, history_start, end_date).await?;
+
+    let mut report = AnomalyReport::default();
+
+    // Single transactions against the category's transactions before the window
+    let mut history: HashMap<Option<Uuid>, Vec<f64>> = HashMap::new();
+    for row in rows.iter().filter(|row| row.date < start_date) {
+        history.entry(row.category_id).or_default().push(row.amount);
+    }
+
+    for row in rows.iter().filter(|row| row.date >= start_date) {
+        let Some(amounts) = history.get(&row.category_id) else { continue };
+        if amounts.len() < MIN_TRANSACTION_HISTORY {
+            continue;
+        }
+        let Some(stats) = RobustStats::new(amounts) else { continue };
+
+        let score = stats.score(row.amount);
+        if score >= threshold {
+            report.transactions.push(TransactionAnomaly {
+                transaction_id: row.id,
+                wallet_id: row.wallet_id,
+                category_id: row.category_id,
+                category_name: row.category_name.clone(),
+                description: row.description.clone(),
+                date: row.date,
+                amount: row.amount,
+                typical_amount: stats.median,
+                score: round_score(score),
+                history_count: amounts.len(),
+            });
+        }
+    }
+
+    // Monthly category totals; the last month may still be in progress
+    let mut totals: HashMap<(Option<Uuid>, i32), f64> = HashMap::new();
+    let mut names: HashMap<Option<Uuid>, Option<String>> = HashMap::new();
+    for row in &rows {
+        *totals.entry((row.category_id, month_index(row.date))).or_default() += row.amount;
+        names.entry(row.category_id).or_insert_with(|| row.category_name.clone());
+    }
+
+    for (category_id, category_name) in &names {
+        for month in month_index(start_date)..=month_index(end_date) {
+            let Some(&total) = totals.get(&(*category_id, month)) else { continue };
+
+            // Months without spending count as zero once the category has enough history
+            let previous: Vec<f64> = (month - months..month)
+                .map(|past| totals.get(&(*category_id, past)).copied().unwrap_or(0.0))
+                .collect();
+            if previous.iter().filter(|amount| **amount > 0.0).count() < MIN_CATEGORY_MONTHS {
+                continue;
+            }
+            let Some(stats) = RobustStats::new(&previous) else { continue };
+
+            let score = stats.score(total);
+            if score >= threshold {
+                report.categories.push(CategoryAnomaly {
+                    category_id: *category_id,
+                    category_name: category_name.clone(),
+                    year: month.div_euclid(12),
+                    month: month.rem_euclid(12) + 1,
+                    total,
+                    typical_total: stats.median,
+                    score: round_score(score),
+                });
+            }
+        }
+    }
+
+    report.transactions.sort_by(|a, b| b.score.total_cmp(&a.score));
+    report.categories.sort_by(|a, b| b.score.total_cmp(&a.score));
+    Ok(report)
+}
+
+// Notifies users with recent expenses about anomalies in their last few days. Returns the
+// number of new notifications.
+async fn notify_anomalies(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let users: Vec<(Uuid, String)> = sqlx::query_as(
+        r#"
+        SELECT DISTINCT u.id, u.timezone
+        FROM users u
+        JOIN transactions t ON t.user_id = u.id
+        WHERE u.anomaly_alerts
+            AND t.deleted_at IS NULL
+            AND NOT t.is_transfer
+            AND t.transaction_type = 'expense'
+            AND t.created_at > NOW() - make_interval(hours => $1)
+        "#
+    )
+    .bind(RECENT_HOURS)
+    .fetch_all(pool)
+    .await?;
+
+    let mut created = 0;
+    for (user_id, timezone) in users {
+        let timezone = parse_timezone(&timezone)
+            .or_else(|| parse_timezone(DEFAULT_TIMEZONE))
+            .unwrap();
+        let today = today_in(timezone);
+        let start_date = today - DateDuration::days(NOTIFY_WINDOW_DAYS - 1);
+
+        // One user's failure should not hold back everyone else's alerts
+        let report = match find_anomalies(
+            pool,
+            user_id,
+            start_date,
+            today,
+            DEFAULT_ANOMALY_HISTORY_MONTHS,
+            DEFAULT_ANOMALY_THRESHOLD,
+        )
+        .await
+        {
+            Ok(report) => report,
+            Err(e) => {
+                tracing::error!("❌ Anomaly detection for user {} failed: {:?}", user_id, e);
+                continue;
+            }
+        };
+
+        for anomaly in &report.transactions {
+            let category = anomaly.category_name.as_deref().unwrap_or("tanpa kategori");
+            let body = format!(
+                "{} sebesar {} jauh di atas biasanya untuk {} (biasanya sekitar {})",
+                anomaly.description.as_deref().unwrap_or("Transaksi"),
+                format_rupiah(anomaly.amount),
+                category,
+                format_rupiah(anomaly.typical_amount)
+            );
+            let dedupe_key = format!("anomaly:transaction:{}", anomaly.transaction_id);
+
+            if db::insert_notification(
+                pool,
+                user_id,
+                "spending_anomaly",
+                "Pengeluaran tidak biasa",
+                &body,
+                Some(json!(anomaly)),
+                Some(&dedupe_key),
+            )
+            .await?
+            {
+                created += 1;
+            }
+        }
+
+        for anomaly in &report.categories {
+            let category = anomaly.category_name.as_deref().unwrap_or("tanpa kategori");
+            let body = format!(
+                "Pengeluaran {} bulan {}/{} sudah {}, biasanya sekitar {} per bulan",
+                category,
+                anomaly.month,
+                anomaly.year,
+                format_rupiah(anomaly.total),
+                format_rupiah(anomaly.typical_total)
+            );
+            let dedupe_key = format!(
+                "anomaly:category:{}:{}-{:02}",
+                anomaly.category_id.map(|id| id.to_string()).unwrap_or_default(),
+                anomaly.year,
+                anomaly.month
+            );
+
+            if db::insert_notification(
+                pool,
+                user_id,
+                "spending_anomaly",
+                "Pengeluaran kategori melonjak",
+                &body,
+                Some(json!(anomaly)),
+                Some(&dedupe_key),
+            )
+            .await?
+            {
+                created += 1;
+            }
+        }
+    }
+
+    Ok(created)
+}
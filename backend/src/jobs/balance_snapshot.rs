@@ -0,0 +1,22 @@
+// Background job that records every wallet's balance once an hour as the balance of the
+// owner's current day, so the last run of a day leaves that day's closing balance
+
+use sqlx::PgPool;
+use std::time::Duration;
+
+use crate::db;
+
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+pub fn spawn(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SNAPSHOT_INTERVAL);
+        loop {
+            interval.tick().await;
+            match db::snapshot_wallet_balances(&pool, None, None).await {
+                Ok(count) => tracing::debug!("📸 Balance snapshot: {} wallets", count),
+                Err(e) => tracing::error!("❌ Balance snapshot failed: {:?}", e),
+            }
+        }
+    });
+}
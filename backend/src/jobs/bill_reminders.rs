@@ -0,0 +1,102 @@
+// Background job that reminds users about bills that are due soon or overdue
+
+use chrono::NaiveDate;
+use serde_json::json;
+use sqlx::{FromRow, PgPool};
+use std::time::Duration;
+
+use crate::db;
+use crate::models::bill::Bill;
+use crate::utils::format::format_rupiah;
+
+const REMINDER_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, FromRow)]
+struct OpenBill {
+    #[sqlx(flatten)]
+    bill: Bill,
+    today: NaiveDate,
+}
+
+pub fn spawn(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(REMINDER_INTERVAL);
+        loop {
+            interval.tick().await;
+            match notify_bills(&pool).await {
+                Ok(0) => {}
+                Ok(count) => tracing::info!("🧾 Bill reminders: {} notifications", count),
+                Err(e) => tracing::error!("❌ Bill reminders failed: {:?}", e),
+            }
+        }
+    });
+}
+
+// One notification per due date when it enters the reminder window and another once it is
+// overdue. Returns the number of new notifications.
+async fn notify_bills(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let query = format!(
+        r#"
+        SELECT b.id, b.user_id, b.payee, b.description, b.amount, b.amount_min, b.amount_max, b.wallet_id,
+               b.category_id, b.frequency, b.interval_count, b.anchor_day, b.next_due_date,
+               b.remind_days_before, b.subscription_key, b.created_at, b.updated_at, b.deleted_at,
+               {today} AS today
+        FROM bills b
+        JOIN users u ON u.id = b.user_id
+        WHERE b.deleted_at IS NULL
+            AND b.next_due_date <= {today} + b.remind_days_before
+        "#,
+        today = db::USER_TODAY_SQL
+    );
+
+    let bills = sqlx::query_as::<_, OpenBill>(&query).fetch_all(pool).await?;
+
+    let mut created = 0;
+    for OpenBill { bill, today } in &bills {
+        let Some(due_date) = bill.next_due_date else { continue };
+        let amount = match bill.amount {
+            Some(amount) => format_rupiah(amount),
+            None => format!("sekitar {}", format_rupiah(bill.expected_amount())),
+        };
+
+        let (title, body, stage) = if due_date < *today {
+            (
+                "Tagihan terlambat",
+                format!(
+                    "Tagihan {} sebesar {} sudah lewat jatuh tempo {}",
+                    bill.payee,
+                    amount,
+                    due_date.format("%d/%m/%Y")
+                ),
+                "overdue",
+            )
+        } else {
+            let when = match (due_date - *today).num_days() {
+                0 => "hari ini".to_string(),
+                1 => "besok".to_string(),
+                days => format!("{} hari lagi ({})", days, due_date.format("%d/%m/%Y")),
+            };
+            (
+                "Tagihan segera jatuh tempo",
+                format!("Tagihan {} sebesar {} jatuh tempo {}", bill.payee, amount, when),
+                "due_soon",
+            )
+        };
+
+        if db::insert_notification(
+            pool,
+            bill.user_id,
+            "bill_reminder",
+            title,
+            &body,
+            Some(json!({ "bill_id": bill.id, "due_date": due_date, "status": stage })),
+            Some(&format!("bill:{}:{}:{}", bill.id, due_date, stage)),
+        )
+        .await?
+        {
+            created += 1;
+        }
+    }
+
+    Ok(created)
+}
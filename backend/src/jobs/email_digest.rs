@@ -0,0 +1,200 @@
+// Background job that emails weekly and monthly summaries to users who subscribed to them
+
+use chrono::{Datelike, Duration as ChronoDuration, NaiveDate, NaiveDateTime, Timelike};
+use sqlx::{FromRow, PgPool};
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    db,
+    error::AppError,
+    mailer::{Email, EmailAttachment, Mailer},
+    models::{
+        digest::{digest_period, Digest, DigestPreferences, DIGEST_FREQUENCIES, DIGEST_TOP_CATEGORIES},
+        statement::{category_breakdown, StatementBudget},
+        user::User,
+    },
+    utils::dates::shift_months,
+};
+
+const DIGEST_INTERVAL: Duration = Duration::from_secs(60 * 60);
+// Digests go out from this local hour on the first days after their period ends
+const SEND_HOUR: u32 = 7;
+const SEND_WINDOW_DAYS: i64 = 3;
+
+#[derive(Debug, FromRow)]
+struct DigestCandidate {
+    #[sqlx(flatten)]
+    user: User,
+    local_now: NaiveDateTime,
+    weekly: bool,
+    monthly: bool,
+}
+
+pub fn spawn(pool: PgPool, mailer: Arc<dyn Mailer>, public_url: String) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(DIGEST_INTERVAL);
+        loop {
+            interval.tick().await;
+            match send_due_digests(&pool, mailer.as_ref(), &public_url).await {
+                Ok(0) => {}
+                Ok(count) => tracing::info!("📧 Email digests: {} sent", count),
+                Err(e) => tracing::error!("❌ Email digests failed: {:?}", e),
+            }
+        }
+    });
+}
+
+// Sends every digest whose period has ended and that has not been sent yet. A digest that
+// fails to send is retried on the next run. Returns the number sent.
+async fn send_due_digests(pool: &PgPool, mailer: &dyn Mailer, public_url: &str) -> Result<u64, sqlx::Error> {
+    let candidates = sqlx::query_as::<_, DigestCandidate>(
+        r#"
+        SELECT u.id, u.email, u.username, u.name, u.password_hash, u.timezone, u.anomaly_alerts,
+               u.created_at, u.updated_at,
+               (NOW() AT TIME ZONE u.timezone) AS local_now,
+               COALESCE(p.weekly, FALSE) AS weekly,
+               COALESCE(p.monthly, TRUE) AS monthly
+        FROM users u
+        LEFT JOIN digest_preferences p ON p.user_id = u.id
+        WHERE COALESCE(p.weekly, FALSE) OR COALESCE(p.monthly, TRUE)
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut sent = 0;
+    for candidate in &candidates {
+        if candidate.local_now.hour() < SEND_HOUR {
+            continue;
+        }
+        let today = candidate.local_now.date();
+        let user = &candidate.user;
+
+        for frequency in DIGEST_FREQUENCIES {
+            let enabled = if frequency == "weekly" { candidate.weekly } else { candidate.monthly };
+            let (start_date, end_date) = digest_period(frequency, today);
+            if !enabled
+                || (today - end_date).num_days() > SEND_WINDOW_DAYS
+                || end_date < user.created_at.date_naive()
+            {
+                continue;
+            }
+            if !db::claim_digest_delivery(pool, user.id, frequency, start_date, end_date).await? {
+                continue;
+            }
+
+            let preferences = db::ensure_digest_preferences(pool, user.id).await?;
+            let result = match build_digest_email(pool, user, &preferences, frequency, start_date, end_date, public_url).await {
+                Ok(email) => mailer.send(&email).await.map_err(|e| e.to_string()),
+                Err(e) => Err(format!("{:?}", e)),
+            };
+            match result {
+                Ok(()) => sent += 1,
+                Err(e) => {
+                    tracing::error!("❌ {} digest for user {} failed: {}", frequency, user.id, e);
+                    db::release_digest_delivery(pool, user.id, frequency, start_date).await?;
+                }
+            }
+        }
+    }
+
+    Ok(sent)
+}
+
+fn previous_period(frequency: &str, start_date: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let previous_start = if frequency == "weekly" {
+        start_date - ChronoDuration::days(7)
+    } else {
+        shift_months(start_date, -1, 1)
+    };
+    (previous_start, start_date - ChronoDuration::days(1))
+}
+
+// Income and expense totals on active wallets, the same figures as the cash flow report
+async fn period_totals(
+    pool: &PgPool,
+    user_id: uuid::Uuid,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<(f64, f64, i64), sqlx::Error> {
+    let flow = db::get_cash_flow(pool, user_id, start_date, end_date, ("month", "1 month"), None, None).await?;
+    Ok(flow.iter().fold((0.0, 0.0, 0), |(income, expense, count), bucket| {
+        (income + bucket.income, expense + bucket.expense, count + bucket.transaction_count)
+    }))
+}
+
+// The digest for one period; monthly digests carry that month's statement as a PDF
+pub async fn build_digest_email(
+    pool: &PgPool,
+    user: &User,
+    preferences: &DigestPreferences,
+    frequency: &str,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    public_url: &str,
+) -> Result<Email, AppError> {
+    let (income, expense, transaction_count) = period_totals(pool, user.id, start_date, end_date).await?;
+    let (previous_start, previous_end) = previous_period(frequency, start_date);
+    let (_, previous_expense, _) = period_totals(pool, user.id, previous_start, previous_end).await?;
+    let closing_balance = db::get_balance_before(pool, user.id, None, end_date + ChronoDuration::days(1)).await?;
+
+    let mut top_categories = category_breakdown(
+        db::get_category_totals(pool, user.id, start_date, Some(end_date), "expense", None).await?,
+        expense,
+    );
+    top_categories.sort_by(|a, b| b.total.total_cmp(&a.total));
+    top_categories.truncate(DIGEST_TOP_CATEGORIES);
+
+    let budget_year = end_date.year();
+    let budget_month = end_date.month();
+    let budgets: Vec<StatementBudget> =
+        db::get_user_budgets_with_usage(pool, user.id, Some(budget_month as i32), Some(budget_year))
+            .await?
+            .into_iter()
+            .filter(|row| row.budget.is_active)
+            .map(StatementBudget::from)
+            .collect();
+
+    let digest = Digest {
+        frequency: frequency.to_string(),
+        user_name: user.name.clone(),
+        start_date,
+        end_date,
+        income,
+        expense,
+        transaction_count,
+        previous_expense,
+        closing_balance,
+        top_categories,
+        budget_year,
+        budget_month,
+        budgets,
+        unsubscribe_url: format!(
+            "{}/api/digests/unsubscribe/{}?frequency={}",
+            public_url, preferences.unsubscribe_token, frequency
+        ),
+    };
+
+    let mut attachments = Vec::new();
+    if frequency == "monthly" {
+        let statement = db::get_monthly_statement(pool, user, budget_year, budget_month).await?;
+        let pdf = statement
+            .to_pdf()
+            .map_err(|e| AppError::InternalError(format!("Gagal membuat PDF: {}", e)))?;
+        attachments.push(EmailAttachment {
+            filename: statement.file_name("pdf"),
+            content_type: "application/pdf".to_string(),
+            data: pdf,
+        });
+    }
+
+    Ok(Email {
+        to: user.email.clone(),
+        to_name: Some(user.name.clone()),
+        subject: digest.subject(),
+        text: digest.to_text(),
+        html: digest.to_html(),
+        attachments,
+        unsubscribe_url: Some(digest.unsubscribe_url.clone()),
+    })
+}
@@ -0,0 +1,155 @@
+// Background job that books installments (cicilan) as expense transactions on their credit
+// wallet once their due date arrives
+
+use chrono::NaiveDate;
+use sqlx::{FromRow, PgConnection, PgPool};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::db;
+use crate::models::audit::AuditContext;
+use crate::models::transaction::NewTransaction;
+
+const POSTING_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, FromRow)]
+struct DueInstallment {
+    id: Uuid,
+    installment_number: i32,
+    due_date: NaiveDate,
+    amount: f64,
+    user_id: Uuid,
+    wallet_id: Uuid,
+    category_id: Option<Uuid>,
+    description: String,
+    tenor_months: i32,
+}
+
+pub fn spawn(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(POSTING_INTERVAL);
+        loop {
+            interval.tick().await;
+            match post_all_due_installments(&pool).await {
+                Ok(0) => {}
+                Ok(posted) => tracing::info!("💳 Installment posting: {} installments booked", posted),
+                Err(e) => tracing::error!("❌ Installment posting failed: {:?}", e),
+            }
+        }
+    });
+}
+
+// Installments not booked yet that are due on or before $1 (by default the owner's current day
+// in their time zone), optionally limited to plan $2. Plans that were cancelled and wallets that
+// were deleted are skipped.
+fn due_installments_sql() -> String {
+    format!(
+        r#"
+        FROM installments i
+        JOIN installment_plans p ON p.id = i.plan_id
+        JOIN wallets w ON w.id = p.wallet_id
+        JOIN users u ON u.id = p.user_id
+        LEFT JOIN categories c ON c.id = p.category_id AND c.deleted_at IS NULL
+        WHERE i.posted_at IS NULL
+            AND i.due_date <= COALESCE($1::date, {})
+            AND ($2::uuid IS NULL OR p.id = $2)
+            AND p.deleted_at IS NULL
+            AND w.deleted_at IS NULL
+        "#,
+        db::USER_TODAY_SQL
+    )
+}
+
+// Books every due installment of every user, one plan per transaction so a plan that fails
+// is logged and retried next run without holding back the others. Returns the number booked.
+async fn post_all_due_installments(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let query = format!("SELECT DISTINCT p.id {}", due_installments_sql());
+    let plan_ids: Vec<Uuid> = sqlx::query_scalar(&query)
+        .bind(None::<NaiveDate>)
+        .bind(None::<Uuid>)
+        .fetch_all(pool)
+        .await?;
+
+    let mut posted = 0;
+    for plan_id in plan_ids {
+        let result = async {
+            let mut tx = pool.begin().await?;
+            let count = post_installments(&mut tx, Some(plan_id), None).await?;
+            tx.commit().await?;
+            Ok::<_, sqlx::Error>(count)
+        }
+        .await;
+
+        match result {
+            Ok(count) => posted += count,
+            Err(e) => tracing::error!("❌ Installment posting for plan {} failed: {:?}", plan_id, e),
+        }
+    }
+
+    Ok(posted)
+}
+
+// Books the installments of one plan that are due on or before `today` (by default the owner's
+// current day in their time zone). Returns the number of installments booked.
+pub async fn post_plan_installments(
+    conn: &mut PgConnection,
+    plan_id: Uuid,
+    today: Option<NaiveDate>,
+) -> Result<u64, sqlx::Error> {
+    post_installments(conn, Some(plan_id), today).await
+}
+
+async fn post_installments(
+    conn: &mut PgConnection,
+    plan_id: Option<Uuid>,
+    today: Option<NaiveDate>,
+) -> Result<u64, sqlx::Error> {
+    // SKIP LOCKED lets a request-triggered run and the hourly run overlap without double booking
+    let query = format!(
+        r#"
+        SELECT i.id, i.installment_number, i.due_date, i.amount,
+               p.user_id, p.wallet_id, c.id AS category_id, p.description, p.tenor_months
+        {}
+        ORDER BY i.due_date, i.installment_number
+        FOR UPDATE OF i SKIP LOCKED
+        "#,
+        due_installments_sql()
+    );
+
+    let due = sqlx::query_as::<_, DueInstallment>(&query)
+        .bind(today)
+        .bind(plan_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+    for installment in &due {
+        let audit = AuditContext::system(installment.user_id);
+
+        let description = format!(
+            "Cicilan {}/{}: {}",
+            installment.installment_number, installment.tenor_months, installment.description
+        );
+        let transaction = db::record_wallet_transaction(
+            &mut *conn,
+            &audit,
+            &NewTransaction {
+                wallet_id: installment.wallet_id,
+                category_id: installment.category_id,
+                transaction_type: "expense",
+                amount: installment.amount,
+                description: &description,
+                date: installment.due_date,
+                is_transfer: false,
+            },
+        )
+        .await?;
+
+        sqlx::query(r#"UPDATE installments SET posted_at = NOW(), transaction_id = $1 WHERE id = $2"#)
+            .bind(transaction.id)
+            .bind(installment.id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(due.len() as u64)
+}
@@ -0,0 +1,7 @@
+pub mod anomaly_detection;
+pub mod balance_snapshot;
+pub mod bill_reminders;
+pub mod email_digest;
+pub mod installment_posting;
+pub mod recurring_posting;
+pub mod trash_purge;
@@ -0,0 +1,186 @@
+// Background job that books recurring transactions once their date arrives, or sends a
+// reminder for schedules that are not booked automatically
+
+use chrono::NaiveDate;
+use serde_json::json;
+use sqlx::{FromRow, PgConnection, PgPool};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::db;
+use crate::models::audit::AuditContext;
+use crate::models::recurring::RecurringTransaction;
+use crate::models::transaction::NewTransaction;
+use crate::utils::format::format_rupiah;
+
+const POSTING_INTERVAL: Duration = Duration::from_secs(60 * 60);
+// Occurrences booked per schedule in one run, in case a start date lies far in the past
+const MAX_OCCURRENCES_PER_RUN: usize = 120;
+
+#[derive(Debug, FromRow)]
+struct DueRecurring {
+    #[sqlx(flatten)]
+    recurring: RecurringTransaction,
+    today: NaiveDate,
+}
+
+pub fn spawn(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(POSTING_INTERVAL);
+        loop {
+            interval.tick().await;
+            match post_all_due_recurring(&pool).await {
+                Ok(0) => {}
+                Ok(posted) => tracing::info!("🔁 Recurring posting: {} occurrences", posted),
+                Err(e) => tracing::error!("❌ Recurring posting failed: {:?}", e),
+            }
+        }
+    });
+}
+
+// Schedules with an occurrence due on or before $1 (by default the owner's current day in
+// their time zone), optionally limited to user $2 or schedule $3. Schedules on deleted wallets
+// are skipped.
+fn due_recurring_sql() -> String {
+    format!(
+        r#"
+        FROM recurring_transactions r
+        JOIN wallets w ON w.id = r.wallet_id
+        JOIN users u ON u.id = r.user_id
+        LEFT JOIN categories c ON c.id = r.category_id AND c.deleted_at IS NULL
+        WHERE r.deleted_at IS NULL
+            AND w.deleted_at IS NULL
+            AND r.next_date <= COALESCE($1::date, {today})
+            AND (r.end_date IS NULL OR r.next_date <= r.end_date)
+            AND ($2::uuid IS NULL OR r.user_id = $2)
+            AND ($3::uuid IS NULL OR r.id = $3)
+        "#,
+        today = db::USER_TODAY_SQL
+    )
+}
+
+// Handles the due occurrences of every user, one schedule per transaction so a schedule that
+// fails is logged and retried next run without holding back the others. Returns the number of
+// occurrences handled.
+async fn post_all_due_recurring(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let query = format!("SELECT r.id {}", due_recurring_sql());
+    let recurring_ids: Vec<Uuid> = sqlx::query_scalar(&query)
+        .bind(None::<NaiveDate>)
+        .bind(None::<Uuid>)
+        .bind(None::<Uuid>)
+        .fetch_all(pool)
+        .await?;
+
+    let mut handled = 0;
+    for recurring_id in recurring_ids {
+        let result = async {
+            let mut tx = pool.begin().await?;
+            let count = post_recurring(&mut tx, None, Some(recurring_id), None).await?;
+            tx.commit().await?;
+            Ok::<_, sqlx::Error>(count)
+        }
+        .await;
+
+        match result {
+            Ok(count) => handled += count,
+            Err(e) => tracing::error!("❌ Recurring posting for schedule {} failed: {:?}", recurring_id, e),
+        }
+    }
+
+    Ok(handled)
+}
+
+// Handles every occurrence due on or before `today` (by default the owner's current day in
+// their time zone), for one user or everyone: books it as a transaction, or writes a reminder
+// notification when auto_post is off. Returns the number of occurrences handled.
+pub async fn post_due_recurring(
+    conn: &mut PgConnection,
+    user_id: Option<Uuid>,
+    today: Option<NaiveDate>,
+) -> Result<u64, sqlx::Error> {
+    post_recurring(conn, user_id, None, today).await
+}
+
+async fn post_recurring(
+    conn: &mut PgConnection,
+    user_id: Option<Uuid>,
+    recurring_id: Option<Uuid>,
+    today: Option<NaiveDate>,
+) -> Result<u64, sqlx::Error> {
+    // SKIP LOCKED lets a request-triggered run and the hourly run overlap without double booking
+    let query = format!(
+        r#"
+        SELECT r.id, r.user_id, r.wallet_id, c.id AS category_id, r.transaction_type, r.amount,
+               r.description, r.frequency, r.interval_count, r.anchor_day, r.next_date, r.end_date,
+               r.auto_post, r.subscription_key, r.created_at, r.updated_at, r.deleted_at,
+               COALESCE($1::date, {today}) AS today
+        {from}
+        FOR UPDATE OF r SKIP LOCKED
+        "#,
+        today = db::USER_TODAY_SQL,
+        from = due_recurring_sql()
+    );
+
+    let due = sqlx::query_as::<_, DueRecurring>(&query)
+        .bind(today)
+        .bind(user_id)
+        .bind(recurring_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+    let mut handled = 0;
+    for DueRecurring { recurring, today } in &due {
+        let audit = AuditContext::system(recurring.user_id);
+        let mut date = recurring.next_date;
+
+        for _ in 0..MAX_OCCURRENCES_PER_RUN {
+            if date > *today || recurring.end_date.is_some_and(|end_date| date > end_date) {
+                break;
+            }
+
+            if recurring.auto_post {
+                db::record_wallet_transaction(
+                    &mut *conn,
+                    &audit,
+                    &NewTransaction {
+                        wallet_id: recurring.wallet_id,
+                        category_id: recurring.category_id,
+                        transaction_type: &recurring.transaction_type,
+                        amount: recurring.amount,
+                        description: &recurring.description,
+                        date,
+                        is_transfer: false,
+                    },
+                )
+                .await?;
+            } else {
+                db::insert_notification(
+                    &mut *conn,
+                    recurring.user_id,
+                    "recurring_reminder",
+                    "Pengingat transaksi rutin",
+                    &format!(
+                        "{} sebesar {} jatuh tempo {}",
+                        recurring.description,
+                        format_rupiah(recurring.amount),
+                        date.format("%d/%m/%Y")
+                    ),
+                    Some(json!({ "recurring_id": recurring.id, "date": date })),
+                    Some(&format!("recurring:{}:{}", recurring.id, date)),
+                )
+                .await?;
+            }
+
+            handled += 1;
+            date = recurring.occurrence_after(date);
+        }
+
+        sqlx::query(r#"UPDATE recurring_transactions SET next_date = $1 WHERE id = $2"#)
+            .bind(date)
+            .bind(recurring.id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(handled)
+}
@@ -0,0 +1,307 @@
+// Background job that permanently removes soft-deleted rows once they are older than the
+// configured retention period
+
+use sqlx::PgPool;
+use std::time::Duration;
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Default)]
+pub struct PurgeResult {
+    pub transactions: u64,
+    pub budgets: u64,
+    pub categories: u64,
+    pub wallets: u64,
+    pub debts: u64,
+    pub installment_plans: u64,
+    pub goals: u64,
+    pub holdings: u64,
+    pub recurring_transactions: u64,
+    pub bills: u64,
+}
+
+pub fn spawn(pool: PgPool, retention_days: i64) {
+    if retention_days <= 0 {
+        tracing::info!("🗑️ Trash purge disabled (TRASH_RETENTION_DAYS = {})", retention_days);
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PURGE_INTERVAL);
+        loop {
+            interval.tick().await;
+            match purge_expired(&pool, retention_days).await {
+                Ok(result) => tracing::info!(
+                    "🗑️ Trash purge: {} transactions, {} budgets, {} categories, {} wallets, {} debts, {} installment plans, {} goals, {} holdings, {} recurring transactions, {} bills removed",
+                    result.transactions,
+                    result.budgets,
+                    result.categories,
+                    result.wallets,
+                    result.debts,
+                    result.installment_plans,
+                    result.goals,
+                    result.holdings,
+                    result.recurring_transactions,
+                    result.bills
+                ),
+                Err(e) => tracing::error!("❌ Trash purge failed: {:?}", e),
+            }
+        }
+    });
+}
+
+pub async fn purge_expired(pool: &PgPool, retention_days: i64) -> Result<PurgeResult, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    // Each purge leaves a final audit entry, so the history endpoints keep working. Counts
+    // come from the audit inserts, which add one row per deleted row.
+
+    // Balances were already reversed when these were soft-deleted
+    let transactions = sqlx::query(
+        r#"
+        WITH purged AS (
+            DELETE FROM transactions t WHERE t.deleted_at < NOW() - make_interval(days => $1)
+            RETURNING t.user_id, t.id, to_jsonb(t) AS before_data
+        )
+        INSERT INTO audit_logs (user_id, source_channel, entity_type, entity_id, action, before_data)
+        SELECT user_id, 'system', 'transaction', id, 'purge', before_data FROM purged
+        "#
+    )
+    .bind(retention_days as i32)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    let budgets = sqlx::query(
+        r#"
+        WITH purged AS (
+            DELETE FROM budgets b WHERE b.deleted_at < NOW() - make_interval(days => $1)
+            RETURNING b.user_id, b.id, to_jsonb(b) AS before_data
+        )
+        INSERT INTO audit_logs (user_id, source_channel, entity_type, entity_id, action, before_data)
+        SELECT user_id, 'system', 'budget', id, 'purge', before_data FROM purged
+        "#
+    )
+    .bind(retention_days as i32)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    // Keep categories that active budgets still point to (the budget FK would otherwise turn
+    // them into total budgets) and categories of any transaction, trashed ones included, so
+    // past spending keeps its categorisation
+    let categories = sqlx::query(
+        r#"
+        WITH purged AS (
+            DELETE FROM categories c
+            WHERE c.deleted_at < NOW() - make_interval(days => $1)
+                AND c.user_id IS NOT NULL
+                AND NOT EXISTS (SELECT 1 FROM budgets b WHERE b.category_id = c.id AND b.deleted_at IS NULL)
+                AND NOT EXISTS (SELECT 1 FROM transactions t WHERE t.category_id = c.id)
+            RETURNING c.user_id, c.id, to_jsonb(c) AS before_data
+        )
+        INSERT INTO audit_logs (user_id, source_channel, entity_type, entity_id, action, before_data)
+        SELECT user_id, 'system', 'category', id, 'purge', before_data FROM purged
+        "#
+    )
+    .bind(retention_days as i32)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    // Wallets with transactions or non-zero balance snapshots are kept so history survives:
+    // both cascade on delete, and net worth and zakat history read snapshots through the wallet
+    let wallets = sqlx::query(
+        r#"
+        WITH purged AS (
+            DELETE FROM wallets w
+            WHERE w.deleted_at < NOW() - make_interval(days => $1)
+                AND NOT EXISTS (SELECT 1 FROM transactions t WHERE t.wallet_id = w.id)
+                AND NOT EXISTS (
+                    SELECT 1 FROM wallet_balance_snapshots s
+                    WHERE s.wallet_id = w.id AND (s.balance <> 0 OR s.holdings_value <> 0)
+                )
+            RETURNING w.user_id, w.id, to_jsonb(w) AS before_data
+        )
+        INSERT INTO audit_logs (user_id, source_channel, entity_type, entity_id, action, before_data)
+        SELECT user_id, 'system', 'wallet', id, 'purge', before_data FROM purged
+        "#
+    )
+    .bind(retention_days as i32)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    // Debts have no change history; their repayment transactions stay in the ledger
+    let debts = sqlx::query(
+        r#"DELETE FROM debts WHERE deleted_at < NOW() - make_interval(days => $1)"#
+    )
+    .bind(retention_days as i32)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    // Cancelled installment plans; their schedule goes with them, posted transactions stay
+    let installment_plans = sqlx::query(
+        r#"DELETE FROM installment_plans WHERE deleted_at < NOW() - make_interval(days => $1)"#
+    )
+    .bind(retention_days as i32)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    let goals = sqlx::query(
+        r#"DELETE FROM goals WHERE deleted_at < NOW() - make_interval(days => $1)"#
+    )
+    .bind(retention_days as i32)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    let holdings = sqlx::query(
+        r#"DELETE FROM investment_holdings WHERE deleted_at < NOW() - make_interval(days => $1)"#
+    )
+    .bind(retention_days as i32)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    // Booked occurrences stay in the ledger
+    let recurring_transactions = sqlx::query(
+        r#"DELETE FROM recurring_transactions WHERE deleted_at < NOW() - make_interval(days => $1)"#
+    )
+    .bind(retention_days as i32)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    // Payments go with the bill; their transactions stay in the ledger
+    let bills = sqlx::query(
+        r#"DELETE FROM bills WHERE deleted_at < NOW() - make_interval(days => $1)"#
+    )
+    .bind(retention_days as i32)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    tx.commit().await?;
+
+    Ok(PurgeResult {
+        transactions,
+        budgets,
+        categories,
+        wallets,
+        debts,
+        installment_plans,
+        goals,
+        holdings,
+        recurring_transactions,
+        bills,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{create_user, create_wallet};
+    use uuid::Uuid;
+
+    async fn trash(pool: &PgPool, table: &str, id: Uuid, days_ago: i32) {
+        sqlx::query(&format!("UPDATE {} SET deleted_at = NOW() - make_interval(days => $2) WHERE id = $1", table))
+            .bind(id)
+            .bind(days_ago)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    async fn exists(pool: &PgPool, table: &str, id: Uuid) -> bool {
+        sqlx::query_scalar(&format!("SELECT EXISTS(SELECT 1 FROM {} WHERE id = $1)", table))
+            .bind(id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn create_category(pool: &PgPool, user_id: Uuid, name: &str) -> Uuid {
+        sqlx::query_scalar(
+            "INSERT INTO categories (user_id, name, category_type) VALUES ($1, $2, 'expense') RETURNING id",
+        )
+        .bind(user_id)
+        .bind(name)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test]
+    #[cfg_attr(not(feature = "db-tests"), ignore = "needs DATABASE_URL and --features db-tests")]
+    async fn purge_expired_keeps_recent_and_referenced_rows(pool: PgPool) {
+        let (user_id, cash) = create_user(&pool).await;
+        let expired_wallet = create_wallet(&pool, user_id, "Lama", "bank").await;
+        let recent_wallet = create_wallet(&pool, user_id, "Baru", "bank").await;
+        let used_wallet = create_wallet(&pool, user_id, "Dipakai", "bank").await;
+        let expired_category = create_category(&pool, user_id, "Lama").await;
+        let used_category = create_category(&pool, user_id, "Dipakai").await;
+        let budget_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO budgets (user_id, amount, month, year) VALUES ($1, 100000, 1, 2025) RETURNING id",
+        )
+        .bind(user_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let transaction_id: Uuid = sqlx::query_scalar(
+            r#"INSERT INTO transactions (user_id, wallet_id, category_id, transaction_type, amount, date)
+               VALUES ($1, $2, $3, 'expense', 1000, '2025-01-10') RETURNING id"#,
+        )
+        .bind(user_id)
+        .bind(used_wallet)
+        .bind(used_category)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let trashed_transaction: Uuid = sqlx::query_scalar(
+            r#"INSERT INTO transactions (user_id, wallet_id, category_id, transaction_type, amount, date)
+               VALUES ($1, $2, $3, 'expense', 1000, '2025-01-11') RETURNING id"#,
+        )
+        .bind(user_id)
+        .bind(cash)
+        .bind(used_category)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        trash(&pool, "wallets", expired_wallet, 31).await;
+        trash(&pool, "wallets", recent_wallet, 29).await;
+        trash(&pool, "wallets", used_wallet, 31).await;
+        trash(&pool, "categories", expired_category, 31).await;
+        trash(&pool, "categories", used_category, 31).await;
+        trash(&pool, "budgets", budget_id, 31).await;
+        trash(&pool, "transactions", trashed_transaction, 31).await;
+
+        let result = purge_expired(&pool, 30).await.unwrap();
+        assert_eq!(
+            (result.transactions, result.budgets, result.categories, result.wallets),
+            (1, 1, 1, 1)
+        );
+
+        assert!(!exists(&pool, "wallets", expired_wallet).await);
+        assert!(exists(&pool, "wallets", recent_wallet).await);
+        assert!(exists(&pool, "wallets", used_wallet).await);
+        assert!(!exists(&pool, "categories", expired_category).await);
+        assert!(exists(&pool, "transactions", transaction_id).await);
+        assert!(!exists(&pool, "transactions", trashed_transaction).await);
+        assert!(!exists(&pool, "budgets", budget_id).await);
+
+        // Still referenced by a live transaction
+        assert!(exists(&pool, "categories", used_category).await);
+
+        let purge_entries: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM audit_logs WHERE user_id = $1 AND action = 'purge'")
+                .bind(user_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(purge_entries, 4);
+    }
+}
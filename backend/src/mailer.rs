@@ -0,0 +1,348 @@
+// Outgoing email. The transport is chosen by MAIL_TRANSPORT: "smtp" delivers through an SMTP
+// server, "file" writes each message as an .eml file for development and testing.
+
+use async_trait::async_trait;
+use lettre::{
+    message::{
+        header::{ContentType, Header, HeaderName, HeaderValue},
+        Attachment, Mailbox, MultiPart,
+    },
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use std::{path::PathBuf, sync::Arc};
+use uuid::Uuid;
+
+use crate::config::Config;
+
+#[derive(Debug, thiserror::Error)]
+pub enum MailError {
+    #[error("Invalid email address: {0}")]
+    Address(#[from] lettre::address::AddressError),
+    #[error("Invalid email: {0}")]
+    Message(#[from] lettre::error::Error),
+    #[error("Invalid content type: {0}")]
+    ContentType(String),
+    #[error("SMTP error: {0}")]
+    Smtp(#[from] lettre::transport::smtp::Error),
+    #[error("Failed to write email: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid mail configuration: {0}")]
+    Config(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct EmailAttachment {
+    pub filename: String,
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Email {
+    pub to: String,
+    pub to_name: Option<String>,
+    pub subject: String,
+    // Sent as multipart/alternative so clients without HTML get the text part
+    pub text: String,
+    pub html: String,
+    pub attachments: Vec<EmailAttachment>,
+    // Advertised in List-Unsubscribe headers; must accept a one-click POST (RFC 8058)
+    pub unsubscribe_url: Option<String>,
+}
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: &Email) -> Result<(), MailError>;
+}
+
+#[derive(Clone)]
+struct ListUnsubscribe(String);
+
+impl Header for ListUnsubscribe {
+    fn name() -> HeaderName {
+        HeaderName::new_from_ascii_str("List-Unsubscribe")
+    }
+
+    fn parse(s: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(ListUnsubscribe(s.to_string()))
+    }
+
+    fn display(&self) -> HeaderValue {
+        HeaderValue::new(Self::name(), self.0.clone())
+    }
+}
+
+#[derive(Clone)]
+struct ListUnsubscribePost;
+
+impl Header for ListUnsubscribePost {
+    fn name() -> HeaderName {
+        HeaderName::new_from_ascii_str("List-Unsubscribe-Post")
+    }
+
+    fn parse(_: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(ListUnsubscribePost)
+    }
+
+    fn display(&self) -> HeaderValue {
+        HeaderValue::new(Self::name(), "List-Unsubscribe=One-Click".to_string())
+    }
+}
+
+// Both transports send exactly this message
+fn build_message(from: &Mailbox, email: &Email) -> Result<Message, MailError> {
+    let mut builder = Message::builder()
+        .from(from.clone())
+        .to(Mailbox::new(email.to_name.clone(), email.to.parse()?))
+        .subject(email.subject.clone());
+    if let Some(ref url) = email.unsubscribe_url {
+        builder = builder
+            .header(ListUnsubscribe(format!("<{}>", url)))
+            .header(ListUnsubscribePost);
+    }
+
+    let body = MultiPart::alternative_plain_html(email.text.clone(), email.html.clone());
+    if email.attachments.is_empty() {
+        return Ok(builder.multipart(body)?);
+    }
+
+    let mut mixed = MultiPart::mixed().multipart(body);
+    for attachment in &email.attachments {
+        let content_type = ContentType::parse(&attachment.content_type)
+            .map_err(|_| MailError::ContentType(attachment.content_type.clone()))?;
+        mixed = mixed.singlepart(Attachment::new(attachment.filename.clone()).body(attachment.data.clone(), content_type));
+    }
+    Ok(builder.multipart(mixed)?)
+}
+
+pub struct SmtpMailer {
+    from: Mailbox,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpMailer {
+    // `security` is "tls" (implicit TLS, usually port 465), "starttls" (usually 587) or "none"
+    // for local relays and test sinks
+    pub fn new(
+        from: Mailbox,
+        host: &str,
+        port: u16,
+        security: &str,
+        credentials: Option<(String, String)>,
+    ) -> Result<Self, MailError> {
+        let mut builder = match security {
+            "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(host)?,
+            "starttls" => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?,
+            "none" => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+            // A typo must not silently turn off encryption
+            other => {
+                return Err(MailError::Config(format!(
+                    "SMTP_SECURITY must be tls, starttls or none, got \"{}\"",
+                    other
+                )))
+            }
+        }
+        .port(port);
+        if let Some((username, password)) = credentials {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        Ok(SmtpMailer {
+            from,
+            transport: builder.build(),
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, email: &Email) -> Result<(), MailError> {
+        let message = build_message(&self.from, email)?;
+        self.transport.send(message).await?;
+        Ok(())
+    }
+}
+
+pub struct FileMailer {
+    from: Mailbox,
+    dir: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(from: Mailbox, dir: impl Into<PathBuf>) -> Self {
+        FileMailer { from, dir: dir.into() }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, email: &Email) -> Result<(), MailError> {
+        let message = build_message(&self.from, email)?;
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let path = self.dir.join(format!(
+            "{}-{}.eml",
+            chrono::Utc::now().format("%Y%m%dT%H%M%S"),
+            Uuid::new_v4()
+        ));
+        tokio::fs::write(&path, message.formatted()).await?;
+        tracing::debug!("📧 Email to {} written to {}", email.to, path.display());
+        Ok(())
+    }
+}
+
+pub fn from_config(config: &Config) -> Result<Arc<dyn Mailer>, MailError> {
+    let from: Mailbox = config.mail_from.parse()?;
+
+    Ok(match config.mail_transport.as_str() {
+        "smtp" => Arc::new(SmtpMailer::new(
+            from,
+            &config.smtp_host,
+            config.smtp_port,
+            &config.smtp_security,
+            config.smtp_username.clone().zip(config.smtp_password.clone()),
+        )?),
+        "file" => Arc::new(FileMailer::new(from, &config.mail_dir)),
+        other => {
+            return Err(MailError::Config(format!(
+                "MAIL_TRANSPORT must be smtp or file, got \"{}\"",
+                other
+            )))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from() -> Mailbox {
+        "FinTrack <noreply@fintrack.local>".parse().unwrap()
+    }
+
+    fn email() -> Email {
+        Email {
+            to: "budi@example.com".to_string(),
+            to_name: Some("Budi".to_string()),
+            subject: "Ringkasan bulanan September 2026".to_string(),
+            text: "Pengeluaran bulan ini".to_string(),
+            html: "<p>Pengeluaran bulan ini</p>".to_string(),
+            attachments: Vec::new(),
+            unsubscribe_url: Some("https://fintrack.local/api/digests/unsubscribe/abc".to_string()),
+        }
+    }
+
+    fn formatted(email: &Email) -> String {
+        String::from_utf8(build_message(&from(), email).unwrap().formatted()).unwrap()
+    }
+
+    #[test]
+    fn builds_text_and_html_alternatives_with_unsubscribe_headers() {
+        let message = formatted(&email());
+
+        assert!(message.contains("From: FinTrack <noreply@fintrack.local>"));
+        assert!(message.contains("To: Budi <budi@example.com>"));
+        assert!(message.contains("Subject: Ringkasan bulanan September 2026"));
+        assert!(message.contains("List-Unsubscribe: <https://fintrack.local/api/digests/unsubscribe/abc>"));
+        assert!(message.contains("List-Unsubscribe-Post: List-Unsubscribe=One-Click"));
+        assert!(message.contains("multipart/alternative"));
+        assert!(message.contains("text/plain"));
+        assert!(message.contains("text/html"));
+        assert!(!message.contains("multipart/mixed"));
+    }
+
+    #[test]
+    fn attachments_wrap_the_body_in_a_mixed_message() {
+        let message = formatted(&Email {
+            attachments: vec![EmailAttachment {
+                filename: "laporan-2026-09.pdf".to_string(),
+                content_type: "application/pdf".to_string(),
+                data: b"%PDF-1.4".to_vec(),
+            }],
+            unsubscribe_url: None,
+            ..email()
+        });
+
+        assert!(message.contains("multipart/mixed"));
+        assert!(message.contains("multipart/alternative"));
+        assert!(message.contains("application/pdf"));
+        assert!(message.contains("laporan-2026-09.pdf"));
+        assert!(!message.contains("List-Unsubscribe"));
+    }
+
+    #[test]
+    fn rejects_invalid_recipient_and_content_type() {
+        let invalid_to = build_message(&from(), &Email { to: "bukan email".to_string(), ..email() });
+        assert!(matches!(invalid_to, Err(MailError::Address(_))));
+
+        let invalid_type = build_message(
+            &from(),
+            &Email {
+                attachments: vec![EmailAttachment {
+                    filename: "x".to_string(),
+                    content_type: "pdf".to_string(),
+                    data: Vec::new(),
+                }],
+                ..email()
+            },
+        );
+        assert!(matches!(invalid_type, Err(MailError::ContentType(_))));
+    }
+
+    #[tokio::test]
+    async fn smtp_security_must_be_a_known_mode() {
+        for security in ["tls", "starttls", "none"] {
+            assert!(SmtpMailer::new(from(), "localhost", 25, security, None).is_ok(), "{}", security);
+        }
+        for security in ["", "ssl", "STARTTLS"] {
+            let result = SmtpMailer::new(from(), "localhost", 25, security, None);
+            assert!(matches!(result, Err(MailError::Config(_))), "{}", security);
+        }
+    }
+
+    #[tokio::test]
+    async fn file_mailer_writes_one_eml_file_per_email() {
+        let dir = std::env::temp_dir().join(format!("fintrack-mail-{}", Uuid::new_v4()));
+        let mailer = FileMailer::new(from(), &dir);
+
+        mailer.send(&email()).await.unwrap();
+
+        let files: Vec<PathBuf> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].extension().and_then(|ext| ext.to_str()), Some("eml"));
+        let message = std::fs::read_to_string(&files[0]).unwrap();
+        assert!(message.contains("To: Budi <budi@example.com>"));
+        assert!(message.contains("<p>Pengeluaran bulan ini</p>"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // Delivers through a local SMTP sink without TLS, such as MailHog or Mailpit:
+    //
+    //     SMTP_TEST_HOST=127.0.0.1 SMTP_TEST_PORT=1025 cargo test -- --ignored smtp_mailer
+    #[tokio::test]
+    #[ignore = "needs an SMTP sink (SMTP_TEST_HOST, SMTP_TEST_PORT)"]
+    async fn smtp_mailer_delivers_to_a_local_sink() {
+        let host = std::env::var("SMTP_TEST_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
+        let port = std::env::var("SMTP_TEST_PORT")
+            .ok()
+            .and_then(|port| port.parse().ok())
+            .unwrap_or(1025);
+        let mailer = SmtpMailer::new(from(), &host, port, "none", None).unwrap();
+
+        mailer
+            .send(&Email {
+                attachments: vec![EmailAttachment {
+                    filename: "laporan.pdf".to_string(),
+                    content_type: "application/pdf".to_string(),
+                    data: b"%PDF-1.4".to_vec(),
+                }],
+                ..email()
+            })
+            .await
+            .unwrap();
+    }
+}
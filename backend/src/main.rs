@@ -13,8 +13,12 @@ mod config;
 mod db;
 mod error;
 mod handlers;
+mod jobs;
+mod mailer;
 mod middleware;
 mod models;
+#[cfg(test)]
+mod test_support;
 mod utils;
 
 use config::Config;
@@ -23,6 +27,7 @@ use config::Config;
 pub struct AppState {
     pub db: sqlx::PgPool,
     pub config: Arc<Config>,
+    pub mailer: Arc<dyn mailer::Mailer>,
 }
 
 #[tokio::main]
@@ -80,8 +85,18 @@ async fn main() {
     let state = AppState {
         db: pool,
         config: Arc::new(config.clone()),
+        mailer: mailer::from_config(&config).expect("Failed to configure mailer"),
     };
 
+    // Background jobs
+    jobs::trash_purge::spawn(state.db.clone(), config.trash_retention_days);
+    jobs::installment_posting::spawn(state.db.clone());
+    jobs::recurring_posting::spawn(state.db.clone());
+    jobs::balance_snapshot::spawn(state.db.clone());
+    jobs::anomaly_detection::spawn(state.db.clone());
+    jobs::bill_reminders::spawn(state.db.clone());
+    jobs::email_digest::spawn(state.db.clone(), state.mailer.clone(), config.public_url.clone());
+
     // CORS configuration
     let cors = CorsLayer::new()
         .allow_origin(Any)
@@ -96,6 +111,7 @@ async fn main() {
         .route("/api/auth/register", post(handlers::auth::register))
         .route("/api/auth/login", post(handlers::auth::login))
         .route("/api/auth/me", get(handlers::auth::me))
+        .route("/api/auth/me", put(handlers::auth::update_me))
         .route("/api/auth/logout", post(handlers::auth::logout))
         // Wallet routes
         .route("/api/wallets", get(handlers::wallet::list_wallets))
@@ -103,6 +119,23 @@ async fn main() {
         .route("/api/wallets/:id", get(handlers::wallet::get_wallet))
         .route("/api/wallets/:id", put(handlers::wallet::update_wallet))
         .route("/api/wallets/:id", delete(handlers::wallet::delete_wallet))
+        .route("/api/wallets/trash", get(handlers::wallet::list_deleted_wallets))
+        .route(
+            "/api/wallets/due-dates",
+            get(handlers::wallet::list_upcoming_due_dates),
+        )
+        .route(
+            "/api/wallets/:id/statement",
+            get(handlers::wallet::get_credit_statement),
+        )
+        .route(
+            "/api/wallets/:id/restore",
+            post(handlers::wallet::restore_wallet),
+        )
+        .route(
+            "/api/wallets/:id/history",
+            get(handlers::audit::wallet_history),
+        )
         // Transaction routes
         .route(
             "/api/transactions",
@@ -124,6 +157,18 @@ async fn main() {
             "/api/transactions/:id",
             delete(handlers::transaction::delete_transaction),
         )
+        .route(
+            "/api/transactions/trash",
+            get(handlers::transaction::list_deleted_transactions),
+        )
+        .route(
+            "/api/transactions/:id/restore",
+            post(handlers::transaction::restore_transaction),
+        )
+        .route(
+            "/api/transactions/:id/history",
+            get(handlers::audit::transaction_history),
+        )
         // Category routes
         .route("/api/categories", get(handlers::category::list_categories))
         .route("/api/categories", post(handlers::category::create_category))
@@ -132,11 +177,31 @@ async fn main() {
             axum::routing::put(handlers::category::update_category)
                 .delete(handlers::category::delete_category),
         )
+        .route(
+            "/api/categories/:id/merge",
+            post(handlers::category::merge_categories),
+        )
+        .route(
+            "/api/categories/trash",
+            get(handlers::category::list_deleted_categories),
+        )
+        .route(
+            "/api/categories/:id/restore",
+            post(handlers::category::restore_category),
+        )
+        .route(
+            "/api/categories/:id/history",
+            get(handlers::audit::category_history),
+        )
         // Dashboard routes
         .route(
             "/api/dashboard/summary",
             get(handlers::dashboard::get_summary),
         )
+        .route(
+            "/api/dashboard/health",
+            get(handlers::dashboard::get_financial_health),
+        )
         .route(
             "/api/dashboard/monthly",
             get(handlers::dashboard::get_monthly_stats),
@@ -145,13 +210,195 @@ async fn main() {
             "/api/dashboard/by-category",
             get(handlers::dashboard::get_by_category),
         )
+        .route(
+            "/api/dashboard/net-worth",
+            get(handlers::dashboard::get_net_worth_history),
+        )
+        .route(
+            "/api/dashboard/net-worth/backfill",
+            post(handlers::dashboard::backfill_net_worth),
+        )
         // Budget routes
         .route("/api/budgets", get(handlers::budget::list_budgets))
         .route("/api/budgets", post(handlers::budget::create_budget))
         .route("/api/budgets/copy", post(handlers::budget::copy_budget))
+        .route("/api/budgets/history", get(handlers::budget::budget_history))
         .route("/api/budgets/:id", get(handlers::budget::get_budget))
         .route("/api/budgets/:id", put(handlers::budget::update_budget))
         .route("/api/budgets/:id", delete(handlers::budget::delete_budget))
+        .route("/api/budgets/trash", get(handlers::budget::list_deleted_budgets))
+        .route(
+            "/api/budgets/:id/restore",
+            post(handlers::budget::restore_budget),
+        )
+        .route(
+            "/api/budgets/:id/history",
+            get(handlers::audit::budget_history),
+        )
+        // Debt routes
+        .route("/api/debts", get(handlers::debt::list_debts))
+        .route("/api/debts", post(handlers::debt::create_debt))
+        .route(
+            "/api/debts/reminders",
+            get(handlers::debt::list_debt_reminders),
+        )
+        .route("/api/debts/:id", get(handlers::debt::get_debt))
+        .route("/api/debts/:id", put(handlers::debt::update_debt))
+        .route("/api/debts/:id", delete(handlers::debt::delete_debt))
+        .route(
+            "/api/debts/:id/repayments",
+            post(handlers::debt::create_repayment),
+        )
+        // Installment routes
+        .route(
+            "/api/installments",
+            get(handlers::installment::list_installment_plans),
+        )
+        .route(
+            "/api/installments",
+            post(handlers::installment::create_installment_plan),
+        )
+        .route(
+            "/api/installments/commitments",
+            get(handlers::installment::list_commitments),
+        )
+        .route(
+            "/api/installments/:id",
+            get(handlers::installment::get_installment_plan),
+        )
+        .route(
+            "/api/installments/:id",
+            put(handlers::installment::update_installment_plan),
+        )
+        .route(
+            "/api/installments/:id",
+            delete(handlers::installment::delete_installment_plan),
+        )
+        // Goal routes
+        .route("/api/goals", get(handlers::goal::list_goals))
+        .route("/api/goals", post(handlers::goal::create_goal))
+        .route("/api/goals/:id", get(handlers::goal::get_goal))
+        .route("/api/goals/:id", put(handlers::goal::update_goal))
+        .route("/api/goals/:id", delete(handlers::goal::delete_goal))
+        .route(
+            "/api/goals/:id/contributions",
+            post(handlers::goal::add_contribution),
+        )
+        .route(
+            "/api/goals/:id/contributions/:contribution_id",
+            delete(handlers::goal::delete_contribution),
+        )
+        // Investment routes
+        .route("/api/investments", get(handlers::investment::list_holdings))
+        .route("/api/investments", post(handlers::investment::create_holding))
+        .route(
+            "/api/investments/prices",
+            get(handlers::investment::list_prices),
+        )
+        .route(
+            "/api/investments/prices",
+            post(handlers::investment::create_price),
+        )
+        .route(
+            "/api/investments/prices/import",
+            post(handlers::investment::import_prices),
+        )
+        .route("/api/investments/:id", get(handlers::investment::get_holding))
+        .route("/api/investments/:id", put(handlers::investment::update_holding))
+        .route(
+            "/api/investments/:id",
+            delete(handlers::investment::delete_holding),
+        )
+        // Recurring transaction routes
+        .route("/api/recurring", get(handlers::recurring::list_recurring))
+        .route("/api/recurring", post(handlers::recurring::create_recurring))
+        .route("/api/recurring/:id", get(handlers::recurring::get_recurring))
+        .route("/api/recurring/:id", put(handlers::recurring::update_recurring))
+        .route(
+            "/api/recurring/:id",
+            delete(handlers::recurring::delete_recurring),
+        )
+        // Bill routes
+        .route("/api/bills", get(handlers::bill::list_bills))
+        .route("/api/bills", post(handlers::bill::create_bill))
+        .route(
+            "/api/bills/reminders",
+            get(handlers::bill::list_bill_reminders),
+        )
+        .route(
+            "/api/bills/calendar/token",
+            post(handlers::bill::rotate_calendar_token),
+        )
+        .route(
+            "/api/bills/calendar/token",
+            delete(handlers::bill::revoke_calendar_token),
+        )
+        .route(
+            "/api/bills/calendar/:token",
+            get(handlers::bill::get_bills_calendar),
+        )
+        .route("/api/bills/:id", get(handlers::bill::get_bill))
+        .route("/api/bills/:id", put(handlers::bill::update_bill))
+        .route("/api/bills/:id", delete(handlers::bill::delete_bill))
+        .route("/api/bills/:id/pay", post(handlers::bill::pay_bill))
+        // Subscription routes
+        .route(
+            "/api/subscriptions",
+            get(handlers::subscription::list_subscriptions),
+        )
+        .route(
+            "/api/subscriptions/convert",
+            post(handlers::subscription::convert_subscription),
+        )
+        // Notification routes
+        .route(
+            "/api/notifications",
+            get(handlers::notification::list_notifications),
+        )
+        .route(
+            "/api/notifications/read-all",
+            post(handlers::notification::mark_all_notifications_read),
+        )
+        .route(
+            "/api/notifications/:id/read",
+            post(handlers::notification::mark_notification_read),
+        )
+        // Report routes
+        .route(
+            "/api/reports/cash-flow",
+            get(handlers::report::get_cash_flow),
+        )
+        .route(
+            "/api/reports/comparison",
+            get(handlers::report::get_period_comparison),
+        )
+        .route(
+            "/api/reports/anomalies",
+            get(handlers::report::get_anomalies),
+        )
+        .route("/api/reports/ask", get(handlers::report::ask_question))
+        .route(
+            "/api/reports/statement",
+            get(handlers::report::get_monthly_statement),
+        )
+        // Zakat routes
+        .route("/api/zakat", get(handlers::zakat::get_zakat_status))
+        .route(
+            "/api/zakat/settings",
+            get(handlers::zakat::get_zakat_settings).put(handlers::zakat::update_zakat_settings),
+        )
+        .route("/api/zakat/report", get(handlers::zakat::get_zakat_report))
+        // Digest routes
+        .route(
+            "/api/digests/preferences",
+            get(handlers::digest::get_digest_preferences).put(handlers::digest::update_digest_preferences),
+        )
+        .route("/api/digests/preview", get(handlers::digest::preview_digest))
+        .route("/api/digests/test", post(handlers::digest::send_test_digest))
+        .route(
+            "/api/digests/unsubscribe/:token",
+            get(handlers::digest::get_unsubscribe_page).post(handlers::digest::unsubscribe),
+        )
         // Add middleware with request logging
         .layer(
             TraceLayer::new_for_http()
@@ -0,0 +1,66 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+// Months of history each amount is compared against
+pub const DEFAULT_ANOMALY_HISTORY_MONTHS: i32 = 6;
+// Modified z-score above which an amount is unusual
+pub const DEFAULT_ANOMALY_THRESHOLD: f64 = 3.5;
+
+#[derive(Debug, Deserialize)]
+pub struct AnomalyQuery {
+    // Window checked for anomalies, defaults to the current month up to today
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub months: Option<i32>,
+    pub threshold: Option<f64>,
+}
+
+// One expense used as history or checked for anomalies
+#[derive(Debug, Clone, FromRow)]
+pub struct ExpenseRow {
+    pub id: Uuid,
+    pub wallet_id: Uuid,
+    pub category_id: Option<Uuid>,
+    pub category_name: Option<String>,
+    pub amount: f64,
+    pub description: Option<String>,
+    pub date: NaiveDate,
+}
+
+// A transaction that is much larger than the category's usual transactions
+#[derive(Debug, Clone, Serialize)]
+pub struct TransactionAnomaly {
+    pub transaction_id: Uuid,
+    pub wallet_id: Uuid,
+    pub category_id: Option<Uuid>,
+    pub category_name: Option<String>,
+    pub description: Option<String>,
+    pub date: NaiveDate,
+    pub amount: f64,
+    // Median of the category's transactions over the history
+    pub typical_amount: f64,
+    pub score: f64,
+    pub history_count: usize,
+}
+
+// A category whose spending in one month is much higher than in the months before
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryAnomaly {
+    pub category_id: Option<Uuid>,
+    pub category_name: Option<String>,
+    pub year: i32,
+    pub month: i32,
+    // Month-to-date when the month is still in progress
+    pub total: f64,
+    // Median monthly total over the history
+    pub typical_total: f64,
+    pub score: f64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct AnomalyReport {
+    pub transactions: Vec<TransactionAnomaly>,
+    pub categories: Vec<CategoryAnomaly>,
+}
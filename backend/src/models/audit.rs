@@ -0,0 +1,242 @@
+use axum::http::HeaderMap;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::budget::Budget;
+use crate::models::category::Category;
+use crate::models::transaction::Transaction;
+use crate::models::wallet::Wallet;
+
+// Set by clients other than the web app, e.g. the WhatsApp service or importers
+pub const SOURCE_CHANNEL_HEADER: &str = "x-source-channel";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceChannel {
+    Web,
+    Whatsapp,
+    Import,
+    System, // background jobs; never accepted from clients
+}
+
+impl SourceChannel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SourceChannel::Web => "web",
+            SourceChannel::Whatsapp => "whatsapp",
+            SourceChannel::Import => "import",
+            SourceChannel::System => "system",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+    Restore,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Create => "create",
+            AuditAction::Update => "update",
+            AuditAction::Delete => "delete",
+            AuditAction::Restore => "restore",
+        }
+    }
+}
+
+// Who is making a change and through which channel
+#[derive(Debug, Clone, Copy)]
+pub struct AuditContext {
+    pub user_id: Uuid,
+    pub channel: SourceChannel,
+}
+
+impl AuditContext {
+    pub fn from_headers(user_id: Uuid, headers: &HeaderMap) -> Result<Self, AppError> {
+        let channel = match headers
+            .get(SOURCE_CHANNEL_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim().to_lowercase())
+            .as_deref()
+        {
+            None | Some("") | Some("web") => SourceChannel::Web,
+            Some("whatsapp") => SourceChannel::Whatsapp,
+            Some("import") => SourceChannel::Import,
+            Some(_) => {
+                return Err(AppError::ValidationError(
+                    "X-Source-Channel harus salah satu dari: web, whatsapp, import".to_string(),
+                ))
+            }
+        };
+
+        Ok(AuditContext { user_id, channel })
+    }
+
+    // Changes made by a background job on the user's behalf; recorded without an actor
+    pub fn system(user_id: Uuid) -> Self {
+        AuditContext {
+            user_id,
+            channel: SourceChannel::System,
+        }
+    }
+
+    pub fn actor_id(&self) -> Option<Uuid> {
+        (self.channel != SourceChannel::System).then_some(self.user_id)
+    }
+}
+
+// Records that get a change history
+pub trait Auditable: Serialize + Sync {
+    const ENTITY_TYPE: &'static str;
+
+    fn entity_id(&self) -> Uuid;
+}
+
+impl Auditable for Transaction {
+    const ENTITY_TYPE: &'static str = "transaction";
+
+    fn entity_id(&self) -> Uuid {
+        self.id
+    }
+}
+
+impl Auditable for Wallet {
+    const ENTITY_TYPE: &'static str = "wallet";
+
+    fn entity_id(&self) -> Uuid {
+        self.id
+    }
+}
+
+impl Auditable for Budget {
+    const ENTITY_TYPE: &'static str = "budget";
+
+    fn entity_id(&self) -> Uuid {
+        self.id
+    }
+}
+
+impl Auditable for Category {
+    const ENTITY_TYPE: &'static str = "category";
+
+    fn entity_id(&self) -> Uuid {
+        self.id
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct AuditLog {
+    pub id: Uuid,
+    pub actor_id: Option<Uuid>, // NULL for background jobs
+    pub source_channel: String, // web, whatsapp, import, system
+    pub action: String, // create, update, delete, restore, purge
+    pub before_data: Option<Value>,
+    pub after_data: Option<Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditHistoryQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditLogResponse {
+    pub id: Uuid,
+    pub actor_id: Option<Uuid>,
+    pub source_channel: String,
+    pub action: String,
+    pub changed_fields: Vec<String>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<AuditLog> for AuditLogResponse {
+    fn from(log: AuditLog) -> Self {
+        let changed_fields = changed_fields(log.before_data.as_ref(), log.after_data.as_ref());
+
+        AuditLogResponse {
+            id: log.id,
+            actor_id: log.actor_id,
+            source_channel: log.source_channel,
+            action: log.action,
+            changed_fields,
+            before: log.before_data,
+            after: log.after_data,
+            created_at: log.created_at,
+        }
+    }
+}
+
+// Top-level fields whose value differs between the two snapshots, ignoring bookkeeping timestamps
+fn changed_fields(before: Option<&Value>, after: Option<&Value>) -> Vec<String> {
+    let (Some(Value::Object(before)), Some(Value::Object(after))) = (before, after) else {
+        return Vec::new();
+    };
+
+    let mut fields: Vec<String> = before
+        .keys()
+        .chain(after.keys())
+        .filter(|key| key.as_str() != "updated_at")
+        .filter(|key| before.get(key.as_str()) != after.get(key.as_str()))
+        .cloned()
+        .collect();
+    fields.sort();
+    fields.dedup();
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+    use serde_json::json;
+
+    fn channel(value: Option<&str>) -> Result<SourceChannel, AppError> {
+        let mut headers = HeaderMap::new();
+        if let Some(value) = value {
+            headers.insert(SOURCE_CHANNEL_HEADER, HeaderValue::from_str(value).unwrap());
+        }
+        AuditContext::from_headers(Uuid::nil(), &headers).map(|audit| audit.channel)
+    }
+
+    #[test]
+    fn source_channel_from_header() {
+        assert_eq!(channel(None).unwrap(), SourceChannel::Web);
+        assert_eq!(channel(Some("")).unwrap(), SourceChannel::Web);
+        assert_eq!(channel(Some(" WhatsApp ")).unwrap(), SourceChannel::Whatsapp);
+        assert_eq!(channel(Some("import")).unwrap(), SourceChannel::Import);
+        // Only background jobs record system changes
+        assert!(matches!(channel(Some("system")), Err(AppError::ValidationError(_))));
+        assert!(matches!(channel(Some("sms")), Err(AppError::ValidationError(_))));
+    }
+
+    #[test]
+    fn system_changes_have_no_actor() {
+        let user_id = Uuid::new_v4();
+        assert_eq!(AuditContext::system(user_id).actor_id(), None);
+        let audit = AuditContext { user_id, channel: SourceChannel::Import };
+        assert_eq!(audit.actor_id(), Some(user_id));
+    }
+
+    #[test]
+    fn changed_fields_ignores_updated_at() {
+        let before = json!({"amount": 10000.0, "description": "Makan", "updated_at": "2025-01-01"});
+        let after = json!({"amount": 12000.0, "description": "Makan", "updated_at": "2025-01-02", "deleted_at": null});
+        assert_eq!(changed_fields(Some(&before), Some(&after)), ["amount", "deleted_at"]);
+        assert!(changed_fields(Some(&before), Some(&before)).is_empty());
+        // Creates and purges have only one snapshot
+        assert!(changed_fields(None, Some(&after)).is_empty());
+        assert!(changed_fields(Some(&before), None).is_empty());
+    }
+}
@@ -0,0 +1,201 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::installment::round_currency;
+use crate::models::recurring::{next_occurrence, occurrences_per_year};
+
+// once = a single bill, e.g. a yearly tax notice entered by hand
+pub const BILL_FREQUENCIES: [&str; 4] = ["once", "weekly", "monthly", "yearly"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Bill {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub payee: String,
+    pub description: Option<String>,
+    // Fixed amount, or an estimated range when amount is None
+    pub amount: Option<f64>,
+    pub amount_min: Option<f64>,
+    pub amount_max: Option<f64>,
+    pub wallet_id: Option<Uuid>,
+    pub category_id: Option<Uuid>,
+    pub frequency: String, // once, weekly, monthly, yearly
+    pub interval_count: i32,
+    pub anchor_day: i16,
+    pub next_due_date: Option<NaiveDate>, // None once a one-off bill is paid
+    pub remind_days_before: i32,
+    // Set when the bill was created from a detected subscription
+    pub subscription_key: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+impl Bill {
+    // Due date after `date`, or None when the bill does not repeat
+    pub fn due_after(&self, date: NaiveDate) -> Option<NaiveDate> {
+        if self.frequency == "once" {
+            return None;
+        }
+        Some(next_occurrence(date, &self.frequency, self.interval_count, self.anchor_day as i32))
+    }
+
+    // Due dates from next_due_date up to and including `until`, at most `limit` of them
+    pub fn due_dates_until(&self, until: NaiveDate, limit: usize) -> Vec<NaiveDate> {
+        let mut dates = Vec::new();
+        let mut next = self.next_due_date;
+        while let Some(date) = next {
+            if date > until || dates.len() >= limit {
+                break;
+            }
+            dates.push(date);
+            next = self.due_after(date);
+        }
+        dates
+    }
+
+    // The fixed amount, or the middle of the estimated range
+    pub fn expected_amount(&self) -> f64 {
+        match (self.amount, self.amount_min, self.amount_max) {
+            (Some(amount), _, _) => amount,
+            (None, Some(min), Some(max)) => (min + max) / 2.0,
+            _ => 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct BillPayment {
+    pub id: Uuid,
+    pub bill_id: Uuid,
+    pub due_date: NaiveDate,
+    pub paid_date: NaiveDate,
+    pub amount: f64,
+    pub transaction_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateBillRequest {
+    #[validate(length(min = 1, max = 100, message = "Nama penerima wajib diisi (maksimal 100 karakter)"))]
+    pub payee: String,
+    #[validate(length(max = 255, message = "Deskripsi maksimal 255 karakter"))]
+    pub description: Option<String>,
+    // Either amount, or amount_min and amount_max for bills that vary (electricity, water)
+    pub amount: Option<f64>,
+    pub amount_min: Option<f64>,
+    pub amount_max: Option<f64>,
+    // Wallet the bill is usually paid from; can be chosen when paying instead
+    pub wallet_id: Option<Uuid>,
+    pub category_id: Option<Uuid>,
+    pub frequency: String,
+    pub interval_count: Option<i32>,
+    // First due date; repeating bills keep this day of the month
+    pub due_date: NaiveDate,
+    pub remind_days_before: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateBillRequest {
+    #[validate(length(min = 1, max = 100, message = "Nama penerima wajib diisi (maksimal 100 karakter)"))]
+    pub payee: Option<String>,
+    #[validate(length(max = 255, message = "Deskripsi maksimal 255 karakter"))]
+    pub description: Option<String>,
+    // Setting amount replaces a range and setting a range replaces the amount
+    pub amount: Option<f64>,
+    pub amount_min: Option<f64>,
+    pub amount_max: Option<f64>,
+    pub wallet_id: Option<Uuid>,
+    pub category_id: Option<Uuid>,
+    // Moves the schedule; later due dates follow this date's day of month
+    pub next_due_date: Option<NaiveDate>,
+    pub remind_days_before: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BillQuery {
+    pub status: Option<String>, // overdue, due_soon, upcoming, paid
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BillReminderQuery {
+    pub days: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PayBillRequest {
+    // Required for bills with an estimated range
+    pub amount: Option<f64>,
+    pub wallet_id: Option<Uuid>, // defaults to the bill's wallet
+    pub date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BillResponse {
+    pub id: Uuid,
+    pub payee: String,
+    pub description: Option<String>,
+    pub amount: Option<f64>,
+    pub amount_min: Option<f64>,
+    pub amount_max: Option<f64>,
+    pub expected_amount: f64,
+    pub is_estimate: bool,
+    pub wallet_id: Option<Uuid>,
+    pub category_id: Option<Uuid>,
+    pub frequency: String,
+    pub interval_count: i32,
+    pub next_due_date: Option<NaiveDate>,
+    pub days_until_due: Option<i64>,
+    pub remind_days_before: i32,
+    pub annual_amount: f64,
+    pub status: String, // overdue, due_soon, upcoming, paid
+    pub subscription_key: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl BillResponse {
+    pub fn new(bill: Bill, today: NaiveDate) -> Self {
+        let expected_amount = bill.expected_amount();
+        let days_until_due = bill.next_due_date.map(|due_date| (due_date - today).num_days());
+
+        let status = match bill.next_due_date {
+            None => "paid",
+            Some(due_date) if due_date < today => "overdue",
+            Some(due_date) if due_date <= today + Duration::days(bill.remind_days_before as i64) => "due_soon",
+            Some(_) => "upcoming",
+        };
+
+        let annual_amount = match (bill.frequency.as_str(), bill.next_due_date) {
+            ("once", None) => 0.0,
+            ("once", Some(_)) => expected_amount,
+            (frequency, _) => expected_amount * occurrences_per_year(frequency, bill.interval_count),
+        };
+
+        BillResponse {
+            id: bill.id,
+            payee: bill.payee,
+            description: bill.description,
+            amount: bill.amount,
+            amount_min: bill.amount_min,
+            amount_max: bill.amount_max,
+            expected_amount,
+            is_estimate: bill.amount.is_none(),
+            wallet_id: bill.wallet_id,
+            category_id: bill.category_id,
+            frequency: bill.frequency,
+            interval_count: bill.interval_count,
+            next_due_date: bill.next_due_date,
+            days_until_due,
+            remind_days_before: bill.remind_days_before,
+            annual_amount: round_currency(annual_amount),
+            status: status.to_string(),
+            subscription_key: bill.subscription_key,
+            created_at: bill.created_at,
+            updated_at: bill.updated_at,
+        }
+    }
+}
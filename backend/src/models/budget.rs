@@ -13,11 +13,31 @@ pub struct Budget {
     pub year: i32,
     pub is_active: bool,
     pub alert_threshold: Option<i32>,
+    pub wallet_ids: Option<Vec<Uuid>>, // NULL = all wallets
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub deleted_at: Option<DateTime<Utc>>,
 }
 
+// Budget row joined with its category name and the expense total for its period,
+// produced by a single aggregate query (see db::get_user_budgets_with_usage)
+#[derive(Debug, Clone, FromRow)]
+pub struct BudgetWithUsage {
+    #[sqlx(flatten)]
+    pub budget: Budget,
+    pub category_name: Option<String>,
+    pub used_amount: f64,
+    pub committed_amount: f64, // installments due in the period that are not posted yet
+}
+
+// Soft-deleted budget with its category name, listed in the trash bin
+#[derive(Debug, Clone, FromRow)]
+pub struct DeletedBudget {
+    #[sqlx(flatten)]
+    pub budget: Budget,
+    pub category_name: Option<String>,
+}
+
 // Custom deserializer untuk handle category_id yang bisa berupa:
 // - null
 // - string kosong ""
@@ -97,6 +117,8 @@ pub struct CreateBudgetRequest {
     pub year: i32,
     pub is_active: Option<bool>,
     pub alert_threshold: Option<i32>,
+    // Limit the budget to expenses from these wallets; omit for all wallets
+    pub wallet_ids: Option<Vec<Uuid>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -108,6 +130,8 @@ pub struct UpdateBudgetRequest {
     pub year: Option<i32>,
     pub is_active: Option<bool>,
     pub alert_threshold: Option<i32>,
+    // Some([]) removes the wallet scope (back to all wallets), None keeps it unchanged
+    pub wallet_ids: Option<Vec<Uuid>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -120,13 +144,19 @@ pub struct BudgetResponse {
     pub year: i32,
     pub is_active: bool,
     pub alert_threshold: Option<i32>,
+    pub wallet_ids: Option<Vec<Uuid>>,
     pub used_amount: Option<f64>,
     pub remaining_amount: Option<f64>,
     pub usage_percentage: Option<f64>,
     pub is_over_budget: Option<bool>,
     pub should_alert: Option<bool>,
+    pub committed_amount: Option<f64>,
+    // remaining_amount minus committed_amount
+    pub projected_remaining_amount: Option<f64>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -137,3 +167,123 @@ pub struct CopyBudgetRequest {
     pub target_year: i32,
 }
 
+
+impl From<BudgetWithUsage> for BudgetResponse {
+    fn from(row: BudgetWithUsage) -> Self {
+        let budget = row.budget;
+        let used_amount = row.used_amount;
+        let remaining_amount = budget.amount - used_amount;
+        let usage_percentage = if budget.amount > 0.0 {
+            (used_amount / budget.amount) * 100.0
+        } else {
+            0.0
+        };
+        let is_over_budget = used_amount > budget.amount;
+        let should_alert = budget
+            .alert_threshold
+            .map(|threshold| usage_percentage >= threshold as f64)
+            .unwrap_or(false);
+
+        BudgetResponse {
+            id: budget.id,
+            category_id: budget.category_id,
+            category_name: row.category_name,
+            amount: budget.amount,
+            month: budget.month,
+            year: budget.year,
+            is_active: budget.is_active,
+            alert_threshold: budget.alert_threshold,
+            wallet_ids: budget.wallet_ids,
+            used_amount: Some(used_amount),
+            remaining_amount: Some(remaining_amount),
+            usage_percentage: Some(usage_percentage),
+            is_over_budget: Some(is_over_budget),
+            should_alert: Some(should_alert),
+            committed_amount: Some(row.committed_amount),
+            projected_remaining_amount: Some(remaining_amount - row.committed_amount),
+            created_at: budget.created_at,
+            updated_at: budget.updated_at,
+            deleted_at: budget.deleted_at,
+        }
+    }
+}
+
+impl From<DeletedBudget> for BudgetResponse {
+    fn from(row: DeletedBudget) -> Self {
+        BudgetResponse::without_usage(row.budget, row.category_name)
+    }
+}
+
+impl BudgetResponse {
+    pub fn without_usage(budget: Budget, category_name: Option<String>) -> Self {
+        BudgetResponse {
+            id: budget.id,
+            category_id: budget.category_id,
+            category_name,
+            amount: budget.amount,
+            month: budget.month,
+            year: budget.year,
+            is_active: budget.is_active,
+            alert_threshold: budget.alert_threshold,
+            wallet_ids: budget.wallet_ids,
+            used_amount: None,
+            remaining_amount: None,
+            usage_percentage: None,
+            is_over_budget: None,
+            should_alert: None,
+            committed_amount: None,
+            projected_remaining_amount: None,
+            created_at: budget.created_at,
+            updated_at: budget.updated_at,
+            deleted_at: budget.deleted_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BudgetHistoryQuery {
+    // Omit for the total monthly budget
+    #[serde(deserialize_with = "deserialize_optional_uuid", default)]
+    pub category_id: Option<Uuid>,
+    // Number of periods to return, ending at end_month/end_year (default 6, max 24)
+    pub periods: Option<i32>,
+    pub end_month: Option<i32>,
+    pub end_year: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BudgetPeriodUsage {
+    pub month: i32,
+    pub year: i32,
+    pub budgeted: Option<f64>,
+    pub actual: f64,
+    // budgeted - actual; negative when over budget
+    pub variance: Option<f64>,
+    pub usage_percentage: Option<f64>,
+    pub status: String, // over, under, on_track, no_budget
+}
+
+#[derive(Debug, Serialize)]
+pub struct CategoryBudgetTrend {
+    pub category_id: Uuid,
+    pub category_name: Option<String>,
+    pub periods_with_budget: i32,
+    pub over_count: i32,
+    pub under_count: i32,
+    pub total_budgeted: f64,
+    pub total_actual: f64,
+    pub trend: String, // consistently_over, consistently_under
+}
+
+#[derive(Debug, Serialize)]
+pub struct BudgetHistoryResponse {
+    pub category_id: Option<Uuid>,
+    pub category_name: Option<String>,
+    pub periods: Vec<BudgetPeriodUsage>,
+    pub total_budgeted: f64,
+    pub total_actual: f64,
+    pub total_variance: f64,
+    pub over_count: i32,
+    pub under_count: i32,
+    pub flagged_categories: Vec<CategoryBudgetTrend>,
+}
@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 use validator::Validate;
 
@@ -11,6 +12,7 @@ pub struct Category {
     pub icon: Option<String>,
     pub color: Option<String>,
     pub category_type: String, // income, expense
+    pub parent_id: Option<Uuid>, // NULL for top-level categories
     pub created_at: DateTime<Utc>,
     pub deleted_at: Option<DateTime<Utc>>,
 }
@@ -23,6 +25,15 @@ pub struct CreateCategoryRequest {
     pub color: Option<String>,
     #[validate(length(min = 1, message = "Tipe kategori wajib diisi"))]
     pub category_type: String,
+    pub parent_id: Option<Uuid>,
+}
+
+// Distinguishes a missing field (None) from an explicit null (Some(None))
+fn deserialize_some<'de, D>(deserializer: D) -> Result<Option<Option<Uuid>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<Uuid>::deserialize(deserializer).map(Some)
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -33,6 +44,15 @@ pub struct UpdateCategoryRequest {
     pub color: Option<String>,
     #[validate(length(min = 1, message = "Tipe kategori wajib diisi"))]
     pub category_type: String,
+    // Omit to keep the current parent, null to make the category top-level
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub parent_id: Option<Option<Uuid>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CategoryQuery {
+    // Return a flat list instead of a tree
+    pub flat: Option<bool>,
 }
 
 #[derive(Debug, Serialize)]
@@ -43,6 +63,10 @@ pub struct CategoryResponse {
     pub color: Option<String>,
     pub category_type: String,
     pub is_default: bool,
+    pub parent_id: Option<Uuid>,
+    pub children: Vec<CategoryResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 impl From<Category> for CategoryResponse {
@@ -54,7 +78,64 @@ impl From<Category> for CategoryResponse {
             color: cat.color,
             category_type: cat.category_type,
             is_default: cat.user_id.is_none(),
+            parent_id: cat.parent_id,
+            children: Vec::new(),
+            deleted_at: cat.deleted_at,
+        }
+    }
+}
+
+impl CategoryResponse {
+    // Nests categories under their parents, keeping the input order within each level.
+    // Categories whose parent is not in the list (e.g. deleted) are returned as roots.
+    pub fn tree(categories: Vec<Category>) -> Vec<CategoryResponse> {
+        let ids: HashSet<Uuid> = categories.iter().map(|cat| cat.id).collect();
+        let mut children_of: HashMap<Uuid, Vec<Category>> = HashMap::new();
+        let mut roots = Vec::new();
+
+        for cat in categories {
+            match cat.parent_id {
+                Some(parent_id) if ids.contains(&parent_id) => {
+                    children_of.entry(parent_id).or_default().push(cat)
+                }
+                _ => roots.push(cat),
+            }
         }
+
+        fn attach(cat: Category, children_of: &mut HashMap<Uuid, Vec<Category>>) -> CategoryResponse {
+            let children = children_of.remove(&cat.id).unwrap_or_default();
+            let mut response = CategoryResponse::from(cat);
+            response.children = children
+                .into_iter()
+                .map(|child| attach(child, children_of))
+                .collect();
+            response
+        }
+
+        roots
+            .into_iter()
+            .map(|cat| attach(cat, &mut children_of))
+            .collect()
     }
 }
 
+
+#[derive(Debug, Deserialize)]
+pub struct MergeCategoryRequest {
+    pub target_id: Uuid,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteCategoryQuery {
+    // Move transactions, budgets and sub-categories here before deleting
+    pub reassign_to: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CategoryMergeResult {
+    pub transactions_moved: u64,
+    pub budgets_moved: u64,
+    // Budgets folded into an existing target budget for the same wallets and period
+    pub budgets_merged: u64,
+    pub subcategories_moved: u64,
+}
@@ -0,0 +1,145 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+// lent = money others owe the user (piutang), borrowed = money the user owes (hutang)
+pub const DEBT_DIRECTIONS: [&str; 2] = ["lent", "borrowed"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Debt {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub counterparty: String,
+    pub direction: String, // lent, borrowed
+    pub principal: f64,
+    pub description: Option<String>,
+    pub date: NaiveDate,
+    pub due_date: Option<NaiveDate>,
+    pub transaction_id: Option<Uuid>, // wallet transaction that moved the principal
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+// Debt with the sum of its repayments whose transactions are not deleted
+// (see db::get_user_debts)
+#[derive(Debug, Clone, FromRow)]
+pub struct DebtWithRepayments {
+    #[sqlx(flatten)]
+    pub debt: Debt,
+    pub repaid_amount: f64,
+    pub repayment_count: i64,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateDebtRequest {
+    #[validate(length(min = 1, max = 100, message = "Nama pihak wajib diisi (maksimal 100 karakter)"))]
+    pub counterparty: String,
+    pub direction: String,
+    pub principal: f64,
+    pub description: Option<String>,
+    pub date: Option<NaiveDate>,
+    pub due_date: Option<NaiveDate>,
+    // When set, the principal is recorded as a transaction on this wallet
+    pub wallet_id: Option<Uuid>,
+}
+
+// Distinguishes a missing field (None) from an explicit null (Some(None))
+fn deserialize_some<'de, D>(deserializer: D) -> Result<Option<Option<NaiveDate>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<NaiveDate>::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateDebtRequest {
+    #[validate(length(min = 1, max = 100, message = "Nama pihak wajib diisi (maksimal 100 karakter)"))]
+    pub counterparty: Option<String>,
+    pub principal: Option<f64>,
+    pub description: Option<String>,
+    // Omit to keep the current due date, null to remove it
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub due_date: Option<Option<NaiveDate>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DebtQuery {
+    pub direction: Option<String>,
+    pub status: Option<String>, // active, overdue, settled
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DebtReminderQuery {
+    pub days: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateRepaymentRequest {
+    pub amount: f64,
+    pub wallet_id: Option<Uuid>, // defaults to the default wallet
+    pub date: Option<NaiveDate>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DebtResponse {
+    pub id: Uuid,
+    pub counterparty: String,
+    pub direction: String,
+    pub principal: f64,
+    pub repaid_amount: f64,
+    pub outstanding_amount: f64,
+    pub repayment_count: i64,
+    pub status: String, // active, overdue, settled
+    pub description: Option<String>,
+    pub date: NaiveDate,
+    pub due_date: Option<NaiveDate>,
+    pub days_until_due: Option<i64>,
+    pub transaction_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl DebtResponse {
+    pub fn new(row: DebtWithRepayments, today: NaiveDate) -> Self {
+        let debt = row.debt;
+        let outstanding_amount = (debt.principal - row.repaid_amount).max(0.0);
+        let days_until_due = debt.due_date.map(|due_date| (due_date - today).num_days());
+
+        let status = if outstanding_amount <= 0.0 {
+            "settled"
+        } else if days_until_due.is_some_and(|days| days < 0) {
+            "overdue"
+        } else {
+            "active"
+        };
+
+        DebtResponse {
+            id: debt.id,
+            counterparty: debt.counterparty,
+            direction: debt.direction,
+            principal: debt.principal,
+            repaid_amount: row.repaid_amount,
+            outstanding_amount,
+            repayment_count: row.repayment_count,
+            status: status.to_string(),
+            description: debt.description,
+            date: debt.date,
+            due_date: debt.due_date,
+            days_until_due,
+            transaction_id: debt.transaction_id,
+            created_at: debt.created_at,
+            updated_at: debt.updated_at,
+        }
+    }
+}
+
+// Open receivables (piutang) and payables (hutang)
+#[derive(Debug, Clone, Default, Serialize, FromRow)]
+pub struct DebtTotals {
+    pub receivable: f64,
+    pub payable: f64,
+}
@@ -0,0 +1,209 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::fmt::Write;
+use uuid::Uuid;
+
+use crate::models::statement::{
+    budget_rows, category_rows, StatementBudget, StatementCategory, BUDGET_COLUMNS, CATEGORY_COLUMNS,
+};
+use crate::utils::{
+    dates::{day_of_month, shift_months},
+    format::{format_date_id, format_rupiah, MONTH_NAMES_ID},
+    html::{escape_html, html_section, html_table},
+};
+
+pub const DIGEST_FREQUENCIES: [&str; 2] = ["weekly", "monthly"];
+// Expense categories listed in a digest
+pub const DIGEST_TOP_CATEGORIES: usize = 5;
+const UNSUBSCRIBE_TOKEN_LENGTH: usize = 40;
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct DigestPreferences {
+    pub user_id: Uuid,
+    pub weekly: bool,
+    pub monthly: bool,
+    #[serde(skip_serializing)]
+    pub unsubscribe_token: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateDigestPreferencesRequest {
+    pub weekly: Option<bool>,
+    pub monthly: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DigestQuery {
+    // weekly or monthly; unsubscribing without one turns off both
+    pub frequency: Option<String>,
+}
+
+pub fn new_unsubscribe_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(UNSUBSCRIBE_TOKEN_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
+// The latest full period of this frequency that has ended before `today`: last Monday to
+// Sunday, or last calendar month
+pub fn digest_period(frequency: &str, today: NaiveDate) -> (NaiveDate, NaiveDate) {
+    match frequency {
+        "weekly" => {
+            let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+            (monday - Duration::days(7), monday - Duration::days(1))
+        }
+        _ => {
+            let first = day_of_month(today.year(), today.month(), 1);
+            (shift_months(first, -1, 1), first - Duration::days(1))
+        }
+    }
+}
+
+// Figures for one digest email (see jobs::email_digest::build_digest_email)
+#[derive(Debug, Clone)]
+pub struct Digest {
+    pub frequency: String,
+    pub user_name: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub income: f64,
+    pub expense: f64,
+    pub transaction_count: i64,
+    // Spending in the period of the same length just before
+    pub previous_expense: f64,
+    pub closing_balance: f64,
+    pub top_categories: Vec<StatementCategory>,
+    // Budgets of the month the period ends in, with spending so far
+    pub budget_year: i32,
+    pub budget_month: u32,
+    pub budgets: Vec<StatementBudget>,
+    pub unsubscribe_url: String,
+}
+
+impl Digest {
+    fn period_label(&self) -> String {
+        if self.frequency == "weekly" {
+            format!("{} - {}", format_date_id(self.start_date), format_date_id(self.end_date))
+        } else {
+            format!("{} {}", MONTH_NAMES_ID[self.start_date.month0() as usize], self.start_date.year())
+        }
+    }
+
+    fn frequency_label(&self) -> &'static str {
+        if self.frequency == "weekly" { "mingguan" } else { "bulanan" }
+    }
+
+    pub fn subject(&self) -> String {
+        format!("Ringkasan {} FinTrack: {}", self.frequency_label(), self.period_label())
+    }
+
+    fn summary_rows(&self) -> Vec<Vec<String>> {
+        vec![
+            vec!["Pemasukan".to_string(), format_rupiah(self.income)],
+            vec!["Pengeluaran".to_string(), format_rupiah(self.expense)],
+            vec!["Selisih".to_string(), format_rupiah(self.income - self.expense)],
+            vec!["Saldo akhir".to_string(), format_rupiah(self.closing_balance)],
+            vec!["Jumlah transaksi".to_string(), self.transaction_count.to_string()],
+        ]
+    }
+
+    fn expense_change(&self) -> String {
+        let previous = if self.frequency == "weekly" { "minggu sebelumnya" } else { "bulan sebelumnya" };
+        if self.previous_expense <= 0.0 {
+            return format!("Tidak ada pengeluaran pada {}", previous);
+        }
+        let change = (self.expense - self.previous_expense) / self.previous_expense * 100.0;
+        if change.abs() < 0.5 {
+            format!("Pengeluaran sama dengan {}", previous)
+        } else {
+            format!(
+                "Pengeluaran {} {:.0}% dibanding {} ({})",
+                if change > 0.0 { "naik" } else { "turun" },
+                change.abs(),
+                previous,
+                format_rupiah(self.previous_expense)
+            )
+        }
+    }
+
+    fn budget_heading(&self) -> String {
+        format!("Anggaran {} {}", MONTH_NAMES_ID[self.budget_month as usize - 1], self.budget_year)
+    }
+
+    fn footer(&self) -> String {
+        format!(
+            "Anda menerima email ini karena berlangganan ringkasan {}. Atur langganan di pengaturan aplikasi.",
+            self.frequency_label()
+        )
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html lang=\"id\">\n<head>\n<meta charset=\"utf-8\">\n<title>{subject}</title>\n</head>\n\
+             <body style=\"margin:0;padding:24px;background:#ffffff;color:#222;font-family:Helvetica,Arial,sans-serif\">\n\
+             <div style=\"max-width:600px;margin:0 auto\">\n\
+             <h1 style=\"font-size:20px;margin:0 0 4px\">{subject}</h1>\n\
+             <p style=\"font-size:14px\">Halo {name}, berikut ringkasan keuangan Anda untuk periode {period}.</p>\n",
+            subject = escape_html(&self.subject()),
+            name = escape_html(&self.user_name),
+            period = escape_html(&self.period_label()),
+        );
+
+        html_section(&mut html, "Ringkasan");
+        html_table(&mut html, &[("Keterangan", false), ("Jumlah", true)], &self.summary_rows());
+        let _ = writeln!(html, "<p style=\"font-size:13px;color:#444\">{}</p>", escape_html(&self.expense_change()));
+
+        html_section(&mut html, "Pengeluaran terbesar");
+        html_table(&mut html, &CATEGORY_COLUMNS, &category_rows(&self.top_categories));
+
+        html_section(&mut html, &self.budget_heading());
+        html_table(&mut html, &BUDGET_COLUMNS, &budget_rows(&self.budgets));
+
+        let _ = write!(
+            html,
+            "<p style=\"margin-top:24px;font-size:11px;color:#999\">{footer} \
+             <a href=\"{url}\" style=\"color:#999\">Berhenti berlangganan</a></p>\n</div>\n</body>\n</html>\n",
+            footer = escape_html(&self.footer()),
+            url = escape_html(&self.unsubscribe_url),
+        );
+        html
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        let _ = writeln!(text, "{}\n", self.subject());
+        let _ = writeln!(text, "Halo {}, berikut ringkasan keuangan Anda untuk periode {}.\n", self.user_name, self.period_label());
+
+        for row in self.summary_rows() {
+            let _ = writeln!(text, "{}: {}", row[0], row[1]);
+        }
+        let _ = writeln!(text, "{}", self.expense_change());
+
+        let _ = writeln!(text, "\nPengeluaran terbesar");
+        if self.top_categories.is_empty() {
+            let _ = writeln!(text, "- Tidak ada data");
+        }
+        for row in category_rows(&self.top_categories) {
+            let _ = writeln!(text, "- {}: {} ({})", row[0], row[1], row[2]);
+        }
+
+        let _ = writeln!(text, "\n{}", self.budget_heading());
+        if self.budgets.is_empty() {
+            let _ = writeln!(text, "- Tidak ada data");
+        }
+        for row in budget_rows(&self.budgets) {
+            let _ = writeln!(text, "- {}: terpakai {} dari {} ({})", row[0], row[2], row[1], row[4]);
+        }
+
+        let _ = writeln!(text, "\n{}\nBerhenti berlangganan: {}", self.footer(), self.unsubscribe_url);
+        text
+    }
+}
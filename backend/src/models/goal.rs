@@ -0,0 +1,276 @@
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::utils::dates::shift_months;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Goal {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub target_amount: f64,
+    pub target_date: Option<NaiveDate>,
+    pub wallet_id: Option<Uuid>, // NULL = manual contributions
+    pub icon: Option<String>,
+    pub color: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+// Goal with the amount saved so far: the linked wallet's balance, or the sum of its
+// contributions (see db::get_user_goals)
+#[derive(Debug, Clone, FromRow)]
+pub struct GoalWithProgress {
+    #[sqlx(flatten)]
+    pub goal: Goal,
+    pub wallet_name: Option<String>,
+    pub saved_amount: f64,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct GoalContribution {
+    pub id: Uuid,
+    pub goal_id: Uuid,
+    pub amount: f64,
+    pub date: NaiveDate,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+// Net amount added to a goal in one month: contributions, or the linked wallet's
+// income minus expenses
+#[derive(Debug, Clone, Copy, Serialize, FromRow)]
+pub struct GoalMonthlyContribution {
+    #[serde(skip)]
+    pub goal_id: Uuid,
+    pub year: i32,
+    pub month: i32,
+    pub amount: f64,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateGoalRequest {
+    #[validate(length(min = 1, max = 100, message = "Nama goal wajib diisi (maksimal 100 karakter)"))]
+    pub name: String,
+    pub target_amount: f64,
+    pub target_date: Option<NaiveDate>,
+    pub wallet_id: Option<Uuid>,
+    pub icon: Option<String>,
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateGoalRequest {
+    #[validate(length(min = 1, max = 100, message = "Nama goal wajib diisi (maksimal 100 karakter)"))]
+    pub name: Option<String>,
+    pub target_amount: Option<f64>,
+    pub target_date: Option<NaiveDate>,
+    pub wallet_id: Option<Uuid>,
+    // Switches a wallet-linked goal to manual contributions
+    pub unlink_wallet: Option<bool>,
+    pub icon: Option<String>,
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateContributionRequest {
+    pub amount: f64, // negative to withdraw
+    pub date: Option<NaiveDate>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GoalResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub target_amount: f64,
+    pub target_date: Option<NaiveDate>,
+    pub wallet_id: Option<Uuid>,
+    pub wallet_name: Option<String>,
+    pub icon: Option<String>,
+    pub color: Option<String>,
+    pub saved_amount: f64,
+    pub remaining_amount: f64,
+    pub progress_percentage: f64,
+    pub status: String, // achieved, on_track, behind, overdue, in_progress
+    pub months_left: Option<i32>,
+    // Needed each month from now on to reach the target by target_date
+    pub required_monthly_contribution: Option<f64>,
+    // Average over the last GOAL_HISTORY_MONTHS months with contributions
+    pub average_monthly_contribution: f64,
+    pub projected_completion_date: Option<NaiveDate>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Months of contribution history used for the projection
+pub const GOAL_HISTORY_MONTHS: i32 = 6;
+// Completion dates further out than this are not projected
+pub const GOAL_MAX_PROJECTION_MONTHS: i32 = 1200;
+
+fn month_index(date: NaiveDate) -> i32 {
+    date.year() * 12 + date.month0() as i32
+}
+
+impl GoalResponse {
+    // `history` holds this goal's monthly totals for the last GOAL_HISTORY_MONTHS months
+    // including the current one
+    pub fn new(row: GoalWithProgress, history: &[GoalMonthlyContribution], today: NaiveDate) -> Self {
+        let goal = row.goal;
+        let saved_amount = row.saved_amount.max(0.0);
+        let remaining_amount = (goal.target_amount - saved_amount).max(0.0);
+        let progress_percentage = (saved_amount / goal.target_amount * 100.0).min(100.0);
+
+        // Months left to save, counting the current one and the target's month
+        let months_left = goal
+            .target_date
+            .filter(|target_date| *target_date >= today)
+            .map(|target_date| month_index(target_date) - month_index(today) + 1);
+        let required_monthly_contribution = months_left
+            .filter(|_| remaining_amount > 0.0)
+            .map(|months| remaining_amount / months as f64);
+
+        // Average from the first month with activity, so a new goal is not diluted by
+        // months before it existed
+        let current_month = month_index(today);
+        let first_month = history
+            .iter()
+            .map(|entry| entry.year * 12 + entry.month - 1)
+            .min()
+            .unwrap_or(current_month)
+            .max(current_month - GOAL_HISTORY_MONTHS + 1);
+        let observed_months = (current_month - first_month + 1) as f64;
+        let average_monthly_contribution =
+            history.iter().map(|entry| entry.amount).sum::<f64>() / observed_months;
+
+        let projected_completion_date = if remaining_amount <= 0.0 {
+            Some(today)
+        } else if average_monthly_contribution > 0.0 {
+            // Checked as f64 first: a tiny average against a large target would overflow the cast
+            let months_needed = (remaining_amount / average_monthly_contribution).ceil();
+            (months_needed <= GOAL_MAX_PROJECTION_MONTHS as f64)
+                .then(|| shift_months(today, months_needed as i32, today.day() as i32))
+        } else {
+            None
+        };
+
+        let status = if remaining_amount <= 0.0 {
+            "achieved"
+        } else {
+            match (goal.target_date, projected_completion_date) {
+                (Some(target_date), _) if target_date < today => "overdue",
+                (Some(target_date), Some(projected)) if projected <= target_date => "on_track",
+                (Some(_), _) => "behind",
+                (None, _) => "in_progress",
+            }
+        };
+
+        GoalResponse {
+            id: goal.id,
+            name: goal.name,
+            target_amount: goal.target_amount,
+            target_date: goal.target_date,
+            wallet_id: goal.wallet_id,
+            wallet_name: row.wallet_name,
+            icon: goal.icon,
+            color: goal.color,
+            saved_amount,
+            remaining_amount,
+            progress_percentage,
+            status: status.to_string(),
+            months_left,
+            required_monthly_contribution,
+            average_monthly_contribution,
+            projected_completion_date,
+            created_at: goal.created_at,
+            updated_at: goal.updated_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn goal_row(target_amount: f64, saved_amount: f64) -> GoalWithProgress {
+        GoalWithProgress {
+            goal: Goal {
+                id: Uuid::new_v4(),
+                user_id: Uuid::new_v4(),
+                name: "Dana darurat".to_string(),
+                target_amount,
+                target_date: None,
+                wallet_id: None,
+                icon: None,
+                color: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                deleted_at: None,
+            },
+            wallet_name: None,
+            saved_amount,
+        }
+    }
+
+    fn contribution(year: i32, month: i32, amount: f64) -> GoalMonthlyContribution {
+        GoalMonthlyContribution { goal_id: Uuid::nil(), year, month, amount }
+    }
+
+    #[test]
+    fn projects_completion_from_average_contribution() {
+        let today = NaiveDate::from_ymd_opt(2025, 3, 15).unwrap();
+        let history = [contribution(2025, 2, 1_000_000.0), contribution(2025, 3, 1_000_000.0)];
+        let goal = GoalResponse::new(goal_row(10_000_000.0, 2_000_000.0), &history, today);
+
+        assert_eq!(goal.average_monthly_contribution, 1_000_000.0);
+        assert_eq!(goal.projected_completion_date, NaiveDate::from_ymd_opt(2025, 11, 15));
+    }
+
+    #[test]
+    fn no_projection_beyond_the_cap() {
+        let today = NaiveDate::from_ymd_opt(2025, 3, 15).unwrap();
+        let history = [contribution(2025, 3, 0.01)];
+
+        let goal = GoalResponse::new(goal_row(1e15, 0.0), &history, today);
+        assert_eq!(goal.projected_completion_date, None);
+        assert_eq!(goal.status, "in_progress");
+
+        // Exactly at the cap is still projected
+        let history = [contribution(2025, 3, 1_000.0)];
+        let goal = GoalResponse::new(goal_row(1_000.0 * GOAL_MAX_PROJECTION_MONTHS as f64, 0.0), &history, today);
+        assert_eq!(goal.projected_completion_date, NaiveDate::from_ymd_opt(2125, 3, 15));
+    }
+
+    #[test]
+    fn status_against_the_target_date() {
+        let today = NaiveDate::from_ymd_opt(2025, 3, 15).unwrap();
+        let history = [contribution(2025, 3, 1_000_000.0)];
+        let with_target = |saved_amount: f64, target_date: NaiveDate| {
+            let mut row = goal_row(6_000_000.0, saved_amount);
+            row.goal.target_date = Some(target_date);
+            GoalResponse::new(row, &history, today)
+        };
+
+        // 5 million left over March to July
+        let goal = with_target(1_000_000.0, NaiveDate::from_ymd_opt(2025, 7, 31).unwrap());
+        assert_eq!(goal.months_left, Some(5));
+        assert_eq!(goal.required_monthly_contribution, Some(1_000_000.0));
+        assert_eq!(goal.projected_completion_date, NaiveDate::from_ymd_opt(2025, 8, 15));
+        assert_eq!(goal.status, "behind");
+
+        let goal = with_target(1_000_000.0, NaiveDate::from_ymd_opt(2025, 8, 31).unwrap());
+        assert_eq!(goal.status, "on_track");
+
+        let goal = with_target(1_000_000.0, NaiveDate::from_ymd_opt(2025, 3, 1).unwrap());
+        assert_eq!((goal.status.as_str(), goal.months_left), ("overdue", None));
+
+        let goal = with_target(7_000_000.0, NaiveDate::from_ymd_opt(2025, 3, 1).unwrap());
+        assert_eq!(goal.status, "achieved");
+        assert_eq!((goal.remaining_amount, goal.progress_percentage), (0.0, 100.0));
+        assert_eq!(goal.required_monthly_contribution, None);
+    }
+}
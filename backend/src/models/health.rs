@@ -0,0 +1,315 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::models::installment::round_currency;
+use crate::utils::format::format_rupiah;
+
+pub const DEFAULT_HEALTH_MONTHS: i32 = 3;
+
+#[derive(Debug, Deserialize)]
+pub struct HealthQuery {
+    // Completed months the income and spending averages are taken over
+    pub months: Option<i32>,
+}
+
+// Figures the score is computed from (see handlers::dashboard::get_financial_health)
+#[derive(Debug, Clone, Default)]
+pub struct HealthInputs {
+    pub months: i32,
+    pub income: f64,
+    pub expense: f64,
+    // Positive balances of wallets that are not credit or investment wallets
+    pub liquid_balance: f64,
+    // Installments due next month plus payables (hutang) due by the end of next month
+    pub monthly_debt_payments: f64,
+    // The payables part of monthly_debt_payments
+    pub payables_due: f64,
+    // Owed on credit wallets, and the limits of those with one set
+    pub credit_used: f64,
+    pub credit_limit: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthMetric {
+    pub key: String,
+    pub label: String,
+    // None when there is not enough data; such metrics do not count towards the score
+    pub value: Option<f64>,
+    pub score: Option<f64>, // 0-100
+    pub weight: f64,
+    pub status: String, // good, fair, poor, unavailable
+    pub explanation: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FinancialHealth {
+    pub score: Option<f64>, // 0-100
+    pub grade: String,      // excellent, good, fair, poor, unavailable
+    pub summary: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub months: i32,
+    pub average_monthly_income: f64,
+    pub average_monthly_expense: f64,
+    pub metrics: Vec<HealthMetric>,
+}
+
+// 0 at `worst`, 100 at `best`, linear in between; works for either direction
+fn scale(value: f64, worst: f64, best: f64) -> f64 {
+    (((value - worst) / (best - worst)) * 100.0).clamp(0.0, 100.0)
+}
+
+fn percent(ratio: f64) -> String {
+    format!("{:.0}%", ratio * 100.0)
+}
+
+fn metric(key: &str, label: &str, weight: f64, value: Option<f64>, score: Option<f64>, explanation: String) -> HealthMetric {
+    let status = match score {
+        None => "unavailable",
+        Some(score) if score >= 70.0 => "good",
+        Some(score) if score >= 40.0 => "fair",
+        Some(_) => "poor",
+    };
+    HealthMetric {
+        key: key.to_string(),
+        label: label.to_string(),
+        value: value.map(|value| (value * 100.0).round() / 100.0),
+        score: score.map(f64::round),
+        weight,
+        status: status.to_string(),
+        explanation,
+    }
+}
+
+// Scores five ratios against common personal finance rules of thumb (save 20% of income,
+// six months of spending in cash, installments and debts due under 30% of income, credit use
+// under 30%) and combines them into a weighted 0-100 score
+pub fn compute_health(inputs: &HealthInputs, start_date: NaiveDate, end_date: NaiveDate) -> FinancialHealth {
+    let months = inputs.months.max(1) as f64;
+    let monthly_income = inputs.income / months;
+    let monthly_expense = inputs.expense / months;
+    let has_income = inputs.income > 0.0;
+
+    let savings_rate = has_income.then(|| (inputs.income - inputs.expense) / inputs.income);
+    let savings = metric(
+        "savings_rate",
+        "Rasio tabungan",
+        25.0,
+        savings_rate,
+        savings_rate.map(|rate| scale(rate, 0.0, 0.2)),
+        match savings_rate {
+            None => "Belum ada pemasukan tercatat pada periode ini".to_string(),
+            Some(rate) if rate < 0.0 => format!(
+                "Pengeluaran melebihi pemasukan; rata-rata defisit {} per bulan",
+                format_rupiah(monthly_expense - monthly_income)
+            ),
+            Some(rate) => format!(
+                "Anda menyisihkan {} dari pemasukan; idealnya minimal 20%",
+                percent(rate)
+            ),
+        },
+    );
+
+    let expense_ratio = has_income.then(|| inputs.expense / inputs.income);
+    let expense = metric(
+        "expense_to_income",
+        "Rasio pengeluaran terhadap pemasukan",
+        10.0,
+        expense_ratio,
+        expense_ratio.map(|ratio| scale(ratio, 1.0, 0.6)),
+        match expense_ratio {
+            None => "Belum ada pemasukan tercatat pada periode ini".to_string(),
+            Some(ratio) => format!(
+                "{} dari pemasukan habis untuk pengeluaran; di bawah 80% tergolong sehat",
+                percent(ratio)
+            ),
+        },
+    );
+
+    let emergency_months = (monthly_expense > 0.0).then(|| inputs.liquid_balance / monthly_expense);
+    let emergency = metric(
+        "emergency_fund_months",
+        "Dana darurat",
+        25.0,
+        emergency_months,
+        emergency_months.map(|months| scale(months, 0.0, 6.0)),
+        match emergency_months {
+            None => "Belum ada pengeluaran tercatat untuk menghitung kebutuhan bulanan".to_string(),
+            Some(months) => format!(
+                "Saldo tunai dan bank {} cukup untuk {:.1} bulan pengeluaran; idealnya 3-6 bulan",
+                format_rupiah(inputs.liquid_balance),
+                months
+            ),
+        },
+    );
+
+    let debt_ratio = has_income.then(|| inputs.monthly_debt_payments / monthly_income);
+    let debt = metric(
+        "debt_to_income",
+        "Rasio cicilan dan hutang terhadap pemasukan",
+        20.0,
+        debt_ratio,
+        debt_ratio.map(|ratio| scale(ratio, 0.5, 0.2)),
+        match debt_ratio {
+            None => "Belum ada pemasukan tercatat pada periode ini".to_string(),
+            Some(_) if inputs.monthly_debt_payments <= 0.0 => {
+                "Tidak ada cicilan atau hutang yang jatuh tempo bulan depan".to_string()
+            }
+            Some(ratio) if inputs.payables_due > 0.0 => format!(
+                "Cicilan dan hutang jatuh tempo bulan depan {} (termasuk hutang {}) atau {} dari pemasukan bulanan; sebaiknya di bawah 30%",
+                format_rupiah(inputs.monthly_debt_payments),
+                format_rupiah(inputs.payables_due),
+                percent(ratio)
+            ),
+            Some(ratio) => format!(
+                "Cicilan bulan depan {} atau {} dari pemasukan bulanan; sebaiknya di bawah 30%",
+                format_rupiah(inputs.monthly_debt_payments),
+                percent(ratio)
+            ),
+        },
+    );
+
+    let utilization = (inputs.credit_limit > 0.0).then(|| inputs.credit_used / inputs.credit_limit);
+    let credit = metric(
+        "credit_utilization",
+        "Pemakaian limit kredit",
+        20.0,
+        utilization,
+        utilization.map(|ratio| scale(ratio, 1.0, 0.3)),
+        match utilization {
+            None => "Tidak ada kartu kredit atau paylater dengan limit".to_string(),
+            Some(ratio) => format!(
+                "Terpakai {} dari limit {} ({}); sebaiknya di bawah 30%",
+                format_rupiah(inputs.credit_used),
+                format_rupiah(inputs.credit_limit),
+                percent(ratio)
+            ),
+        },
+    );
+
+    let metrics = vec![savings, expense, emergency, debt, credit];
+
+    let (weighted, weights) = metrics
+        .iter()
+        .filter_map(|metric| metric.score.map(|score| (score * metric.weight, metric.weight)))
+        .fold((0.0, 0.0), |(sum, total), (score, weight)| (sum + score, total + weight));
+    let score = (weights > 0.0).then(|| (weighted / weights).round());
+
+    let (grade, summary) = match score {
+        None => ("unavailable", "Catat pemasukan dan pengeluaran untuk melihat skor kesehatan keuangan"),
+        Some(score) if score >= 80.0 => ("excellent", "Keuangan Anda sangat sehat"),
+        Some(score) if score >= 60.0 => ("good", "Keuangan Anda sehat, masih ada yang bisa ditingkatkan"),
+        Some(score) if score >= 40.0 => ("fair", "Keuangan Anda cukup, perhatikan poin yang masih lemah"),
+        Some(_) => ("poor", "Keuangan Anda perlu perhatian"),
+    };
+
+    FinancialHealth {
+        score,
+        grade: grade.to_string(),
+        summary: summary.to_string(),
+        start_date,
+        end_date,
+        months: inputs.months,
+        average_monthly_income: round_currency(monthly_income),
+        average_monthly_expense: round_currency(monthly_expense),
+        metrics,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn health(inputs: HealthInputs) -> FinancialHealth {
+        compute_health(
+            &inputs,
+            NaiveDate::from_ymd_opt(2026, 7, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2026, 9, 30).unwrap(),
+        )
+    }
+
+    fn find<'a>(health: &'a FinancialHealth, key: &str) -> &'a HealthMetric {
+        health.metrics.iter().find(|metric| metric.key == key).unwrap()
+    }
+
+    #[test]
+    fn healthy_finances_score_full_marks() {
+        let health = health(HealthInputs {
+            months: 3,
+            income: 30_000_000.0,
+            expense: 18_000_000.0,
+            liquid_balance: 40_000_000.0,
+            monthly_debt_payments: 1_000_000.0,
+            payables_due: 0.0,
+            credit_used: 1_000_000.0,
+            credit_limit: 10_000_000.0,
+        });
+
+        assert_eq!(health.average_monthly_income, 10_000_000.0);
+        assert_eq!(health.average_monthly_expense, 6_000_000.0);
+        assert_eq!(find(&health, "savings_rate").value, Some(0.4));
+        assert_eq!(find(&health, "emergency_fund_months").value, Some(6.67));
+        assert_eq!(find(&health, "debt_to_income").value, Some(0.1));
+        assert!(health.metrics.iter().all(|metric| metric.score == Some(100.0)));
+        assert_eq!(health.score, Some(100.0));
+        assert_eq!(health.grade, "excellent");
+    }
+
+    #[test]
+    fn overspending_scores_poorly() {
+        let health = health(HealthInputs {
+            months: 1,
+            income: 5_000_000.0,
+            expense: 6_000_000.0,
+            liquid_balance: 0.0,
+            monthly_debt_payments: 3_000_000.0,
+            payables_due: 0.0,
+            credit_used: 9_500_000.0,
+            credit_limit: 10_000_000.0,
+        });
+
+        let savings = find(&health, "savings_rate");
+        assert_eq!(savings.score, Some(0.0));
+        assert!(savings.explanation.contains("defisit"));
+        assert_eq!(find(&health, "debt_to_income").status, "poor");
+        assert_eq!(health.grade, "poor");
+    }
+
+    #[test]
+    fn metrics_without_data_do_not_count() {
+        // Only emergency fund and credit use can be computed without income
+        let scored = health(HealthInputs {
+            months: 3,
+            expense: 3_000_000.0,
+            liquid_balance: 3_000_000.0,
+            credit_used: 3_000_000.0,
+            credit_limit: 10_000_000.0,
+            ..Default::default()
+        });
+
+        assert_eq!(find(&scored, "savings_rate").status, "unavailable");
+        assert_eq!(find(&scored, "debt_to_income").score, None);
+        // Emergency fund: 3 months of 1,000,000 scores 50 (weight 25); credit use of 30% scores 100 (weight 20)
+        assert_eq!(scored.score, Some(((50.0 * 25.0 + 100.0 * 20.0) / 45.0_f64).round()));
+
+        let empty = health(HealthInputs { months: 3, ..Default::default() });
+        assert_eq!(empty.score, None);
+        assert_eq!(empty.grade, "unavailable");
+    }
+
+    #[test]
+    fn payables_due_are_explained() {
+        let health = health(HealthInputs {
+            months: 1,
+            income: 10_000_000.0,
+            expense: 5_000_000.0,
+            monthly_debt_payments: 3_500_000.0,
+            payables_due: 2_000_000.0,
+            ..Default::default()
+        });
+
+        let debt = find(&health, "debt_to_income");
+        assert_eq!(debt.value, Some(0.35));
+        assert!(debt.explanation.contains("termasuk hutang"), "{}", debt.explanation);
+    }
+}
@@ -0,0 +1,142 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+// Installment amounts are kept to two decimals
+pub fn round_currency(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct InstallmentPlan {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub wallet_id: Uuid,
+    pub category_id: Option<Uuid>,
+    pub description: String,
+    pub principal: f64,
+    pub interest_rate: f64, // flat, percent of principal per month
+    pub tenor_months: i32,
+    pub purchase_date: NaiveDate,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+// Plan with totals over its schedule (see db::get_user_installment_plans)
+#[derive(Debug, Clone, FromRow)]
+pub struct InstallmentPlanWithProgress {
+    #[sqlx(flatten)]
+    pub plan: InstallmentPlan,
+    pub monthly_amount: f64,
+    pub total_amount: f64,
+    pub paid_count: i64,
+    pub paid_amount: f64,
+    pub next_due_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Installment {
+    pub id: Uuid,
+    pub installment_number: i32,
+    pub due_date: NaiveDate,
+    pub amount: f64,
+    pub posted_at: Option<DateTime<Utc>>, // NULL until the expense is booked
+    pub transaction_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateInstallmentPlanRequest {
+    pub wallet_id: Uuid,
+    pub category_id: Option<Uuid>,
+    #[validate(length(min = 1, max = 255, message = "Deskripsi wajib diisi (maksimal 255 karakter)"))]
+    pub description: String,
+    pub principal: f64,
+    pub interest_rate: Option<f64>,
+    pub tenor_months: i32,
+    pub purchase_date: Option<NaiveDate>,
+    // Defaults to the purchase date; later installments fall on the same day of month
+    pub first_due_date: Option<NaiveDate>,
+}
+
+// Changes apply to installments that are not posted yet
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateInstallmentPlanRequest {
+    #[validate(length(min = 1, max = 255, message = "Deskripsi wajib diisi (maksimal 255 karakter)"))]
+    pub description: Option<String>,
+    pub category_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InstallmentPlanQuery {
+    pub status: Option<String>, // active, completed
+    pub wallet_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CommitmentQuery {
+    pub months: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InstallmentPlanResponse {
+    pub id: Uuid,
+    pub wallet_id: Uuid,
+    pub category_id: Option<Uuid>,
+    pub description: String,
+    pub principal: f64,
+    pub interest_rate: f64,
+    pub tenor_months: i32,
+    pub monthly_amount: f64,
+    pub total_amount: f64,
+    pub total_interest: f64,
+    pub purchase_date: NaiveDate,
+    pub paid_count: i64,
+    pub remaining_count: i64,
+    pub paid_amount: f64,
+    pub remaining_amount: f64,
+    pub next_due_date: Option<NaiveDate>,
+    pub status: String, // active, completed
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<InstallmentPlanWithProgress> for InstallmentPlanResponse {
+    fn from(row: InstallmentPlanWithProgress) -> Self {
+        let plan = row.plan;
+        let remaining_count = plan.tenor_months as i64 - row.paid_count;
+
+        InstallmentPlanResponse {
+            id: plan.id,
+            wallet_id: plan.wallet_id,
+            category_id: plan.category_id,
+            description: plan.description,
+            principal: plan.principal,
+            interest_rate: plan.interest_rate,
+            tenor_months: plan.tenor_months,
+            monthly_amount: row.monthly_amount,
+            total_amount: row.total_amount,
+            total_interest: round_currency(row.total_amount - plan.principal),
+            purchase_date: plan.purchase_date,
+            paid_count: row.paid_count,
+            remaining_count,
+            paid_amount: row.paid_amount,
+            remaining_amount: round_currency(row.total_amount - row.paid_amount),
+            next_due_date: row.next_due_date,
+            status: if remaining_count > 0 { "active" } else { "completed" }.to_string(),
+            created_at: plan.created_at,
+            updated_at: plan.updated_at,
+        }
+    }
+}
+
+// Installments still to be posted in one month, across all active plans
+#[derive(Debug, Clone, Copy, Serialize, FromRow)]
+pub struct MonthlyCommitment {
+    pub month: i32,
+    pub year: i32,
+    pub amount: f64,
+    pub installment_count: i64,
+}
@@ -0,0 +1,198 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+// stock: shares, mutual_fund: units, gold: grams, crypto: coins
+pub const ASSET_TYPES: [&str; 4] = ["stock", "mutual_fund", "gold", "crypto"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Holding {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub wallet_id: Uuid,
+    pub asset_type: String,
+    pub symbol: String,
+    pub name: Option<String>,
+    pub quantity: f64,
+    pub cost_basis: f64, // total paid for the quantity held
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+// Holding with the latest recorded price of its symbol (see db::get_user_holdings)
+#[derive(Debug, Clone, FromRow)]
+pub struct HoldingWithPrice {
+    #[sqlx(flatten)]
+    pub holding: Holding,
+    pub wallet_name: String,
+    pub latest_price: Option<f64>,
+    pub price_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct AssetPrice {
+    pub id: Uuid,
+    pub asset_type: String,
+    pub symbol: String,
+    pub price: f64,
+    pub price_date: NaiveDate,
+    pub source: String, // manual, import
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateHoldingRequest {
+    pub wallet_id: Uuid,
+    pub asset_type: String,
+    #[validate(length(min = 1, max = 30, message = "Kode aset wajib diisi (maksimal 30 karakter)"))]
+    pub symbol: String,
+    #[validate(length(max = 100, message = "Nama aset maksimal 100 karakter"))]
+    pub name: Option<String>,
+    pub quantity: f64,
+    // Either the total cost or the average price per unit
+    pub cost_basis: Option<f64>,
+    pub average_price: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateHoldingRequest {
+    #[validate(length(max = 100, message = "Nama aset maksimal 100 karakter"))]
+    pub name: Option<String>,
+    pub quantity: Option<f64>,
+    pub cost_basis: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HoldingQuery {
+    pub wallet_id: Option<Uuid>,
+    pub asset_type: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreatePriceRequest {
+    pub asset_type: String,
+    pub symbol: String,
+    pub price: f64,
+    pub date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PriceQuery {
+    pub asset_type: Option<String>,
+    pub symbol: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HoldingResponse {
+    pub id: Uuid,
+    pub wallet_id: Uuid,
+    pub wallet_name: String,
+    pub asset_type: String,
+    pub symbol: String,
+    pub name: Option<String>,
+    pub quantity: f64,
+    pub cost_basis: f64,
+    pub average_cost: Option<f64>,
+    pub latest_price: Option<f64>,
+    pub price_date: Option<NaiveDate>,
+    // Valued at cost until a price is recorded
+    pub market_value: f64,
+    pub unrealized_gain: f64,
+    pub unrealized_gain_percentage: Option<f64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<HoldingWithPrice> for HoldingResponse {
+    fn from(row: HoldingWithPrice) -> Self {
+        let holding = row.holding;
+        let market_value = row
+            .latest_price
+            .map(|price| price * holding.quantity)
+            .unwrap_or(holding.cost_basis);
+        let unrealized_gain = market_value - holding.cost_basis;
+
+        HoldingResponse {
+            id: holding.id,
+            wallet_id: holding.wallet_id,
+            wallet_name: row.wallet_name,
+            asset_type: holding.asset_type,
+            symbol: holding.symbol,
+            name: holding.name,
+            quantity: holding.quantity,
+            cost_basis: holding.cost_basis,
+            average_cost: (holding.quantity > 0.0).then(|| holding.cost_basis / holding.quantity),
+            latest_price: row.latest_price,
+            price_date: row.price_date,
+            market_value,
+            unrealized_gain,
+            unrealized_gain_percentage: (holding.cost_basis > 0.0)
+                .then(|| unrealized_gain / holding.cost_basis * 100.0),
+            created_at: holding.created_at,
+            updated_at: holding.updated_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holding(quantity: f64, cost_basis: f64, latest_price: Option<f64>) -> HoldingResponse {
+        HoldingResponse::from(HoldingWithPrice {
+            holding: Holding {
+                id: Uuid::nil(),
+                user_id: Uuid::nil(),
+                wallet_id: Uuid::nil(),
+                asset_type: "stock".to_string(),
+                symbol: "BBCA".to_string(),
+                name: None,
+                quantity,
+                cost_basis,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                deleted_at: None,
+            },
+            wallet_name: "Saham".to_string(),
+            latest_price,
+            price_date: latest_price.map(|_| NaiveDate::from_ymd_opt(2025, 3, 1).unwrap()),
+        })
+    }
+
+    #[test]
+    fn unrealized_gain_at_latest_price() {
+        let response = holding(100.0, 900_000.0, Some(9_800.0));
+        assert_eq!(response.average_cost, Some(9_000.0));
+        assert_eq!(response.market_value, 980_000.0);
+        assert_eq!(response.unrealized_gain, 80_000.0);
+        assert_eq!(response.unrealized_gain_percentage.map(|p| (p * 100.0).round() / 100.0), Some(8.89));
+
+        let loss = holding(100.0, 900_000.0, Some(8_100.0));
+        assert_eq!(loss.unrealized_gain, -90_000.0);
+        assert_eq!(loss.unrealized_gain_percentage, Some(-10.0));
+    }
+
+    #[test]
+    fn valued_at_cost_without_a_price() {
+        let response = holding(10.0, 10_000_000.0, None);
+        assert_eq!(response.market_value, 10_000_000.0);
+        assert_eq!(response.unrealized_gain, 0.0);
+        assert_eq!(response.unrealized_gain_percentage, Some(0.0));
+    }
+
+    #[test]
+    fn empty_or_free_holdings_have_no_ratios() {
+        let sold = holding(0.0, 0.0, Some(9_800.0));
+        assert_eq!(sold.average_cost, None);
+        assert_eq!(sold.market_value, 0.0);
+        assert_eq!(sold.unrealized_gain_percentage, None);
+
+        // e.g. a stock dividend or an airdrop
+        let free = holding(5.0, 0.0, Some(100.0));
+        assert_eq!(free.unrealized_gain, 500.0);
+        assert_eq!(free.unrealized_gain_percentage, None);
+    }
+}
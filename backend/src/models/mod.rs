@@ -3,3 +3,20 @@ pub mod wallet;
 pub mod transaction;
 pub mod category;
 pub mod budget;
+pub mod audit;
+pub mod debt;
+pub mod installment;
+pub mod goal;
+pub mod net_worth;
+pub mod investment;
+pub mod report;
+pub mod anomaly;
+pub mod notification;
+pub mod recurring;
+pub mod subscription;
+pub mod bill;
+pub mod question;
+pub mod health;
+pub mod zakat;
+pub mod statement;
+pub mod digest;
@@ -0,0 +1,59 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Deserialize)]
+pub struct NetWorthQuery {
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub interval: Option<String>, // day, week, month
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BalanceBackfillRequest {
+    // Defaults to one year ago; never earlier than each wallet's creation
+    pub start_date: Option<NaiveDate>,
+}
+
+// Balances as of the end of `date` (see db::get_net_worth_history)
+#[derive(Debug, Clone, FromRow)]
+pub struct NetWorthPoint {
+    pub date: NaiveDate,
+    pub wallet_assets: f64,      // regular wallets, plus credit wallets paid in advance
+    pub investment_assets: f64,  // market value of investment holdings
+    pub credit_liabilities: f64, // amount owed on credit wallets
+    pub receivables: f64,        // open piutang
+    pub payables: f64,           // open hutang
+}
+
+#[derive(Debug, Serialize)]
+pub struct NetWorthPointResponse {
+    pub date: NaiveDate,
+    pub assets: f64,
+    pub liabilities: f64,
+    pub net_worth: f64,
+    pub wallet_assets: f64,
+    pub investment_assets: f64,
+    pub credit_liabilities: f64,
+    pub receivables: f64,
+    pub payables: f64,
+}
+
+impl From<NetWorthPoint> for NetWorthPointResponse {
+    fn from(point: NetWorthPoint) -> Self {
+        let assets = point.wallet_assets + point.investment_assets + point.receivables;
+        let liabilities = point.credit_liabilities + point.payables;
+
+        NetWorthPointResponse {
+            date: point.date,
+            assets,
+            liabilities,
+            net_worth: assets - liabilities,
+            wallet_assets: point.wallet_assets,
+            investment_assets: point.investment_assets,
+            credit_liabilities: point.credit_liabilities,
+            receivables: point.receivables,
+            payables: point.payables,
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Notification {
+    pub id: Uuid,
+    pub kind: String, // e.g. spending_anomaly
+    pub title: String,
+    pub body: String,
+    pub data: Option<Value>,
+    pub read_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NotificationQuery {
+    pub unread: Option<bool>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
@@ -0,0 +1,782 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::models::category::Category;
+use crate::models::wallet::Wallet;
+use crate::utils::dates::{day_of_month, shift_months};
+use crate::utils::format::{format_rupiah, MONTH_NAMES_ID};
+
+// Words that point to a language; the answer is written in the one with more hits
+const ENGLISH_HINTS: &[&str] = &[
+    "how", "much", "many", "did", "do", "spend", "spent", "spending", "income", "earn", "earned",
+    "last", "this", "week", "month", "year", "today", "yesterday", "on", "in", "my", "what", "was",
+    "the", "average", "biggest", "largest", "past",
+];
+const INDONESIAN_HINTS: &[&str] = &[
+    "berapa", "pengeluaran", "pemasukan", "bulan", "minggu", "pekan", "tahun", "lalu", "ini", "hari",
+    "kemarin", "saya", "aku", "untuk", "di", "yang", "terakhir", "rata", "terbesar", "buat", "dari",
+    "habis", "kali", "belanja",
+];
+
+// Alternative word sequences with the same meaning
+type Phrases = &'static [&'static [&'static str]];
+
+const COUNT_PHRASES: Phrases = &[
+    &["berapa", "kali"], &["berapa", "transaksi"], &["jumlah", "transaksi"], &["banyaknya", "transaksi"],
+    &["how", "many"], &["number", "of"], &["count"],
+];
+const AVERAGE_PHRASES: Phrases = &[&["rata", "rata"], &["rerata"], &["average"], &["avg"], &["mean"]];
+const LARGEST_PHRASES: Phrases = &[
+    &["paling", "besar"], &["paling", "mahal"], &["terbesar"], &["termahal"], &["tertinggi"],
+    &["biggest"], &["largest"], &["most", "expensive"], &["highest"],
+];
+// "tabungan" is left out on purpose, it is a common wallet name
+const NET_PHRASES: Phrases = &[
+    &["sisa", "uang"], &["selisih"], &["arus", "kas"], &["cash", "flow"], &["net"], &["nabung"],
+    &["menabung"], &["saved"], &["save"], &["savings"],
+];
+
+const EXPENSE_WORDS: &[&str] = &[
+    "pengeluaran", "keluar", "habis", "menghabiskan", "dihabiskan", "bayar", "membayar", "dibayar",
+    "biaya", "ongkos", "beli", "membeli", "spend", "spent", "spending", "expense", "expenses", "paid",
+    "pay", "cost", "costs", "bought", "buy",
+];
+const INCOME_WORDS: &[&str] = &[
+    "pemasukan", "pendapatan", "penghasilan", "masuk", "dapat", "mendapat", "dapet", "terima",
+    "menerima", "diterima", "income", "earn", "earned", "earnings", "received", "receive", "revenue",
+    "made",
+];
+
+// Everyday words for the default categories (see handlers::auth), by category name
+const CATEGORY_SYNONYMS: &[(&str, &[&str])] = &[
+    ("makanan", &[
+        "makan", "makanan", "food", "foods", "eat", "eating", "dining", "meal", "meals", "kuliner",
+        "restoran", "restaurant", "jajan", "snack", "snacks", "minuman", "drinks", "kopi", "coffee",
+        "groceries",
+    ]),
+    ("transport", &[
+        "transport", "transportasi", "transportation", "bensin", "bbm", "fuel", "ojek", "ojol", "grab",
+        "gojek", "taxi", "taksi", "parkir", "parking", "tol", "toll", "kereta", "krl", "commute",
+    ]),
+    ("belanja", &["belanja", "shopping", "shop"]),
+    ("hiburan", &[
+        "hiburan", "entertainment", "nonton", "movie", "movies", "film", "bioskop", "game", "games",
+        "streaming",
+    ]),
+    ("tagihan", &[
+        "tagihan", "bill", "bills", "utilities", "utility", "listrik", "electricity", "internet", "pulsa",
+    ]),
+    ("kesehatan", &[
+        "kesehatan", "health", "obat", "medicine", "dokter", "doctor", "medical", "hospital",
+    ]),
+    ("pendidikan", &[
+        "pendidikan", "education", "sekolah", "school", "kuliah", "kursus", "course", "courses",
+    ]),
+    ("gaji", &["gaji", "gajian", "salary", "payroll"]),
+    ("freelance", &["freelance", "freelancing"]),
+    ("investasi", &["investasi", "investment", "investments", "dividen", "dividend", "dividends"]),
+    ("bonus", &["bonus", "thr"]),
+    ("lainnya", &["lainnya", "other", "others"]),
+];
+
+// Filler words that are neither filters nor a search term
+const STOPWORDS: &[&str] = &[
+    "berapa", "berapakah", "total", "jumlah", "semua", "seluruh", "saya", "aku", "gue", "gw", "ku", "kita",
+    "yang", "untuk", "buat", "di", "ke", "dari", "pada", "selama", "dalam", "dan", "atau", "sih", "ya",
+    "kah", "apa", "adalah", "ada", "sudah", "udah", "telah", "uang", "duit", "transaksi", "kategori",
+    "dompet", "bulan", "tahun", "minggu", "pekan", "hari", "pakai", "pake", "lewat", "via", "dengan",
+    "itu", "ini", "sampai", "hingga", "tolong", "coba", "lihat", "nya", "how", "much", "many", "did",
+    "do", "does", "i", "my", "me", "we", "our", "the", "a", "an", "on", "in", "at", "for", "of", "to",
+    "from", "with", "using", "was", "were", "is", "are", "what", "whats", "s", "have", "has", "money",
+    "sum", "all", "category", "wallet", "transactions", "transaction", "so", "far", "during", "this",
+    "last", "past", "lalu", "terakhir", "belakangan", "kali", "per", "may", "might", "can", "could",
+    "should", "will", "would",
+];
+
+const MONTH_WORDS: &[(&str, u32)] = &[
+    ("januari", 1), ("january", 1), ("jan", 1), ("februari", 2), ("february", 2), ("feb", 2),
+    ("maret", 3), ("march", 3), ("mar", 3), ("april", 4), ("apr", 4), ("mei", 5), ("may", 5),
+    ("juni", 6), ("june", 6), ("jun", 6), ("juli", 7), ("july", 7), ("jul", 7), ("agustus", 8),
+    ("august", 8), ("agu", 8), ("aug", 8), ("september", 9), ("sep", 9), ("oktober", 10),
+    ("october", 10), ("okt", 10), ("oct", 10), ("november", 11), ("nov", 11), ("desember", 12),
+    ("december", 12), ("des", 12),
+];
+// Month words that are also ordinary English words; they only count as a month right before a
+// year or right after one of MONTH_CUES ("in may", "bulan mar 2025")
+const AMBIGUOUS_MONTH_WORDS: &[&str] = &["may", "mar"];
+const MONTH_CUES: &[&str] = &["in", "during", "of", "since", "bulan"];
+const MONTH_NAMES_EN: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June", "July", "August", "September", "October",
+    "November", "December",
+];
+
+#[derive(Debug, Deserialize)]
+pub struct QuestionQuery {
+    pub q: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FilterRef {
+    pub id: Uuid,
+    pub name: String,
+}
+
+// Structured filter a question was turned into; returned with the answer so the user can see
+// how the question was understood
+#[derive(Debug, Clone, Serialize)]
+pub struct QuestionFilter {
+    pub metric: String, // total, count, average, largest, net
+    pub transaction_type: Option<String>, // None for net
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub period: String,
+    pub categories: Vec<FilterRef>,
+    // The categories above with their sub-categories
+    pub category_ids: Vec<Uuid>,
+    pub wallet: Option<FilterRef>,
+    // Words left over, searched for in descriptions
+    pub keyword: Option<String>,
+    pub language: String, // id, en
+}
+
+// Sums over the transactions matching a filter (see db::get_question_totals)
+#[derive(Debug, Clone, Default, FromRow)]
+pub struct QuestionTotals {
+    pub total: f64,
+    pub net: f64,
+    pub count: i64,
+}
+
+// Lowercased words of the question with a flag for words that were already understood
+struct Words {
+    words: Vec<String>,
+    used: Vec<bool>,
+}
+
+impl Words {
+    fn new(text: &str) -> Self {
+        let words: Vec<String> = text
+            .to_lowercase()
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+            .collect::<String>()
+            .split_whitespace()
+            .map(str::to_string)
+            .collect();
+        let used = vec![false; words.len()];
+        Words { words, used }
+    }
+
+    fn count_of(&self, hints: &[&str]) -> usize {
+        self.words.iter().filter(|word| hints.contains(&word.as_str())).count()
+    }
+
+    // Marks the first unused occurrence of `phrase` as used and returns where it starts
+    fn take(&mut self, phrase: &[&str]) -> Option<usize> {
+        if phrase.is_empty() || phrase.len() > self.words.len() {
+            return None;
+        }
+        let start = (0..=self.words.len() - phrase.len()).find(|start| {
+            phrase
+                .iter()
+                .enumerate()
+                .all(|(offset, word)| !self.used[start + offset] && self.words[start + offset] == *word)
+        })?;
+        self.used[start..start + phrase.len()].fill(true);
+        Some(start)
+    }
+
+    // Marks every occurrence of the phrases; true when any was found
+    fn take_all(&mut self, phrases: &[&[&str]]) -> bool {
+        let mut found = false;
+        for phrase in phrases {
+            while self.take(phrase).is_some() {
+                found = true;
+            }
+        }
+        found
+    }
+
+    fn take_words(&mut self, words: &[&str]) -> bool {
+        let phrases: Vec<[&str; 1]> = words.iter().map(|word| [*word]).collect();
+        let phrases: Vec<&[&str]> = phrases.iter().map(|phrase| phrase.as_slice()).collect();
+        self.take_all(&phrases)
+    }
+
+    fn unused_at(&self, index: usize) -> Option<&str> {
+        match self.used.get(index) {
+            Some(false) => Some(self.words[index].as_str()),
+            _ => None,
+        }
+    }
+
+    fn mark(&mut self, index: usize) {
+        self.used[index] = true;
+    }
+
+    fn leftover(&self) -> Vec<&str> {
+        self.words
+            .iter()
+            .zip(&self.used)
+            .filter(|(_, used)| !**used)
+            .map(|(word, _)| word.as_str())
+            .collect()
+    }
+}
+
+struct Period {
+    start: NaiveDate,
+    end: NaiveDate,
+    label: String,
+}
+
+fn label(english: bool, indonesian: &str, english_text: &str) -> String {
+    if english { english_text } else { indonesian }.to_string()
+}
+
+fn parse_relative_period(words: &mut Words, today: NaiveDate, english: bool) -> Option<Period> {
+    let week_start = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    let month_start = today.with_day(1).unwrap();
+    let year_start = NaiveDate::from_ymd_opt(today.year(), 1, 1).unwrap();
+
+    // Longer phrases first, so "minggu kemarin" is not read as "kemarin"
+    let periods: [(Phrases, NaiveDate, NaiveDate, &str, &str); 8] = [
+        (&[&["minggu", "ini"], &["pekan", "ini"], &["this", "week"]], week_start, today, "minggu ini", "this week"),
+        (
+            &[&["minggu", "lalu"], &["pekan", "lalu"], &["minggu", "kemarin"], &["last", "week"]],
+            week_start - Duration::days(7),
+            week_start - Duration::days(1),
+            "minggu lalu",
+            "last week",
+        ),
+        (&[&["bulan", "ini"], &["this", "month"]], month_start, today, "bulan ini", "this month"),
+        (
+            &[&["bulan", "lalu"], &["bulan", "kemarin"], &["last", "month"]],
+            shift_months(month_start, -1, 1),
+            month_start - Duration::days(1),
+            "bulan lalu",
+            "last month",
+        ),
+        (&[&["tahun", "ini"], &["this", "year"]], year_start, today, "tahun ini", "this year"),
+        (
+            &[&["tahun", "lalu"], &["tahun", "kemarin"], &["last", "year"]],
+            NaiveDate::from_ymd_opt(today.year() - 1, 1, 1).unwrap(),
+            year_start - Duration::days(1),
+            "tahun lalu",
+            "last year",
+        ),
+        (&[&["hari", "ini"], &["today"]], today, today, "hari ini", "today"),
+        (
+            &[&["kemarin"], &["yesterday"]],
+            today - Duration::days(1),
+            today - Duration::days(1),
+            "kemarin",
+            "yesterday",
+        ),
+    ];
+
+    for (phrases, start, end, indonesian, english_text) in periods {
+        if phrases.iter().any(|phrase| words.take(phrase).is_some()) {
+            return Some(Period { start, end, label: label(english, indonesian, english_text) });
+        }
+    }
+    None
+}
+
+// "3 bulan terakhir", "30 hari belakangan", "last 2 weeks", "past 6 months"
+fn parse_trailing_period(words: &mut Words, today: NaiveDate, english: bool) -> Option<Period> {
+    for index in 0..words.words.len() {
+        let Some(count) = words.unused_at(index).and_then(|word| word.parse::<i32>().ok()) else {
+            continue;
+        };
+        if !(1..=366).contains(&count) {
+            continue;
+        }
+        let Some(unit) = words.unused_at(index + 1).map(str::to_string) else { continue };
+
+        let trailing_word = words
+            .unused_at(index + 2)
+            .is_some_and(|word| word == "terakhir" || word == "belakangan");
+        let leading_word = index > 0
+            && words
+                .unused_at(index - 1)
+                .is_some_and(|word| word == "last" || word == "past");
+        if !trailing_word && !leading_word {
+            continue;
+        }
+
+        let (start, indonesian, english_text) = match unit.as_str() {
+            "hari" | "day" | "days" => (today - Duration::days(count as i64 - 1), "hari", "days"),
+            "minggu" | "pekan" | "week" | "weeks" => {
+                (today - Duration::days(7 * count as i64 - 1), "minggu", "weeks")
+            }
+            "bulan" | "month" | "months" => (
+                shift_months(today, -count, today.day() as i32) + Duration::days(1),
+                "bulan",
+                "months",
+            ),
+            "tahun" | "year" | "years" => (
+                shift_months(today, -12 * count, today.day() as i32) + Duration::days(1),
+                "tahun",
+                "years",
+            ),
+            _ => continue,
+        };
+
+        words.mark(index);
+        words.mark(index + 1);
+        if trailing_word {
+            words.mark(index + 2);
+        } else {
+            words.mark(index - 1);
+        }
+
+        return Some(Period {
+            start,
+            end: today,
+            label: if english {
+                format!("last {} {}", count, english_text)
+            } else {
+                format!("{} {} terakhir", count, indonesian)
+            },
+        });
+    }
+    None
+}
+
+fn parse_year(word: &str) -> Option<i32> {
+    word.parse::<i32>()
+        .ok()
+        .filter(|year| word.len() == 4 && (1900..=2200).contains(year))
+}
+
+// "agustus", "agustus 2025", "aug 2025", "2025"
+fn parse_calendar_period(words: &mut Words, today: NaiveDate, english: bool) -> Option<Period> {
+    for index in 0..words.words.len() {
+        let Some(month) = words.unused_at(index).and_then(|word| {
+            MONTH_WORDS
+                .iter()
+                .find(|(name, _)| *name == word)
+                .map(|(_, month)| *month)
+        }) else {
+            continue;
+        };
+
+        let next_year = words.unused_at(index + 1).and_then(parse_year);
+        if AMBIGUOUS_MONTH_WORDS.contains(&words.words[index].as_str())
+            && next_year.is_none()
+            && !(index > 0 && MONTH_CUES.contains(&words.words[index - 1].as_str()))
+        {
+            continue;
+        }
+
+        words.mark(index);
+        let year = match next_year {
+            Some(year) => {
+                words.mark(index + 1);
+                year
+            }
+            // Without a year, the latest such month that has started
+            None if month > today.month() => today.year() - 1,
+            None => today.year(),
+        };
+
+        let month_name = if english { MONTH_NAMES_EN } else { MONTH_NAMES_ID }[month as usize - 1];
+        return Some(Period {
+            start: day_of_month(year, month, 1),
+            end: day_of_month(year, month, 31),
+            label: format!("{} {}", month_name, year),
+        });
+    }
+
+    for index in 0..words.words.len() {
+        let Some(year) = words.unused_at(index).and_then(parse_year) else { continue };
+        words.mark(index);
+        return Some(Period {
+            start: NaiveDate::from_ymd_opt(year, 1, 1).unwrap(),
+            end: NaiveDate::from_ymd_opt(year, 12, 31).unwrap(),
+            label: if english { year.to_string() } else { format!("tahun {}", year) },
+        });
+    }
+    None
+}
+
+fn name_words(name: &str) -> Vec<String> {
+    Words::new(name).words
+}
+
+// A category and its sub-categories, at any depth
+fn with_subcategories(categories: &[Category], roots: &[Uuid]) -> Vec<Uuid> {
+    let mut ids = roots.to_vec();
+    let mut index = 0;
+    while index < ids.len() {
+        let parent_id = ids[index];
+        for category in categories {
+            if category.parent_id == Some(parent_id) && !ids.contains(&category.id) {
+                ids.push(category.id);
+            }
+        }
+        index += 1;
+    }
+    ids
+}
+
+fn parse_categories(words: &mut Words, categories: &[Category]) -> Vec<Category> {
+    let mut found: Vec<Category> = Vec::new();
+
+    // The user's own category names first, longest first so "Makan Siang" beats "Makan"
+    let mut named: Vec<(Vec<String>, &Category)> = categories
+        .iter()
+        .map(|category| (name_words(&category.name), category))
+        .filter(|(name, _)| !name.is_empty())
+        .collect();
+    named.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+
+    for (name, category) in &named {
+        let phrase: Vec<&str> = name.iter().map(String::as_str).collect();
+        if words.take_all(&[phrase.as_slice()]) && !found.iter().any(|c| c.id == category.id) {
+            found.push((*category).clone());
+        }
+    }
+
+    // Everyday words for categories the user still has under their default names
+    for &(category_name, synonyms) in CATEGORY_SYNONYMS {
+        let Some(category) = categories
+            .iter()
+            .find(|category| category.name.to_lowercase() == category_name)
+        else {
+            continue;
+        };
+        if words.take_words(synonyms) && !found.iter().any(|c| c.id == category.id) {
+            found.push(category.clone());
+        }
+    }
+
+    found
+}
+
+fn parse_wallet(words: &mut Words, wallets: &[Wallet]) -> Option<Wallet> {
+    let mut named: Vec<(Vec<String>, &Wallet)> = wallets
+        .iter()
+        .map(|wallet| (name_words(&wallet.name), wallet))
+        .filter(|(name, _)| !name.is_empty())
+        .collect();
+    named.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+
+    named.into_iter().find_map(|(name, wallet)| {
+        let phrase: Vec<&str> = name.iter().map(String::as_str).collect();
+        words.take(&phrase).map(|_| wallet.clone())
+    })
+}
+
+// Turns a question such as "berapa pengeluaran makanan bulan lalu?" or "how much did I spend
+// on transport this week" into a filter. Periods, the user's categories and wallets, the kind
+// of number asked for and income/expense cues are recognized by word lists; words left over
+// become a description search. Returns None when nothing in the question was recognized.
+pub fn interpret_question(
+    question: &str,
+    today: NaiveDate,
+    categories: &[Category],
+    wallets: &[Wallet],
+) -> Option<QuestionFilter> {
+    let mut words = Words::new(question);
+    let english = words.count_of(ENGLISH_HINTS) > words.count_of(INDONESIAN_HINTS);
+
+    let period = parse_relative_period(&mut words, today, english)
+        .or_else(|| parse_trailing_period(&mut words, today, english))
+        .or_else(|| parse_calendar_period(&mut words, today, english));
+    let found_categories = parse_categories(&mut words, categories);
+    let wallet = parse_wallet(&mut words, wallets);
+
+    let metric = if words.take_all(COUNT_PHRASES) {
+        Some("count")
+    } else if words.take_all(AVERAGE_PHRASES) {
+        Some("average")
+    } else if words.take_all(LARGEST_PHRASES) {
+        Some("largest")
+    } else if words.take_all(NET_PHRASES) {
+        Some("net")
+    } else {
+        None
+    };
+    let expense_cue = words.take_words(EXPENSE_WORDS);
+    let income_cue = words.take_words(INCOME_WORDS);
+
+    if period.is_none()
+        && found_categories.is_empty()
+        && wallet.is_none()
+        && metric.is_none()
+        && !expense_cue
+        && !income_cue
+    {
+        return None;
+    }
+
+    let metric = metric.unwrap_or("total");
+    let transaction_type = if metric == "net" {
+        None
+    } else if income_cue && !expense_cue {
+        Some("income".to_string())
+    } else if expense_cue {
+        Some("expense".to_string())
+    } else {
+        // "gaji bulan ini" asks about income through the category's type
+        Some(
+            found_categories
+                .first()
+                .map(|category| category.category_type.clone())
+                .unwrap_or_else(|| "expense".to_string()),
+        )
+    };
+
+    let keyword: Vec<&str> = words
+        .leftover()
+        .into_iter()
+        .filter(|word| !STOPWORDS.contains(word) && word.parse::<f64>().is_err())
+        .collect();
+
+    let period = period.unwrap_or_else(|| Period {
+        start: today.with_day(1).unwrap(),
+        end: today,
+        label: label(english, "bulan ini", "this month"),
+    });
+
+    let roots: Vec<Uuid> = found_categories.iter().map(|category| category.id).collect();
+    Some(QuestionFilter {
+        metric: metric.to_string(),
+        transaction_type,
+        start_date: period.start,
+        end_date: period.end,
+        period: period.label,
+        category_ids: with_subcategories(categories, &roots),
+        categories: found_categories
+            .into_iter()
+            .map(|category| FilterRef { id: category.id, name: category.name })
+            .collect(),
+        wallet: wallet.map(|wallet| FilterRef { id: wallet.id, name: wallet.name }),
+        keyword: (!keyword.is_empty()).then(|| keyword.join(" ")),
+        language: if english { "en" } else { "id" }.to_string(),
+    })
+}
+
+// One-sentence answer in the question's language, e.g.
+// "Total pengeluaran untuk Makanan bulan lalu (01/09/2026 - 30/09/2026): Rp1.250.000 dari 23 transaksi"
+pub fn answer_sentence(
+    filter: &QuestionFilter,
+    value: f64,
+    count: i64,
+    largest: Option<(&str, NaiveDate)>,
+) -> String {
+    let english = filter.language == "en";
+    let income = filter.transaction_type.as_deref() == Some("income");
+
+    let subject = match (filter.metric.as_str(), english, income) {
+        ("count", false, false) => "Jumlah transaksi pengeluaran",
+        ("count", false, true) => "Jumlah transaksi pemasukan",
+        ("count", true, false) => "Number of expense transactions",
+        ("count", true, true) => "Number of income transactions",
+        ("average", false, false) => "Rata-rata pengeluaran per transaksi",
+        ("average", false, true) => "Rata-rata pemasukan per transaksi",
+        ("average", true, false) => "Average spending per transaction",
+        ("average", true, true) => "Average income per transaction",
+        ("largest", false, false) => "Pengeluaran terbesar",
+        ("largest", false, true) => "Pemasukan terbesar",
+        ("largest", true, false) => "Largest expense",
+        ("largest", true, true) => "Largest income",
+        ("net", false, _) => "Pemasukan dikurangi pengeluaran",
+        ("net", true, _) => "Income minus spending",
+        (_, false, false) => "Total pengeluaran",
+        (_, false, true) => "Total pemasukan",
+        (_, true, false) => "Total spending",
+        (_, true, true) => "Total income",
+    };
+
+    let mut sentence = subject.to_string();
+    if !filter.categories.is_empty() {
+        let names: Vec<&str> = filter.categories.iter().map(|category| category.name.as_str()).collect();
+        sentence.push_str(if english { " on " } else { " untuk " });
+        sentence.push_str(&names.join(", "));
+    }
+    if let Some(wallet) = &filter.wallet {
+        sentence.push_str(if english { " from wallet " } else { " dari dompet " });
+        sentence.push_str(&wallet.name);
+    }
+    if let Some(keyword) = &filter.keyword {
+        sentence.push_str(&if english {
+            format!(" matching \"{}\"", keyword)
+        } else {
+            format!(" dengan kata \"{}\"", keyword)
+        });
+    }
+    sentence.push_str(&format!(
+        " {} ({} - {}): ",
+        filter.period,
+        filter.start_date.format("%d/%m/%Y"),
+        filter.end_date.format("%d/%m/%Y")
+    ));
+
+    match filter.metric.as_str() {
+        "count" => sentence.push_str(&format!(
+            "{} {}",
+            count,
+            if english { "transactions" } else { "transaksi" }
+        )),
+        "largest" => match largest {
+            Some((description, date)) => sentence.push_str(&format!(
+                "{} ({}, {})",
+                format_rupiah(value),
+                description,
+                date.format("%d/%m/%Y")
+            )),
+            None => sentence.push_str(if english { "no transactions" } else { "tidak ada transaksi" }),
+        },
+        "total" => sentence.push_str(&format!(
+            "{} {} {} {}",
+            format_rupiah(value),
+            if english { "across" } else { "dari" },
+            count,
+            if english { "transactions" } else { "transaksi" }
+        )),
+        _ => sentence.push_str(&format_rupiah(value)),
+    }
+
+    sentence
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn category(name: &str, category_type: &str, parent_id: Option<Uuid>) -> Category {
+        Category {
+            id: Uuid::new_v4(),
+            user_id: None,
+            name: name.to_string(),
+            icon: None,
+            color: None,
+            category_type: category_type.to_string(),
+            parent_id,
+            created_at: Utc::now(),
+            deleted_at: None,
+        }
+    }
+
+    fn wallet(name: &str) -> Wallet {
+        Wallet {
+            id: Uuid::new_v4(),
+            user_id: Uuid::nil(),
+            name: name.to_string(),
+            wallet_type: "bank".to_string(),
+            balance: 0.0,
+            icon: None,
+            color: None,
+            credit_limit: None,
+            statement_closing_day: None,
+            payment_due_day: None,
+            is_default: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            deleted_at: None,
+        }
+    }
+
+    // Asked on Monday 19 October 2026
+    fn interpret(question: &str, categories: &[Category], wallets: &[Wallet]) -> Option<QuestionFilter> {
+        interpret_question(question, date(2026, 10, 19), categories, wallets)
+    }
+
+    #[test]
+    fn last_month_with_category_and_subcategories() {
+        let makanan = category("Makanan", "expense", None);
+        let makan_siang = category("Makan Siang", "expense", Some(makanan.id));
+        let categories = [makanan.clone(), makan_siang.clone(), category("Gaji", "income", None)];
+
+        let filter = interpret("berapa pengeluaran makanan bulan lalu?", &categories, &[]).unwrap();
+        assert_eq!(filter.metric, "total");
+        assert_eq!(filter.transaction_type.as_deref(), Some("expense"));
+        assert_eq!((filter.start_date, filter.end_date), (date(2026, 9, 1), date(2026, 9, 30)));
+        assert_eq!(filter.period, "bulan lalu");
+        assert_eq!(filter.categories.len(), 1);
+        assert_eq!(filter.categories[0].name, "Makanan");
+        assert_eq!(filter.category_ids, vec![makanan.id, makan_siang.id]);
+        assert_eq!(filter.keyword, None);
+        assert_eq!(filter.language, "id");
+    }
+
+    #[test]
+    fn trailing_months() {
+        let filter = interpret("pengeluaran 3 bulan terakhir", &[], &[]).unwrap();
+        assert_eq!((filter.start_date, filter.end_date), (date(2026, 7, 20), date(2026, 10, 19)));
+        assert_eq!(filter.period, "3 bulan terakhir");
+
+        let filter = interpret("how much did I spend in the last 2 weeks", &[], &[]).unwrap();
+        assert_eq!((filter.start_date, filter.end_date), (date(2026, 10, 6), date(2026, 10, 19)));
+        assert_eq!(filter.period, "last 2 weeks");
+        assert_eq!(filter.language, "en");
+    }
+
+    #[test]
+    fn month_name_with_and_without_year() {
+        let filter = interpret("pemasukan agustus 2025", &[], &[]).unwrap();
+        assert_eq!(filter.transaction_type.as_deref(), Some("income"));
+        assert_eq!((filter.start_date, filter.end_date), (date(2025, 8, 1), date(2025, 8, 31)));
+        assert_eq!(filter.period, "Agustus 2025");
+
+        // Without a year: the latest such month that has started
+        let filter = interpret("pengeluaran agustus", &[], &[]).unwrap();
+        assert_eq!(filter.start_date, date(2026, 8, 1));
+        let filter = interpret("pengeluaran desember", &[], &[]).unwrap();
+        assert_eq!((filter.start_date, filter.end_date), (date(2025, 12, 1), date(2025, 12, 31)));
+    }
+
+    #[test]
+    fn may_and_mar_need_a_year_or_a_cue() {
+        let filter = interpret("how much may I spend on food", &[], &[]).unwrap();
+        assert_eq!((filter.start_date, filter.end_date), (date(2026, 10, 1), date(2026, 10, 19)));
+        assert_eq!(filter.keyword.as_deref(), Some("food"));
+
+        let filter = interpret("how much did I spend in may", &[], &[]).unwrap();
+        assert_eq!((filter.start_date, filter.end_date), (date(2026, 5, 1), date(2026, 5, 31)));
+        assert_eq!(filter.period, "May 2026");
+        let filter = interpret("expenses may 2025", &[], &[]).unwrap();
+        assert_eq!(filter.start_date, date(2025, 5, 1));
+        let filter = interpret("pengeluaran bulan mar", &[], &[]).unwrap();
+        assert_eq!(filter.start_date, date(2026, 3, 1));
+    }
+
+    #[test]
+    fn metric_wallet_and_keyword() {
+        let wallets = [wallet("BCA"), wallet("GoPay")];
+
+        let filter = interpret("berapa kali bayar shopee pakai gopay minggu lalu", &[], &wallets).unwrap();
+        assert_eq!(filter.metric, "count");
+        assert_eq!(filter.wallet.as_ref().map(|wallet| wallet.name.as_str()), Some("GoPay"));
+        assert_eq!((filter.start_date, filter.end_date), (date(2026, 10, 12), date(2026, 10, 18)));
+        assert_eq!(filter.keyword.as_deref(), Some("shopee"));
+    }
+
+    #[test]
+    fn income_category_sets_the_type() {
+        let categories = [category("Gaji", "income", None)];
+
+        let filter = interpret("gaji tahun ini", &categories, &[]).unwrap();
+        assert_eq!(filter.transaction_type.as_deref(), Some("income"));
+        assert_eq!((filter.start_date, filter.end_date), (date(2026, 1, 1), date(2026, 10, 19)));
+    }
+
+    #[test]
+    fn defaults_to_this_month_and_rejects_unrelated_text() {
+        let filter = interpret("rata-rata pengeluaran", &[], &[]).unwrap();
+        assert_eq!(filter.metric, "average");
+        assert_eq!((filter.start_date, filter.end_date), (date(2026, 10, 1), date(2026, 10, 19)));
+
+        assert!(interpret("halo apa kabar", &[], &[]).is_none());
+    }
+}
@@ -0,0 +1,131 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::installment::round_currency;
+use crate::utils::dates::shift_months;
+
+pub const RECURRING_FREQUENCIES: [&str; 3] = ["weekly", "monthly", "yearly"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RecurringTransaction {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub wallet_id: Uuid,
+    pub category_id: Option<Uuid>,
+    pub transaction_type: String,
+    pub amount: f64,
+    pub description: String,
+    pub frequency: String, // weekly, monthly, yearly
+    pub interval_count: i32,
+    pub anchor_day: i16,
+    pub next_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+    pub auto_post: bool, // false = only send a reminder
+    pub subscription_key: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+impl RecurringTransaction {
+    // Occurrence after `date`; monthly and yearly schedules keep returning to anchor_day
+    pub fn occurrence_after(&self, date: NaiveDate) -> NaiveDate {
+        next_occurrence(date, &self.frequency, self.interval_count, self.anchor_day as i32)
+    }
+}
+
+pub fn next_occurrence(date: NaiveDate, frequency: &str, interval_count: i32, anchor_day: i32) -> NaiveDate {
+    match frequency {
+        "weekly" => date + Duration::weeks(interval_count as i64),
+        "yearly" => shift_months(date, 12 * interval_count, anchor_day),
+        _ => shift_months(date, interval_count, anchor_day),
+    }
+}
+
+// How often a schedule occurs in a year
+pub fn occurrences_per_year(frequency: &str, interval_count: i32) -> f64 {
+    let per_year = match frequency {
+        "weekly" => 52.0,
+        "yearly" => 1.0,
+        _ => 12.0,
+    };
+    per_year / interval_count as f64
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateRecurringRequest {
+    pub wallet_id: Uuid,
+    pub category_id: Option<Uuid>,
+    pub transaction_type: String,
+    pub amount: f64,
+    #[validate(length(min = 1, max = 255, message = "Deskripsi wajib diisi (maksimal 255 karakter)"))]
+    pub description: String,
+    pub frequency: String,
+    pub interval_count: Option<i32>,
+    // First occurrence, defaults to today; a past date books the occurrences already due
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub auto_post: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateRecurringRequest {
+    pub wallet_id: Option<Uuid>,
+    pub category_id: Option<Uuid>,
+    pub amount: Option<f64>,
+    #[validate(length(min = 1, max = 255, message = "Deskripsi wajib diisi (maksimal 255 karakter)"))]
+    pub description: Option<String>,
+    // Moves the schedule; later occurrences follow this date's day of month
+    pub next_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub auto_post: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecurringResponse {
+    pub id: Uuid,
+    pub wallet_id: Uuid,
+    pub category_id: Option<Uuid>,
+    pub transaction_type: String,
+    pub amount: f64,
+    pub description: String,
+    pub frequency: String,
+    pub interval_count: i32,
+    pub next_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+    pub auto_post: bool,
+    pub subscription_key: Option<String>,
+    pub annual_amount: f64,
+    pub status: String, // active, ended
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<RecurringTransaction> for RecurringResponse {
+    fn from(recurring: RecurringTransaction) -> Self {
+        let annual_amount = recurring.amount * occurrences_per_year(&recurring.frequency, recurring.interval_count);
+        let ended = recurring.end_date.is_some_and(|end_date| recurring.next_date > end_date);
+
+        RecurringResponse {
+            id: recurring.id,
+            wallet_id: recurring.wallet_id,
+            category_id: recurring.category_id,
+            transaction_type: recurring.transaction_type,
+            amount: recurring.amount,
+            description: recurring.description,
+            frequency: recurring.frequency,
+            interval_count: recurring.interval_count,
+            next_date: recurring.next_date,
+            end_date: recurring.end_date,
+            auto_post: recurring.auto_post,
+            subscription_key: recurring.subscription_key,
+            annual_amount: round_currency(annual_amount),
+            status: if ended { "ended" } else { "active" }.to_string(),
+            created_at: recurring.created_at,
+            updated_at: recurring.updated_at,
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+// Bucket sizes for time-series reports, as (name, date_trunc unit, step interval)
+pub const REPORT_GROUPINGS: [(&str, &str, &str); 5] = [
+    ("day", "day", "1 day"),
+    ("week", "week", "1 week"),
+    ("month", "month", "1 month"),
+    ("quarter", "quarter", "3 months"),
+    ("year", "year", "1 year"),
+];
+
+#[derive(Debug, Deserialize)]
+pub struct CashFlowQuery {
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub group_by: Option<String>, // day, week, month, quarter, year
+    pub wallet_id: Option<Uuid>,
+    // Includes the category's sub-categories
+    pub category_id: Option<Uuid>,
+}
+
+// Totals of one period; periods without transactions come back with zeros
+// (see db::get_cash_flow)
+#[derive(Debug, Clone, FromRow)]
+pub struct CashFlowBucket {
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    pub income: f64,
+    pub expense: f64,
+    pub transaction_count: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CashFlowBucketResponse {
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    pub income: f64,
+    pub expense: f64,
+    pub net: f64,
+    pub running_balance: f64,
+    pub transaction_count: i64,
+}
+
+// Total of one category bucket (see db::get_category_totals)
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct CategoryStat {
+    pub id: Uuid,
+    pub name: String,
+    pub icon: Option<String>,
+    pub color: Option<String>,
+    pub total: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ComparisonQuery {
+    // mom (this month vs last month), yoy (this month vs the same month last year) or custom
+    pub mode: Option<String>,
+    // Month compared by mom/yoy, defaults to the current month
+    pub month: Option<i32>,
+    pub year: Option<i32>,
+    // Periods for mode=custom
+    pub current_start: Option<NaiveDate>,
+    pub current_end: Option<NaiveDate>,
+    pub previous_start: Option<NaiveDate>,
+    pub previous_end: Option<NaiveDate>,
+    pub transaction_type: Option<String>, // expense (default) or income
+    pub parent_id: Option<Uuid>,
+    // Number of top movers in each direction
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ComparisonPeriod {
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub total: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryComparison {
+    pub category_id: Uuid,
+    pub name: String,
+    pub icon: Option<String>,
+    pub color: Option<String>,
+    pub current_total: f64,
+    pub previous_total: f64,
+    pub change_amount: f64,
+    // NULL when the category had nothing in the previous period
+    pub change_percentage: Option<f64>,
+    pub status: String, // new, disappeared, increased, decreased, unchanged
+}
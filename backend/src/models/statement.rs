@@ -0,0 +1,494 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::fmt::Write;
+use uuid::Uuid;
+
+use crate::models::budget::BudgetWithUsage;
+use crate::models::installment::round_currency;
+use crate::models::report::CategoryStat;
+use crate::utils::{
+    format::{format_date_id, format_rupiah, MONTH_NAMES_ID},
+    html::{escape_html, html_section, html_table},
+    pdf::{Column, PdfWriter},
+};
+
+pub const STATEMENT_FORMATS: [&str; 3] = ["json", "html", "pdf"];
+// Transactions listed in one statement; totals always cover the whole month
+pub const MAX_STATEMENT_TRANSACTIONS: i64 = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct StatementQuery {
+    // Defaults to the current month
+    pub month: Option<i32>,
+    pub year: Option<i32>,
+    pub format: Option<String>, // json (default), html or pdf
+}
+
+// Active wallet with its balance at the start and end of the period
+// (see db::get_statement_wallets)
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct StatementWallet {
+    pub id: Uuid,
+    pub name: String,
+    pub wallet_type: String,
+    pub opening_balance: f64,
+    pub closing_balance: f64,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct StatementTransaction {
+    pub id: Uuid,
+    pub date: NaiveDate,
+    pub transaction_type: String,
+    pub amount: f64,
+    pub description: Option<String>,
+    pub category_name: Option<String>,
+    pub wallet_name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatementSummary {
+    pub opening_balance: f64,
+    pub income: f64,
+    pub expense: f64,
+    pub net: f64,
+    // Net money in from loans and their repayments, which are not income or expense
+    pub transfers: f64,
+    pub closing_balance: f64,
+    pub transaction_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatementCategory {
+    pub name: String,
+    pub total: f64,
+    pub percentage: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatementBudget {
+    pub category_name: Option<String>, // None for a budget over all spending
+    pub amount: f64,
+    pub used_amount: f64,
+    pub remaining_amount: f64,
+    pub usage_percentage: f64,
+    pub is_over_budget: bool,
+}
+
+impl From<BudgetWithUsage> for StatementBudget {
+    fn from(row: BudgetWithUsage) -> Self {
+        let amount = row.budget.amount;
+        StatementBudget {
+            category_name: row.category_name,
+            amount,
+            used_amount: round_currency(row.used_amount),
+            remaining_amount: round_currency(amount - row.used_amount),
+            usage_percentage: if amount > 0.0 {
+                (row.used_amount / amount * 1000.0).round() / 10.0
+            } else {
+                0.0
+            },
+            is_over_budget: row.used_amount > amount,
+        }
+    }
+}
+
+// Everything shown on a monthly statement (see db::get_monthly_statement)
+#[derive(Debug, Clone, Serialize)]
+pub struct Statement {
+    pub user_name: String,
+    pub user_email: String,
+    pub year: i32,
+    pub month: u32,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub generated_at: DateTime<Utc>,
+    pub summary: StatementSummary,
+    pub expense_categories: Vec<StatementCategory>,
+    pub income_categories: Vec<StatementCategory>,
+    pub budgets: Vec<StatementBudget>,
+    pub wallets: Vec<StatementWallet>,
+    pub transactions: Vec<StatementTransaction>,
+    // More than MAX_STATEMENT_TRANSACTIONS in the month; the oldest are listed
+    pub transactions_truncated: bool,
+}
+
+// Category totals with their share of `total`. Amounts without a category make up the
+// difference and get a row of their own.
+pub fn category_breakdown(stats: Vec<CategoryStat>, total: f64) -> Vec<StatementCategory> {
+    let categorized = stats.iter().fold(0.0, |sum, stat| sum + stat.total);
+    let mut rows: Vec<(String, f64)> = stats.into_iter().map(|stat| (stat.name, stat.total)).collect();
+    if total - categorized >= 0.01 {
+        rows.push(("Tanpa kategori".to_string(), total - categorized));
+    }
+
+    rows.into_iter()
+        .map(|(name, amount)| StatementCategory {
+            name,
+            total: round_currency(amount),
+            percentage: if total > 0.0 {
+                (amount / total * 1000.0).round() / 10.0
+            } else {
+                0.0
+            },
+        })
+        .collect()
+}
+
+fn percent(value: f64) -> String {
+    format!("{:.1}%", value)
+}
+
+fn signed_amount(transaction: &StatementTransaction) -> String {
+    if transaction.transaction_type == "income" {
+        format!("+{}", format_rupiah(transaction.amount))
+    } else {
+        format!("-{}", format_rupiah(transaction.amount))
+    }
+}
+
+// Table rows shared by statements and email digests
+pub fn category_rows(categories: &[StatementCategory]) -> Vec<Vec<String>> {
+    categories
+        .iter()
+        .map(|category| vec![category.name.clone(), format_rupiah(category.total), percent(category.percentage)])
+        .collect()
+}
+
+pub fn budget_rows(budgets: &[StatementBudget]) -> Vec<Vec<String>> {
+    budgets
+        .iter()
+        .map(|budget| {
+            vec![
+                budget.category_name.clone().unwrap_or_else(|| "Semua pengeluaran".to_string()),
+                format_rupiah(budget.amount),
+                format_rupiah(budget.used_amount),
+                format_rupiah(budget.remaining_amount),
+                percent(budget.usage_percentage),
+            ]
+        })
+        .collect()
+}
+
+pub const CATEGORY_COLUMNS: [(&str, bool); 3] = [("Kategori", false), ("Jumlah", true), ("Porsi", true)];
+pub const BUDGET_COLUMNS: [(&str, bool); 5] = [
+    ("Anggaran", false),
+    ("Batas", true),
+    ("Terpakai", true),
+    ("Sisa", true),
+    ("Pemakaian", true),
+];
+
+impl Statement {
+    pub fn month_name(&self) -> &'static str {
+        MONTH_NAMES_ID[self.month as usize - 1]
+    }
+
+    pub fn title(&self) -> String {
+        format!("Laporan Keuangan {} {}", self.month_name(), self.year)
+    }
+
+    pub fn file_name(&self, extension: &str) -> String {
+        format!("laporan-keuangan-{}-{:02}.{}", self.year, self.month, extension)
+    }
+
+    fn period(&self) -> String {
+        format!("{} - {}", format_date_id(self.start_date), format_date_id(self.end_date))
+    }
+
+    fn summary_rows(&self) -> Vec<(&'static str, String)> {
+        let summary = &self.summary;
+        vec![
+            ("Saldo awal", format_rupiah(summary.opening_balance)),
+            ("Pemasukan", format_rupiah(summary.income)),
+            ("Pengeluaran", format_rupiah(summary.expense)),
+            ("Selisih", format_rupiah(summary.net)),
+            ("Pinjaman & pelunasan", format_rupiah(summary.transfers)),
+            ("Saldo akhir", format_rupiah(summary.closing_balance)),
+            ("Jumlah transaksi", summary.transaction_count.to_string()),
+        ]
+    }
+
+    fn wallet_rows(&self) -> Vec<Vec<String>> {
+        self.wallets
+            .iter()
+            .map(|wallet| {
+                vec![
+                    wallet.name.clone(),
+                    wallet.wallet_type.clone(),
+                    format_rupiah(wallet.opening_balance),
+                    format_rupiah(wallet.closing_balance),
+                ]
+            })
+            .collect()
+    }
+
+    fn transaction_rows(&self) -> Vec<Vec<String>> {
+        self.transactions
+            .iter()
+            .map(|transaction| {
+                vec![
+                    transaction.date.format("%d/%m").to_string(),
+                    transaction.description.clone().unwrap_or_default(),
+                    transaction.category_name.clone().unwrap_or_else(|| "-".to_string()),
+                    transaction.wallet_name.clone(),
+                    signed_amount(transaction),
+                ]
+            })
+            .collect()
+    }
+
+    fn truncation_note(&self) -> Option<String> {
+        self.transactions_truncated.then(|| {
+            format!(
+                "Hanya {} transaksi pertama yang ditampilkan dari {} transaksi",
+                self.transactions.len(),
+                self.summary.transaction_count
+            )
+        })
+    }
+
+    // Standalone HTML document; also suitable as an email body
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html lang=\"id\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n</head>\n\
+             <body style=\"margin:0;padding:24px;background:#ffffff;color:#222;font-family:Helvetica,Arial,sans-serif\">\n\
+             <div style=\"max-width:720px;margin:0 auto\">\n\
+             <h1 style=\"font-size:22px;margin:0 0 4px\">{title}</h1>\n\
+             <p style=\"margin:0;color:#666;font-size:13px\">{name} &middot; {period}</p>\n",
+            title = escape_html(&self.title()),
+            name = escape_html(&self.user_name),
+            period = escape_html(&self.period()),
+        );
+
+        html_section(&mut html, "Ringkasan");
+        let summary: Vec<Vec<String>> = self
+            .summary_rows()
+            .into_iter()
+            .map(|(label, value)| vec![label.to_string(), value])
+            .collect();
+        html_table(&mut html, &[("Keterangan", false), ("Jumlah", true)], &summary);
+
+        html_section(&mut html, "Pengeluaran per kategori");
+        html_table(
+            &mut html,
+            &CATEGORY_COLUMNS,
+            &category_rows(&self.expense_categories),
+        );
+
+        html_section(&mut html, "Pemasukan per kategori");
+        html_table(
+            &mut html,
+            &CATEGORY_COLUMNS,
+            &category_rows(&self.income_categories),
+        );
+
+        html_section(&mut html, "Realisasi anggaran");
+        html_table(
+            &mut html,
+            &BUDGET_COLUMNS,
+            &budget_rows(&self.budgets),
+        );
+
+        html_section(&mut html, "Saldo wallet");
+        html_table(
+            &mut html,
+            &[("Wallet", false), ("Jenis", false), ("Saldo awal", true), ("Saldo akhir", true)],
+            &self.wallet_rows(),
+        );
+
+        html_section(&mut html, "Daftar transaksi");
+        html_table(
+            &mut html,
+            &[("Tanggal", false), ("Keterangan", false), ("Kategori", false), ("Wallet", false), ("Jumlah", true)],
+            &self.transaction_rows(),
+        );
+        if let Some(note) = self.truncation_note() {
+            let _ = writeln!(html, "<p style=\"font-size:12px;color:#666\">{}</p>", escape_html(&note));
+        }
+
+        let _ = write!(
+            html,
+            "<p style=\"margin-top:24px;font-size:11px;color:#999\">Dibuat pada {} UTC</p>\n</div>\n</body>\n</html>\n",
+            self.generated_at.format("%d/%m/%Y %H:%M")
+        );
+        html
+    }
+
+    pub fn to_pdf(&self) -> Result<Vec<u8>, printpdf::Error> {
+        let mut pdf = PdfWriter::new(&self.title())?;
+        let width = pdf.content_width();
+
+        pdf.text(&self.title(), 18.0, true);
+        pdf.text(&format!("{} - {}", self.user_name, self.period()), 10.0, false);
+        pdf.space(2.0);
+
+        pdf.heading("Ringkasan");
+        let columns = [Column::left(width - 60.0), Column::right(60.0)];
+        for (label, value) in self.summary_rows() {
+            pdf.row(&columns, &[label, &value], 10.0, false);
+        }
+
+        let category_columns = [Column::left(width - 75.0), Column::right(50.0), Column::right(25.0)];
+        for (heading, categories) in [
+            ("Pengeluaran per kategori", &self.expense_categories),
+            ("Pemasukan per kategori", &self.income_categories),
+        ] {
+            pdf.heading(heading);
+            pdf_table(&mut pdf, &category_columns, &["Kategori", "Jumlah", "Porsi"], &category_rows(categories));
+        }
+
+        pdf.heading("Realisasi anggaran");
+        pdf_table(
+            &mut pdf,
+            &[
+                Column::left(width - 144.0),
+                Column::right(38.0),
+                Column::right(38.0),
+                Column::right(38.0),
+                Column::right(30.0),
+            ],
+            &["Anggaran", "Batas", "Terpakai", "Sisa", "Pemakaian"],
+            &budget_rows(&self.budgets),
+        );
+
+        pdf.heading("Saldo wallet");
+        pdf_table(
+            &mut pdf,
+            &[Column::left(width - 110.0), Column::left(30.0), Column::right(40.0), Column::right(40.0)],
+            &["Wallet", "Jenis", "Saldo awal", "Saldo akhir"],
+            &self.wallet_rows(),
+        );
+
+        pdf.heading("Daftar transaksi");
+        pdf_table(
+            &mut pdf,
+            &[
+                Column::left(14.0),
+                Column::left(width - 134.0),
+                Column::left(40.0),
+                Column::left(40.0),
+                Column::right(40.0),
+            ],
+            &["Tgl", "Keterangan", "Kategori", "Wallet", "Jumlah"],
+            &self.transaction_rows(),
+        );
+        if let Some(note) = self.truncation_note() {
+            pdf.text(&note, 8.0, false);
+        }
+
+        pdf.space(4.0);
+        pdf.text(
+            &format!("Dibuat pada {} UTC", self.generated_at.format("%d/%m/%Y %H:%M")),
+            8.0,
+            false,
+        );
+        pdf.finish()
+    }
+}
+
+fn pdf_table(pdf: &mut PdfWriter, columns: &[Column], headers: &[&str], rows: &[Vec<String>]) {
+    if rows.is_empty() {
+        pdf.text("Tidak ada data", 9.0, false);
+        return;
+    }
+
+    pdf.header_row(columns, headers, 9.0);
+    for row in rows {
+        let cells: Vec<&str> = row.iter().map(String::as_str).collect();
+        pdf.row(columns, &cells, 9.0, false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn stat(name: &str, total: f64) -> CategoryStat {
+        CategoryStat { id: Uuid::new_v4(), name: name.to_string(), icon: None, color: None, total }
+    }
+
+    fn statement() -> Statement {
+        Statement {
+            user_name: "Budi <b>".to_string(),
+            user_email: "budi@test.local".to_string(),
+            year: 2025,
+            month: 3,
+            start_date: date(2025, 3, 1),
+            end_date: date(2025, 3, 31),
+            generated_at: Utc::now(),
+            summary: StatementSummary {
+                opening_balance: 1_000_000.0,
+                income: 500_000.0,
+                expense: 200_000.0,
+                net: 300_000.0,
+                transfers: -100_000.0,
+                closing_balance: 1_200_000.0,
+                transaction_count: 3,
+            },
+            expense_categories: category_breakdown(vec![stat("Makan & Minum", 200_000.0)], 200_000.0),
+            income_categories: Vec::new(),
+            budgets: Vec::new(),
+            wallets: vec![StatementWallet {
+                id: Uuid::new_v4(),
+                name: "Kas".to_string(),
+                wallet_type: "cash".to_string(),
+                opening_balance: 1_000_000.0,
+                closing_balance: 1_200_000.0,
+            }],
+            transactions: vec![StatementTransaction {
+                id: Uuid::new_v4(),
+                date: date(2025, 3, 5),
+                transaction_type: "expense".to_string(),
+                amount: 200_000.0,
+                description: Some("<script>alert('x')</script>".to_string()),
+                category_name: Some("Makan & Minum".to_string()),
+                wallet_name: "Kas".to_string(),
+            }],
+            transactions_truncated: false,
+        }
+    }
+
+    #[test]
+    fn category_breakdown_adds_uncategorized_remainder() {
+        let rows = category_breakdown(vec![stat("Makanan", 300.0), stat("Transport", 100.0)], 500.0);
+        let rows: Vec<(&str, f64, f64)> =
+            rows.iter().map(|row| (row.name.as_str(), row.total, row.percentage)).collect();
+        assert_eq!(rows, [("Makanan", 300.0, 60.0), ("Transport", 100.0, 20.0), ("Tanpa kategori", 100.0, 20.0)]);
+
+        assert!(category_breakdown(Vec::new(), 0.0).is_empty());
+    }
+
+    #[test]
+    fn html_escapes_user_supplied_text() {
+        let html = statement().to_html();
+
+        assert!(html.contains("Laporan Keuangan Maret 2025"));
+        assert!(html.contains("Budi &lt;b&gt;"));
+        assert!(html.contains("&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt;"));
+        assert!(html.contains("Makan &amp; Minum"));
+        assert!(!html.contains("<script>"));
+        assert!(!html.contains("<b>"));
+        // Empty sections say so instead of rendering an empty table
+        assert!(html.contains("Tidak ada data"));
+    }
+
+    #[test]
+    fn pdf_renders_long_statements() {
+        let mut statement = statement();
+        let transaction = statement.transactions[0].clone();
+        statement.transactions = vec![transaction; 300];
+        statement.transactions_truncated = true;
+
+        let pdf = statement.to_pdf().unwrap();
+        assert!(pdf.starts_with(b"%PDF"));
+        assert_eq!(statement.file_name("pdf"), "laporan-keuangan-2025-03.pdf");
+    }
+}
@@ -0,0 +1,284 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::models::anomaly::ExpenseRow;
+use crate::models::installment::round_currency;
+use crate::models::recurring::{next_occurrence, occurrences_per_year};
+use crate::utils::stats::median;
+
+// Months of history scanned; two years so yearly renewals show up twice
+pub const SUBSCRIPTION_HISTORY_MONTHS: i32 = 25;
+// Only the latest charges decide the amount and the rhythm, so a price change long ago
+// does not hide a subscription
+const RECENT_CHARGES: usize = 6;
+// Charges may differ this much from their median (taxes, exchange rates)
+const AMOUNT_TOLERANCE: f64 = 0.2;
+// Share of intervals that must match the period, so one late payment is forgiven
+const MIN_REGULAR_SHARE: f64 = 0.75;
+
+// (frequency, typical days between charges, allowed deviation in days, minimum charges)
+const SUBSCRIPTION_PERIODS: [(&str, i64, i64, usize); 3] = [
+    ("weekly", 7, 2, 4),
+    ("monthly", 30, 5, 3),
+    ("yearly", 365, 20, 2),
+];
+
+// recurring = a recurring transaction, bill = a bill with payment reminders
+pub const SUBSCRIPTION_TARGETS: [&str; 2] = ["recurring", "bill"];
+
+#[derive(Debug, Deserialize)]
+pub struct SubscriptionQuery {
+    // Also list subscriptions whose charges stopped
+    pub include_inactive: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConvertSubscriptionRequest {
+    pub key: String,
+    // Defaults to recurring
+    pub target: Option<String>,
+    // Recurring only: false creates a reminder instead of booking the charge automatically
+    pub auto_post: Option<bool>,
+    pub wallet_id: Option<Uuid>,
+    pub amount: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DetectedSubscription {
+    // Normalized description shared by the charges
+    pub key: String,
+    pub name: String,
+    pub frequency: String, // weekly, monthly, yearly
+    pub amount: f64,
+    pub annual_cost: f64,
+    pub charge_count: usize,
+    pub average_interval_days: f64,
+    pub first_date: NaiveDate,
+    pub last_date: NaiveDate,
+    pub next_expected_date: NaiveDate,
+    pub wallet_id: Uuid,
+    pub category_id: Option<Uuid>,
+    pub category_name: Option<String>,
+    pub status: String, // active, inactive
+    // Set once the subscription was turned into a recurring transaction
+    pub recurring_id: Option<Uuid>,
+    // Set once the subscription was turned into a bill
+    pub bill_id: Option<Uuid>,
+}
+
+// Lowercase letters only, so "Spotify Premium 10/2026" and "SPOTIFY premium #11" match
+pub fn subscription_key(description: &str) -> String {
+    description
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphabetic() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// The latest description without words that hold no letters, e.g. the period in
+// "Spotify Premium 10/2026"
+fn subscription_name(description: &str) -> String {
+    description
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphabetic))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Groups expenses by normalized description and keeps the groups that repeat at a steady
+// weekly, monthly or yearly rhythm with a steady amount. `rows` must be ordered by date.
+pub fn detect_subscriptions(rows: &[ExpenseRow], today: NaiveDate) -> Vec<DetectedSubscription> {
+    let mut groups: HashMap<String, Vec<&ExpenseRow>> = HashMap::new();
+    for row in rows {
+        let key = subscription_key(row.description.as_deref().unwrap_or(""));
+        if !key.is_empty() {
+            groups.entry(key).or_default().push(row);
+        }
+    }
+
+    let mut subscriptions: Vec<DetectedSubscription> = groups
+        .into_iter()
+        .filter_map(|(key, charges)| detect_subscription(key, &charges, today))
+        .collect();
+
+    subscriptions.sort_by(|a, b| b.annual_cost.total_cmp(&a.annual_cost));
+    subscriptions
+}
+
+fn detect_subscription(key: String, charges: &[&ExpenseRow], today: NaiveDate) -> Option<DetectedSubscription> {
+    let recent = &charges[charges.len().saturating_sub(RECENT_CHARGES)..];
+    if recent.len() < 2 {
+        return None;
+    }
+
+    let amounts: Vec<f64> = recent.iter().map(|charge| charge.amount).collect();
+    let typical_amount = median(&amounts)?;
+    if amounts
+        .iter()
+        .any(|amount| (amount - typical_amount).abs() > typical_amount * AMOUNT_TOLERANCE)
+    {
+        return None;
+    }
+
+    let intervals: Vec<i64> = recent
+        .windows(2)
+        .map(|pair| (pair[1].date - pair[0].date).num_days())
+        .collect();
+    let typical_interval = median(&intervals.iter().map(|days| *days as f64).collect::<Vec<_>>())?;
+
+    let (frequency, period_days, tolerance_days, min_charges) = SUBSCRIPTION_PERIODS
+        .into_iter()
+        .find(|(_, days, tolerance, _)| (typical_interval - *days as f64).abs() <= *tolerance as f64)?;
+    if recent.len() < min_charges {
+        return None;
+    }
+
+    let regular = intervals
+        .iter()
+        .filter(|days| (**days - period_days).abs() <= tolerance_days)
+        .count();
+    if (regular as f64) < intervals.len() as f64 * MIN_REGULAR_SHARE {
+        return None;
+    }
+
+    let last = recent.last()?;
+    let next_expected_date = next_occurrence(last.date, frequency, 1, last.date.day() as i32);
+    // A charge that is overdue by more than the tolerance most likely was cancelled
+    let active = today <= next_expected_date + Duration::days(tolerance_days);
+
+    Some(DetectedSubscription {
+        key,
+        name: subscription_name(last.description.as_deref().unwrap_or("")),
+        frequency: frequency.to_string(),
+        amount: last.amount,
+        annual_cost: round_currency(last.amount * occurrences_per_year(frequency, 1)),
+        charge_count: charges.len(),
+        average_interval_days: round_currency(
+            intervals.iter().sum::<i64>() as f64 / intervals.len() as f64,
+        ),
+        first_date: charges.first()?.date,
+        last_date: last.date,
+        next_expected_date,
+        wallet_id: last.wallet_id,
+        category_id: last.category_id,
+        category_name: last.category_name.clone(),
+        status: if active { "active" } else { "inactive" }.to_string(),
+        recurring_id: None,
+        bill_id: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn charge(description: &str, amount: f64, date: NaiveDate) -> ExpenseRow {
+        ExpenseRow {
+            id: Uuid::new_v4(),
+            wallet_id: Uuid::nil(),
+            category_id: None,
+            category_name: None,
+            amount,
+            description: Some(description.to_string()),
+            date,
+        }
+    }
+
+    fn detect(mut rows: Vec<ExpenseRow>, today: NaiveDate) -> Vec<DetectedSubscription> {
+        rows.sort_by_key(|row| row.date);
+        detect_subscriptions(&rows, today)
+    }
+
+    #[test]
+    fn key_ignores_case_digits_and_punctuation() {
+        assert_eq!(subscription_key("Spotify Premium 10/2026"), "spotify premium");
+        assert_eq!(subscription_key("SPOTIFY premium #11"), "spotify premium");
+        assert_eq!(subscription_name("Spotify Premium 10/2026"), "Spotify Premium");
+    }
+
+    #[test]
+    fn detects_weekly_charges() {
+        let start = date(2026, 9, 1);
+        let rows = (0..5)
+            .map(|week| charge("Laundry kiloan", 45_000.0, start + Duration::days(7 * week)))
+            .collect();
+
+        let found = detect(rows, date(2026, 10, 1));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].frequency, "weekly");
+        assert_eq!(found[0].next_expected_date, date(2026, 10, 6));
+        assert_eq!(found[0].status, "active");
+    }
+
+    #[test]
+    fn detects_monthly_charges_paid_a_few_days_late() {
+        let rows = vec![
+            charge("Netflix 06/2026", 186_000.0, date(2026, 6, 10)),
+            charge("Netflix 07/2026", 186_000.0, date(2026, 7, 10)),
+            charge("Netflix 08/2026", 186_000.0, date(2026, 8, 14)),
+            charge("Netflix 09/2026", 186_000.0, date(2026, 9, 10)),
+            charge("Netflix 10/2026", 186_000.0, date(2026, 10, 10)),
+        ];
+
+        let found = detect(rows, date(2026, 10, 18));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].key, "netflix");
+        assert_eq!(found[0].frequency, "monthly");
+        assert_eq!(found[0].annual_cost, 186_000.0 * 12.0);
+        assert_eq!(found[0].next_expected_date, date(2026, 11, 10));
+    }
+
+    #[test]
+    fn detects_yearly_renewals() {
+        let rows = vec![
+            charge("Domain renewal", 150_000.0, date(2024, 3, 2)),
+            charge("Domain renewal", 165_000.0, date(2025, 3, 4)),
+        ];
+
+        let found = detect(rows, date(2025, 6, 1));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].frequency, "yearly");
+        assert_eq!(found[0].amount, 165_000.0);
+        assert_eq!(found[0].next_expected_date, date(2026, 3, 4));
+    }
+
+    #[test]
+    fn stopped_subscription_is_inactive() {
+        let rows = vec![
+            charge("Gym", 300_000.0, date(2026, 1, 5)),
+            charge("Gym", 300_000.0, date(2026, 2, 5)),
+            charge("Gym", 300_000.0, date(2026, 3, 5)),
+        ];
+
+        let found = detect(rows, date(2026, 10, 18));
+        assert_eq!(found[0].status, "inactive");
+    }
+
+    #[test]
+    fn ignores_irregular_or_too_few_charges() {
+        let rows = vec![
+            // Two monthly charges are not enough
+            charge("Parkir", 5_000.0, date(2026, 8, 1)),
+            charge("Parkir", 5_000.0, date(2026, 9, 1)),
+            // Monthly rhythm but amounts vary too much
+            charge("Belanja", 100_000.0, date(2026, 7, 3)),
+            charge("Belanja", 250_000.0, date(2026, 8, 3)),
+            charge("Belanja", 90_000.0, date(2026, 9, 3)),
+            // Steady amount without a rhythm
+            charge("Kopi", 25_000.0, date(2026, 7, 1)),
+            charge("Kopi", 25_000.0, date(2026, 7, 13)),
+            charge("Kopi", 25_000.0, date(2026, 8, 30)),
+        ];
+
+        assert!(detect(rows, date(2026, 10, 1)).is_empty());
+    }
+}
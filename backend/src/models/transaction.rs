@@ -14,8 +14,32 @@ pub struct Transaction {
     pub amount: f64,
     pub description: Option<String>,
     pub date: NaiveDate,
+    // Loan disbursements and repayments: they move wallet balances but are not income or expense
+    pub is_transfer: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+// Transaction row with its category name resolved by a join
+#[derive(Debug, Clone, FromRow)]
+pub struct TransactionWithCategory {
+    #[sqlx(flatten)]
+    pub transaction: Transaction,
+    pub category_name: Option<String>,
+}
+
+// A transaction booked for another record (a debt, bill payment, installment or recurring
+// schedule), see db::record_wallet_transaction
+#[derive(Debug)]
+pub struct NewTransaction<'a> {
+    pub wallet_id: Uuid,
+    pub category_id: Option<Uuid>,
+    pub transaction_type: &'a str,
+    pub amount: f64,
+    pub description: &'a str,
+    pub date: NaiveDate,
+    pub is_transfer: bool,
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -62,7 +86,48 @@ pub struct TransactionResponse {
     pub amount: f64,
     pub description: Option<String>,
     pub date: NaiveDate,
+    pub is_transfer: bool,
     pub created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+impl From<TransactionWithCategory> for TransactionResponse {
+    fn from(row: TransactionWithCategory) -> Self {
+        let tx = row.transaction;
+        TransactionResponse {
+            id: tx.id,
+            wallet_id: tx.wallet_id,
+            category_id: tx.category_id,
+            category_name: row.category_name,
+            transaction_type: tx.transaction_type,
+            amount: tx.amount,
+            description: tx.description,
+            date: tx.date,
+            is_transfer: tx.is_transfer,
+            created_at: tx.created_at,
+            deleted_at: tx.deleted_at,
+        }
+    }
+}
+
+// For transactions without a category, e.g. debt disbursements and repayments
+impl From<Transaction> for TransactionResponse {
+    fn from(tx: Transaction) -> Self {
+        TransactionResponse {
+            id: tx.id,
+            wallet_id: tx.wallet_id,
+            category_id: tx.category_id,
+            category_name: None,
+            transaction_type: tx.transaction_type,
+            amount: tx.amount,
+            description: tx.description,
+            date: tx.date,
+            is_transfer: tx.is_transfer,
+            created_at: tx.created_at,
+            deleted_at: tx.deleted_at,
+        }
+    }
 }
 
 impl TransactionResponse {
@@ -91,7 +156,9 @@ impl TransactionResponse {
             amount: tx.amount,
             description: tx.description,
             date: tx.date,
+            is_transfer: tx.is_transfer,
             created_at: tx.created_at,
+            deleted_at: tx.deleted_at,
         })
     }
 }
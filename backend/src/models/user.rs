@@ -11,6 +11,8 @@ pub struct User {
     pub name: String,
     #[serde(skip_serializing)]
     pub password_hash: String,
+    pub timezone: String, // IANA name, e.g. Asia/Jakarta
+    pub anomaly_alerts: bool, // notify about unusual spending
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -25,6 +27,15 @@ pub struct RegisterRequest {
     pub email: String,
     #[validate(length(min = 8, message = "Password minimal 8 karakter"))]
     pub password: String,
+    pub timezone: Option<String>, // defaults to Asia/Jakarta
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateProfileRequest {
+    #[validate(length(min = 2, message = "Nama minimal 2 karakter"))]
+    pub name: Option<String>,
+    pub timezone: Option<String>,
+    pub anomaly_alerts: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -49,6 +60,8 @@ pub struct UserResponse {
     pub email: String,
     pub username: String,
     pub name: String,
+    pub timezone: String,
+    pub anomaly_alerts: bool,
     pub created_at: DateTime<Utc>,
 }
 
@@ -59,6 +72,8 @@ impl From<User> for UserResponse {
             email: user.email,
             username: user.username,
             name: user.name,
+            timezone: user.timezone,
+            anomaly_alerts: user.anomaly_alerts,
             created_at: user.created_at,
         }
     }
@@ -1,9 +1,19 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 use validator::Validate;
 
+// Wallet types whose balance is money owed, with a credit limit and statement cycle
+pub const CREDIT_WALLET_TYPES: [&str; 2] = ["credit-card", "paylater"];
+
+pub fn is_credit_wallet_type(wallet_type: &str) -> bool {
+    CREDIT_WALLET_TYPES.contains(&wallet_type)
+}
+
+// Wallet type whose balance is uninvested cash and that holds investment positions
+pub const INVESTMENT_WALLET_TYPE: &str = "investment";
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Wallet {
     pub id: Uuid,
@@ -14,6 +24,8 @@ pub struct Wallet {
     pub icon: Option<String>,
     pub color: Option<String>,
     pub credit_limit: Option<f64>,
+    pub statement_closing_day: Option<i32>, // credit wallets only, 1-31
+    pub payment_due_day: Option<i32>,       // credit wallets only, 1-31
     pub is_default: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -30,6 +42,8 @@ pub struct CreateWalletRequest {
     pub icon: Option<String>,
     pub color: Option<String>,
     pub credit_limit: Option<f64>,
+    pub statement_closing_day: Option<i32>,
+    pub payment_due_day: Option<i32>,
     pub is_default: Option<bool>,
 }
 
@@ -41,6 +55,8 @@ pub struct UpdateWalletRequest {
     pub icon: Option<String>,
     pub color: Option<String>,
     pub credit_limit: Option<f64>,
+    pub statement_closing_day: Option<i32>,
+    pub payment_due_day: Option<i32>,
     pub is_default: Option<bool>,
 }
 
@@ -53,6 +69,8 @@ pub struct WalletResponse {
     pub icon: Option<String>,
     pub color: Option<String>,
     pub credit_limit: Option<f64>,
+    pub statement_closing_day: Option<i32>,
+    pub payment_due_day: Option<i32>,
     pub is_default: bool,
     pub created_at: DateTime<Utc>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -69,9 +87,64 @@ impl From<Wallet> for WalletResponse {
             icon: wallet.icon,
             color: wallet.color,
             credit_limit: wallet.credit_limit,
+            statement_closing_day: wallet.statement_closing_day,
+            payment_due_day: wallet.payment_due_day,
             is_default: wallet.is_default,
             created_at: wallet.created_at,
             deleted_at: wallet.deleted_at,
         }
     }
 }
+
+// Charges (expenses) and payments (income) on a credit wallet after a cutoff date
+#[derive(Debug, Clone, FromRow)]
+pub struct CreditActivity {
+    pub wallet_id: Uuid,
+    pub charges: f64,
+    pub payments: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DueDateQuery {
+    pub days: Option<i64>,
+}
+
+// Billing position of a credit wallet as of today
+#[derive(Debug, Serialize)]
+pub struct CreditStatementResponse {
+    pub wallet_id: Uuid,
+    pub wallet_name: String,
+    pub wallet_type: String,
+    pub credit_limit: Option<f64>,
+    pub outstanding_balance: f64,
+    pub available_credit: Option<f64>,
+    pub utilization_percentage: Option<f64>,
+    pub statement_closing_day: i32,
+    pub payment_due_day: i32,
+    pub period_start: NaiveDate,
+    pub statement_date: NaiveDate,
+    pub due_date: NaiveDate,
+    pub statement_balance: f64,
+    pub minimum_payment: f64,
+    pub paid_since_statement: f64,
+    pub remaining_due: f64,
+    pub remaining_minimum_payment: f64,
+    pub unbilled_charges: f64,
+    pub next_statement_date: NaiveDate,
+    pub next_due_date: NaiveDate,
+    pub days_until_due: i64,
+    pub is_overdue: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UpcomingDueResponse {
+    pub wallet_id: Uuid,
+    pub wallet_name: String,
+    pub statement_date: NaiveDate,
+    pub due_date: NaiveDate,
+    pub days_until_due: i64,
+    pub amount_due: f64,
+    pub minimum_payment: f64,
+    pub is_estimate: bool, // the statement has not closed yet
+    pub is_overdue: bool,
+}
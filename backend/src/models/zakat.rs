@@ -0,0 +1,262 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::models::installment::round_currency;
+use crate::models::net_worth::NetWorthPoint;
+use crate::utils::format::format_rupiah;
+
+// Nisab zakat maal: the value of 85 grams of gold
+pub const DEFAULT_NISAB_GRAMS: f64 = 85.0;
+pub const ZAKAT_RATE: f64 = 0.025;
+// One lunar (hijri) year
+pub const HAUL_DAYS: i64 = 354;
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ZakatSettings {
+    pub user_id: Uuid,
+    pub gold_price_per_gram: f64,
+    pub nisab_grams: f64,
+    pub include_receivables: bool,
+    pub deduct_liabilities: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ZakatSettings {
+    pub fn nisab(&self) -> f64 {
+        round_currency(self.gold_price_per_gram * self.nisab_grams)
+    }
+
+    // Wealth that counts towards nisab at the end of a day
+    pub fn zakatable_wealth(&self, point: &NetWorthPoint) -> ZakatableWealth {
+        let receivables = if self.include_receivables { point.receivables } else { 0.0 };
+        let liabilities = if self.deduct_liabilities {
+            point.credit_liabilities + point.payables
+        } else {
+            0.0
+        };
+        let total = point.wallet_assets + point.investment_assets + receivables - liabilities;
+
+        ZakatableWealth {
+            date: point.date,
+            cash: round_currency(point.wallet_assets),
+            investments: round_currency(point.investment_assets),
+            receivables: round_currency(receivables),
+            liabilities: round_currency(liabilities),
+            total: round_currency(total),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateZakatSettingsRequest {
+    // Required the first time settings are saved
+    pub gold_price_per_gram: Option<f64>,
+    pub nisab_grams: Option<f64>,
+    pub include_receivables: Option<bool>,
+    pub deduct_liabilities: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ZakatReportQuery {
+    pub year: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ZakatableWealth {
+    pub date: NaiveDate,
+    pub cash: f64,        // wallet balances, including credit wallets paid in advance
+    pub investments: f64, // market value of investment holdings
+    pub receivables: f64, // 0 unless include_receivables
+    pub liabilities: f64, // 0 unless deduct_liabilities
+    pub total: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HaulStatus {
+    // below_nisab, in_progress, insufficient_history or complete
+    pub status: String,
+    pub days_required: i64,
+    // Wealth has stayed at or above nisab since this date
+    pub start_date: Option<NaiveDate>,
+    // When the haul completes if wealth stays above nisab
+    pub due_date: Option<NaiveDate>,
+    pub days_elapsed: i64,
+    pub days_remaining: i64,
+    pub lowest_wealth: Option<f64>,
+    pub lowest_wealth_date: Option<NaiveDate>,
+    // Last day in the haul period that wealth was below nisab
+    pub last_below_nisab_date: Option<NaiveDate>,
+}
+
+// Applies the haul rule to daily wealth ending at `as_of`: zakat is due when wealth stayed at
+// or above nisab on every day of the last HAUL_DAYS. Days before `history_start` have no
+// balance data, so a run of wealth reaching back to it cannot be confirmed as a full haul.
+pub fn evaluate_haul(
+    wealth: &[ZakatableWealth],
+    nisab: f64,
+    as_of: NaiveDate,
+    history_start: NaiveDate,
+) -> HaulStatus {
+    let window_start = as_of - Duration::days(HAUL_DAYS);
+    let known: Vec<&ZakatableWealth> = wealth
+        .iter()
+        .filter(|day| day.date >= window_start.max(history_start) && day.date <= as_of)
+        .collect();
+
+    let lowest = known
+        .iter()
+        .min_by(|a, b| a.total.total_cmp(&b.total))
+        .map(|day| (day.total, day.date));
+    let last_below = known.iter().rev().find(|day| day.total < nisab).map(|day| day.date);
+    let above_today = known.last().is_some_and(|day| day.date == as_of && day.total >= nisab);
+
+    let (status, start_date) = if history_start > as_of {
+        ("insufficient_history", None)
+    } else if !above_today {
+        ("below_nisab", None)
+    } else if let Some(date) = last_below {
+        ("in_progress", Some(date + Duration::days(1)))
+    } else if history_start > window_start {
+        ("insufficient_history", Some(history_start))
+    } else {
+        ("complete", Some(window_start))
+    };
+
+    let days_elapsed = start_date.map_or(0, |start| (as_of - start).num_days());
+
+    HaulStatus {
+        status: status.to_string(),
+        days_required: HAUL_DAYS,
+        start_date,
+        due_date: start_date.map(|start| start + Duration::days(HAUL_DAYS)),
+        days_elapsed,
+        days_remaining: start_date.map_or(HAUL_DAYS, |_| (HAUL_DAYS - days_elapsed).max(0)),
+        lowest_wealth: lowest.map(|(total, _)| total),
+        lowest_wealth_date: lowest.map(|(_, date)| date),
+        last_below_nisab_date: last_below,
+    }
+}
+
+pub fn haul_summary(haul: &HaulStatus, wealth: f64, nisab: f64) -> String {
+    let date = |date: Option<NaiveDate>| date.map(|date| date.format("%d/%m/%Y").to_string()).unwrap_or_default();
+    match haul.status.as_str() {
+        "complete" => format!(
+            "Harta Anda {} bertahan di atas nisab selama satu haul; zakat yang wajib dibayar {}",
+            format_rupiah(wealth),
+            format_rupiah(zakat_amount(wealth))
+        ),
+        "in_progress" => format!(
+            "Harta Anda mencapai nisab sejak {}; haul genap pada {} ({} hari lagi)",
+            date(haul.start_date),
+            date(haul.due_date),
+            haul.days_remaining
+        ),
+        "insufficient_history" if haul.start_date.is_some() => format!(
+            "Harta Anda di atas nisab sejak data saldo pertama {}; lengkapi riwayat saldo untuk memastikan haul",
+            date(haul.start_date)
+        ),
+        "insufficient_history" => "Belum ada data saldo pada periode ini".to_string(),
+        _ => format!(
+            "Harta Anda {} belum mencapai nisab {}; belum wajib zakat",
+            format_rupiah(wealth),
+            format_rupiah(nisab)
+        ),
+    }
+}
+
+pub fn zakat_amount(wealth: f64) -> f64 {
+    round_currency(wealth.max(0.0) * ZAKAT_RATE)
+}
+
+// Dates a haul ending at `as_of` is evaluated over, oldest first
+pub fn haul_dates(as_of: NaiveDate) -> Vec<NaiveDate> {
+    (0..=HAUL_DAYS).rev().map(|days| as_of - Duration::days(days)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NISAB: f64 = 85_000_000.0;
+
+    fn as_of() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, 19).unwrap()
+    }
+
+    fn window_start() -> NaiveDate {
+        as_of() - Duration::days(HAUL_DAYS)
+    }
+
+    // Daily wealth over the haul ending at as_of, plus a few days before it
+    fn wealth(total_on: impl Fn(NaiveDate) -> f64) -> Vec<ZakatableWealth> {
+        (0..=HAUL_DAYS + 5)
+            .rev()
+            .map(|days| as_of() - Duration::days(days))
+            .map(|date| {
+                let total = total_on(date);
+                ZakatableWealth { date, cash: total, investments: 0.0, receivables: 0.0, liabilities: 0.0, total }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn complete_when_above_nisab_for_the_whole_haul() {
+        // Exactly at nisab counts, and a dip the day before the haul started does not matter
+        let wealth = wealth(|date| if date < window_start() { 1_000_000.0 } else { NISAB });
+        let haul = evaluate_haul(&wealth, NISAB, as_of(), window_start());
+
+        assert_eq!(haul.status, "complete");
+        assert_eq!(haul.start_date, Some(window_start()));
+        assert_eq!(haul.due_date, Some(as_of()));
+        assert_eq!((haul.days_elapsed, haul.days_remaining), (HAUL_DAYS, 0));
+        assert_eq!(haul.last_below_nisab_date, None);
+    }
+
+    #[test]
+    fn dip_on_the_first_day_of_the_haul_restarts_it() {
+        let wealth = wealth(|date| if date == window_start() { NISAB - 1.0 } else { 100_000_000.0 });
+        let haul = evaluate_haul(&wealth, NISAB, as_of(), window_start());
+
+        assert_eq!(haul.status, "in_progress");
+        assert_eq!(haul.start_date, Some(window_start() + Duration::days(1)));
+        assert_eq!(haul.due_date, Some(as_of() + Duration::days(1)));
+        assert_eq!((haul.days_elapsed, haul.days_remaining), (HAUL_DAYS - 1, 1));
+        assert_eq!(haul.last_below_nisab_date, Some(window_start()));
+        assert_eq!(haul.lowest_wealth, Some(NISAB - 1.0));
+    }
+
+    #[test]
+    fn below_nisab_today() {
+        let wealth = wealth(|date| if date == as_of() { NISAB - 1.0 } else { 100_000_000.0 });
+        let haul = evaluate_haul(&wealth, NISAB, as_of(), window_start());
+
+        assert_eq!(haul.status, "below_nisab");
+        assert_eq!(haul.start_date, None);
+        assert_eq!(haul.days_remaining, HAUL_DAYS);
+    }
+
+    #[test]
+    fn history_starting_inside_the_haul_cannot_confirm_it() {
+        let history_start = window_start() + Duration::days(1);
+        let wealth = wealth(|_| 100_000_000.0);
+
+        let haul = evaluate_haul(&wealth, NISAB, as_of(), history_start);
+        assert_eq!(haul.status, "insufficient_history");
+        assert_eq!(haul.start_date, Some(history_start));
+
+        let haul = evaluate_haul(&wealth, NISAB, as_of(), as_of() + Duration::days(1));
+        assert_eq!(haul.status, "insufficient_history");
+        assert_eq!(haul.start_date, None);
+    }
+
+    #[test]
+    fn zakat_is_two_and_a_half_percent() {
+        assert_eq!(zakat_amount(100_000_000.0), 2_500_000.0);
+        assert_eq!(zakat_amount(-5_000.0), 0.0);
+        assert_eq!(haul_dates(as_of()).len() as i64, HAUL_DAYS + 1);
+        assert_eq!(haul_dates(as_of()).first(), Some(&window_start()));
+    }
+}
@@ -0,0 +1,117 @@
+// Helpers for tests that run handlers against a real database. They use #[sqlx::test], which
+// creates a fresh database per test from DATABASE_URL, so they only run with the db-tests
+// feature and are #[ignore]d otherwise:
+//
+//     DATABASE_URL=postgres://... cargo test --features db-tests
+
+use axum::http::{header::AUTHORIZATION, HeaderMap, HeaderValue};
+use sqlx::PgPool;
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::{
+    layer::{Context, Layer},
+    prelude::*,
+};
+use uuid::Uuid;
+
+use crate::{config::Config, mailer::FileMailer, utils::jwt::create_token, AppState};
+
+const TEST_JWT_SECRET: &str = "test-secret";
+
+pub fn test_state(pool: PgPool) -> AppState {
+    let config = Config {
+        database_url: String::new(),
+        redis_url: None,
+        jwt_secret: TEST_JWT_SECRET.to_string(),
+        host: "127.0.0.1".to_string(),
+        port: 0,
+        trash_retention_days: 30,
+        public_url: "http://localhost".to_string(),
+        mail_transport: "file".to_string(),
+        mail_from: "FinTrack <noreply@fintrack.local>".to_string(),
+        mail_dir: std::env::temp_dir().join("fintrack-test-mail").display().to_string(),
+        smtp_host: "localhost".to_string(),
+        smtp_port: 25,
+        smtp_security: "none".to_string(),
+        smtp_username: None,
+        smtp_password: None,
+    };
+
+    AppState {
+        db: pool,
+        mailer: Arc::new(FileMailer::new(config.mail_from.parse().unwrap(), &config.mail_dir)),
+        config: Arc::new(config),
+    }
+}
+
+// A user with a cash wallet; returns (user_id, wallet_id)
+pub async fn create_user(pool: &PgPool) -> (Uuid, Uuid) {
+    let user_id: Uuid = sqlx::query_scalar(
+        r#"INSERT INTO users (email, username, name, password_hash)
+           VALUES ($1, $2, 'Test', 'x') RETURNING id"#,
+    )
+    .bind(format!("{}@test.local", Uuid::new_v4()))
+    .bind(Uuid::new_v4().to_string())
+    .fetch_one(pool)
+    .await
+    .unwrap();
+
+    let wallet_id: Uuid = sqlx::query_scalar(
+        r#"INSERT INTO wallets (user_id, name, wallet_type, balance, is_default)
+           VALUES ($1, 'Kas', 'cash', 0, TRUE) RETURNING id"#,
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .unwrap();
+
+    (user_id, wallet_id)
+}
+
+// Another wallet for the user, with a zero balance
+pub async fn create_wallet(pool: &PgPool, user_id: Uuid, name: &str, wallet_type: &str) -> Uuid {
+    sqlx::query_scalar(
+        r#"INSERT INTO wallets (user_id, name, wallet_type, balance)
+           VALUES ($1, $2, $3, 0) RETURNING id"#,
+    )
+    .bind(user_id)
+    .bind(name)
+    .bind(wallet_type)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+pub fn auth_headers(user_id: Uuid) -> HeaderMap {
+    let token = create_token(user_id, "test@test.local", TEST_JWT_SECRET).unwrap();
+    let mut headers = HeaderMap::new();
+    headers.insert(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap());
+    headers
+}
+
+// Counts the statements sqlx logs (target "sqlx::query"), one event per executed query
+struct QueryCounter(Arc<AtomicUsize>);
+
+impl<S: Subscriber> Layer<S> for QueryCounter {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if event.metadata().target() == "sqlx::query" {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+// Runs `future` and returns its output with the number of queries it executed. The counter is
+// thread-local, which covers everything #[sqlx::test]'s current-thread runtime polls.
+pub async fn count_queries<F: Future>(future: F) -> (F::Output, usize) {
+    let count = Arc::new(AtomicUsize::new(0));
+    let subscriber = tracing_subscriber::registry().with(QueryCounter(count.clone()));
+    let _guard = tracing::subscriber::set_default(subscriber);
+    let output = future.await;
+    (output, count.load(Ordering::SeqCst))
+}
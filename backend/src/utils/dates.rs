@@ -0,0 +1,30 @@
+// Calendar helpers shared by handlers and background jobs
+
+use chrono::{Datelike, NaiveDate, Utc};
+use chrono_tz::Tz;
+
+// Time zone for users who have not picked one (WIB)
+pub const DEFAULT_TIMEZONE: &str = "Asia/Jakarta";
+
+// Parses an IANA time zone name such as "Asia/Makassar"
+pub fn parse_timezone(name: &str) -> Option<Tz> {
+    name.parse::<Tz>().ok()
+}
+
+// The current calendar day in the given time zone
+pub fn today_in(timezone: Tz) -> NaiveDate {
+    Utc::now().with_timezone(&timezone).date_naive()
+}
+
+// `day` of the given month, moved back to the month's last day when the month is shorter
+pub fn day_of_month(year: i32, month: u32, day: i32) -> NaiveDate {
+    let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    let last_day = NaiveDate::from_ymd_opt(next_year, next_month, 1).unwrap().pred_opt().unwrap();
+    last_day.with_day((day as u32).min(last_day.day())).unwrap()
+}
+
+// The same day of month `months` months after `date`'s month (negative goes back)
+pub fn shift_months(date: NaiveDate, months: i32, day: i32) -> NaiveDate {
+    let index = date.year() * 12 + date.month0() as i32 + months;
+    day_of_month(index.div_euclid(12), index.rem_euclid(12) as u32 + 1, day)
+}
@@ -0,0 +1,31 @@
+// Text formatting for messages shown to users
+
+use chrono::{Datelike, NaiveDate};
+
+pub const MONTH_NAMES_ID: [&str; 12] = [
+    "Januari", "Februari", "Maret", "April", "Mei", "Juni", "Juli", "Agustus", "September", "Oktober",
+    "November", "Desember",
+];
+
+// Whole rupiah with dot thousands separators, e.g. Rp1.250.000
+pub fn format_rupiah(amount: f64) -> String {
+    let digits = (amount.abs().round() as u64).to_string();
+    let mut grouped = String::new();
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            grouped.push('.');
+        }
+        grouped.push(digit);
+    }
+
+    if amount < 0.0 && grouped != "0" {
+        format!("-Rp{}", grouped)
+    } else {
+        format!("Rp{}", grouped)
+    }
+}
+
+// Day month year with the Indonesian month name, e.g. 5 Oktober 2026
+pub fn format_date_id(date: NaiveDate) -> String {
+    format!("{} {} {}", date.day(), MONTH_NAMES_ID[date.month0() as usize], date.year())
+}
@@ -0,0 +1,52 @@
+// Helpers for HTML rendered on the server. Styles are inline only, so the markup survives
+// being used as an email body.
+
+use std::fmt::Write;
+
+const TABLE_STYLE: &str = "width:100%;border-collapse:collapse;font-size:13px;margin-bottom:8px";
+const TH_STYLE: &str = "background:#f0f0f0;padding:6px 8px;border-bottom:1px solid #ccc";
+const TD_STYLE: &str = "padding:5px 8px;border-bottom:1px solid #eee";
+const HEADING_STYLE: &str = "font-size:16px;margin:24px 0 8px";
+
+// Escapes text for use in element content and double-quoted attribute values
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+pub fn html_section(html: &mut String, title: &str) {
+    let _ = writeln!(html, "<h2 style=\"{}\">{}</h2>", HEADING_STYLE, escape_html(title));
+}
+
+// Columns are (header, right aligned)
+pub fn html_table(html: &mut String, columns: &[(&str, bool)], rows: &[Vec<String>]) {
+    if rows.is_empty() {
+        let _ = writeln!(html, "<p style=\"font-size:13px;color:#666\">Tidak ada data</p>");
+        return;
+    }
+
+    let align = |right: bool| if right { "text-align:right" } else { "text-align:left" };
+    let _ = write!(html, "<table style=\"{}\">\n<tr>", TABLE_STYLE);
+    for (header, right) in columns {
+        let _ = write!(html, "<th style=\"{};{}\">{}</th>", TH_STYLE, align(*right), escape_html(header));
+    }
+    let _ = writeln!(html, "</tr>");
+    for row in rows {
+        let _ = write!(html, "<tr>");
+        for ((_, right), cell) in columns.iter().zip(row) {
+            let _ = write!(html, "<td style=\"{};{}\">{}</td>", TD_STYLE, align(*right), escape_html(cell));
+        }
+        let _ = writeln!(html, "</tr>");
+    }
+    let _ = writeln!(html, "</table>");
+}
@@ -0,0 +1,150 @@
+// Minimal iCalendar (RFC 5545) writer for read-only calendar feeds
+
+use chrono::{DateTime, NaiveDate, Utc};
+
+// Content lines longer than this many octets are folded onto continuation lines
+const MAX_LINE_OCTETS: usize = 75;
+
+#[derive(Debug, Default)]
+pub struct IcsWriter {
+    out: String,
+}
+
+impl IcsWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Writes `name:value` with the value taken as is (dates, durations, fixed keywords)
+    pub fn property(&mut self, name: &str, value: &str) -> &mut Self {
+        self.line(&format!("{}:{}", name, value))
+    }
+
+    // Writes `name:value` with the value escaped as TEXT
+    pub fn text(&mut self, name: &str, value: &str) -> &mut Self {
+        self.property(name, &escape_text(value))
+    }
+
+    pub fn begin(&mut self, component: &str) -> &mut Self {
+        self.property("BEGIN", component)
+    }
+
+    pub fn end(&mut self, component: &str) -> &mut Self {
+        self.property("END", component)
+    }
+
+    pub fn finish(self) -> String {
+        self.out
+    }
+
+    // Lines end in CRLF and are folded at character boundaries, continuation lines start
+    // with a space
+    fn line(&mut self, line: &str) -> &mut Self {
+        let mut octets = 0;
+        for c in line.chars() {
+            if octets + c.len_utf8() > MAX_LINE_OCTETS {
+                self.out.push_str("\r\n ");
+                octets = 1;
+            }
+            self.out.push(c);
+            octets += c.len_utf8();
+        }
+        self.out.push_str("\r\n");
+        self
+    }
+}
+
+pub fn escape_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// DATE value, e.g. 20261019
+pub fn format_date(date: NaiveDate) -> String {
+    date.format("%Y%m%d").to_string()
+}
+
+// DATE-TIME value in UTC, e.g. 20261019T083000Z
+pub fn format_utc(timestamp: DateTime<Utc>) -> String {
+    timestamp.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_text(value: &str) -> String {
+        let mut writer = IcsWriter::new();
+        writer.text("SUMMARY", value);
+        writer.finish()
+    }
+
+    // Reverses the folding: CRLF followed by a space joins the lines again
+    fn unfold(out: &str) -> String {
+        out.replace("\r\n ", "")
+    }
+
+    #[test]
+    fn escapes_text_values() {
+        assert_eq!(escape_text("Listrik; air, gas"), r"Listrik\; air\, gas");
+        assert_eq!(escape_text(r"C:\tagihan"), r"C:\\tagihan");
+        assert_eq!(escape_text("baris 1\r\nbaris 2"), r"baris 1\nbaris 2");
+    }
+
+    #[test]
+    fn short_lines_are_not_folded() {
+        assert_eq!(write_text("Tagihan PLN"), "SUMMARY:Tagihan PLN\r\n");
+    }
+
+    #[test]
+    fn folds_ascii_lines_at_75_octets() {
+        let value = "a".repeat(200);
+        let out = write_text(&value);
+        let lines: Vec<&str> = out.trim_end_matches("\r\n").split("\r\n").collect();
+
+        // 208 octets: 75, then a space and 74, then a space and the remaining 59
+        assert_eq!(lines.iter().map(|line| line.len()).collect::<Vec<_>>(), [75, 75, 60]);
+        assert!(lines[1..].iter().all(|line| line.starts_with(' ')));
+        assert_eq!(unfold(&out), format!("SUMMARY:{}\r\n", value));
+    }
+
+    #[test]
+    fn folds_multibyte_text_without_splitting_characters() {
+        // 3-octet and 4-octet characters, offset by the 8-octet name so they straddle the limit
+        let value = "Tagihan ₹ 💡 listrik ".repeat(10);
+        let out = write_text(&value);
+
+        for line in out.split("\r\n") {
+            assert!(line.len() <= MAX_LINE_OCTETS, "{} octets: {:?}", line.len(), line);
+        }
+        assert!(out.split("\r\n").count() > 3);
+        assert_eq!(unfold(&out), format!("SUMMARY:{}\r\n", escape_text(&value)));
+    }
+
+    #[test]
+    fn folds_before_a_character_that_would_cross_the_limit() {
+        // 74 octets of ASCII leave one octet, too few for the 2-octet "é"
+        let value = format!("{}é", "x".repeat(74 - "SUMMARY:".len()));
+        let out = write_text(&value);
+
+        assert_eq!(out, format!("SUMMARY:{}\r\n é\r\n", "x".repeat(66)));
+    }
+
+    #[test]
+    fn formats_dates() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        assert_eq!(format_date(date), "20261019");
+        let timestamp = date.and_hms_opt(8, 30, 0).unwrap().and_utc();
+        assert_eq!(format_utc(timestamp), "20261019T083000Z");
+    }
+}
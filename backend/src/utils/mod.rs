@@ -1,2 +1,8 @@
+pub mod dates;
+pub mod format;
+pub mod html;
+pub mod ical;
 pub mod jwt;
 pub mod password;
+pub mod pdf;
+pub mod stats;
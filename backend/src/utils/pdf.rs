@@ -0,0 +1,250 @@
+// Minimal flowing layout on top of printpdf for generated documents: headings, paragraphs and
+// fixed-column tables on A4 pages, breaking to a new page when the current one is full
+
+use printpdf::{
+    path::PaintMode, BuiltinFont, Color, IndirectFontRef, Line, Mm, PdfDocument, PdfDocumentReference,
+    PdfLayerReference, Point, Rect, Rgb,
+};
+
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 15.0;
+// Millimetres per point, to turn font sizes into line heights
+const PT_TO_MM: f32 = 0.3528;
+const LINE_SPACING: f32 = 1.4;
+
+// Helvetica advance widths in 1/1000 em for ASCII 32-126; other characters use the width of
+// a digit. Used to right-align and truncate text, the built-in fonts carry no metrics.
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, // space - /
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, // 0 - 9
+    278, 278, 584, 584, 584, 556, 1015, // : - @
+    667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, // A - M
+    722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, // N - Z
+    278, 278, 278, 469, 556, 333, // [ - `
+    556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, // a - m
+    556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, // n - z
+    334, 260, 334, 584, // { - ~
+];
+// Helvetica-Bold runs slightly wider
+const BOLD_WIDTH_FACTOR: f32 = 1.07;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Column {
+    pub width: f32, // mm
+    pub align: Align,
+}
+
+impl Column {
+    pub fn left(width: f32) -> Self {
+        Column { width, align: Align::Left }
+    }
+
+    pub fn right(width: f32) -> Self {
+        Column { width, align: Align::Right }
+    }
+}
+
+pub fn text_width(text: &str, size: f32, bold: bool) -> f32 {
+    let units: u32 = text
+        .chars()
+        .map(|ch| match ch as u32 {
+            code @ 32..=126 => HELVETICA_WIDTHS[(code - 32) as usize] as u32,
+            _ => 556,
+        })
+        .sum();
+    let width = units as f32 / 1000.0 * size * PT_TO_MM;
+    if bold {
+        width * BOLD_WIDTH_FACTOR
+    } else {
+        width
+    }
+}
+
+// Cuts `text` to fit in `width` mm, ending it with an ellipsis when shortened
+pub fn fit_text(text: &str, width: f32, size: f32, bold: bool) -> String {
+    if text_width(text, size, bold) <= width {
+        return text.to_string();
+    }
+    let mut fitted = String::new();
+    for ch in text.chars() {
+        fitted.push(ch);
+        if text_width(&fitted, size, bold) + text_width("...", size, bold) > width {
+            fitted.pop();
+            break;
+        }
+    }
+    format!("{}...", fitted.trim_end())
+}
+
+pub struct PdfWriter {
+    doc: PdfDocumentReference,
+    layer: PdfLayerReference,
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+    // Baseline of the next line, in mm from the bottom of the page
+    y: f32,
+    pages: usize,
+}
+
+impl PdfWriter {
+    pub fn new(title: &str) -> Result<Self, printpdf::Error> {
+        let (doc, page, layer) = PdfDocument::new(title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Halaman 1");
+        let regular = doc.add_builtin_font(BuiltinFont::Helvetica)?;
+        let bold = doc.add_builtin_font(BuiltinFont::HelveticaBold)?;
+        let layer = doc.get_page(page).get_layer(layer);
+
+        Ok(PdfWriter {
+            doc,
+            layer,
+            regular,
+            bold,
+            y: PAGE_HEIGHT - MARGIN,
+            pages: 1,
+        })
+    }
+
+    pub fn content_width(&self) -> f32 {
+        PAGE_WIDTH - 2.0 * MARGIN
+    }
+
+    // Starts a new page unless `height` mm still fit on the current one
+    pub fn ensure_space(&mut self, height: f32) {
+        if self.y - height >= MARGIN {
+            return;
+        }
+        self.pages += 1;
+        let (page, layer) = self.doc.add_page(
+            Mm(PAGE_WIDTH),
+            Mm(PAGE_HEIGHT),
+            format!("Halaman {}", self.pages),
+        );
+        self.layer = self.doc.get_page(page).get_layer(layer);
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    pub fn space(&mut self, height: f32) {
+        self.y -= height;
+    }
+
+    // One line of text, cut to the page width
+    pub fn text(&mut self, text: &str, size: f32, bold: bool) {
+        let width = self.content_width();
+        self.row(&[Column::left(width)], &[text], size, bold);
+    }
+
+    pub fn heading(&mut self, text: &str) {
+        self.space(3.0);
+        // Keep a heading together with at least a couple of lines below it
+        self.ensure_space(line_height(12.0) + 3.0 * line_height(9.0));
+        self.text(text, 12.0, true);
+        self.rule();
+    }
+
+    // One table row; cells are cut to their column width
+    pub fn row(&mut self, columns: &[Column], cells: &[&str], size: f32, bold: bool) {
+        let height = line_height(size);
+        self.ensure_space(height);
+        self.y -= height;
+
+        let font = if bold { &self.bold } else { &self.regular };
+        let mut x = MARGIN;
+        for (column, cell) in columns.iter().zip(cells) {
+            let padding = 1.0;
+            let text = fit_text(cell, column.width - 2.0 * padding, size, bold);
+            let offset = match column.align {
+                Align::Left => padding,
+                Align::Right => column.width - padding - text_width(&text, size, bold),
+            };
+            self.layer.use_text(text, size, Mm(x + offset), Mm(self.y + height * 0.25), font);
+            x += column.width;
+        }
+    }
+
+    // Bold header row on a grey band
+    pub fn header_row(&mut self, columns: &[Column], cells: &[&str], size: f32) {
+        let height = line_height(size);
+        // A header alone at the bottom of a page is useless, keep one row under it
+        self.ensure_space(2.0 * height);
+        let width: f32 = columns.iter().map(|column| column.width).sum();
+        self.layer.set_fill_color(grey(0.9));
+        self.layer.add_rect(
+            Rect::new(Mm(MARGIN), Mm(self.y - height), Mm(MARGIN + width), Mm(self.y))
+                .with_mode(PaintMode::Fill),
+        );
+        self.layer.set_fill_color(grey(0.0));
+        self.row(columns, cells, size, true);
+    }
+
+    // Thin horizontal line across the page
+    pub fn rule(&mut self) {
+        self.y -= 1.0;
+        self.layer.set_outline_color(grey(0.75));
+        self.layer.set_outline_thickness(0.5);
+        self.layer.add_line(Line {
+            points: vec![
+                (Point::new(Mm(MARGIN), Mm(self.y)), false),
+                (Point::new(Mm(PAGE_WIDTH - MARGIN), Mm(self.y)), false),
+            ],
+            is_closed: false,
+        });
+        self.y -= 1.0;
+    }
+
+    pub fn finish(self) -> Result<Vec<u8>, printpdf::Error> {
+        self.doc.save_to_bytes()
+    }
+}
+
+fn line_height(size: f32) -> f32 {
+    size * PT_TO_MM * LINE_SPACING
+}
+
+fn grey(level: f32) -> Color {
+    Color::Rgb(Rgb::new(level, level, level, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_width_uses_helvetica_metrics() {
+        // "i" is 222 units, "W" 944 units, other characters as wide as a digit
+        assert!((text_width("ii", 10.0, false) - 0.444 * 10.0 * PT_TO_MM).abs() < 1e-4);
+        assert!(text_width("W", 10.0, false) > text_width("i", 10.0, false) * 4.0);
+        assert_eq!(text_width("é", 10.0, false), text_width("0", 10.0, false));
+        assert!((text_width("W", 10.0, true) - text_width("W", 10.0, false) * BOLD_WIDTH_FACTOR).abs() < 1e-4);
+    }
+
+    #[test]
+    fn fit_text_cuts_with_an_ellipsis() {
+        assert_eq!(fit_text("Makan siang", 50.0, 9.0, false), "Makan siang");
+
+        let long = "Belanja bulanan di supermarket dekat rumah bersama keluarga";
+        let fitted = fit_text(long, 30.0, 9.0, false);
+        assert!(fitted.ends_with("..."));
+        assert!(long.starts_with(fitted.trim_end_matches("...")));
+        assert!(text_width(&fitted, 9.0, false) <= 30.0);
+    }
+
+    #[test]
+    fn rows_break_onto_new_pages() {
+        let mut pdf = PdfWriter::new("Tes").unwrap();
+        let columns = [Column::left(100.0), Column::right(80.0)];
+        for index in 0..200 {
+            pdf.row(&columns, &[&format!("Baris {}", index), "Rp 1.000"], 9.0, false);
+        }
+        assert!(pdf.pages > 1);
+        assert!(pdf.y >= MARGIN);
+
+        let bytes = pdf.finish().unwrap();
+        assert!(bytes.starts_with(b"%PDF"));
+    }
+}
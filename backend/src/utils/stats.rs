@@ -0,0 +1,104 @@
+// Robust statistics for spotting outliers in a user's own history
+
+// Middle value of `values`, None when empty
+pub fn median(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let middle = sorted.len() / 2;
+    Some(if sorted.len().is_multiple_of(2) {
+        (sorted[middle - 1] + sorted[middle]) / 2.0
+    } else {
+        sorted[middle]
+    })
+}
+
+// Scale factors that turn absolute deviations into standard-deviation units for normal data
+const MAD_SCALE: f64 = 1.4826;
+const MEAN_AD_SCALE: f64 = 1.2533;
+// Smallest spread as a fraction of the median, so a perfectly flat history (e.g. the same
+// amount every month) still gives a finite score
+const MIN_RELATIVE_SPREAD: f64 = 0.05;
+
+// Median and robust spread of a sample
+#[derive(Debug, Clone, Copy)]
+pub struct RobustStats {
+    pub median: f64,
+    pub spread: f64,
+}
+
+impl RobustStats {
+    // None when the sample is empty or all zeros
+    pub fn new(values: &[f64]) -> Option<Self> {
+        let median = median(values)?;
+        let deviations: Vec<f64> = values.iter().map(|value| (value - median).abs()).collect();
+
+        // The median absolute deviation is zero when more than half of the values are equal;
+        // the mean absolute deviation still sees the rest
+        let mut spread = MAD_SCALE * self::median(&deviations)?;
+        if spread == 0.0 {
+            spread = MEAN_AD_SCALE * deviations.iter().sum::<f64>() / deviations.len() as f64;
+        }
+        spread = spread.max(median.abs() * MIN_RELATIVE_SPREAD);
+
+        if spread == 0.0 {
+            return None;
+        }
+        Some(RobustStats { median, spread })
+    }
+
+    // Modified z-score: how many robust standard deviations `value` lies above the median
+    pub fn score(&self, value: f64) -> f64 {
+        (value - self.median) / self.spread
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn median_of_odd_and_even_samples() {
+        assert_eq!(median(&[]), None);
+        assert_eq!(median(&[3.0, 1.0, 2.0]), Some(2.0));
+        assert_eq!(median(&[4.0, 1.0, 3.0, 2.0]), Some(2.5));
+    }
+
+    #[test]
+    fn flat_history_uses_the_minimum_spread() {
+        let stats = RobustStats::new(&[500_000.0; 6]).unwrap();
+
+        assert_eq!(stats.median, 500_000.0);
+        assert_eq!(stats.spread, 500_000.0 * MIN_RELATIVE_SPREAD);
+        assert_eq!(stats.score(500_000.0), 0.0);
+        // Double the usual amount is 20 spreads above the median, finite rather than infinite
+        assert!((stats.score(1_000_000.0) - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn mostly_flat_history_falls_back_to_the_mean_deviation() {
+        // The median absolute deviation is zero, but the one different month is not ignored
+        let stats = RobustStats::new(&[100.0, 100.0, 100.0, 100.0, 300.0]).unwrap();
+
+        assert_eq!(stats.median, 100.0);
+        assert!((stats.spread - MEAN_AD_SCALE * 40.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn no_stats_for_empty_or_all_zero_samples() {
+        assert!(RobustStats::new(&[]).is_none());
+        assert!(RobustStats::new(&[0.0, 0.0, 0.0]).is_none());
+    }
+
+    #[test]
+    fn outlier_scores_high() {
+        let stats = RobustStats::new(&[90.0, 100.0, 110.0, 95.0, 105.0]).unwrap();
+
+        assert!(stats.score(100.0).abs() < 1e-9);
+        assert!(stats.score(400.0) > 3.5);
+        assert!(stats.score(50.0) < 0.0);
+    }
+}